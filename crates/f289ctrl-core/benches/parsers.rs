@@ -0,0 +1,129 @@
+//! Throughput of the binary parsers and the protocol codec.
+//!
+//! The frames are built like captured `qddb` and `qsrr` responses of a
+//! Fluke 289. Run with `cargo bench -p f289ctrl-core`.
+
+use bytes::BytesMut;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use f289ctrl_core::{
+    proto::{codec::ProtocolCodec, command::Command},
+    rawmea::{RawMeasurement, RawSessionRecordReadings},
+};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// V DC reading, doubles are stored with swapped 32 bit words.
+fn reading(reading_id: u16, value: f64) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(30);
+    buf.extend_from_slice(&reading_id.to_le_bytes());
+    buf.extend_from_slice(&swapped(value));
+    buf.extend_from_slice(&4_u16.to_le_bytes()); // unit
+    buf.extend_from_slice(&0_i16.to_le_bytes()); // unit multiplier
+    buf.extend_from_slice(&4_i16.to_le_bytes()); // decimals
+    buf.extend_from_slice(&5_i16.to_le_bytes()); // display digits
+    buf.extend_from_slice(&2_u16.to_le_bytes()); // state
+    buf.extend_from_slice(&0_u16.to_le_bytes()); // attribute
+    buf.extend_from_slice(&swapped(1_700_000_000.5));
+    buf
+}
+
+fn swapped(value: f64) -> [u8; 8] {
+    let mut data = value.to_be_bytes();
+    data.swap(0, 3);
+    data.swap(1, 2);
+    data.swap(4, 7);
+    data.swap(5, 6);
+    data
+}
+
+/// `qddb` payload with two readings.
+fn measurement_frame() -> Vec<u8> {
+    let mut buf = b"#0".to_vec();
+    for field in [3_u16, 0, 1, 4] {
+        buf.extend_from_slice(&field.to_le_bytes());
+    }
+    buf.extend_from_slice(&swapped(5.0)); // range max
+    buf.extend_from_slice(&0_i16.to_le_bytes());
+    buf.extend_from_slice(&0_u16.to_le_bytes()); // bolt
+    buf.extend_from_slice(&1_700_000_000.5_f64.to_le_bytes());
+    for field in [0_u16, 0, 2] {
+        buf.extend_from_slice(&field.to_le_bytes());
+    }
+    buf.extend(reading(2, 1.234));
+    buf.extend(reading(5, 1.234));
+    buf.push(b'\r');
+    buf
+}
+
+/// `qsrr` payload of one recording interval.
+fn session_record_frame() -> Vec<u8> {
+    let mut buf = b"#0".to_vec();
+    buf.extend_from_slice(&swapped(1_700_000_000.0));
+    buf.extend_from_slice(&swapped(1_700_000_001.0));
+    buf.extend(reading(2, 1.240));
+    buf.extend(reading(2, 1.230));
+    buf.extend(reading(2, 1.235));
+    buf.extend_from_slice(&10_u16.to_le_bytes()); // sampling
+    buf.extend_from_slice(&0_u16.to_le_bytes());
+    buf.extend(reading(2, 1.234));
+    for field in [2_u16, 1, 0] {
+        buf.extend_from_slice(&field.to_le_bytes());
+    }
+    buf.push(b'\r');
+    buf
+}
+
+fn bench_parsers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+
+    let frame = measurement_frame();
+    group.throughput(Throughput::Bytes(frame.len() as u64));
+    group.bench_function("RawMeasurement", |b| {
+        b.iter(|| RawMeasurement::try_from(black_box(&frame[..])).expect("measurement"))
+    });
+
+    let frame = session_record_frame();
+    group.throughput(Throughput::Bytes(frame.len() as u64));
+    group.bench_function("RawSessionRecordReadings", |b| {
+        b.iter(|| RawSessionRecordReadings::try_from(black_box(&frame[..])).expect("interval"))
+    });
+
+    group.finish();
+}
+
+/// Decode `count` pipelined responses to `command` from a single buffer.
+fn decode_all(command: Command, response: &[u8], count: usize) {
+    let mut codec = ProtocolCodec::default();
+    let mut tx = BytesMut::new();
+    let mut rx = BytesMut::with_capacity(response.len() * count);
+    for _ in 0..count {
+        codec.encode(command.clone(), &mut tx).expect("encode");
+        rx.extend_from_slice(response);
+    }
+    for _ in 0..count {
+        black_box(codec.decode(&mut rx).expect("decode").expect("response"));
+    }
+}
+
+fn bench_codec(c: &mut Criterion) {
+    const FRAMES: usize = 100;
+    let mut group = c.benchmark_group("decode");
+
+    let mut response = b"0\r".to_vec();
+    response.extend(measurement_frame());
+    group.throughput(Throughput::Bytes((response.len() * FRAMES) as u64));
+    group.bench_function("qddb", |b| {
+        b.iter(|| decode_all(Command::GetMeasurementBinary, &response, FRAMES))
+    });
+
+    let mut response = b"0\r".to_vec();
+    response.extend(session_record_frame());
+    group.throughput(Throughput::Bytes((response.len() * FRAMES) as u64));
+    group.bench_function("qsrr", |b| {
+        b.iter(|| decode_all(Command::QuerySessionRecordReadings(0, 0), &response, FRAMES))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parsers, bench_codec);
+criterion_main!(benches);
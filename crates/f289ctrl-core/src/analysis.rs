@@ -0,0 +1,216 @@
+//! Event detection over downloaded recordings.
+//!
+//! [`events`] scans the intervals of a recording session for threshold
+//! crossings of the interval average, changes between unstable and stable
+//! readings and transients the meter recorded, e.g. range changes.
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+use crate::measurement::{ReadingValue, SessionRecordReadings, TransientState};
+
+/// What happened in an interval.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum EventKind {
+    /// The average rose above `threshold` (base SI unit)
+    Rising { threshold: f64 },
+    /// The average fell below `threshold` (base SI unit)
+    Falling { threshold: f64 },
+    /// The reading became stable after unstable intervals
+    Stable,
+    /// The reading became unstable
+    Unstable,
+    /// Transient recorded by the meter, e.g. a range change
+    Transient(TransientState),
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventKind::Rising { threshold } => write!(f, "Rising above {}", threshold),
+            EventKind::Falling { threshold } => write!(f, "Falling below {}", threshold),
+            EventKind::Stable => f.write_str("Stable"),
+            EventKind::Unstable => f.write_str("Unstable"),
+            EventKind::Transient(state) => write!(f, "Transient: {}", state),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RecordingEvent {
+    /// Index of the interval in the recording
+    pub index: usize,
+    /// Start of the interval
+    pub ts: DateTime<Utc>,
+    pub kind: EventKind,
+    /// Average of the interval in the base SI unit, `None` without a value
+    pub value: Option<f64>,
+}
+
+/// Settings of [`events`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventOptions {
+    /// Levels in the base SI unit to report crossings of
+    pub thresholds: Vec<f64>,
+    /// Distance from a threshold the average has to exceed before a
+    /// crossing is reported, suppresses events from noise around it
+    pub hysteresis: f64,
+}
+
+/// Events of a recording in the order of its intervals.
+///
+/// The first interval sets the initial state and only reports transients.
+/// Overloads count as above and `-OL` as below every threshold, intervals
+/// without a value (e.g. `OPEN-TC`) keep the previous state.
+pub fn events(records: &[SessionRecordReadings], options: &EventOptions) -> Vec<RecordingEvent> {
+    let mut events = Vec::new();
+    let mut above: Vec<Option<bool>> = vec![None; options.thresholds.len()];
+    let mut stable: Option<bool> = None;
+
+    for (index, rec) in records.iter().enumerate() {
+        let average = rec.average_reading();
+        let value = average.si_value();
+        let mut push = |kind: EventKind| {
+            events.push(RecordingEvent {
+                index,
+                ts: rec.start_ts,
+                kind,
+                value,
+            })
+        };
+
+        if !matches!(rec.transient_state, TransientState::NonT) {
+            push(EventKind::Transient(rec.transient_state.clone()));
+        }
+
+        if stable.map_or(false, |stable| stable != rec.stable.0) {
+            push(if rec.stable.0 {
+                EventKind::Stable
+            } else {
+                EventKind::Unstable
+            });
+        }
+        stable = Some(rec.stable.0);
+
+        let level = match average.reading_value() {
+            ReadingValue::Value(value) => value,
+            ReadingValue::Overload => f64::INFINITY,
+            ReadingValue::OverloadNegative => f64::NEG_INFINITY,
+            _ => continue,
+        };
+        for (threshold, above) in options.thresholds.iter().zip(above.iter_mut()) {
+            let now = if level > threshold + options.hysteresis {
+                true
+            } else if level < threshold - options.hysteresis {
+                false
+            } else {
+                continue;
+            };
+            match *above {
+                Some(false) if now => push(EventKind::Rising {
+                    threshold: *threshold,
+                }),
+                Some(true) if !now => push(EventKind::Falling {
+                    threshold: *threshold,
+                }),
+                _ => {}
+            }
+            *above = Some(now);
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measurement::{Reading, RecordType, Stable, State, Unit};
+    use chrono::Duration;
+
+    fn interval(i: usize, value: f64, state: State, stable: bool) -> SessionRecordReadings {
+        let reading = Reading {
+            reading_id: 0,
+            value,
+            unit: Unit::VoltDC,
+            unit_multiplier: 0,
+            decimals: 3,
+            display_digits: 5,
+            state,
+            attribute: None,
+            ts: DateTime::default(),
+            device_ts: 0.0,
+            utc_offset: 0,
+        };
+        SessionRecordReadings {
+            start_ts: DateTime::default() + Duration::seconds(i as i64),
+            end_ts: DateTime::default() + Duration::seconds(i as i64 + 1),
+            span_readings: [reading.clone(), reading.clone(), reading.clone()],
+            sampling: 1,
+            fixed_reading: reading,
+            record_type: RecordType::Interval,
+            stable: Stable(stable),
+            transient_state: TransientState::NonT,
+        }
+    }
+
+    #[test]
+    fn test_threshold_events() {
+        let values = [1.0, 4.9, 5.05, 5.2, 4.95, 4.5, 6.0];
+        let records: Vec<_> = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| interval(i, *v, State::Normal, true))
+            .collect();
+        let options = EventOptions {
+            thresholds: vec![5.0],
+            hysteresis: 0.1,
+        };
+        let events = events(&records, &options);
+        let kinds: Vec<_> = events.iter().map(|e| (e.index, e.kind.clone())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (3, EventKind::Rising { threshold: 5.0 }),
+                (5, EventKind::Falling { threshold: 5.0 }),
+                (6, EventKind::Rising { threshold: 5.0 }),
+            ]
+        );
+        assert_eq!(events[0].value, Some(5.2));
+        assert_eq!(events[0].ts, records[3].start_ts);
+    }
+
+    #[test]
+    fn test_state_events() {
+        let mut records = vec![
+            interval(0, 1.0, State::Normal, false),
+            interval(1, 1.0, State::Normal, true),
+            interval(2, 0.0, State::OL, true),
+            interval(3, 0.0, State::OpenTC, false),
+            interval(4, 0.5, State::Normal, false),
+        ];
+        records[2].transient_state = TransientState::RangeUp;
+        let options = EventOptions {
+            thresholds: vec![2.0],
+            hysteresis: 0.0,
+        };
+        let kinds: Vec<_> = events(&records, &options)
+            .into_iter()
+            .map(|e| (e.index, e.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (1, EventKind::Stable),
+                (2, EventKind::Transient(TransientState::RangeUp)),
+                (2, EventKind::Rising { threshold: 2.0 }),
+                (3, EventKind::Unstable),
+                (4, EventKind::Falling { threshold: 2.0 }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Trigger based capture of live measurements.
+//!
+//! [`Capture`] keeps the last few measurements until the [`Trigger`] is met
+//! on the primary reading, then releases them together with every following
+//! measurement.
+
+use std::{collections::VecDeque, fmt, str::FromStr};
+
+use crate::measurement::Measurement;
+
+/// Condition on the value of the primary reading, in the base SI unit.
+///
+/// The trigger is met if the value is above `above` or below `below`.
+/// Readings without a value (overload, blank, ...) never meet it.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Trigger {
+    pub above: Option<f64>,
+    pub below: Option<f64>,
+}
+
+impl Trigger {
+    pub fn is_met(&self, mea: &Measurement) -> bool {
+        let value = match mea.readings.first().and_then(|r| r.si_value()) {
+            Some(value) => value,
+            None => return false,
+        };
+        self.above.map_or(false, |above| value > above)
+            || self.below.map_or(false, |below| value < below)
+    }
+}
+
+/// Parse `value > 4.5` or `value < 0.1`.
+impl FromStr for Trigger {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid condition '{}', expected 'value > X' or 'value < X'",
+                s
+            )
+        };
+        let rest = s
+            .trim()
+            .strip_prefix("value")
+            .ok_or_else(invalid)?
+            .trim_start();
+        let (above, threshold) = if let Some(threshold) = rest.strip_prefix('>') {
+            (true, threshold)
+        } else if let Some(threshold) = rest.strip_prefix('<') {
+            (false, threshold)
+        } else {
+            return Err(invalid());
+        };
+        let threshold: f64 = threshold.trim().parse().map_err(|_| invalid())?;
+        Ok(if above {
+            Self {
+                above: Some(threshold),
+                below: None,
+            }
+        } else {
+            Self {
+                above: None,
+                below: Some(threshold),
+            }
+        })
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.above, self.below) {
+            (Some(above), Some(below)) => write!(f, "value > {} or value < {}", above, below),
+            (Some(above), None) => write!(f, "value > {}", above),
+            (None, Some(below)) => write!(f, "value < {}", below),
+            (None, None) => f.write_str("never"),
+        }
+    }
+}
+
+/// Pre-trigger buffer and trigger state.
+#[derive(Debug, Clone)]
+pub struct Capture {
+    trigger: Trigger,
+    pre_trigger: usize,
+    buffer: VecDeque<Measurement>,
+    triggered: bool,
+}
+
+impl Capture {
+    /// Keep up to `pre_trigger` measurements before the trigger is met.
+    pub fn new(trigger: Trigger, pre_trigger: usize) -> Self {
+        Self {
+            trigger,
+            pre_trigger,
+            buffer: VecDeque::with_capacity(pre_trigger),
+            triggered: false,
+        }
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.triggered
+    }
+
+    /// Add a measurement, returns the measurements to keep in order.
+    ///
+    /// Nothing is returned until the trigger is met, then the buffered
+    /// measurements followed by `mea`, and `mea` alone afterwards.
+    pub fn push(&mut self, mea: Measurement) -> Vec<Measurement> {
+        if self.triggered {
+            return vec![mea];
+        }
+        if self.trigger.is_met(&mea) {
+            self.triggered = true;
+            let mut released: Vec<Measurement> = self.buffer.drain(..).collect();
+            released.push(mea);
+            return released;
+        }
+        if self.pre_trigger > 0 {
+            if self.buffer.len() == self.pre_trigger {
+                self.buffer.pop_front();
+            }
+            self.buffer.push_back(mea);
+        }
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measurement::{
+        AutoRange, Bolt, Modes, PrimaryFunction, Reading, SecondaryFunction, State, Unit,
+    };
+
+    fn mea(value: f64) -> Measurement {
+        Measurement {
+            pri_function: PrimaryFunction::V_DC,
+            sec_function: SecondaryFunction::None,
+            auto_range: AutoRange(true),
+            unit: Unit::VoltDC,
+            range_max: 5.0,
+            unit_multiplier: 0,
+            bolt: Bolt(false),
+            ts: None,
+            host_ts: None,
+            modes: Modes::empty(),
+            readings: vec![Reading {
+                reading_id: 0,
+                value,
+                unit: Unit::VoltDC,
+                unit_multiplier: 0,
+                decimals: 3,
+                display_digits: 5,
+                state: State::Normal,
+                attribute: None,
+                ts: Default::default(),
+                device_ts: 0.0,
+                utc_offset: 0,
+            }],
+        }
+    }
+
+    fn values(measurements: &[Measurement]) -> Vec<f64> {
+        measurements.iter().map(|m| m.readings[0].value).collect()
+    }
+
+    #[test]
+    fn test_parse_trigger() {
+        let trigger: Trigger = "value > 4.5".parse().expect("trigger");
+        assert_eq!(trigger.above, Some(4.5));
+        assert_eq!(trigger.to_string(), "value > 4.5");
+        let trigger: Trigger = " value<-1e-3".parse().expect("trigger");
+        assert_eq!(trigger.below, Some(-0.001));
+        assert!("value = 1".parse::<Trigger>().is_err());
+        assert!("voltage > 1".parse::<Trigger>().is_err());
+    }
+
+    #[test]
+    fn test_capture() {
+        let mut capture = Capture::new("value > 4.5".parse().expect("trigger"), 2);
+        for value in [1.0, 2.0, 3.0] {
+            assert!(capture.push(mea(value)).is_empty());
+        }
+        assert!(!capture.is_triggered());
+        assert_eq!(values(&capture.push(mea(5.0))), [2.0, 3.0, 5.0]);
+        assert!(capture.is_triggered());
+        assert_eq!(values(&capture.push(mea(1.0))), [1.0]);
+    }
+}
@@ -0,0 +1,221 @@
+//! Loader for captured binary frames, available with the `test-util`
+//! feature.
+//!
+//! Each file holds one frame as answered by the meter. The file name starts
+//! with the command that returned it, which selects the frame type, e.g.
+//! `qddb-ohms-v1.16.hex` or `qsmr-287-temperature.bin`:
+//!
+//! * `.bin` files contain the raw bytes.
+//! * `.hex` files contain hex bytes separated by whitespace or commas, with
+//!   optional `0x` prefixes. Text after `#` or `//` is a comment, so byte
+//!   arrays and hex dumps can be pasted as they are.
+//!
+//! A leading success status line (`0\r`) is removed, so whole responses can
+//! be stored as well. The frames of this crate are in `tests/frames`.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::rawmea::{
+    RawMeasurement, RawSavedMeasurement, RawSavedMinMaxMeasurement, RawSavedRecordingSessionInfo,
+    RawSessionRecordReadings,
+};
+
+/// Frame type, named by the command returning it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    /// `qddb`
+    Measurement,
+    /// `qsmr`
+    SavedMeasurement,
+    /// `qmmsi`
+    SavedMinMax,
+    /// `qpsi`
+    SavedPeak,
+    /// `qrsi`
+    SavedRecording,
+    /// `qsrr`
+    RecordReadings,
+}
+
+impl FrameKind {
+    pub fn command(&self) -> &'static str {
+        match self {
+            FrameKind::Measurement => "qddb",
+            FrameKind::SavedMeasurement => "qsmr",
+            FrameKind::SavedMinMax => "qmmsi",
+            FrameKind::SavedPeak => "qpsi",
+            FrameKind::SavedRecording => "qrsi",
+            FrameKind::RecordReadings => "qsrr",
+        }
+    }
+
+    fn from_command(command: &str) -> Option<Self> {
+        [
+            FrameKind::Measurement,
+            FrameKind::SavedMeasurement,
+            FrameKind::SavedMinMax,
+            FrameKind::SavedPeak,
+            FrameKind::SavedRecording,
+            FrameKind::RecordReadings,
+        ]
+        .into_iter()
+        .find(|kind| kind.command().eq_ignore_ascii_case(command))
+    }
+}
+
+/// A parsed frame.
+#[derive(Debug, Clone)]
+pub enum Fixture {
+    Measurement(RawMeasurement),
+    SavedMeasurement(RawSavedMeasurement),
+    SavedMinMax(RawSavedMinMaxMeasurement),
+    SavedPeak(RawSavedMinMaxMeasurement),
+    SavedRecording(RawSavedRecordingSessionInfo),
+    RecordReadings(RawSessionRecordReadings),
+}
+
+/// Bytes of a captured frame file.
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    pub path: PathBuf,
+    pub kind: FrameKind,
+    /// Frame starting with the binary marker `#0`
+    pub bytes: Vec<u8>,
+}
+
+impl CapturedFrame {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let invalid = |msg: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), msg),
+            )
+        };
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let command = file_name.split(['-', '_', '.']).next().unwrap_or_default();
+        let kind = FrameKind::from_command(command)
+            .ok_or_else(|| invalid(format!("unknown command prefix '{}'", command)))?;
+        let mut bytes = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("bin") => fs::read(path)?,
+            Some("hex") => parse_hex(&fs::read_to_string(path)?).map_err(invalid)?,
+            _ => return Err(invalid("expected a .bin or .hex file".to_string())),
+        };
+        if bytes.starts_with(b"0\r") {
+            bytes.drain(..2);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            kind,
+            bytes,
+        })
+    }
+
+    /// Parse the frame according to its kind.
+    pub fn parse(&self) -> io::Result<Fixture> {
+        let bytes = &self.bytes[..];
+        Ok(match self.kind {
+            FrameKind::Measurement => Fixture::Measurement(RawMeasurement::try_from(bytes)?),
+            FrameKind::SavedMeasurement => {
+                Fixture::SavedMeasurement(RawSavedMeasurement::try_from(bytes)?)
+            }
+            FrameKind::SavedMinMax => {
+                Fixture::SavedMinMax(RawSavedMinMaxMeasurement::try_from(bytes)?)
+            }
+            FrameKind::SavedPeak => Fixture::SavedPeak(RawSavedMinMaxMeasurement::try_from(bytes)?),
+            FrameKind::SavedRecording => {
+                Fixture::SavedRecording(RawSavedRecordingSessionInfo::try_from(bytes)?)
+            }
+            FrameKind::RecordReadings => {
+                Fixture::RecordReadings(RawSessionRecordReadings::try_from(bytes)?)
+            }
+        })
+    }
+}
+
+/// All `.bin` and `.hex` frames in `dir`, sorted by file name.
+pub fn load_dir(dir: impl AsRef<Path>) -> io::Result<Vec<CapturedFrame>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("bin") | Some("hex")
+        ) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    paths.iter().map(CapturedFrame::load).collect()
+}
+
+fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let line = line.split("//").next().unwrap_or_default();
+        for token in line.split(|c: char| c.is_whitespace() || c == ',') {
+            let token = token.trim_start_matches("0x");
+            if token.is_empty() {
+                continue;
+            }
+            if token.len() % 2 != 0 {
+                return Err(format!("odd number of hex digits in '{}'", token));
+            }
+            for i in (0..token.len()).step_by(2) {
+                let byte = token
+                    .get(i..i + 2)
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                    .ok_or_else(|| format!("invalid hex byte in '{}'", token))?;
+                bytes.push(byte);
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(
+            parse_hex("0x30, 0x0d, // 0\\r\n2330 1b00 # #0\n").expect("hex"),
+            b"0\r#0\x1b\x00"
+        );
+        assert!(parse_hex("0x3").is_err());
+        assert!(parse_hex("zz").is_err());
+    }
+
+    #[test]
+    fn test_frame_corpus() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/frames");
+        let frames = load_dir(dir).expect("corpus");
+        assert!(!frames.is_empty());
+        for frame in &frames {
+            match frame.parse() {
+                Ok(_) => {}
+                Err(err) => panic!("{}: {}", frame.path.display(), err),
+            }
+        }
+        let ohms = frames
+            .iter()
+            .find(|frame| frame.path.ends_with("qddb-ohms-v1.16.hex"))
+            .expect("ohms frame");
+        assert_eq!(ohms.kind, FrameKind::Measurement);
+        match ohms.parse().expect("parse") {
+            Fixture::Measurement(m) => {
+                assert_eq!(m.pri_function, 27);
+                assert_eq!(m.readings.len(), 2);
+            }
+            other => panic!("unexpected fixture {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,2101 @@
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::measurement::{Memory, SavedPeakMeasurement};
+use super::progress::{Phase, Progress, ProgressTracker};
+use super::proto::{
+    client::ProtocolClient,
+    codec::FrameLimits,
+    command::Command,
+    observer::ProtocolObserver,
+    response::{Ident, Response, ResponsePayload},
+    ProtoError,
+};
+use super::quirks::Quirks;
+use super::rawmea::{
+    RawMeasurement, RawSavedMeasurement, RawSavedMinMaxMeasurement, RawSavedPeakMeasurement,
+    RawSavedRecordingSessionInfo, RawSessionRecordReadings,
+};
+use super::retry::{LockedRecovery, NoRetry, RetryPolicy};
+use super::snapshot::{MemorySnapshot, RecordingSnapshot};
+use super::transport::{self, DmmTransport, LineControl};
+use crate::maps;
+use crate::measurement::{
+    Measurement, Reading, SavedMeasurement, SavedMinMaxMeasurement, SavedRecordingSessionInfo,
+    SessionRecordReadings,
+};
+use crate::model::{Capability, Model};
+use crate::proto::command::{
+    validate_save_name, validate_string, ClearMemory, DateFormat, DezibelReference, DigitCount,
+    Key, Language, NumericFormat, TimeFormat, MAX_OWNER_LEN,
+};
+use crate::proto::response::MemoryStat;
+use crate::proto::Result;
+
+pub type ValueMap = HashMap<u16, String>;
+pub type ValueMaps = HashMap<String, ValueMap>;
+
+pub struct Device {
+    client: ProtocolClient,
+    /// `qddb` requests sent by a live measurement stream without a decoded response yet
+    stream_backlog: usize,
+    /// Minimum pause between a response and the next command
+    turnaround: Duration,
+    last_response: Option<tokio::time::Instant>,
+    retry: Box<dyn RetryPolicy>,
+    locked: LockedRecovery,
+    model: Model,
+    backend: Backend,
+}
+
+/// How live values are polled, see [`Device::detect_backend`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Backend {
+    /// Binary measurements with all readings (`qddb`)
+    #[default]
+    Binary,
+    /// Primary reading only, from the ASCII query `qm`, for meters and
+    /// firmwares without the binary queries
+    Ascii,
+}
+
+/// Value maps queried by [`Device::value_maps`], all of them are required
+/// to decode measurements.
+pub const VALUE_MAP_KEYS: [&str; 11] = [
+    "primfunction",
+    "secfunction",
+    "autorange",
+    "unit",
+    "bolt",
+    "mode",
+    "state",
+    "attribute",
+    "recordtype",
+    "isstableflag",
+    "transientstate",
+];
+
+/// Value maps needed to decode live and saved measurements, see
+/// [`Device::value_maps_for`]. Recording samples need all of
+/// [`VALUE_MAP_KEYS`].
+pub const MEASUREMENT_MAP_KEYS: [&str; 8] = [
+    "primfunction",
+    "secfunction",
+    "autorange",
+    "unit",
+    "bolt",
+    "mode",
+    "state",
+    "attribute",
+];
+
+/// Map queries sent before the first response is read.
+const MAP_PIPELINE_DEPTH: usize = 4;
+
+/// Baudrates tried by [`Device::connect_auto`], in order.
+pub const AUTO_BAUDRATES: &[u32] = &[115200, 9600, 19200, 38400, 57600];
+
+/// Time to wait for the `id` response at each baudrate.
+const AUTO_BAUDRATE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Maximum number of `qddb` requests in flight for [`Device::live_measurements`].
+const MAX_STREAM_IN_FLIGHT: usize = 2;
+
+impl Device {
+    /// Open a serial port, or a terminal server given as `tcp://host:port`
+    /// (raw), `rfc2217://host:port` (RFC 2217 COM port control),
+    /// `unix:///path` or `pty:///dev/pts/N`.
+    pub fn new(com: impl AsRef<str>, baudrate: u32) -> Result<Self> {
+        Self::new_with_limits(com, baudrate, FrameLimits::default())
+    }
+
+    /// Serial device with bounded decoder memory, see [`FrameLimits`].
+    pub fn new_with_limits(
+        com: impl AsRef<str>,
+        baudrate: u32,
+        limits: FrameLimits,
+    ) -> Result<Self> {
+        Ok(Self::with_limits(
+            transport::open(com.as_ref(), baudrate)?,
+            limits,
+        ))
+    }
+
+    /// Create a device communicating over an arbitrary transport.
+    pub fn with_transport(transport: impl DmmTransport + 'static) -> Self {
+        Self::with_limits(transport, FrameLimits::default())
+    }
+
+    /// Like [`Device::with_transport`], with bounded decoder memory for small hosts.
+    pub fn with_limits(transport: impl DmmTransport + 'static, limits: FrameLimits) -> Self {
+        Self {
+            client: ProtocolClient::with_limits(transport, limits),
+            stream_backlog: 0,
+            turnaround: Duration::ZERO,
+            last_response: None,
+            retry: Box::new(NoRetry),
+            locked: LockedRecovery::default(),
+            model: Model::Unknown,
+            backend: Backend::default(),
+        }
+    }
+
+    /// Open `com` trying each of [`AUTO_BAUDRATES`] until the meter answers `id`.
+    ///
+    /// Returns the device together with the working baudrate.
+    pub async fn connect_auto(com: impl AsRef<str>) -> Result<(Self, u32)> {
+        Self::connect_auto_with_lines(com, &LineControl::default()).await
+    }
+
+    /// Like [`Device::connect_auto`], setting the modem control lines of serial ports.
+    pub async fn connect_auto_with_lines(
+        com: impl AsRef<str>,
+        lines: &LineControl,
+    ) -> Result<(Self, u32)> {
+        for &baudrate in AUTO_BAUDRATES {
+            let transport = transport::open_with_lines(com.as_ref(), baudrate, lines).await?;
+            let mut device = Self::with_transport(transport);
+            // Garbage received at a wrong rate may still be buffered in the
+            // meter, so the first `id` can fail with a syntax error.
+            for _ in 0..2 {
+                match tokio::time::timeout(AUTO_BAUDRATE_TIMEOUT, device.ident()).await {
+                    Ok(Ok(_)) => return Ok((device, baudrate)),
+                    Ok(Err(ProtoError::SyntaxError { .. })) => continue,
+                    _ => break,
+                }
+            }
+        }
+        Err(ProtoError::Timeout {
+            command: Some(Command::Id),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn new_faked(response_buf: Vec<char>) -> Self {
+        let converted: Vec<u8> = response_buf.iter().map(|x| *x as u8).collect();
+        Self::with_transport(crate::mock::MockTransport::new().respond(converted))
+    }
+
+    /// Pause between receiving a response and sending the next command.
+    ///
+    /// The IR link is half-duplex and some firmwares drop commands sent
+    /// immediately after a response. Applies to every command, including each
+    /// request of bulk downloads; pipelined live measurement streams are not paced.
+    pub fn set_turnaround(&mut self, delay: Duration) {
+        self.turnaround = delay;
+    }
+
+    pub fn turnaround(&self) -> Duration {
+        self.turnaround
+    }
+
+    /// Send a command, honoring the turnaround delay.
+    async fn send(&mut self, command: Command) -> std::io::Result<()> {
+        if let Some(last) = self.last_response {
+            tokio::time::sleep_until(last + self.turnaround).await;
+        }
+        self.client.send(command).await
+    }
+
+    /// Repeat queries on transient failures, see [`RetryPolicy`].
+    pub fn set_retry_policy(&mut self, policy: impl RetryPolicy + 'static) {
+        self.retry = Box::new(policy);
+    }
+
+    /// Handle commands rejected because of the screen shown by the meter,
+    /// see [`LockedRecovery`]. The default is to fail with
+    /// [`ProtoError::DeviceLocked`].
+    pub fn set_locked_recovery(&mut self, recovery: LockedRecovery) {
+        self.locked = recovery;
+    }
+
+    /// Send a command and read its response, retrying transient failures
+    /// according to the retry policy.
+    async fn request(&mut self, command: Command) -> Result<Option<std::io::Result<Response>>> {
+        let mut attempt = 0;
+        let mut recovery = 0;
+        loop {
+            attempt += 1;
+            self.send(command.clone()).await?;
+            let response = self.next_response().await;
+            if let Some(Ok(
+                failure @ (Response::NoData | Response::ExecutionError | Response::FramingError(_)),
+            )) = &response
+            {
+                if let Some(delay) = self.retry.retry(&command, attempt, failure) {
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+            }
+            if matches!(response, Some(Ok(Response::ExecutionError))) {
+                recovery += 1;
+                if self.recover_locked(&command, recovery).await? {
+                    continue;
+                }
+            }
+            return reject(response, command);
+        }
+    }
+
+    /// Send a command and read its response, without retries.
+    async fn execute(&mut self, command: Command) -> Result<Option<std::io::Result<Response>>> {
+        let mut recovery = 0;
+        loop {
+            self.send(command.clone()).await?;
+            let response = self.next_response().await;
+            if matches!(response, Some(Ok(Response::ExecutionError))) {
+                recovery += 1;
+                if self.recover_locked(&command, recovery).await? {
+                    continue;
+                }
+            }
+            return reject(response, command);
+        }
+    }
+
+    /// Called after `command` failed with an execution error for the
+    /// `recovery`th time. The meter answers `id` in every screen, if it does
+    /// the command was rejected because of the screen: returns whether to
+    /// repeat the command, or [`ProtoError::DeviceLocked`].
+    async fn recover_locked(&mut self, command: &Command, recovery: u32) -> Result<bool> {
+        if matches!(command, Command::Id | Command::PressKey(_)) {
+            return Ok(false);
+        }
+        self.send(Command::Id).await?;
+        if !matches!(
+            self.next_response().await,
+            Some(Ok(Response::Success(Some(ResponsePayload::Id(_)))))
+        ) {
+            return Ok(false);
+        }
+        match self.locked {
+            LockedRecovery::PressKey { key, attempts } if recovery <= attempts => {
+                self.press_key(key).await?;
+                Ok(true)
+            }
+            LockedRecovery::Wait { delay, attempts } if recovery <= attempts => {
+                tokio::time::sleep(delay).await;
+                Ok(true)
+            }
+            _ => Err(ProtoError::DeviceLocked {
+                command: Some(command.clone()),
+            }),
+        }
+    }
+
+    /// Read the next response. Garbage skipped by the decoder is tolerated as
+    /// long as a valid frame follows, otherwise [`ProtoError::FramingError`] is returned.
+    async fn next_response(&mut self) -> Option<std::io::Result<Response>> {
+        // Responses of a dropped live measurement stream arrive first
+        while self.stream_backlog > 0 {
+            self.stream_backlog -= 1;
+            let _ = self.client.recv().await?;
+        }
+
+        let mut skipped = 0;
+        let response = loop {
+            match self.client.recv().await {
+                Some(Ok(Response::FramingError(n))) => skipped += n,
+                None if skipped > 0 => break Some(Ok(Response::FramingError(skipped))),
+                other => break other,
+            }
+        };
+        self.last_response = Some(tokio::time::Instant::now());
+        response
+    }
+
+    /// Trace all commands and responses, replacing a previous observer.
+    pub fn set_observer(&mut self, observer: impl ProtocolObserver + 'static) {
+        self.client.set_observer(observer);
+    }
+
+    pub fn clear_observer(&mut self) {
+        self.client.clear_observer();
+    }
+
+    /// Total bytes received from the device since the connection was opened.
+    pub fn rx_bytes(&self) -> u64 {
+        self.client.rx_bytes()
+    }
+
+    fn tracker(&self, phase: Phase, total: usize) -> ProgressTracker {
+        ProgressTracker::new(phase, total, self.rx_bytes())
+    }
+
+    /// Send the map queries of `keys` and collect the responses in order.
+    ///
+    /// After the first query succeeded, up to [`MAP_PIPELINE_DEPTH`] queries
+    /// are sent before reading the responses, which saves a round trip per
+    /// map. With a turnaround delay the queries are sent one by one. With
+    /// `stop_on_reject` no further queries are sent after a rejected one.
+    async fn query_maps<'k>(
+        &mut self,
+        keys: &[&'k str],
+        stop_on_reject: bool,
+    ) -> Result<Vec<(&'k str, Response)>> {
+        let depth = if self.turnaround.is_zero() {
+            MAP_PIPELINE_DEPTH
+        } else {
+            1
+        };
+        let (first, rest) = keys.split_at(keys.len().min(1));
+        let mut responses = Vec::with_capacity(keys.len());
+        for chunk in std::iter::once(first).chain(rest.chunks(depth)) {
+            for k in chunk {
+                self.send(Command::QueryMap(String::from(*k))).await?;
+            }
+            for k in chunk {
+                match self.next_response().await {
+                    Some(Ok(response)) => responses.push((*k, response)),
+                    Some(Err(ioerr)) => return Err(ioerr.into()),
+                    None => return Err(ProtoError::Abort),
+                }
+            }
+            let rejected = responses.iter().any(|(_, response)| {
+                !matches!(response, Response::Success(Some(ResponsePayload::Map(_))))
+            });
+            if stop_on_reject && rejected {
+                break;
+            }
+        }
+        Ok(responses)
+    }
+
+    /// Firmware quirks currently applied while decoding.
+    pub fn quirks(&self) -> Quirks {
+        self.client.quirks()
+    }
+
+    /// Query the device identification. Quirks for the reported firmware are
+    /// activated for all following commands.
+    pub async fn ident(&mut self) -> Result<Ident> {
+        match self.request(Command::Id).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::Id(id))))) => {
+                self.client.set_quirks(Quirks::for_firmware(&id.firmware));
+                self.model = id.device_model();
+                Ok(id)
+            }
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    /// Model reported by the last [`Device::ident`], [`Model::Unknown`] before.
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    /// Backend used by [`Device::primary_reading`].
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.backend = backend;
+    }
+
+    /// Select the ASCII backend if the meter rejects `qddb` as unknown.
+    pub async fn detect_backend(&mut self) -> Result<Backend> {
+        self.backend = match self.live_measurement().await {
+            Ok(_) => Backend::Binary,
+            Err(ProtoError::SyntaxError { .. }) => Backend::Ascii,
+            Err(err) => return Err(err),
+        };
+        Ok(self.backend)
+    }
+
+    /// Fail with [`ProtoError::Unsupported`] if the model lacks `capability`.
+    fn require(&self, capability: Capability) -> Result<()> {
+        if self.model.supports(capability) {
+            Ok(())
+        } else {
+            Err(ProtoError::Unsupported {
+                model: self.model,
+                capability,
+            })
+        }
+    }
+
+    pub async fn value_maps(&mut self) -> Result<ValueMaps> {
+        self.value_maps_for(&VALUE_MAP_KEYS).await
+    }
+
+    /// Query only the value maps of `keys`, e.g. [`MEASUREMENT_MAP_KEYS`].
+    pub async fn value_maps_for(&mut self, keys: &[&str]) -> Result<ValueMaps> {
+        let mut maps = ValueMaps::new();
+        for (k, response) in self.query_maps(keys, true).await? {
+            match response {
+                Response::Success(Some(ResponsePayload::Map(map))) => {
+                    maps.insert(k.to_string(), map);
+                }
+                response => {
+                    return Err(
+                        ProtoError::from(response).with_command(Command::QueryMap(k.to_string()))
+                    )
+                }
+            }
+        }
+        self.client.quirks().patch_maps(&mut maps);
+        Ok(maps)
+    }
+
+    /// Like [`Device::value_maps`], but maps the device rejects and entries
+    /// it doesn't report are taken from the built-in tables for `firmware`
+    /// (see [`crate::maps`]).
+    ///
+    /// Returns the maps together with the keys filled from the built-in
+    /// tables. I/O errors are not covered by the fallback.
+    pub async fn value_maps_with_fallback(
+        &mut self,
+        firmware: Option<&str>,
+    ) -> Result<(ValueMaps, Vec<String>)> {
+        self.value_maps_with_fallback_for(firmware, &VALUE_MAP_KEYS)
+            .await
+    }
+
+    /// [`Device::value_maps_with_fallback`] for the maps of `keys` only.
+    pub async fn value_maps_with_fallback_for(
+        &mut self,
+        firmware: Option<&str>,
+        keys: &[&str],
+    ) -> Result<(ValueMaps, Vec<String>)> {
+        let mut maps = ValueMaps::new();
+        let mut rejected = None;
+
+        for (k, response) in self.query_maps(keys, false).await? {
+            match response {
+                Response::Success(Some(ResponsePayload::Map(map))) => {
+                    maps.insert(k.to_string(), map);
+                }
+                response => {
+                    rejected.get_or_insert(response);
+                }
+            }
+        }
+        let filled = match maps::builtin_maps(firmware) {
+            Some(mut builtin) => {
+                builtin.retain(|k, _| keys.contains(&k.as_str()));
+                maps::fill_missing(&mut maps, &builtin)
+            }
+            None => match rejected {
+                Some(response) => return Err(response.into()),
+                None => Vec::new(),
+            },
+        };
+        self.client.quirks().patch_maps(&mut maps);
+        Ok((maps, filled))
+    }
+
+    pub async fn all_memory(&mut self, maps: &ValueMaps) -> Result<Vec<Memory>> {
+        self.all_memory_with_progress(maps, |_| {}).await
+    }
+
+    pub async fn all_memory_with_progress(
+        &mut self,
+        maps: &ValueMaps,
+        progress: impl FnMut(&Progress),
+    ) -> Result<Vec<Memory>> {
+        let stats = self.memory_statistics().await?;
+        self.all_memory_with_stats(maps, &stats, progress).await
+    }
+
+    /// Like [`Device::all_memory_with_progress`] for callers which already
+    /// queried the [`MemoryStat`].
+    pub async fn all_memory_with_stats(
+        &mut self,
+        maps: &ValueMaps,
+        stats: &MemoryStat,
+        mut progress: impl FnMut(&Progress),
+    ) -> Result<Vec<Memory>> {
+        let mea: Vec<SavedMeasurement> = self
+            .saved_measurements_first(stats.measurement, &mut progress)
+            .await?
+            .into_iter()
+            .map(|raw| (raw, maps).try_into())
+            .collect::<Result<_>>()?;
+
+        let mea_minmax: Vec<SavedMinMaxMeasurement> = self
+            .saved_minmax_first(stats.min_max, &mut progress)
+            .await?
+            .into_iter()
+            .map(|raw| (raw, maps).try_into())
+            .collect::<Result<_>>()?;
+
+        let mea_peak: Vec<SavedPeakMeasurement> = self
+            .saved_peak_first(stats.peak, &mut progress)
+            .await?
+            .into_iter()
+            .map(|raw| (raw, maps).try_into())
+            .collect::<Result<_>>()?;
+
+        let recordings: Vec<SavedRecordingSessionInfo> = self
+            .saved_recordings_first(stats.recordings, &mut progress)
+            .await?
+            .into_iter()
+            .map(|raw| (raw, maps).try_into())
+            .collect::<Result<_>>()?;
+
+        Ok(mea
+            .into_iter()
+            .map(Memory::Measurement)
+            .chain(mea_minmax.into_iter().map(Memory::MinMaxMeasurement))
+            .chain(mea_peak.into_iter().map(Memory::PeakMeasurement))
+            .chain(recordings.into_iter().map(Memory::Recording))
+            .collect())
+    }
+
+    /// Download the complete device memory, including all recording samples.
+    pub async fn snapshot_memory(&mut self, maps: &ValueMaps) -> Result<MemorySnapshot> {
+        self.snapshot_memory_with_progress(maps, |_| {}).await
+    }
+
+    pub async fn snapshot_memory_with_progress(
+        &mut self,
+        maps: &ValueMaps,
+        mut progress: impl FnMut(&Progress),
+    ) -> Result<MemorySnapshot> {
+        let taken_at = Utc::now();
+        let ident = self.ident().await?;
+        let stats = self.memory_statistics().await?;
+
+        let measurements = self
+            .saved_measurements_first(stats.measurement, &mut progress)
+            .await?
+            .into_iter()
+            .map(|raw| (raw, maps).try_into())
+            .collect::<Result<_>>()?;
+
+        let min_max = self
+            .saved_minmax_first(stats.min_max, &mut progress)
+            .await?
+            .into_iter()
+            .map(|raw| (raw, maps).try_into())
+            .collect::<Result<_>>()?;
+
+        let peak = self
+            .saved_peak_first(stats.peak, &mut progress)
+            .await?
+            .into_iter()
+            .map(|raw| (raw, maps).try_into())
+            .collect::<Result<_>>()?;
+
+        let infos: Vec<SavedRecordingSessionInfo> = self
+            .saved_recordings_first(stats.recordings, &mut progress)
+            .await?
+            .into_iter()
+            .map(|raw| (raw, maps).try_into())
+            .collect::<Result<_>>()?;
+
+        let mut recordings = Vec::with_capacity(infos.len());
+        for info in infos {
+            let samples = self
+                .session_record_reading_all_with_progress(
+                    info.reading_index as usize,
+                    info.num_samples as usize,
+                    &mut progress,
+                )
+                .await?
+                .into_iter()
+                .map(|raw| SessionRecordReadings::try_from((raw, maps)))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            recordings.push(RecordingSnapshot { info, samples });
+        }
+
+        Ok(MemorySnapshot {
+            taken_at,
+            ident,
+            measurements,
+            min_max,
+            peak,
+            recordings,
+        })
+    }
+
+    pub async fn backlight(&mut self) -> Result<Duration> {
+        match self.execute(Command::GetBacklightTimeout).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::BacklightTimeout(duration))))) => {
+                Ok(duration)
+            }
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_backlight(&mut self, duration: Duration) -> Result<()> {
+        match self.execute(Command::SetBacklightTimeout(duration)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn poweroff(&mut self) -> Result<Duration> {
+        match self.execute(Command::GetDevicePowerOff).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::DevicePowerOff(duration))))) => {
+                Ok(duration)
+            }
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_poweroff(&mut self, duration: Duration) -> Result<()> {
+        match self.execute(Command::SetDevicePowerOff(duration)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn operator(&mut self) -> Result<String> {
+        match self.execute(Command::GetOperator).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::Operator(operator))))) => Ok(operator),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_operator(&mut self, operator: impl AsRef<str>) -> Result<()> {
+        check_string("Operator", operator.as_ref(), MAX_OWNER_LEN)?;
+        match self
+            .execute(Command::SetOperator(operator.as_ref().to_string()))
+            .await?
+        {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn company(&mut self) -> Result<String> {
+        match self.execute(Command::GetCompany).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::Company(company))))) => Ok(company),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_company(&mut self, company: impl AsRef<str>) -> Result<()> {
+        check_string("Company", company.as_ref(), MAX_OWNER_LEN)?;
+        match self
+            .execute(Command::SetCompany(company.as_ref().to_string()))
+            .await?
+        {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn site(&mut self) -> Result<String> {
+        match self.execute(Command::GetSite).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::Site(site))))) => Ok(site),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_site(&mut self, site: impl AsRef<str>) -> Result<()> {
+        check_string("Site", site.as_ref(), MAX_OWNER_LEN)?;
+        match self
+            .execute(Command::SetSite(site.as_ref().to_string()))
+            .await?
+        {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn contact(&mut self) -> Result<String> {
+        match self.execute(Command::GetContact).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::Contact(contact))))) => Ok(contact),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_contact(&mut self, contact: impl AsRef<str>) -> Result<()> {
+        check_string("Contact", contact.as_ref(), MAX_OWNER_LEN)?;
+        match self
+            .execute(Command::SetContact(contact.as_ref().to_string()))
+            .await?
+        {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn beeper(&mut self) -> Result<bool> {
+        match self.execute(Command::GetBeeper).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::Beeper(state))))) => Ok(state),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_beeper(&mut self, state: bool) -> Result<()> {
+        match self.execute(Command::SetBeeper(state)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    /// Press a front panel key remotely.
+    pub async fn press_key(&mut self, key: Key) -> Result<()> {
+        self.send(Command::PressKey(key)).await?;
+        match self.next_response().await {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => {
+                Err(ProtoError::from(response).with_command(Command::PressKey(key)))
+            }
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    /// Beep and blink the backlight, so the meter can be told apart from
+    /// others on the bench.
+    ///
+    /// Each round presses the backlight key three times, which beeps and
+    /// steps the backlight through its states back to the initial one. The
+    /// beeper is enabled for the duration and restored afterwards.
+    pub async fn identify(&mut self, rounds: usize) -> Result<()> {
+        let beeper = self.beeper().await?;
+        if !beeper {
+            self.set_beeper(true).await?;
+        }
+        let mut result = Ok(());
+        for _ in 0..rounds * 3 {
+            result = self.press_key(Key::Backlight).await;
+            if result.is_err() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(300)).await;
+        }
+        if !beeper {
+            self.set_beeper(false).await?;
+        }
+        result
+    }
+
+    pub async fn smoothing(&mut self) -> Result<bool> {
+        match self.execute(Command::GetSmoothing).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::Smoothing(state))))) => Ok(state),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_smoothing(&mut self, state: bool) -> Result<()> {
+        match self.execute(Command::SetSmoothing(state)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn clock(&mut self) -> Result<u64> {
+        match self.execute(Command::GetClock).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::Clock(clock))))) => Ok(clock),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    /// Set the clock to the local time of `clock`, the device has no time
+    /// zone setting.
+    pub async fn set_clock<Tz: TimeZone>(&mut self, clock: DateTime<Tz>) -> Result<()> {
+        let naive = clock.naive_local();
+        let utc: DateTime<Utc> = Utc.from_utc_datetime(&naive);
+        let secs = utc.timestamp() as u64;
+
+        /*
+        let secs = clock
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_err(|_e| ProtoError::Abort)?
+            .as_secs();
+             */
+
+        match self.execute(Command::SetClock(secs)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn clear(&mut self, mem: ClearMemory) -> Result<()> {
+        match self.execute(Command::Clear(mem)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn reset(&mut self) -> Result<()> {
+        match self.execute(Command::ResetDevice).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn custom_dbm(&mut self) -> Result<u16> {
+        match self.execute(Command::GetCustomDbm).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::CustomDbm(dbm))))) => Ok(dbm),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_custom_dbm(&mut self, dbm: u16) -> Result<()> {
+        match self.execute(Command::SetCustomDbm(dbm)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn dbm_ref(&mut self) -> Result<DezibelReference> {
+        match self.execute(Command::GetDbmRef).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::DbmRef(dbm))))) => Ok(dbm),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_dbm_ref(&mut self, dbm: DezibelReference) -> Result<()> {
+        match self.execute(Command::SetDbmRef(dbm)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn temp_offset(&mut self) -> Result<i16> {
+        self.require(Capability::Temperature)?;
+        match self.execute(Command::GetTempOffset).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::TempOffset(offset))))) => Ok(offset),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_temp_offset(&mut self, offset: i16) -> Result<()> {
+        self.require(Capability::Temperature)?;
+        match self.execute(Command::SetTempOffset(offset)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn digit_count(&mut self) -> Result<DigitCount> {
+        match self.execute(Command::GetDigitCount).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::DigitCount(dc))))) => Ok(dc),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_digit_count(&mut self, dc: DigitCount) -> Result<()> {
+        match self.execute(Command::SetDigitCount(dc)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn autohold_event_threshold(&mut self) -> Result<u8> {
+        match self.execute(Command::GetAutoHoldEventThreshold).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::AutoHoldEventThreshold(thd))))) => {
+                Ok(thd)
+            }
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_autohold_event_threshold(&mut self, thd: u8) -> Result<()> {
+        match self
+            .execute(Command::SetAutoHoldEventThreshold(thd))
+            .await?
+        {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn recording_event_threshold(&mut self) -> Result<u8> {
+        match self.execute(Command::GetRecordingEventThreshold).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::RecordingEventThreshold(thd))))) => {
+                Ok(thd)
+            }
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_recording_event_threshold(&mut self, thd: u8) -> Result<()> {
+        match self
+            .execute(Command::SetRecordingEventThreshold(thd))
+            .await?
+        {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn language(&mut self) -> Result<Language> {
+        match self.execute(Command::GetLanguage).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::Language(lang))))) => Ok(lang),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_language(&mut self, lang: Language) -> Result<()> {
+        match self.execute(Command::SetLanguage(lang)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn date_format(&mut self) -> Result<DateFormat> {
+        match self.execute(Command::GetDateFormat).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::DateFormat(fmt))))) => Ok(fmt),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_date_format(&mut self, fmt: DateFormat) -> Result<()> {
+        match self.execute(Command::SetDateFormat(fmt)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn time_format(&mut self) -> Result<TimeFormat> {
+        match self.execute(Command::GetTimeFormat).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::TimeFormat(fmt))))) => Ok(fmt),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_time_format(&mut self, fmt: TimeFormat) -> Result<()> {
+        match self.execute(Command::SetTimeFormat(fmt)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn numeric_format(&mut self) -> Result<NumericFormat> {
+        match self.execute(Command::GetNumFormat).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::NumericFormat(fmt))))) => Ok(fmt),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_numeric_format(&mut self, fmt: NumericFormat) -> Result<()> {
+        match self.execute(Command::SetNumFormat(fmt)).await? {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn save_name(&mut self, slot: u16) -> Result<String> {
+        match self.execute(Command::GetSaveName(slot)).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::SaveName(name))))) => Ok(name),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn set_save_name(&mut self, slot: u16, name: impl AsRef<str>) -> Result<()> {
+        validate_save_name(name.as_ref())?;
+        match self
+            .execute(Command::SetSaveName(slot, name.as_ref().to_string()))
+            .await?
+        {
+            Some(Ok(Response::Success(None))) => Ok(()),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn live_measurement(&mut self) -> Result<Option<RawMeasurement>> {
+        match self.request(Command::GetMeasurementBinary).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::MeasurementBinary(m))))) => Ok(Some(m)),
+            Some(Ok(Response::NoData)) => Ok(None),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    /// Primary reading from the ASCII query `qm`, `None` if the meter has no
+    /// value to show.
+    pub async fn ascii_reading(&mut self) -> Result<Option<Reading>> {
+        match self.request(Command::GetMeasurementAscii).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::MeasurementAscii(m))))) => {
+                Ok(Some(Reading::try_from(m)?))
+            }
+            Some(Ok(Response::NoData)) => Ok(None),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    /// Primary reading of the live measurement using the selected
+    /// [`Backend`], `maps` are only needed for [`Backend::Binary`].
+    pub async fn primary_reading(&mut self, maps: &ValueMaps) -> Result<Option<Reading>> {
+        match self.backend {
+            Backend::Binary => match self.live_measurement().await? {
+                Some(raw) => Ok(Measurement::try_from((raw, maps))?
+                    .readings
+                    .into_iter()
+                    .next()),
+                None => Ok(None),
+            },
+            Backend::Ascii => self.ascii_reading().await,
+        }
+    }
+
+    /// Stream of live measurements, requested every `interval`.
+    ///
+    /// Requests are pipelined: the next `qddb` is sent on schedule even if the
+    /// previous response has not arrived yet, and responses are decoded as
+    /// they come in. Dropping the stream is safe, outstanding responses are
+    /// discarded before the next command.
+    pub fn live_measurements(
+        &mut self,
+        interval: Duration,
+    ) -> impl futures::Stream<Item = Result<Option<RawMeasurement>>> + '_ {
+        let next_request = tokio::time::Instant::now();
+
+        futures::stream::unfold(
+            (self, next_request),
+            move |(device, mut next_request)| async move {
+                loop {
+                    let frame = if device.stream_backlog == 0 {
+                        tokio::time::sleep_until(next_request).await;
+                        None
+                    } else if device.stream_backlog < MAX_STREAM_IN_FLIGHT {
+                        tokio::time::timeout_at(next_request, device.client.recv())
+                            .await
+                            .ok()
+                    } else {
+                        Some(device.client.recv().await)
+                    };
+
+                    let item = match frame {
+                        // Time for the next request
+                        None => {
+                            next_request += interval;
+                            if let Err(err) =
+                                device.client.send(Command::GetMeasurementBinary).await
+                            {
+                                Err(err.into())
+                            } else {
+                                device.stream_backlog += 1;
+                                continue;
+                            }
+                        }
+                        Some(frame) => {
+                            device.stream_backlog -= 1;
+                            match frame {
+                                Some(Ok(Response::Success(Some(
+                                    ResponsePayload::MeasurementBinary(m),
+                                )))) => Ok(Some(m)),
+                                Some(Ok(Response::NoData)) => Ok(None),
+                                Some(Ok(response)) => Err(response.into()),
+                                Some(Err(ioerr)) => Err(ioerr.into()),
+                                None => return None,
+                            }
+                        }
+                    };
+                    return Some((item, (device, next_request)));
+                }
+            },
+        )
+    }
+
+    pub async fn memory_statistics(&mut self) -> Result<MemoryStat> {
+        match self.request(Command::GetMemoryStat).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::MemoryStat(m))))) => Ok(m),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn saved_measurement(&mut self, idx: usize) -> Result<RawSavedMeasurement> {
+        match self.request(Command::QuerySavedMeasurement(idx)).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::SavedMeasurement(m))))) => Ok(m),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn saved_measurements_all(&mut self) -> Result<Vec<RawSavedMeasurement>> {
+        self.saved_measurements_all_with_progress(|_| {}).await
+    }
+
+    pub async fn saved_measurements_all_with_progress(
+        &mut self,
+        progress: impl FnMut(&Progress),
+    ) -> Result<Vec<RawSavedMeasurement>> {
+        let stats = self.memory_statistics().await?;
+        self.saved_measurements_first(stats.measurement, progress)
+            .await
+    }
+
+    /// The first `count` entries, `count` is taken from [`MemoryStat`].
+    async fn saved_measurements_first(
+        &mut self,
+        count: usize,
+        mut progress: impl FnMut(&Progress),
+    ) -> Result<Vec<RawSavedMeasurement>> {
+        let tracker = self.tracker(Phase::Measurements, count);
+        let mut v = Vec::with_capacity(count);
+        for i in 0..count {
+            let m = self.saved_measurement(i).await?;
+            v.push(m);
+            progress(&tracker.update(i + 1, self.rx_bytes()));
+        }
+        Ok(v)
+    }
+
+    pub async fn saved_minmax(&mut self, idx: usize) -> Result<RawSavedMinMaxMeasurement> {
+        match self.request(Command::QueryMinMaxSessionInfo(idx)).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::MinMaxSessionInfo(m))))) => Ok(m),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn saved_minmax_all(&mut self) -> Result<Vec<RawSavedMinMaxMeasurement>> {
+        self.saved_minmax_all_with_progress(|_| {}).await
+    }
+
+    pub async fn saved_minmax_all_with_progress(
+        &mut self,
+        progress: impl FnMut(&Progress),
+    ) -> Result<Vec<RawSavedMinMaxMeasurement>> {
+        let stats = self.memory_statistics().await?;
+        self.saved_minmax_first(stats.min_max, progress).await
+    }
+
+    /// The first `count` entries, `count` is taken from [`MemoryStat`].
+    async fn saved_minmax_first(
+        &mut self,
+        count: usize,
+        mut progress: impl FnMut(&Progress),
+    ) -> Result<Vec<RawSavedMinMaxMeasurement>> {
+        let tracker = self.tracker(Phase::MinMax, count);
+        let mut v = Vec::with_capacity(count);
+        for i in 0..count {
+            let m = self.saved_minmax(i).await?;
+            v.push(m);
+            progress(&tracker.update(i + 1, self.rx_bytes()));
+        }
+        Ok(v)
+    }
+
+    pub async fn saved_peak(&mut self, idx: usize) -> Result<RawSavedPeakMeasurement> {
+        match self.request(Command::QueryPeakSessionInfo(idx)).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::PeakSessionInfo(m))))) => Ok(m),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn saved_peak_all(&mut self) -> Result<Vec<RawSavedPeakMeasurement>> {
+        self.saved_peak_all_with_progress(|_| {}).await
+    }
+
+    pub async fn saved_peak_all_with_progress(
+        &mut self,
+        progress: impl FnMut(&Progress),
+    ) -> Result<Vec<RawSavedPeakMeasurement>> {
+        let stats = self.memory_statistics().await?;
+        self.saved_peak_first(stats.peak, progress).await
+    }
+
+    /// The first `count` entries, `count` is taken from [`MemoryStat`].
+    async fn saved_peak_first(
+        &mut self,
+        count: usize,
+        mut progress: impl FnMut(&Progress),
+    ) -> Result<Vec<RawSavedPeakMeasurement>> {
+        let tracker = self.tracker(Phase::Peak, count);
+        let mut v = Vec::with_capacity(count);
+        for i in 0..count {
+            let m = self.saved_peak(i).await?;
+            v.push(m);
+            progress(&tracker.update(i + 1, self.rx_bytes()));
+        }
+        Ok(v)
+    }
+
+    pub async fn saved_recording(&mut self, idx: usize) -> Result<RawSavedRecordingSessionInfo> {
+        match self.request(Command::QueryRecordedSessionInfo(idx)).await? {
+            Some(Ok(Response::Success(Some(ResponsePayload::RecordedSessionInfo(m))))) => Ok(m),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn saved_recordings_all(&mut self) -> Result<Vec<RawSavedRecordingSessionInfo>> {
+        self.saved_recordings_all_with_progress(|_| {}).await
+    }
+
+    pub async fn saved_recordings_all_with_progress(
+        &mut self,
+        progress: impl FnMut(&Progress),
+    ) -> Result<Vec<RawSavedRecordingSessionInfo>> {
+        let stats = self.memory_statistics().await?;
+        self.saved_recordings_first(stats.recordings, progress)
+            .await
+    }
+
+    /// The first `count` entries, `count` is taken from [`MemoryStat`].
+    async fn saved_recordings_first(
+        &mut self,
+        count: usize,
+        mut progress: impl FnMut(&Progress),
+    ) -> Result<Vec<RawSavedRecordingSessionInfo>> {
+        let tracker = self.tracker(Phase::Recordings, count);
+        let mut v = Vec::with_capacity(count);
+        for i in 0..count {
+            let m = self.saved_recording(i).await?;
+            v.push(m);
+            progress(&tracker.update(i + 1, self.rx_bytes()));
+        }
+        Ok(v)
+    }
+
+    pub async fn session_record_reading(
+        &mut self,
+        reading_idx: usize,
+        sample_idx: usize,
+    ) -> Result<RawSessionRecordReadings> {
+        match self
+            .request(Command::QuerySessionRecordReadings(reading_idx, sample_idx))
+            .await?
+        {
+            Some(Ok(Response::Success(Some(ResponsePayload::SessionRecordReading(m))))) => Ok(m),
+            Some(Ok(response)) => Err(response.into()),
+            Some(Err(ioerr)) => Err(ioerr.into()),
+            None => Err(ProtoError::Abort),
+        }
+    }
+
+    pub async fn session_record_reading_all_cb(
+        &mut self,
+        reading_index: usize,
+        num_samples: usize,
+        callback: impl FnOnce(usize, usize) + Copy + 'static,
+    ) -> Result<Vec<RawSessionRecordReadings>> {
+        self.session_record_reading_all_with_progress(reading_index, num_samples, |p| {
+            callback(p.done - 1, p.total)
+        })
+        .await
+    }
+
+    pub async fn session_record_reading_all_with_progress(
+        &mut self,
+        reading_index: usize,
+        num_samples: usize,
+        mut progress: impl FnMut(&Progress),
+    ) -> Result<Vec<RawSessionRecordReadings>> {
+        let tracker = self.tracker(Phase::RecordingSamples, num_samples);
+        let mut v = Vec::with_capacity(num_samples);
+        for i in 0..num_samples {
+            let m = self.session_record_reading(reading_index, i).await?;
+            v.push(m);
+            progress(&tracker.update(i + 1, self.rx_bytes()));
+        }
+        Ok(v)
+    }
+
+    pub async fn session_record_reading_all(
+        &mut self,
+        reading_index: usize,
+        num_samples: usize,
+    ) -> Result<Vec<RawSessionRecordReadings>> {
+        self.session_record_reading_all_cb(reading_index, num_samples, |_, _| {})
+            .await
+    }
+
+    /// Stream of the samples of a recording session, requested one after
+    /// another.
+    ///
+    /// Unlike [`Device::session_record_reading_all`] nothing is collected, so
+    /// exporters can write samples while the download continues. The stream
+    /// ends after the first error.
+    pub fn session_record_readings_stream(
+        &mut self,
+        reading_index: usize,
+        num_samples: usize,
+    ) -> impl futures::Stream<Item = Result<RawSessionRecordReadings>> + '_ {
+        futures::stream::unfold(Some((self, 0)), move |state| async move {
+            let (device, sample_idx) = state?;
+            if sample_idx >= num_samples {
+                return None;
+            }
+            match device
+                .session_record_reading(reading_index, sample_idx)
+                .await
+            {
+                Ok(m) => Some((Ok(m), Some((device, sample_idx + 1)))),
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+}
+
+fn check_string(what: &str, value: &str, max_len: usize) -> Result<()> {
+    validate_string(what, value, max_len).map_err(ProtoError::InvalidArgument)
+}
+
+/// Turn a syntax or execution error response into an error carrying `command`.
+fn reject(
+    response: Option<std::io::Result<Response>>,
+    command: Command,
+) -> Result<Option<std::io::Result<Response>>> {
+    match response {
+        Some(Ok(response @ (Response::SyntaxError | Response::ExecutionError))) => {
+            Err(ProtoError::from(response).with_command(command))
+        }
+        response => Ok(response),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::measurement::{Measurement, Reading};
+    use crate::rawmea::MEA_METADATA_LEN;
+    use futures::StreamExt;
+    use std::sync::Arc;
+
+    use super::*;
+
+    const GETEMAP: [u8; 1452] = [
+        0x30, 0x0d, 0x34, 0x39, 0x2c, 0x30, 0x2c, 0x4c, 0x49, 0x4d, 0x42, 0x4f, 0x2c, 0x31, 0x2c,
+        0x56, // 0.49,0,LIMBO,1,V
+        0x5f, 0x41, 0x43, 0x2c, 0x32, 0x2c, 0x4d, 0x56, 0x5f, 0x41, 0x43, 0x2c, 0x33, 0x2c, 0x56,
+        0x5f, // _AC,2,MV_AC,3,V_
+        0x44, 0x43, 0x2c, 0x34, 0x2c, 0x4d, 0x56, 0x5f, 0x44, 0x43, 0x2c, 0x35, 0x2c, 0x56, 0x5f,
+        0x41, //0xDC,,4,MV_DC,5,V_A
+        0x43, 0x5f, 0x4f, 0x56, 0x45, 0x52, 0x5f, 0x44, 0x43, 0x2c, 0x36, 0x2c, 0x56, 0x5f, 0x44,
+        0x43, // C_OVER_DC,6,V_DC
+        0x5f, 0x4f, 0x56, 0x45, 0x52, 0x5f, 0x41, 0x43, 0x2c, 0x37, 0x2c, 0x56, 0x5f, 0x41, 0x43,
+        0x5f, // _OVER_AC,7,V_AC_
+        0x50, 0x4c, 0x55, 0x53, 0x5f, 0x44, 0x43, 0x2c, 0x38, 0x2c, 0x4d, 0x56, 0x5f, 0x41, 0x43,
+        0x5f, // PLUS_DC,8,MV_AC_
+        0x4f, 0x56, 0x45, 0x52, 0x5f, 0x44, 0x43, 0x2c, 0x39, 0x2c, 0x4d, 0x56, 0x5f, 0x44, 0x43,
+        0x5f, // OVER_DC,9,MV_DC_
+        0x4f, 0x56, 0x45, 0x52, 0x5f, 0x41, 0x43, 0x2c, 0x31, 0x30, 0x2c, 0x4d, 0x56, 0x5f, 0x41,
+        0x43, // OVER_AC,10,MV_AC
+        0x5f, 0x50, 0x4c, 0x55, 0x53, 0x5f, 0x44, 0x43, 0x2c, 0x31, 0x31, 0x2c, 0x41, 0x5f, 0x41,
+        0x43, // _PLUS_DC,11,A_AC
+        0x2c, 0x31, 0x32, 0x2c, 0x4d, 0x41, 0x5f, 0x41, 0x43, 0x2c, 0x31, 0x33, 0x2c, 0x55, 0x41,
+        0x5f, // ,12,MA_AC,13,UA_
+        0x41, 0x43, 0x2c, 0x31, 0x34, 0x2c, 0x41, 0x5f, 0x44, 0x43, 0x2c, 0x31, 0x35, 0x2c, 0x4d,
+        0x41, //0xAC,,14,A_DC,15,MA
+        0x5f, 0x44, 0x43, 0x2c, 0x31, 0x36, 0x2c, 0x55, 0x41, 0x5f, 0x44, 0x43, 0x2c, 0x31, 0x37,
+        0x2c, // _DC,16,UA_DC,17,
+        0x41, 0x5f, 0x41, 0x43, 0x5f, 0x4f, 0x56, 0x45, 0x52, 0x5f, 0x44, 0x43, 0x2c, 0x31, 0x38,
+        0x2c, // A_AC_OVER_DC,18,
+        0x41, 0x5f, 0x44, 0x43, 0x5f, 0x4f, 0x56, 0x45, 0x52, 0x5f, 0x41, 0x43, 0x2c, 0x31, 0x39,
+        0x2c, // A_DC_OVER_AC,19,
+        0x41, 0x5f, 0x41, 0x43, 0x5f, 0x50, 0x4c, 0x55, 0x53, 0x5f, 0x44, 0x43, 0x2c, 0x32, 0x30,
+        0x2c, // A_AC_PLUS_DC,20,
+        0x4d, 0x41, 0x5f, 0x41, 0x43, 0x5f, 0x4f, 0x56, 0x45, 0x52, 0x5f, 0x44, 0x43, 0x2c, 0x32,
+        0x31, // MA_AC_OVER_DC,21
+        0x2c, 0x4d, 0x41, 0x5f, 0x44, 0x43, 0x5f, 0x4f, 0x56, 0x45, 0x52, 0x5f, 0x41, 0x43, 0x2c,
+        0x32, // ,MA_DC_OVER_AC,2
+        0x32, 0x2c, 0x4d, 0x41, 0x5f, 0x41, 0x43, 0x5f, 0x50, 0x4c, 0x55, 0x53, 0x5f, 0x44, 0x43,
+        0x2c, // 2,MA_AC_PLUS_DC,
+        0x32, 0x33, 0x2c, 0x55, 0x41, 0x5f, 0x41, 0x43, 0x5f, 0x4f, 0x56, 0x45, 0x52, 0x5f, 0x44,
+        0x43, //0x23,,UA_AC_OVER_DC
+        0x2c, 0x32, 0x34, 0x2c, 0x55, 0x41, 0x5f, 0x44, 0x43, 0x5f, 0x4f, 0x56, 0x45, 0x52, 0x5f,
+        0x41, // ,24,UA_DC_OVER_A
+        0x43, 0x2c, 0x32, 0x35, 0x2c, 0x55, 0x41, 0x5f, 0x41, 0x43, 0x5f, 0x50, 0x4c, 0x55, 0x53,
+        0x5f, // C,25,UA_AC_PLUS_
+        0x44, 0x43, 0x2c, 0x32, 0x36, 0x2c, 0x54, 0x45, 0x4d, 0x50, 0x45, 0x52, 0x41, 0x54, 0x55,
+        0x52, //0xDC,,26,TEMPERATUR
+        0x45, 0x2c, 0x32, 0x37, 0x2c, 0x4f, 0x48, 0x4d, 0x53, 0x2c, 0x32, 0x38, 0x2c, 0x43, 0x4f,
+        0x4e, // E,27,OHMS,28,CON
+        0x44, 0x55, 0x43, 0x54, 0x41, 0x4e, 0x43, 0x45, 0x2c, 0x32, 0x39, 0x2c, 0x43, 0x4f, 0x4e,
+        0x54, // DUCTANCE,29,CONT
+        0x49, 0x4e, 0x55, 0x49, 0x54, 0x59, 0x2c, 0x33, 0x30, 0x2c, 0x43, 0x41, 0x50, 0x41, 0x43,
+        0x49, // INUITY,30,CAPACI
+        0x54, 0x41, 0x4e, 0x43, 0x45, 0x2c, 0x33, 0x31, 0x2c, 0x44, 0x49, 0x4f, 0x44, 0x45, 0x5f,
+        0x54, // TANCE,31,DIODE_T
+        0x45, 0x53, 0x54, 0x2c, 0x33, 0x32, 0x2c, 0x56, 0x5f, 0x41, 0x43, 0x5f, 0x4c, 0x4f, 0x5a,
+        0x2c, // EST,32,V_AC_LOZ,
+        0x33, 0x33, 0x2c, 0x4f, 0x48, 0x4d, 0x53, 0x5f, 0x4c, 0x4f, 0x57, 0x2c, 0x33, 0x34, 0x2c,
+        0x43, //0x33,,OHMS_LOW,34,C
+        0x41, 0x4c, 0x5f, 0x56, 0x5f, 0x44, 0x43, 0x5f, 0x4c, 0x4f, 0x5a, 0x2c, 0x33, 0x35, 0x2c,
+        0x43, // AL_V_DC_LOZ,35,C
+        0x41, 0x4c, 0x5f, 0x41, 0x44, 0x5f, 0x47, 0x41, 0x49, 0x4e, 0x5f, 0x58, 0x32, 0x2c, 0x33,
+        0x36, // AL_AD_GAIN_X2,36
+        0x2c, 0x43, 0x41, 0x4c, 0x5f, 0x41, 0x44, 0x5f, 0x47, 0x41, 0x49, 0x4e, 0x5f, 0x58, 0x31,
+        0x2c, // ,CAL_AD_GAIN_X1,
+        0x33, 0x37, 0x2c, 0x43, 0x41, 0x4c, 0x5f, 0x52, 0x4d, 0x53, 0x2c, 0x33, 0x38, 0x2c, 0x43,
+        0x41, //0x37,,CAL_RMS,38,CA
+        0x4c, 0x5f, 0x46, 0x49, 0x4c, 0x54, 0x5f, 0x41, 0x4d, 0x50, 0x2c, 0x33, 0x39, 0x2c, 0x43,
+        0x41, // L_FILT_AMP,39,CA
+        0x4c, 0x5f, 0x44, 0x43, 0x5f, 0x41, 0x4d, 0x50, 0x5f, 0x58, 0x35, 0x2c, 0x34, 0x30, 0x2c,
+        0x43, // L_DC_AMP_X5,40,C
+        0x41, 0x4c, 0x5f, 0x44, 0x43, 0x5f, 0x41, 0x4d, 0x50, 0x5f, 0x58, 0x31, 0x30, 0x2c, 0x34,
+        0x31, // AL_DC_AMP_X10,41
+        0x2c, 0x43, 0x41, 0x4c, 0x5f, 0x4e, 0x49, 0x4e, 0x56, 0x5f, 0x41, 0x43, 0x5f, 0x41, 0x4d,
+        0x50, // ,CAL_NINV_AC_AMP
+        0x2c, 0x34, 0x32, 0x2c, 0x43, 0x41, 0x4c, 0x5f, 0x49, 0x53, 0x52, 0x43, 0x5f, 0x35, 0x30,
+        0x30, // ,42,CAL_ISRC_500
+        0x4e, 0x41, 0x2c, 0x34, 0x33, 0x2c, 0x43, 0x41, 0x4c, 0x5f, 0x43, 0x4f, 0x4d, 0x50, 0x5f,
+        0x54, // NA,43,CAL_COMP_T
+        0x52, 0x49, 0x4d, 0x5f, 0x4d, 0x56, 0x5f, 0x44, 0x43, 0x2c, 0x34, 0x34, 0x2c, 0x43, 0x41,
+        0x4c, // RIM_MV_DC,44,CAL
+        0x5f, 0x41, 0x43, 0x44, 0x43, 0x5f, 0x41, 0x43, 0x5f, 0x43, 0x4f, 0x4d, 0x50, 0x2c, 0x34,
+        0x35, // _ACDC_AC_COMP,45
+        0x2c, 0x43, 0x41, 0x4c, 0x5f, 0x56, 0x5f, 0x41, 0x43, 0x5f, 0x4c, 0x4f, 0x5a, 0x2c, 0x34,
+        0x36, // ,CAL_V_AC_LOZ,46
+        0x2c, 0x43, 0x41, 0x4c, 0x5f, 0x56, 0x5f, 0x41, 0x43, 0x5f, 0x50, 0x45, 0x41, 0x4b, 0x2c,
+        0x34, // ,CAL_V_AC_PEAK,4
+        0x37, 0x2c, 0x43, 0x41, 0x4c, 0x5f, 0x4d, 0x56, 0x5f, 0x41, 0x43, 0x5f, 0x50, 0x45, 0x41,
+        0x4b, // 7,CAL_MV_AC_PEAK
+        0x2c, 0x34, 0x38, 0x2c, 0x43, 0x41, 0x4c, 0x5f, 0x54, 0x45, 0x4d, 0x50, 0x45, 0x52, 0x41,
+        0x54, // ,48,CAL_TEMPERAT
+        0x55, 0x52, 0x45, 0x0d, //             URE.
+        //
+        0x30, 0x0d, 0x31, 0x30, 0x2c, 0x30, 0x2c, 0x4e, 0x4f, 0x4e, 0x45, 0x2c, 0x31, 0x2c, 0x48,
+        0x45, // 0.10,0,NONE,1,HE
+        0x52, 0x54, 0x5a, 0x2c, 0x32, 0x2c, 0x44, 0x55, 0x54, 0x59, 0x5f, 0x43, 0x59, 0x43, 0x4c,
+        0x45, // RTZ,2,DUTY_CYCLE
+        0x2c, 0x33, 0x2c, 0x50, 0x55, 0x4c, 0x53, 0x45, 0x5f, 0x57, 0x49, 0x44, 0x54, 0x48, 0x2c,
+        0x34, // ,3,PULSE_WIDTH,4
+        0x2c, 0x44, 0x42, 0x4d, 0x2c, 0x35, 0x2c, 0x44, 0x42, 0x56, 0x2c, 0x36, 0x2c, 0x44, 0x42,
+        0x4d, // ,DBM,5,DBV,6,DBM
+        0x5f, 0x48, 0x45, 0x52, 0x54, 0x5a, 0x2c, 0x37, 0x2c, 0x44, 0x42, 0x56, 0x5f, 0x48, 0x45,
+        0x52, // _HERTZ,7,DBV_HER
+        0x54, 0x5a, 0x2c, 0x38, 0x2c, 0x43, 0x52, 0x45, 0x53, 0x54, 0x5f, 0x46, 0x41, 0x43, 0x54,
+        0x4f, // TZ,8,CREST_FACTO
+        0x52, 0x2c, 0x39, 0x2c, 0x50, 0x45, 0x41, 0x4b, 0x5f, 0x4d, 0x49, 0x4e, 0x5f, 0x4d, 0x41,
+        0x58, // R,9,PEAK_MIN_MAX
+        0x0d, //                .
+        0x30, 0x0d, 0x32, 0x2c, 0x31, 0x2c, 0x41, 0x55, 0x54, 0x4f, 0x2c, 0x30, 0x2c, 0x4d, 0x41,
+        0x4e, // 0.2,1,AUTO,0,MAN
+        0x55, 0x41, 0x4c, 0x0d, //             UAL.
+        0x30, 0x0d, 0x32, 0x31, 0x2c, 0x30, 0x2c, 0x4e, 0x4f, 0x4e, 0x45, 0x2c, 0x31, 0x2c, 0x56,
+        0x44, // 0.21,0,NONE,1,VD
+        0x43, 0x2c, 0x32, 0x2c, 0x56, 0x41, 0x43, 0x2c, 0x33, 0x2c, 0x56, 0x41, 0x43, 0x5f, 0x50,
+        0x4c, // C,2,VAC,3,VAC_PL
+        0x55, 0x53, 0x5f, 0x44, 0x43, 0x2c, 0x34, 0x2c, 0x56, 0x2c, 0x35, 0x2c, 0x41, 0x44, 0x43,
+        0x2c, // US_DC,4,V,5,ADC,
+        0x36, 0x2c, 0x41, 0x41, 0x43, 0x2c, 0x37, 0x2c, 0x41, 0x41, 0x43, 0x5f, 0x50, 0x4c, 0x55,
+        0x53, // 6,AAC,7,AAC_PLUS
+        0x5f, 0x44, 0x43, 0x2c, 0x38, 0x2c, 0x41, 0x2c, 0x39, 0x2c, 0x4f, 0x48, 0x4d, 0x2c, 0x31,
+        0x30, // _DC,8,A,9,OHM,10
+        0x2c, 0x53, 0x49, 0x45, 0x2c, 0x31, 0x31, 0x2c, 0x48, 0x7a, 0x2c, 0x31, 0x32, 0x2c, 0x53,
+        0x2c, // ,SIE,11,Hz,12,S,
+        0x31, 0x33, 0x2c, 0x46, 0x2c, 0x31, 0x34, 0x2c, 0x43, 0x45, 0x4c, 0x2c, 0x31, 0x35, 0x2c,
+        0x46, //0x13,,F,14,CEL,15,F
+        0x41, 0x52, 0x2c, 0x31, 0x36, 0x2c, 0x50, 0x43, 0x54, 0x2c, 0x31, 0x37, 0x2c, 0x64, 0x42,
+        0x2c, // AR,16,PCT,17,dB,
+        0x31, 0x38, 0x2c, 0x64, 0x42, 0x56, 0x2c, 0x31, 0x39, 0x2c, 0x64, 0x42, 0x6d, 0x2c, 0x32,
+        0x30, //0x18,,dBV,19,dBm,20
+        0x2c, 0x43, 0x52, 0x45, 0x53, 0x54, 0x5f, 0x46, 0x41, 0x43, 0x54, 0x4f, 0x52,
+        0x0d, //   ,CREST_FACTOR.
+        0x30, 0x0d, 0x32, 0x2c, 0x30, 0x2c, 0x4f, 0x46, 0x46, 0x2c, 0x31, 0x2c, 0x4f, 0x4e,
+        0x0d, //  0.2,0,OFF,1,ON.
+        0x30, 0x0d, 0x31, 0x30, 0x2c, 0x30, 0x2c, 0x4e, 0x4f, 0x4e, 0x45, 0x2c, 0x31, 0x2c, 0x41,
+        0x55, // 0.10,0,NONE,1,AU
+        0x54, 0x4f, 0x5f, 0x48, 0x4f, 0x4c, 0x44, 0x2c, 0x32, 0x2c, 0x41, 0x55, 0x54, 0x4f, 0x5f,
+        0x53, // TO_HOLD,2,AUTO_S
+        0x41, 0x56, 0x45, 0x2c, 0x34, 0x2c, 0x48, 0x4f, 0x4c, 0x44, 0x2c, 0x38, 0x2c, 0x4c, 0x4f,
+        0x57, // AVE,4,HOLD,8,LOW
+        0x5f, 0x50, 0x41, 0x53, 0x53, 0x5f, 0x46, 0x49, 0x4c, 0x54, 0x45, 0x52, 0x2c, 0x31, 0x36,
+        0x2c, // _PASS_FILTER,16,
+        0x4d, 0x49, 0x4e, 0x5f, 0x4d, 0x41, 0x58, 0x5f, 0x41, 0x56, 0x47, 0x2c, 0x33, 0x32, 0x2c,
+        0x52, // MIN_MAX_AVG,32,R
+        0x45, 0x43, 0x4f, 0x52, 0x44, 0x2c, 0x36, 0x34, 0x2c, 0x52, 0x45, 0x4c, 0x2c, 0x31, 0x32,
+        0x38, //0xEC,ORD,64,REL,128
+        0x2c, 0x52, 0x45, 0x4c, 0x5f, 0x50, 0x45, 0x52, 0x43, 0x45, 0x4e, 0x54, 0x2c, 0x32, 0x35,
+        0x36, // ,REL_PERCENT,256
+        0x2c, 0x43, 0x41, 0x4c, 0x49, 0x42, 0x52, 0x41, 0x54, 0x49, 0x4f, 0x4e,
+        0x0d, //    ,CALIBRATION.
+        0x30, 0x0d, 0x38, 0x2c, 0x30, 0x2c, 0x49, 0x4e, 0x41, 0x43, 0x54, 0x49, 0x56, 0x45, 0x2c,
+        0x31, // 0.8,0,INACTIVE,1
+        0x2c, 0x49, 0x4e, 0x56, 0x41, 0x4c, 0x49, 0x44, 0x2c, 0x32, 0x2c, 0x4e, 0x4f, 0x52, 0x4d,
+        0x41, // ,INVALID,2,NORMA
+        0x4c, 0x2c, 0x33, 0x2c, 0x42, 0x4c, 0x41, 0x4e, 0x4b, 0x2c, 0x34, 0x2c, 0x44, 0x49, 0x53,
+        0x43, // L,3,BLANK,4,DISC
+        0x48, 0x41, 0x52, 0x47, 0x45, 0x2c, 0x35, 0x2c, 0x4f, 0x4c, 0x2c, 0x36, 0x2c, 0x4f, 0x4c,
+        0x5f, // HARGE,5,OL,6,OL_
+        0x4d, 0x49, 0x4e, 0x55, 0x53, 0x2c, 0x37, 0x2c, 0x4f, 0x50, 0x45, 0x4e, 0x5f, 0x54, 0x43,
+        0x0d, // MINUS,7,OPEN_TC.
+        0x30, 0x0d, 0x39, 0x2c, 0x30, 0x2c, 0x4e, 0x4f, 0x4e, 0x45, 0x2c, 0x31, 0x2c, 0x4f, 0x50,
+        0x45, // 0.9,0,NONE,1,OPE
+        0x4e, 0x5f, 0x43, 0x49, 0x52, 0x43, 0x55, 0x49, 0x54, 0x2c, 0x32, 0x2c, 0x53, 0x48, 0x4f,
+        0x52, // N_CIRCUIT,2,SHOR
+        0x54, 0x5f, 0x43, 0x49, 0x52, 0x43, 0x55, 0x49, 0x54, 0x2c, 0x33, 0x2c, 0x47, 0x4c, 0x49,
+        0x54, // T_CIRCUIT,3,GLIT
+        0x43, 0x48, 0x5f, 0x43, 0x49, 0x52, 0x43, 0x55, 0x49, 0x54, 0x2c, 0x34, 0x2c, 0x47, 0x4f,
+        0x4f, // CH_CIRCUIT,4,GOO
+        0x44, 0x5f, 0x44, 0x49, 0x4f, 0x44, 0x45, 0x2c, 0x35, 0x2c, 0x4c, 0x4f, 0x5f, 0x4f, 0x48,
+        0x4d, // D_DIODE,5,LO_OHM
+        0x53, 0x2c, 0x36, 0x2c, 0x4e, 0x45, 0x47, 0x41, 0x54, 0x49, 0x56, 0x45, 0x5f, 0x45, 0x44,
+        0x47, // S,6,NEGATIVE_EDG
+        0x45, 0x2c, 0x37, 0x2c, 0x50, 0x4f, 0x53, 0x49, 0x54, 0x49, 0x56, 0x45, 0x5f, 0x45, 0x44,
+        0x47, // E,7,POSITIVE_EDG
+        0x45, 0x2c, 0x38, 0x2c, 0x48, 0x49, 0x47, 0x48, 0x5f, 0x43, 0x55, 0x52, 0x52, 0x45, 0x4e,
+        0x54, // E,8,HIGH_CURRENT
+        0x0d, //                .
+        0x30, 0x0d, 0x32, 0x2c, 0x30, 0x2c, 0x49, 0x4e, 0x50, 0x55, 0x54, 0x2c, 0x31, 0x2c, 0x49,
+        0x4e, // 0.2,0,INPUT,1,IN
+        0x54, 0x45, 0x52, 0x56, 0x41, 0x4c, 0x0d, //          TERVAL.
+        0x30, 0x0d, 0x32, 0x2c, 0x30, 0x2c, 0x55, 0x4e, 0x53, 0x54, 0x41, 0x42, 0x4c, 0x45, 0x2c,
+        0x31, // 0.2,0,UNSTABLE,1
+        0x2c, 0x53, 0x54, 0x41, 0x42, 0x4c, 0x45, 0x0d, //         ,STABLE.
+        0x30, 0x0d, 0x35, 0x2c, 0x30, 0x2c, 0x4e, 0x4f, 0x4e, 0x5f, 0x54, 0x2c, 0x31, 0x2c, 0x52,
+        0x41, // 0.5,0,NON_T,1,RA
+        0x4e, 0x47, 0x45, 0x5f, 0x55, 0x50, 0x2c, 0x32, 0x2c, 0x52, 0x41, 0x4e, 0x47, 0x45, 0x5f,
+        0x44, // NGE_UP,2,RANGE_D
+        0x4f, 0x57, 0x4e, 0x2c, 0x33, 0x2c, 0x4f, 0x56, 0x45, 0x52, 0x4c, 0x4f, 0x41, 0x44, 0x2c,
+        0x34, // OWN,3,OVERLOAD,4
+        0x2c, 0x4f, 0x50, 0x45, 0x4e, 0x5f, 0x54, 0x43, 0x0d, //        ,OPEN_TC.
+    ];
+
+    #[tokio::test]
+    async fn test_get_id() {
+        let mut device = Device::new_faked(vec![
+            '0', '\r', 'F', 'l', 'u', 'k', 'e', ',', 'x', ',', 'x', '\r',
+        ]);
+        assert!(device.ident().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_resync_on_garbage() {
+        let mut device = Device::new_faked(vec![
+            '\u{ff}', 'x', '\r', '0', '\r', 'F', 'l', 'u', 'k', 'e', ',', 'x', ',', 'x', '\r',
+        ]);
+        assert!(device.ident().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_framing_error() {
+        let mut device = Device::new_faked(vec!['x', ',', '0', '\r']);
+        assert!(matches!(
+            device.ident().await,
+            Err(ProtoError::FramingError { skipped: 4 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_readings_count_limit() {
+        let mut response = vec!['0', '\r', '#', '0'];
+        response.extend(std::iter::repeat('\0').take(MEA_METADATA_LEN - 2));
+        response.extend(['\u{ff}', '\u{ff}']);
+        let mut device = Device::new_faked(response);
+        assert!(matches!(
+            device.live_measurement().await,
+            Err(ProtoError::Io(err)) if err.kind() == std::io::ErrorKind::InvalidData
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_live_measurements_stream() {
+        let mut device = Device::new_faked(vec![
+            '5', '\r', '5', '\r', '0', '\r', 'F', 'l', 'u', 'k', 'e', ',', 'x', ',', 'x', '\r',
+        ]);
+        {
+            let measurements = device.live_measurements(Duration::from_millis(1));
+            let items: Vec<_> = measurements.take(2).collect().await;
+            assert!(items.iter().all(|item| matches!(item, Ok(None))));
+        }
+        assert!(device.ident().await.is_ok());
+    }
+
+    /// Sent and received frames.
+    struct Log(Arc<std::sync::Mutex<Vec<Vec<u8>>>>);
+    impl ProtocolObserver for Log {
+        fn on_tx(&mut self, _ts: DateTime<Utc>, _command: &Command, bytes: &[u8]) {
+            self.0.lock().expect("log").push(bytes.to_vec());
+        }
+        fn on_rx(&mut self, _ts: DateTime<Utc>, frame: &[u8]) {
+            self.0.lock().expect("log").push(frame.to_vec());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observer() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut device = Device::new_faked(vec![
+            '0', '\r', 'F', 'l', 'u', 'k', 'e', ',', 'x', ',', 'x', '\r',
+        ]);
+        device.set_observer(Log(log.clone()));
+        assert!(device.ident().await.is_ok());
+        assert_eq!(
+            *log.lock().expect("log"),
+            vec![b"id\r".to_vec(), b"0\rFluke,x,x\r".to_vec()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_record_readings_stream() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut device = Device::new_faked(vec![
+            '1', '\r', '0', '\r', 'F', 'l', 'u', 'k', 'e', ',', 'x', ',', 'x', '\r',
+        ]);
+        device.set_observer(Log(log.clone()));
+        {
+            let samples = device.session_record_readings_stream(3, 0);
+            assert_eq!(samples.count().await, 0);
+        }
+        {
+            let samples = device.session_record_readings_stream(3, 10);
+            let items: Vec<_> = samples.collect().await;
+            assert_eq!(items.len(), 1);
+            assert!(items[0].is_err());
+        }
+        assert!(device.ident().await.is_ok());
+        assert_eq!(log.lock().expect("log")[0], b"qsrr 3,0\r".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_string_setters() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut device = Device::new_faked(vec!['0', '\r', '0', '\r']);
+        device.set_observer(Log(log.clone()));
+        assert!(matches!(
+            device.set_operator("J. Doe\rrst").await,
+            Err(ProtoError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            device.set_save_name(0, "A name of 17 char").await,
+            Err(ProtoError::InvalidSaveName(
+                crate::proto::command::SaveNameError::TooLong
+            ))
+        ));
+        assert!(matches!(
+            device.set_save_name(0, "LOG;1").await,
+            Err(ProtoError::InvalidSaveName(
+                crate::proto::command::SaveNameError::UnsupportedChar(';')
+            ))
+        ));
+        device.set_contact("O'Neil").await.expect("contact");
+        device.set_site("Lab").await.expect("site");
+        let log = log.lock().expect("log");
+        assert_eq!(log[0], b"mpq contact,'O''Neil'\r".to_vec());
+        assert_eq!(log[2], b"mpq site,'Lab'\r".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_identify() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut device =
+            Device::new_faked(format!("0\rOFF\r{}", "0\r".repeat(5)).chars().collect());
+        device.set_observer(Log(log.clone()));
+        device.identify(1).await.expect("identify");
+        let sent: Vec<Vec<u8>> = log
+            .lock()
+            .expect("log")
+            .iter()
+            // Responses start with the status digit
+            .filter(|bytes| bytes[0].is_ascii_lowercase())
+            .cloned()
+            .collect();
+        assert_eq!(
+            sent,
+            [
+                &b"qmp beeper\r"[..],
+                b"mp beeper,ON\r",
+                b"press BACKLIGHT\r",
+                b"press BACKLIGHT\r",
+                b"press BACKLIGHT\r",
+                b"mp beeper,OFF\r",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_all_memory_queries_statistics_once() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut device = Device::new_faked("0\r0,0,0,0\r".chars().collect());
+        device.set_observer(Log(log.clone()));
+        let memory = device.all_memory(&ValueMaps::new()).await.expect("memory");
+        assert!(memory.is_empty());
+        assert_eq!(log.lock().expect("log")[0], b"qsls\r");
+        assert_eq!(log.lock().expect("log").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_commands() {
+        let mut device = Device::new_faked(vec![
+            '0', '\r', 'F', 'l', 'u', 'k', 'e', ',', 'x', ',', 'x', '\r', '1', '\r', '0', '\r',
+            'O', 'F', 'F', '\r',
+        ]);
+        device.client.send(Command::Id).await.expect("send");
+        device
+            .client
+            .send(Command::GetBacklightTimeout)
+            .await
+            .expect("send");
+        device.client.send(Command::GetBeeper).await.expect("send");
+        assert!(matches!(
+            device.next_response().await,
+            Some(Ok(Response::Success(Some(ResponsePayload::Id(_)))))
+        ));
+        assert!(matches!(
+            device.next_response().await,
+            Some(Ok(Response::SyntaxError))
+        ));
+        assert!(matches!(
+            device.next_response().await,
+            Some(Ok(Response::Success(Some(ResponsePayload::Beeper(false)))))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_turnaround() {
+        let mut device = Device::new_faked("0\rFluke,x,x\r".repeat(2).chars().collect());
+        device.set_turnaround(Duration::from_millis(50));
+        device.ident().await.expect("ident");
+        let start = std::time::Instant::now();
+        device.ident().await.expect("ident");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy() {
+        let response = "2\r0\rFluke,x,x\r";
+        let mut device = Device::new_faked(response.chars().collect());
+        assert!(matches!(
+            device.ident().await,
+            Err(ProtoError::ExecutionError {
+                command: Some(Command::Id)
+            })
+        ));
+
+        let mut device = Device::new_faked(response.chars().collect());
+        device.set_retry_policy(crate::retry::Backoff {
+            initial_delay: Duration::from_millis(1),
+            ..Default::default()
+        });
+        assert!(device.ident().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_language_quirk() {
+        let mut device = Device::new_faked(vec!['0', '\r', 'E', 'N', 'L', 'I', 'S', 'H', '\r']);
+        assert!(matches!(device.language().await, Ok(Language::English)));
+    }
+
+    #[tokio::test]
+    async fn test_set_backlight() {
+        let mut device = Device::new_faked(vec!['0', '\r']);
+        assert!(device
+            .set_backlight(Duration::from_secs(60 * 15))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_set_backlight_in_settings_mode() {
+        let mut device = Device::new_faked(vec!['2', '\r']);
+        assert!(device
+            .set_backlight(Duration::from_secs(60 * 15))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_device_locked() {
+        let mut device = Device::new_faked("2\r0\rFluke,x,x\r".chars().collect());
+        assert!(matches!(
+            device.set_beeper(true).await,
+            Err(ProtoError::DeviceLocked {
+                command: Some(Command::SetBeeper(true))
+            })
+        ));
+
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut device = Device::new_faked("2\r0\rFluke,x,x\r0\r0\r".chars().collect());
+        device.set_observer(Log(log.clone()));
+        device.set_locked_recovery(LockedRecovery::close_screen());
+        device.set_beeper(true).await.expect("beeper");
+        let sent: Vec<Vec<u8>> = log
+            .lock()
+            .expect("log")
+            .iter()
+            .step_by(2)
+            .cloned()
+            .collect();
+        assert_eq!(
+            sent,
+            [
+                b"mp beeper,ON\r".to_vec(),
+                b"id\r".to_vec(),
+                b"press F4\r".to_vec(),
+                b"mp beeper,ON\r".to_vec()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ascii_backend() {
+        let (mut device, handle) = crate::mock::MockTransport::new()
+            .expect("qddb", "1\r")
+            .expect("qm", "0\r-1.2345E-1,VDC,NORMAL,NONE\r")
+            .expect("qm", "5\r")
+            .into_device();
+        assert_eq!(
+            device.detect_backend().await.expect("detect"),
+            Backend::Ascii
+        );
+        let reading = device
+            .primary_reading(&ValueMaps::new())
+            .await
+            .expect("qm")
+            .expect("data");
+        assert_eq!(reading.value, -0.12345);
+        assert_eq!(reading.decimals, 5);
+        assert!(matches!(reading.unit, crate::measurement::Unit::VoltDC));
+        assert!(device.ascii_reading().await.expect("qm").is_none());
+        handle.assert_done();
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_on_model() {
+        let mut device = Device::new_faked("0\rFluke 287,V1.16,1\r0\r0\r".chars().collect());
+        assert_eq!(device.model(), Model::Unknown);
+        device.ident().await.expect("ident");
+        assert_eq!(device.model(), Model::Fluke287);
+        assert!(matches!(
+            device.set_temp_offset(5).await,
+            Err(ProtoError::Unsupported {
+                model: Model::Fluke287,
+                capability: Capability::Temperature
+            })
+        ));
+        // Nothing was sent for the rejected command
+        assert!(device.set_beeper(true).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_digit_count() {
+        let mut device = Device::new_faked(vec!['0', '\r', '7', '\r']);
+        assert!(device.digit_count().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_frame_limits() {
+        let faked = |response: &str, limits| {
+            Device::with_limits(crate::mock::MockTransport::new().respond(response), limits)
+        };
+
+        let mut device = faked("0\rFluke 289,V1.16,12345678\r", FrameLimits::compact());
+        assert!(device.ident().await.is_ok());
+
+        // Unterminated frame is not buffered beyond the limit
+        let limits = FrameLimits {
+            max_frame_len: 16,
+            read_buffer: 8,
+        };
+        let mut device = faked("0\rFluke 289,V1.16,12345678", limits);
+        assert!(matches!(device.ident().await, Err(ProtoError::Io(_))));
+    }
+
+    #[tokio::test]
+    async fn test_ready_device() {
+        let mut device = Device::new_faked(
+            b"0\rFluke,x,x\r"
+                .iter()
+                .chain(GETEMAP.iter())
+                .chain(b"5\r".iter())
+                .map(|x| *x as char)
+                .collect(),
+        )
+        .ready()
+        .await
+        .expect("ready");
+        assert_eq!(device.maps().len(), VALUE_MAP_KEYS.len());
+        assert!(matches!(device.live_measurement().await, Ok(None)));
+
+        let ident = device.ident().clone();
+        let mut maps = device.maps().clone();
+        maps.remove("unit");
+        assert!(crate::ReadyDevice::new(device.into_inner(), ident, maps).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_builtin_map_fallback() {
+        let device = Device::new_faked(
+            b"0\rFluke 289,V1.16,1\r"
+                .iter()
+                .chain(b"1\r".repeat(VALUE_MAP_KEYS.len()).iter())
+                .map(|x| *x as char)
+                .collect(),
+        )
+        .ready()
+        .await
+        .expect("ready");
+        assert_eq!(device.builtin_maps().len(), VALUE_MAP_KEYS.len());
+        assert_eq!(device.maps()["unit"][&1], "VDC");
+
+        let mut device = Device::new_faked(
+            b"1\r"
+                .repeat(VALUE_MAP_KEYS.len())
+                .iter()
+                .map(|x| *x as char)
+                .collect(),
+        );
+        assert!(device
+            .value_maps_with_fallback(Some("V9.00"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_value_maps_for() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut device =
+            Device::new_faked("0\r1,0,AUTO\r0\r1,1,V\r0\r1,0,OFF\r1\r".chars().collect());
+        device.set_observer(Log(log.clone()));
+        let maps = device
+            .value_maps_for(&["autorange", "unit", "bolt"])
+            .await
+            .expect("maps");
+        assert_eq!(maps.len(), 3);
+        assert_eq!(maps["unit"][&1], "V");
+        // The queries after the first are sent before their responses arrive
+        assert_eq!(
+            log.lock().expect("log")[2..4],
+            [b"qemap unit\r".to_vec(), b"qemap bolt\r".to_vec()]
+        );
+        assert!(device.value_maps_for(&["mode"]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn qddb_parse() {
+        let fake: Vec<u8> = vec![
+            0x30, 0x0d, 0x23, 0x30, 0x1b, 0x00, 0x00, 0x00, 0x01, 0x00, 0x09, 0x00, 0x00, 0x40,
+            0x7f, 0x40, // l1
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, // l2
+            0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0xc2, 0xf5, 0x11, 0x40, 0xf6, 0x28,
+            0x5c, 0x8f, // l3
+            0x09, 0x00, 0x00, 0x00, 0x02, 0x00, 0x05, 0x00, 0x02, 0x00, 0x00, 0x00, 0xbf, 0xf3,
+            0xd8, 0x41, // l4
+            0x00, 0x40, 0x9d, 0xeb, 0x02, 0x00, 0xc2, 0xf5, 0x11, 0x40, 0xf6, 0x28, 0x5c, 0x8f,
+            0x09, 0x00, // l5
+            0x00, 0x00, 0x02, 0x00, 0x05, 0x00, 0x02, 0x00, 0x00, 0x00, 0xbf, 0xf3, 0xd8, 0x41,
+            0x00, 0x40, // l6
+            0x9d, 0xeb, 0x0d, // l7
+        ];
+
+        let mut device = Device::new_faked(
+            GETEMAP
+                .iter()
+                .chain(fake.iter())
+                .map(|x| *x as char)
+                .collect(),
+        );
+
+        let maps = device.value_maps().await.expect("Value Maps");
+
+        let raw_mea = device
+            .live_measurement()
+            .await
+            .expect("Raw measurement")
+            .expect("No data returned");
+        println!("Raw measurement: {:?}", raw_mea);
+        assert_eq!(raw_mea.pri_function, 27);
+        assert_eq!(raw_mea.sec_function, 0);
+        assert_eq!(raw_mea.auto_range, 1);
+        assert_eq!(raw_mea.unit, 9);
+        assert_eq!(raw_mea.unit_multiplier, 0);
+        assert_eq!(raw_mea.bolt, 0);
+
+        assert_eq!(raw_mea.bolt, 0);
+        assert_eq!(raw_mea.modes, 0);
+        assert_eq!(raw_mea.readings.len(), 2);
+
+        println!(
+            "{:?}",
+            Measurement::try_from((raw_mea.clone(), &maps)).expect("measurement")
+        );
+
+        for rr in &raw_mea.readings {
+            let r: Reading = (rr.clone(), &maps).try_into().expect("reading");
+            println!("{}", r);
+        }
+
+        // TODO: check readings
+    }
+}
@@ -0,0 +1,169 @@
+//! Downsampling of long recordings.
+//!
+//! A recording over days holds tens of thousands of intervals. For charts
+//! and exports they can be reduced to buckets with min/max/mean
+//! ([`by_duration`], [`by_count`]) or to a subset of intervals keeping the
+//! visual shape ([`lttb`]).
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    measurement::SessionRecordReadings,
+    stats::{self, Stats},
+};
+
+/// Consecutive intervals of a recording merged into one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    /// Start of the first interval
+    pub start_ts: DateTime<Utc>,
+    /// End of the last interval
+    pub end_ts: DateTime<Utc>,
+    /// Statistics over the intervals, see [`stats::recording_stats`]
+    pub stats: Stats,
+}
+
+impl Bucket {
+    fn new(records: &[SessionRecordReadings]) -> Self {
+        Self {
+            start_ts: records[0].start_ts,
+            end_ts: records[records.len() - 1].end_ts,
+            stats: stats::recording_stats(records),
+        }
+    }
+}
+
+/// Buckets of `width`, aligned to the start of the recording. Empty
+/// buckets are left out.
+pub fn by_duration(records: &[SessionRecordReadings], width: Duration) -> Vec<Bucket> {
+    let origin = match records.first() {
+        Some(rec) => rec.start_ts,
+        None => return Vec::new(),
+    };
+    let width = width.num_milliseconds().max(1);
+    let slot = |rec: &SessionRecordReadings| (rec.start_ts - origin).num_milliseconds() / width;
+
+    let mut buckets = Vec::new();
+    let mut first = 0;
+    for i in 1..=records.len() {
+        if i == records.len() || slot(&records[i]) != slot(&records[first]) {
+            buckets.push(Bucket::new(&records[first..i]));
+            first = i;
+        }
+    }
+    buckets
+}
+
+/// At most `count` buckets with the same number of intervals each.
+pub fn by_count(records: &[SessionRecordReadings], count: usize) -> Vec<Bucket> {
+    if records.is_empty() || count == 0 {
+        return Vec::new();
+    }
+    let size = (records.len() + count - 1) / count;
+    records.chunks(size).map(Bucket::new).collect()
+}
+
+/// Select `threshold` intervals with the Largest-Triangle-Three-Buckets
+/// algorithm on the interval averages, for plotting.
+///
+/// Intervals without an average value (e.g. overload) are left out. All
+/// remaining intervals are returned if there are not more than `threshold`.
+pub fn lttb(records: &[SessionRecordReadings], threshold: usize) -> Vec<&SessionRecordReadings> {
+    let points: Vec<(f64, f64, &SessionRecordReadings)> = records
+        .iter()
+        .filter_map(|rec| {
+            let y = rec.average_reading().si_value()?;
+            let x = rec.start_ts.timestamp_millis() as f64 / 1000.0;
+            Some((x, y, rec))
+        })
+        .collect();
+    let n = points.len();
+    if threshold >= n || threshold < 3 {
+        return points.into_iter().map(|(_, _, rec)| rec).collect();
+    }
+
+    let every = (n - 2) as f64 / (threshold - 2) as f64;
+    let mut selected = Vec::with_capacity(threshold);
+    let mut a = 0;
+    selected.push(points[0].2);
+
+    for i in 0..threshold - 2 {
+        // Average of the next bucket
+        let next_start = ((i + 1) as f64 * every) as usize + 1;
+        let next_end = (((i + 2) as f64 * every) as usize + 1).min(n);
+        let next = &points[next_start..next_end];
+        let avg_x = next.iter().map(|p| p.0).sum::<f64>() / next.len() as f64;
+        let avg_y = next.iter().map(|p| p.1).sum::<f64>() / next.len() as f64;
+
+        // Point of the current bucket with the largest triangle
+        let start = (i as f64 * every) as usize + 1;
+        let end = next_start;
+        let (ax, ay) = (points[a].0, points[a].1);
+        let mut max_area = -1.0;
+        for (j, p) in points.iter().enumerate().take(end).skip(start) {
+            let area = ((ax - avg_x) * (p.1 - ay) - (ax - p.0) * (avg_y - ay)).abs();
+            if area > max_area {
+                max_area = area;
+                a = j;
+            }
+        }
+        selected.push(points[a].2);
+    }
+
+    selected.push(points[n - 1].2);
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixture,
+        measurement::{RecordType, Stable, State, TransientState},
+    };
+
+    fn recording(values: &[f64]) -> Vec<SessionRecordReadings> {
+        let reading = |value: f64| fixture::reading(value, State::Normal);
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| SessionRecordReadings {
+                start_ts: DateTime::default() + Duration::seconds(i as i64),
+                end_ts: DateTime::default() + Duration::seconds(i as i64 + 1),
+                span_readings: [reading(*v), reading(*v), reading(*v)],
+                sampling: 1,
+                fixed_reading: reading(*v),
+                record_type: RecordType::Interval,
+                stable: Stable(true),
+                transient_state: TransientState::NonT,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_buckets() {
+        let records = recording(&[1.0, 3.0, 5.0, 7.0, 9.0]);
+
+        let buckets = by_duration(&records, Duration::seconds(2));
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets[0].stats.mean, Some(2.0));
+        assert_eq!(buckets[2].stats.count, 1);
+        assert_eq!(buckets[1].end_ts, records[3].end_ts);
+
+        let buckets = by_count(&records, 2);
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].stats.max, Some(5.0));
+        assert_eq!(buckets[1].stats.min, Some(7.0));
+    }
+
+    #[test]
+    fn test_lttb() {
+        let records = recording(&[0.0, 1.0, 0.0, 10.0, 0.0, 1.0, 0.0, 0.0]);
+        let selected = lttb(&records, 4);
+        assert_eq!(selected.len(), 4);
+        assert_eq!(selected[0].fixed_reading.value, 0.0);
+        // The spike survives downsampling
+        assert!(selected.iter().any(|r| r.fixed_reading.value == 10.0));
+        assert_eq!(lttb(&records, 100).len(), records.len());
+    }
+}
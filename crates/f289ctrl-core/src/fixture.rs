@@ -0,0 +1,85 @@
+//! Builders for decoded readings and measurements, available with the
+//! `test-util` feature.
+//!
+//! ```
+//! use f289ctrl_core::{fixture, measurement::State};
+//!
+//! let mea = fixture::measurement(&[1.5]);
+//! assert_eq!(mea.readings[0].si_value(), Some(1.5));
+//! assert_eq!(fixture::reading(0.0, State::OL).si_value(), None);
+//! ```
+
+use chrono::DateTime;
+
+use crate::{
+    device::ValueMaps,
+    measurement::{Measurement, Reading, State, Unit},
+    rawmea::{RawMeasurement, RawReading},
+};
+
+/// Reading in V DC with 3 decimals, without attribute and timestamp.
+pub fn reading(value: f64, state: State) -> Reading {
+    Reading {
+        reading_id: 0,
+        value,
+        unit: Unit::VoltDC,
+        unit_multiplier: 0,
+        decimals: 3,
+        display_digits: 5,
+        state,
+        attribute: None,
+        ts: DateTime::default(),
+        device_ts: 0.0,
+        utc_offset: 0,
+    }
+}
+
+/// V DC measurement with one normal reading per value, decoded from raw
+/// values like a response of the meter.
+pub fn measurement(values: &[f64]) -> Measurement {
+    let readings = values
+        .iter()
+        .map(|&value| RawReading {
+            reading_id: 0,
+            value,
+            unit: 0,
+            unit_multiplier: 0,
+            decimals: 3,
+            display_digits: 5,
+            state: 0,
+            attribute: 0,
+            ts: 0.0,
+        })
+        .collect();
+    let raw = RawMeasurement {
+        pri_function: 0,
+        sec_function: 0,
+        auto_range: 0,
+        unit: 0,
+        range_max: 0.0,
+        unit_multiplier: 0,
+        bolt: 0,
+        ts: 0.0,
+        modes: 0,
+        un1: 0,
+        readings,
+    };
+    Measurement::try_from((raw, &value_maps())).expect("measurement")
+}
+
+/// Maps with a single entry `0` each, as used by [`measurement`].
+pub fn value_maps() -> ValueMaps {
+    [
+        ("attribute", "NONE"),
+        ("autorange", "MANUAL"),
+        ("bolt", "OFF"),
+        ("mode", "NONE"),
+        ("primfunction", "V_DC"),
+        ("secfunction", "NONE"),
+        ("state", "NORMAL"),
+        ("unit", "VDC"),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), [(0, value.to_string())].into()))
+    .collect()
+}
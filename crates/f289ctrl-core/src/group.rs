@@ -0,0 +1,281 @@
+//! Synchronized acquisition from several meters.
+//!
+//! A [`DeviceGroup`] polls all its meters at once and combines their
+//! measurements into one [`GroupSample`], e.g. voltage and current from two
+//! meters for power measurements:
+//!
+//! ```no_run
+//! # async fn example() -> f289ctrl_core::Result<()> {
+//! use f289ctrl_core::{group::DeviceGroup, Device};
+//! use futures::StreamExt;
+//! use std::time::Duration;
+//!
+//! let mut group = DeviceGroup::new();
+//! group.add("volts", Device::new("/dev/ttyUSB0", 115200)?.ready().await?);
+//! group.add("amps", Device::new("/dev/ttyUSB1", 115200)?.ready().await?);
+//!
+//! let mut samples = Box::pin(group.samples(Duration::from_secs(1)));
+//! while let Some(sample) = samples.next().await {
+//!     if let (Some(u), Some(i)) = (sample.primary("volts"), sample.primary("amps")) {
+//!         println!("{} {} W", sample.ts, u * i);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::{future::join_all, Stream};
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::{measurement::Measurement, proto::Result, ready::ReadyDevice};
+
+struct Member {
+    name: String,
+    device: ReadyDevice,
+}
+
+/// Meters polled together, see the [module documentation](self).
+#[derive(Default)]
+pub struct DeviceGroup {
+    members: Vec<Member>,
+}
+
+/// Result of one meter in a [`GroupSample`].
+#[derive(Debug)]
+pub struct GroupReading {
+    pub name: String,
+    /// `None` if the meter has no data (e.g. in setup)
+    pub measurement: Result<Option<Measurement>>,
+    /// Time from sending the request until the response was decoded
+    pub latency: Duration,
+}
+
+/// Measurements of all meters of a group, requested at the same time.
+#[derive(Debug)]
+pub struct GroupSample {
+    /// Host time the requests were sent
+    pub ts: DateTime<Utc>,
+    /// Difference between the first and the last response, an upper bound
+    /// for the time between the measurements of two meters
+    pub skew: Duration,
+    /// In the order the meters were added
+    pub readings: Vec<GroupReading>,
+}
+
+impl GroupSample {
+    pub fn get(&self, name: &str) -> Option<&GroupReading> {
+        self.readings.iter().find(|reading| reading.name == name)
+    }
+
+    /// Primary reading of the meter `name` in its base SI unit.
+    ///
+    /// `None` if the meter failed, has no data or shows no numeric value.
+    pub fn primary(&self, name: &str) -> Option<f64> {
+        match &self.get(name)?.measurement {
+            Ok(Some(measurement)) => measurement.readings.first()?.si_value(),
+            _ => None,
+        }
+    }
+
+    /// True if every meter returned a measurement.
+    pub fn is_complete(&self) -> bool {
+        self.readings
+            .iter()
+            .all(|reading| matches!(reading.measurement, Ok(Some(_))))
+    }
+}
+
+impl DeviceGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a meter, a meter already added as `name` is replaced and returned.
+    pub fn add(&mut self, name: impl Into<String>, device: ReadyDevice) -> Option<ReadyDevice> {
+        let name = name.into();
+        match self.members.iter_mut().find(|member| member.name == name) {
+            Some(member) => Some(std::mem::replace(&mut member.device, device)),
+            None => {
+                self.members.push(Member { name, device });
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<ReadyDevice> {
+        let index = self.members.iter().position(|member| member.name == name)?;
+        Some(self.members.remove(index).device)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut ReadyDevice> {
+        self.members
+            .iter_mut()
+            .find(|member| member.name == name)
+            .map(|member| &mut member.device)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.members
+            .iter()
+            .map(|member| member.name.as_str())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn into_devices(self) -> Vec<(String, ReadyDevice)> {
+        self.members
+            .into_iter()
+            .map(|member| (member.name, member.device))
+            .collect()
+    }
+
+    /// Request a measurement from all meters at once.
+    ///
+    /// A failing meter does not fail the sample, its error is reported in
+    /// [`GroupReading::measurement`].
+    pub async fn sample(&mut self) -> GroupSample {
+        let ts = Utc::now();
+        let start = Instant::now();
+        let readings = join_all(self.members.iter_mut().map(|member| async move {
+            let measurement = member.device.live_measurement().await;
+            GroupReading {
+                name: member.name.clone(),
+                measurement,
+                latency: start.elapsed(),
+            }
+        }))
+        .await;
+
+        let first = readings.iter().map(|reading| reading.latency).min();
+        let last = readings.iter().map(|reading| reading.latency).max();
+        GroupSample {
+            ts,
+            skew: match (first, last) {
+                (Some(first), Some(last)) => last - first,
+                _ => Duration::ZERO,
+            },
+            readings,
+        }
+    }
+
+    /// Endless stream of samples, one every `interval`.
+    ///
+    /// A sample taking longer than `interval` delays the following ones
+    /// instead of sending requests in bursts.
+    pub fn samples(&mut self, interval: Duration) -> impl Stream<Item = GroupSample> + '_ {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        futures::stream::unfold((self, ticker), |(group, mut ticker)| async move {
+            ticker.tick().await;
+            let sample = group.sample().await;
+            Some((sample, (group, ticker)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock::{MockHandle, MockTransport},
+        proto::response::Ident,
+        rawmea::{RawMeasurement, RawReading},
+    };
+    use futures::StreamExt;
+
+    fn meter(pri_function: u16, unit: u16, value: f64) -> (ReadyDevice, MockHandle) {
+        let raw = RawMeasurement {
+            pri_function,
+            sec_function: 0,
+            auto_range: 1,
+            unit,
+            range_max: 50.0,
+            unit_multiplier: 0,
+            bolt: 0,
+            ts: 0.0,
+            modes: 0,
+            un1: 0,
+            readings: vec![RawReading {
+                reading_id: 2,
+                value,
+                unit,
+                unit_multiplier: 0,
+                decimals: 3,
+                display_digits: 5,
+                state: 2,
+                attribute: 0,
+                ts: 0.0,
+            }],
+        };
+        let mut response = b"0\r".to_vec();
+        response.extend_from_slice(&raw.to_frame());
+        let (device, handle) = MockTransport::new().always("qddb", response).into_device();
+        let ident = Ident {
+            model: "FLUKE 289".into(),
+            firmware: "V1.16".into(),
+            serial: "1".into(),
+        };
+        let device = ReadyDevice::with_builtin_maps(device, ident).expect("ready");
+        (device, handle)
+    }
+
+    #[tokio::test]
+    async fn test_group_sample() {
+        let maps = crate::maps::builtin_maps(Some("V1.16")).expect("maps");
+        let id = |map: &str, name: &str| {
+            maps[map]
+                .iter()
+                .find(|(_, value)| value.as_str() == name)
+                .map(|(id, _)| *id)
+                .expect(name)
+        };
+        let (volts, volts_handle) = meter(id("primfunction", "V_DC"), id("unit", "VDC"), 12.0);
+        let (amps, amps_handle) = meter(id("primfunction", "A_DC"), id("unit", "ADC"), 0.5);
+        let (failing, _) = MockTransport::new().into_device();
+        let failing = ReadyDevice::with_builtin_maps(
+            failing,
+            Ident {
+                model: "FLUKE 289".into(),
+                firmware: "V1.16".into(),
+                serial: "2".into(),
+            },
+        )
+        .expect("ready");
+
+        let mut group = DeviceGroup::new();
+        assert!(group.add("volts", volts).is_none());
+        assert!(group.add("amps", amps).is_none());
+        assert!(group.add("spare", failing).is_none());
+        assert_eq!(group.names(), vec!["volts", "amps", "spare"]);
+
+        let sample = group.sample().await;
+        assert_eq!(sample.primary("volts"), Some(12.0));
+        assert_eq!(sample.primary("amps"), Some(0.5));
+        assert!(sample.get("spare").expect("spare").measurement.is_err());
+        assert!(!sample.is_complete());
+
+        assert!(group.remove("spare").is_some());
+        let samples: Vec<_> = group
+            .samples(Duration::from_millis(1))
+            .take(2)
+            .collect()
+            .await;
+        for sample in samples {
+            assert!(sample.is_complete());
+            let power = sample.primary("volts").zip(sample.primary("amps"));
+            assert_eq!(power.map(|(u, i)| u * i), Some(6.0));
+        }
+        assert_eq!(volts_handle.sent().len(), 3);
+        assert_eq!(amps_handle.sent().len(), 3);
+    }
+}
@@ -0,0 +1,24 @@
+//! Sharing one physical connection between several local programs.
+//!
+//! A [`server::Server`] owns the [`crate::Device`] and serves requests and
+//! live measurements to clients over a newline delimited JSON protocol
+//! (see [`protocol`]). [`client::Client`] is the matching client side. The
+//! server can also expose a read-only JSON API over HTTP, with live
+//! measurements over WebSocket, for other languages and web dashboards, and
+//! bridge a subset of SCPI for lab automation frameworks. With the `mdns`
+//! feature, these listeners can be announced on the LAN like LXI instruments.
+
+pub mod client;
+#[cfg(feature = "mdns")]
+mod discovery;
+pub mod history;
+mod http;
+pub mod protocol;
+pub mod scheduler;
+mod scpi;
+pub mod server;
+mod websocket;
+
+pub use client::Client;
+pub use history::HistoryConfig;
+pub use server::{Protocol, Server};
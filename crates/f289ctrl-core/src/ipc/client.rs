@@ -0,0 +1,428 @@
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, Utc};
+use serde::de::DeserializeOwned;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{broadcast, oneshot, Mutex},
+};
+
+use super::history::HistorySample;
+use super::protocol::{decode_line, encode_line, Call, Message, Request};
+use super::scheduler::ClientStats;
+use crate::{
+    measurement::Measurement,
+    proto::{
+        command::{
+            ClearMemory, DateFormat, DezibelReference, DigitCount, Language, NumericFormat,
+            TimeFormat,
+        },
+        response::{Ident, MemoryStat},
+        ProtoError, Result,
+    },
+    snapshot::MemorySnapshot,
+};
+
+/// Waiters for replies by request id, `None` once the connection is closed.
+type Pending = Arc<std::sync::Mutex<Option<HashMap<u64, oneshot::Sender<Message>>>>>;
+
+/// Client for a [`super::Server`].
+///
+/// Mirrors the [`crate::Device`] API. Calls may be issued concurrently from
+/// clones of the same client; replies are matched to their requests by id.
+#[derive(Clone)]
+pub struct Client {
+    writer: Arc<Mutex<Pin<Box<dyn AsyncWrite + Send>>>>,
+    pending: Pending,
+    next_id: Arc<AtomicU64>,
+    measurements: Arc<broadcast::Receiver<Option<Measurement>>>,
+}
+
+/// Stream of live measurements, see [`Client::subscribe`].
+pub struct Subscription {
+    rx: broadcast::Receiver<Option<Measurement>>,
+}
+
+impl Subscription {
+    /// Wait for the next measurement polled by the server. Measurements are
+    /// skipped if the subscriber falls behind.
+    pub async fn next(&mut self) -> Result<Option<Measurement>> {
+        loop {
+            match self.rx.recv().await {
+                Ok(m) => return Ok(m),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Err(ProtoError::Abort),
+            }
+        }
+    }
+}
+
+impl Client {
+    #[cfg(unix)]
+    pub async fn connect_unix(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let stream = tokio::net::UnixStream::connect(path).await?;
+        Ok(Self::new(stream))
+    }
+
+    pub async fn connect_tcp(addr: impl ToSocketAddrs) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        Ok(Self::new(stream))
+    }
+
+    pub fn new(stream: impl AsyncRead + AsyncWrite + Send + 'static) -> Self {
+        let (reader, writer) = tokio::io::split(stream);
+        let pending: Pending = Arc::new(std::sync::Mutex::new(Some(HashMap::new())));
+        let (tx, rx) = broadcast::channel(64);
+
+        let dispatch = pending.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let msg: Message = match decode_line(&line) {
+                    Ok(msg) => msg,
+                    Err(_) => continue,
+                };
+                let id = match &msg {
+                    Message::Reply { id, .. } | Message::Error { id, .. } => *id,
+                    Message::Measurement { measurement } => {
+                        let _ = tx.send(measurement.clone());
+                        continue;
+                    }
+                };
+                let waiter = dispatch
+                    .lock()
+                    .expect("pending lock")
+                    .as_mut()
+                    .and_then(|pending| pending.remove(&id));
+                if let Some(waiter) = waiter {
+                    let _ = waiter.send(msg);
+                }
+            }
+            // Connection closed, fail all outstanding and later calls
+            dispatch.lock().expect("pending lock").take();
+        });
+
+        Self {
+            writer: Arc::new(Mutex::new(Box::pin(writer))),
+            pending,
+            next_id: Arc::new(AtomicU64::new(1)),
+            measurements: Arc::new(rx),
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(&self, call: Call) -> Result<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        match self.pending.lock().expect("pending lock").as_mut() {
+            Some(pending) => pending.insert(id, tx),
+            None => return Err(ProtoError::Abort),
+        };
+
+        let line = encode_line(&Request { id, call })?;
+        if let Err(err) = self.writer.lock().await.write_all(line.as_bytes()).await {
+            if let Some(pending) = self.pending.lock().expect("pending lock").as_mut() {
+                pending.remove(&id);
+            }
+            return Err(err.into());
+        }
+
+        match rx.await {
+            Ok(Message::Reply { value, .. }) => {
+                Ok(serde_json::from_value(value).map_err(std::io::Error::from)?)
+            }
+            Ok(Message::Error { message, .. }) => Err(ProtoError::Remote(message)),
+            Ok(Message::Measurement { .. }) | Err(_) => Err(ProtoError::Abort),
+        }
+    }
+
+    /// Subscribe to measurements polled by the server.
+    pub async fn subscribe(&self) -> Result<Subscription> {
+        let rx = self.measurements.resubscribe();
+        self.call::<()>(Call::Subscribe).await?;
+        Ok(Subscription { rx })
+    }
+
+    /// Stop the server from pushing measurements to this connection.
+    pub async fn unsubscribe(&self) -> Result<()> {
+        self.call(Call::Unsubscribe).await
+    }
+
+    /// Device usage of all clients connected to the server.
+    pub async fn statistics(&self) -> Result<Vec<ClientStats>> {
+        self.call(Call::Statistics).await
+    }
+
+    /// Measurements retained by the server, newer than `since` if given.
+    /// Empty if the server keeps no history.
+    pub async fn history(&self, since: Option<DateTime<Utc>>) -> Result<Vec<HistorySample>> {
+        self.call(Call::History { since }).await
+    }
+
+    pub async fn ident(&self) -> Result<Ident> {
+        self.call(Call::Ident).await
+    }
+
+    pub async fn live_measurement(&self) -> Result<Option<Measurement>> {
+        self.call(Call::LiveMeasurement).await
+    }
+
+    pub async fn memory_statistics(&self) -> Result<MemoryStat> {
+        self.call(Call::MemoryStatistics).await
+    }
+
+    pub async fn snapshot_memory(&self) -> Result<MemorySnapshot> {
+        self.call(Call::SnapshotMemory).await
+    }
+
+    pub async fn backlight(&self) -> Result<Duration> {
+        self.call(Call::Backlight).await
+    }
+
+    pub async fn set_backlight(&self, duration: Duration) -> Result<()> {
+        self.call(Call::SetBacklight { duration }).await
+    }
+
+    pub async fn poweroff(&self) -> Result<Duration> {
+        self.call(Call::Poweroff).await
+    }
+
+    pub async fn set_poweroff(&self, duration: Duration) -> Result<()> {
+        self.call(Call::SetPoweroff { duration }).await
+    }
+
+    pub async fn operator(&self) -> Result<String> {
+        self.call(Call::Operator).await
+    }
+
+    pub async fn set_operator(&self, operator: impl AsRef<str>) -> Result<()> {
+        let value = operator.as_ref().to_string();
+        self.call(Call::SetOperator { value }).await
+    }
+
+    pub async fn company(&self) -> Result<String> {
+        self.call(Call::Company).await
+    }
+
+    pub async fn set_company(&self, company: impl AsRef<str>) -> Result<()> {
+        let value = company.as_ref().to_string();
+        self.call(Call::SetCompany { value }).await
+    }
+
+    pub async fn site(&self) -> Result<String> {
+        self.call(Call::Site).await
+    }
+
+    pub async fn set_site(&self, site: impl AsRef<str>) -> Result<()> {
+        let value = site.as_ref().to_string();
+        self.call(Call::SetSite { value }).await
+    }
+
+    pub async fn contact(&self) -> Result<String> {
+        self.call(Call::Contact).await
+    }
+
+    pub async fn set_contact(&self, contact: impl AsRef<str>) -> Result<()> {
+        let value = contact.as_ref().to_string();
+        self.call(Call::SetContact { value }).await
+    }
+
+    pub async fn beeper(&self) -> Result<bool> {
+        self.call(Call::Beeper).await
+    }
+
+    pub async fn set_beeper(&self, state: bool) -> Result<()> {
+        self.call(Call::SetBeeper { state }).await
+    }
+
+    pub async fn smoothing(&self) -> Result<bool> {
+        self.call(Call::Smoothing).await
+    }
+
+    pub async fn set_smoothing(&self, state: bool) -> Result<()> {
+        self.call(Call::SetSmoothing { state }).await
+    }
+
+    pub async fn clock(&self) -> Result<u64> {
+        self.call(Call::Clock).await
+    }
+
+    pub async fn set_clock(&self, clock: DateTime<Local>) -> Result<()> {
+        self.call(Call::SetClock { clock }).await
+    }
+
+    pub async fn clear(&self, mem: ClearMemory) -> Result<()> {
+        self.call(Call::Clear { mem }).await
+    }
+
+    pub async fn reset(&self) -> Result<()> {
+        self.call(Call::Reset).await
+    }
+
+    pub async fn custom_dbm(&self) -> Result<u16> {
+        self.call(Call::CustomDbm).await
+    }
+
+    pub async fn set_custom_dbm(&self, dbm: u16) -> Result<()> {
+        self.call(Call::SetCustomDbm { dbm }).await
+    }
+
+    pub async fn dbm_ref(&self) -> Result<DezibelReference> {
+        self.call(Call::DbmRef).await
+    }
+
+    pub async fn set_dbm_ref(&self, dbm: DezibelReference) -> Result<()> {
+        self.call(Call::SetDbmRef { dbm }).await
+    }
+
+    pub async fn temp_offset(&self) -> Result<i16> {
+        self.call(Call::TempOffset).await
+    }
+
+    pub async fn set_temp_offset(&self, offset: i16) -> Result<()> {
+        self.call(Call::SetTempOffset { offset }).await
+    }
+
+    pub async fn digit_count(&self) -> Result<DigitCount> {
+        self.call(Call::DigitCount).await
+    }
+
+    pub async fn set_digit_count(&self, dc: DigitCount) -> Result<()> {
+        self.call(Call::SetDigitCount { dc }).await
+    }
+
+    pub async fn autohold_event_threshold(&self) -> Result<u8> {
+        self.call(Call::AutoholdEventThreshold).await
+    }
+
+    pub async fn set_autohold_event_threshold(&self, thd: u8) -> Result<()> {
+        self.call(Call::SetAutoholdEventThreshold { thd }).await
+    }
+
+    pub async fn recording_event_threshold(&self) -> Result<u8> {
+        self.call(Call::RecordingEventThreshold).await
+    }
+
+    pub async fn set_recording_event_threshold(&self, thd: u8) -> Result<()> {
+        self.call(Call::SetRecordingEventThreshold { thd }).await
+    }
+
+    pub async fn language(&self) -> Result<Language> {
+        self.call(Call::Language).await
+    }
+
+    pub async fn set_language(&self, lang: Language) -> Result<()> {
+        self.call(Call::SetLanguage { lang }).await
+    }
+
+    pub async fn date_format(&self) -> Result<DateFormat> {
+        self.call(Call::DateFormat).await
+    }
+
+    pub async fn set_date_format(&self, fmt: DateFormat) -> Result<()> {
+        self.call(Call::SetDateFormat { fmt }).await
+    }
+
+    pub async fn time_format(&self) -> Result<TimeFormat> {
+        self.call(Call::TimeFormat).await
+    }
+
+    pub async fn set_time_format(&self, fmt: TimeFormat) -> Result<()> {
+        self.call(Call::SetTimeFormat { fmt }).await
+    }
+
+    pub async fn numeric_format(&self) -> Result<NumericFormat> {
+        self.call(Call::NumericFormat).await
+    }
+
+    pub async fn set_numeric_format(&self, fmt: NumericFormat) -> Result<()> {
+        self.call(Call::SetNumericFormat { fmt }).await
+    }
+
+    pub async fn save_name(&self, slot: u16) -> Result<String> {
+        self.call(Call::SaveName { slot }).await
+    }
+
+    pub async fn set_save_name(&self, slot: u16, name: impl AsRef<str>) -> Result<()> {
+        let name = name.as_ref().to_string();
+        self.call(Call::SetSaveName { slot, name }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wait until the dispatcher of `client` noticed the closed connection.
+    async fn closed(client: &Client) {
+        let mut rx = client.measurements.resubscribe();
+        while !matches!(rx.recv().await, Err(broadcast::error::RecvError::Closed)) {}
+    }
+
+    #[tokio::test]
+    async fn test_replies_out_of_order() {
+        let (client_io, server_io) = tokio::io::duplex(1024);
+        let client = Client::new(client_io);
+
+        let server = tokio::spawn(async move {
+            let (reader, mut writer) = tokio::io::split(server_io);
+            let mut lines = BufReader::new(reader).lines();
+            let mut ids = Vec::new();
+            for _ in 0..2 {
+                let line = lines.next_line().await.expect("read").expect("request");
+                let request: Request = decode_line(&line).expect("decode");
+                ids.push(request.id);
+            }
+            for id in ids.into_iter().rev() {
+                let reply = Message::Reply {
+                    id,
+                    value: serde_json::json!(format!("operator {}", id)),
+                };
+                let line = encode_line(&reply).expect("encode");
+                writer.write_all(line.as_bytes()).await.expect("write");
+            }
+        });
+
+        let (first, second) = tokio::join!(client.operator(), client.operator());
+        server.await.expect("server");
+        assert_eq!(first.expect("first"), "operator 1");
+        assert_eq!(second.expect("second"), "operator 2");
+    }
+
+    #[tokio::test]
+    async fn test_call_after_disconnect() {
+        let (client_io, server_io) = tokio::io::duplex(1024);
+        let client = Client::new(client_io);
+        drop(server_io);
+        closed(&client).await;
+
+        for _ in 0..2 {
+            let result = tokio::time::timeout(Duration::from_secs(1), client.ident()).await;
+            assert!(matches!(result, Ok(Err(ProtoError::Abort))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tcp_call_after_disconnect() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let accept = tokio::spawn(async move { listener.accept().await.map(drop) });
+
+        let client = Client::connect_tcp(addr).await.expect("connect");
+        accept.await.expect("accept").expect("accept");
+        closed(&client).await;
+
+        let result = tokio::time::timeout(Duration::from_secs(1), client.ident()).await;
+        assert!(matches!(result, Ok(Err(ProtoError::Abort))));
+    }
+}
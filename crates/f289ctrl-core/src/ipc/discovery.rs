@@ -0,0 +1,111 @@
+//! LXI style instrument discovery, see [`super::Server::advertise`].
+
+use std::io;
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+
+use super::scpi;
+use super::server::Protocol;
+use crate::proto::{response::Ident, Result};
+
+/// DNS-SD service types announced for a listener.
+fn service_types(protocol: Protocol) -> &'static [&'static str] {
+    match protocol {
+        Protocol::Json => &[],
+        Protocol::Http => &["_lxi._tcp.local.", "_http._tcp.local."],
+        Protocol::Scpi => &["_scpi-raw._tcp.local."],
+    }
+}
+
+/// Registered mDNS services, withdrawn on drop.
+pub(crate) struct Advertisement {
+    daemon: ServiceDaemon,
+}
+
+impl Advertisement {
+    pub fn new(ident: &Ident, listeners: &[(Protocol, u16)]) -> Result<Self> {
+        let daemon = ServiceDaemon::new().map_err(mdns_error)?;
+        let (manufacturer, model) = scpi::manufacturer_model(ident);
+        let instance = format!("{} {} ({})", manufacturer, model, ident.serial);
+        let host_name = format!("f289-{}.local.", ident.serial);
+        let properties = [
+            ("txtvers", "1"),
+            ("Manufacturer", manufacturer.as_str()),
+            ("Model", model),
+            ("SerialNumber", ident.serial.as_str()),
+            ("FirmwareVersion", ident.firmware.as_str()),
+        ];
+
+        for (protocol, port) in listeners {
+            for service_type in service_types(*protocol) {
+                let info = ServiceInfo::new(
+                    service_type,
+                    &instance,
+                    &host_name,
+                    "",
+                    *port,
+                    &properties[..],
+                )
+                .map_err(mdns_error)?
+                .enable_addr_auto();
+                daemon.register(info).map_err(mdns_error)?;
+            }
+        }
+        Ok(Self { daemon })
+    }
+}
+
+impl Drop for Advertisement {
+    fn drop(&mut self) {
+        let _ = self.daemon.shutdown();
+    }
+}
+
+fn mdns_error(err: mdns_sd::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+/// LXI identification document, served at `/lxi/identification`.
+pub(crate) fn identification_xml(ident: &Ident) -> String {
+    let (manufacturer, model) = scpi::manufacturer_model(ident);
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<LXIDevice xmlns="http://www.lxistandard.org/InstrumentIdentification/1.0">
+  <Manufacturer>{}</Manufacturer>
+  <Model>{}</Model>
+  <SerialNumber>{}</SerialNumber>
+  <FirmwareRevision>{}</FirmwareRevision>
+  <ManufacturerDescription>Digital multimeter (f289ctrl bridge)</ManufacturerDescription>
+  <LXIVersion>1.5</LXIVersion>
+</LXIDevice>
+"#,
+        escape(&manufacturer),
+        escape(model),
+        escape(&ident.serial),
+        escape(&ident.firmware)
+    )
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identification_xml() {
+        let xml = identification_xml(&Ident {
+            model: "Fluke 289".into(),
+            firmware: "V1.16".into(),
+            serial: "1<2".into(),
+        });
+        assert!(xml.contains("<Manufacturer>FLUKE</Manufacturer>"));
+        assert!(xml.contains("<Model>289</Model>"));
+        assert!(xml.contains("<SerialNumber>1&lt;2</SerialNumber>"));
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::protocol::{decode_line, encode_line};
+use crate::measurement::Measurement;
+
+/// Retention settings for the measurement history kept by the server.
+#[derive(Debug, Clone)]
+pub struct HistoryConfig {
+    /// How long samples are kept
+    pub retention: Duration,
+    /// Minimum distance between two retained samples
+    pub resolution: Duration,
+    /// Optional JSON lines file the history is persisted to
+    pub path: Option<PathBuf>,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            retention: Duration::from_secs(24 * 60 * 60),
+            resolution: Duration::from_secs(1),
+            path: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorySample {
+    pub ts: DateTime<Utc>,
+    pub measurement: Measurement,
+}
+
+/// Downsampled measurement history.
+pub(crate) struct History {
+    config: HistoryConfig,
+    samples: VecDeque<HistorySample>,
+    file: Option<File>,
+    /// Lines written since the file was last compacted
+    appended: usize,
+}
+
+impl History {
+    /// Create the history, restoring samples still within retention from disk.
+    pub fn open(config: HistoryConfig) -> io::Result<Self> {
+        let mut history = Self {
+            config,
+            samples: VecDeque::new(),
+            file: None,
+            appended: 0,
+        };
+        if let Some(path) = history.config.path.clone() {
+            if path.exists() {
+                for line in BufReader::new(File::open(&path)?).lines() {
+                    // Skip a partially written last line
+                    if let Ok(sample) = decode_line::<HistorySample>(&line?) {
+                        history.samples.push_back(sample);
+                    }
+                }
+            }
+            history.expire(Utc::now());
+            history.compact()?;
+        }
+        Ok(history)
+    }
+
+    pub fn push(&mut self, measurement: Measurement) -> io::Result<()> {
+        let now = Utc::now();
+        if let Some(last) = self.samples.back() {
+            if (now - last.ts).to_std().unwrap_or_default() < self.config.resolution {
+                return Ok(());
+            }
+        }
+        let sample = HistorySample {
+            ts: now,
+            measurement,
+        };
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(encode_line(&sample)?.as_bytes())?;
+            self.appended += 1;
+        }
+        self.samples.push_back(sample);
+        self.expire(now);
+
+        // Rewrite the file once it holds about twice the retained samples
+        if self.file.is_some() && self.appended > self.samples.len() {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// All samples newer than `since`, oldest first.
+    pub fn since(&self, since: Option<DateTime<Utc>>) -> Vec<HistorySample> {
+        self.samples
+            .iter()
+            .filter(|s| since.map_or(true, |since| s.ts > since))
+            .cloned()
+            .collect()
+    }
+
+    fn expire(&mut self, now: DateTime<Utc>) {
+        while let Some(first) = self.samples.front() {
+            if (now - first.ts).to_std().unwrap_or_default() > self.config.retention {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn compact(&mut self) -> io::Result<()> {
+        let path = match &self.config.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let tmp = path.with_extension("tmp");
+        {
+            let mut file = File::create(&tmp)?;
+            for sample in &self.samples {
+                file.write_all(encode_line(sample)?.as_bytes())?;
+            }
+            file.sync_all()?;
+        }
+        std::fs::rename(&tmp, path)?;
+        self.file = Some(OpenOptions::new().append(true).open(path)?);
+        self.appended = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::measurement;
+
+    #[test]
+    fn test_history_persisted() {
+        let path = std::env::temp_dir().join(format!("f289-history-{}.jsonl", std::process::id()));
+        let config = HistoryConfig {
+            resolution: Duration::ZERO,
+            path: Some(path.clone()),
+            ..Default::default()
+        };
+
+        let mut history = History::open(config.clone()).expect("open");
+        history.push(measurement(&[])).expect("push");
+        history.push(measurement(&[])).expect("push");
+        drop(history);
+
+        let history = History::open(config).expect("reopen");
+        assert_eq!(history.since(None).len(), 2);
+        std::fs::remove_file(path).expect("cleanup");
+    }
+}
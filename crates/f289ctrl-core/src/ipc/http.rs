@@ -0,0 +1,180 @@
+//! Minimal HTTP/1.1 framing for [`super::Server::serve_http`].
+//!
+//! Only what a JSON API needs: the request line and headers are parsed, a
+//! request body is ignored and every connection is closed after the
+//! response.
+
+use std::io;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Upper limit for the request line and all headers together.
+const MAX_HEAD: usize = 8 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HttpRequest {
+    pub method: String,
+    /// Path without query string
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: Vec<(String, String)>,
+}
+
+impl HttpRequest {
+    /// Value of the first header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Read the request head, `None` if the client closed the connection first.
+pub(crate) async fn read_request(
+    reader: &mut (impl AsyncBufRead + Unpin),
+) -> io::Result<Option<HttpRequest>> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let mut total = 0;
+    let mut line = String::new();
+
+    if read_head_line(reader, &mut line, &mut total).await? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+            (method.to_string(), target.to_string())
+        }
+        _ => return Err(invalid("Invalid request line")),
+    };
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), Some(query.to_string())),
+        None => (target, None),
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        if read_head_line(reader, &mut line, &mut total).await? == 0 {
+            return Err(invalid("Incomplete request head"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        match header.split_once(':') {
+            Some((key, value)) => headers.push((key.trim().to_string(), value.trim().to_string())),
+            None => return Err(invalid("Invalid header")),
+        }
+    }
+
+    Ok(Some(HttpRequest {
+        method,
+        path,
+        query,
+        headers,
+    }))
+}
+
+/// Read one line of the request head, `total` counts the bytes read so far.
+///
+/// Fails as soon as the head exceeds [`MAX_HEAD`], without buffering the
+/// rest of an overlong line.
+async fn read_head_line(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    line: &mut String,
+    total: &mut usize,
+) -> io::Result<usize> {
+    let too_large = || io::Error::new(io::ErrorKind::InvalidData, "Request head too large");
+    if *total >= MAX_HEAD {
+        return Err(too_large());
+    }
+    let limit = (MAX_HEAD - *total) as u64;
+    let len = reader.take(limit).read_line(line).await?;
+    *total += len;
+    if len > 0 && !line.ends_with('\n') && *total >= MAX_HEAD {
+        return Err(too_large());
+    }
+    Ok(len)
+}
+
+/// Write a complete response with `Connection: close`.
+pub(crate) async fn write_response(
+    writer: &mut (impl AsyncWrite + Unpin),
+    status: u16,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+        status,
+        reason(status),
+        content_type,
+        body.len()
+    );
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(body).await?;
+    writer.flush().await
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let mut input: &[u8] =
+            b"GET /measurement?pretty HTTP/1.1\r\nHost: localhost\r\nupgrade: websocket\r\n\r\n";
+        let request = read_request(&mut input)
+            .await
+            .expect("request")
+            .expect("not closed");
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/measurement");
+        assert_eq!(request.query.as_deref(), Some("pretty"));
+        assert_eq!(request.header("Upgrade"), Some("websocket"));
+
+        let mut input: &[u8] = b"";
+        assert!(read_request(&mut input).await.expect("closed").is_none());
+        let mut input: &[u8] = b"GET /\r\n\r\n";
+        assert!(read_request(&mut input).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_head_too_large() {
+        // An endless header line must fail without being buffered
+        let (mut client, server) = tokio::io::duplex(1024);
+        let writer = tokio::spawn(async move {
+            client.write_all(b"GET / HTTP/1.1\r\nX-Long: ").await?;
+            loop {
+                client.write_all(&[b'a'; 512]).await?;
+            }
+        });
+        let mut reader = tokio::io::BufReader::new(server);
+        let err = read_request(&mut reader).await.expect_err("too large");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        drop(reader);
+        let result: io::Result<()> = writer.await.expect("writer");
+        assert!(result.is_err());
+
+        let mut head = b"GET / HTTP/1.1\r\n".to_vec();
+        while head.len() < MAX_HEAD {
+            head.extend_from_slice(b"X-Header: value\r\n");
+        }
+        head.extend_from_slice(b"\r\n");
+        let mut input: &[u8] = &head;
+        assert!(read_request(&mut input).await.is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    measurement::Measurement,
+    proto::command::{
+        ClearMemory, DateFormat, DezibelReference, DigitCount, Language, NumericFormat, TimeFormat,
+    },
+};
+
+/// Request sent from a client to the server, one JSON object per line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request {
+    /// Client chosen identifier, repeated in the matching reply
+    pub id: u64,
+    pub call: Call,
+}
+
+/// Remote counterpart of the [`crate::Device`] methods.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Call {
+    Ident,
+    LiveMeasurement,
+    MemoryStatistics,
+    SnapshotMemory,
+    Backlight,
+    SetBacklight {
+        duration: Duration,
+    },
+    Poweroff,
+    SetPoweroff {
+        duration: Duration,
+    },
+    Operator,
+    SetOperator {
+        value: String,
+    },
+    Company,
+    SetCompany {
+        value: String,
+    },
+    Site,
+    SetSite {
+        value: String,
+    },
+    Contact,
+    SetContact {
+        value: String,
+    },
+    Beeper,
+    SetBeeper {
+        state: bool,
+    },
+    Smoothing,
+    SetSmoothing {
+        state: bool,
+    },
+    Clock,
+    SetClock {
+        clock: DateTime<Local>,
+    },
+    Clear {
+        mem: ClearMemory,
+    },
+    Reset,
+    CustomDbm,
+    SetCustomDbm {
+        dbm: u16,
+    },
+    DbmRef,
+    SetDbmRef {
+        dbm: DezibelReference,
+    },
+    TempOffset,
+    SetTempOffset {
+        offset: i16,
+    },
+    DigitCount,
+    SetDigitCount {
+        dc: DigitCount,
+    },
+    AutoholdEventThreshold,
+    SetAutoholdEventThreshold {
+        thd: u8,
+    },
+    RecordingEventThreshold,
+    SetRecordingEventThreshold {
+        thd: u8,
+    },
+    Language,
+    SetLanguage {
+        lang: Language,
+    },
+    DateFormat,
+    SetDateFormat {
+        fmt: DateFormat,
+    },
+    TimeFormat,
+    SetTimeFormat {
+        fmt: TimeFormat,
+    },
+    NumericFormat,
+    SetNumericFormat {
+        fmt: NumericFormat,
+    },
+    SaveName {
+        slot: u16,
+    },
+    SetSaveName {
+        slot: u16,
+        name: String,
+    },
+    /// Device usage of all connected clients, see [`super::scheduler::ClientStats`]
+    Statistics,
+    /// Retained measurements newer than `since`, see [`super::history::HistorySample`]
+    History {
+        since: Option<DateTime<Utc>>,
+    },
+    /// Start receiving [`Message::Measurement`] for every polled measurement
+    Subscribe,
+    Unsubscribe,
+}
+
+/// Message sent from the server to a client, one JSON object per line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    /// Successful result of the request `id`, encoded like the return value of the [`crate::Device`] method
+    Reply {
+        id: u64,
+        value: serde_json::Value,
+    },
+    Error {
+        id: u64,
+        message: String,
+    },
+    /// Live measurement pushed to subscribed clients, `None` if the device has no data
+    Measurement {
+        measurement: Option<Measurement>,
+    },
+}
+
+pub(crate) fn encode_line<T: Serialize>(value: &T) -> std::io::Result<String> {
+    let mut line = serde_json::to_string(value).map_err(std::io::Error::from)?;
+    line.push('\n');
+    Ok(line)
+}
+
+pub(crate) fn decode_line<'a, T: Deserialize<'a>>(line: &'a str) -> std::io::Result<T> {
+    serde_json::from_str(line).map_err(std::io::Error::from)
+}
@@ -0,0 +1,251 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{oneshot, Mutex as AsyncMutex, OwnedMutexGuard};
+
+use crate::device::Device;
+
+/// Bulk frames granted in a row are interrupted by interactive requests, but
+/// after this many interactive grants a waiting bulk frame is served anyway.
+const MAX_INTERACTIVE_STREAK: u32 = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Priority {
+    /// Single commands a user is waiting for
+    Interactive,
+    /// One frame of a long running download
+    Bulk,
+}
+
+/// Device usage of a single client connection.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientStats {
+    pub client: u64,
+    pub interactive: u64,
+    pub bulk: u64,
+    /// Total time spent waiting for the device
+    pub waited: Duration,
+    /// Total time the device was busy for this client
+    pub busy: Duration,
+}
+
+struct Waiter {
+    grant: oneshot::Sender<()>,
+}
+
+/// Queued request, hands the grant back if the request is cancelled after it
+/// was granted access but before the permit was created.
+struct Pending<'a> {
+    scheduler: &'a Scheduler,
+    granted: oneshot::Receiver<()>,
+    done: bool,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.granted.close();
+            if self.granted.try_recv().is_ok() {
+                self.scheduler.release(None, Duration::ZERO);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    busy: bool,
+    interactive: VecDeque<Waiter>,
+    bulk: VecDeque<Waiter>,
+    interactive_streak: u32,
+    stats: HashMap<u64, ClientStats>,
+}
+
+/// Hands out exclusive device access, one command (frame) at a time.
+///
+/// Interactive requests are preferred over bulk frames, so a memory download
+/// is preempted between frames. Within a priority requests are served in
+/// order of arrival.
+pub struct Scheduler {
+    device: Arc<AsyncMutex<Device>>,
+    state: Mutex<State>,
+}
+
+impl Scheduler {
+    pub fn new(device: Device) -> Arc<Self> {
+        Arc::new(Self {
+            device: Arc::new(AsyncMutex::new(device)),
+            state: Mutex::default(),
+        })
+    }
+
+    pub async fn acquire(self: &Arc<Self>, client: u64, priority: Priority) -> Permit {
+        let queued = Instant::now();
+        let granted = {
+            let mut state = self.state.lock().expect("scheduler lock");
+            if state.busy {
+                let (grant, granted) = oneshot::channel();
+                let waiter = Waiter { grant };
+                match priority {
+                    Priority::Interactive => state.interactive.push_back(waiter),
+                    Priority::Bulk => state.bulk.push_back(waiter),
+                }
+                Some(granted)
+            } else {
+                state.busy = true;
+                None
+            }
+        };
+        if let Some(granted) = granted {
+            let mut pending = Pending {
+                scheduler: self,
+                granted,
+                done: false,
+            };
+            // The sender is only dropped after the grant was handed over
+            let _ = (&mut pending.granted).await;
+            pending.done = true;
+        }
+
+        let started = Instant::now();
+        {
+            let mut state = self.state.lock().expect("scheduler lock");
+            let stats = state.stats.entry(client).or_insert_with(|| ClientStats {
+                client,
+                ..Default::default()
+            });
+            match priority {
+                Priority::Interactive => stats.interactive += 1,
+                Priority::Bulk => stats.bulk += 1,
+            }
+            stats.waited += started - queued;
+        }
+
+        let mut permit = Permit {
+            device: None,
+            scheduler: self.clone(),
+            client,
+            started,
+        };
+        permit.device = Some(self.device.clone().lock_owned().await);
+        permit
+    }
+
+    /// Statistics of all connected clients.
+    pub fn stats(&self) -> Vec<ClientStats> {
+        let state = self.state.lock().expect("scheduler lock");
+        let mut stats: Vec<ClientStats> = state.stats.values().cloned().collect();
+        stats.sort_by_key(|s| s.client);
+        stats
+    }
+
+    /// Drop the statistics of a disconnected client.
+    pub fn forget(&self, client: u64) {
+        self.state
+            .lock()
+            .expect("scheduler lock")
+            .stats
+            .remove(&client);
+    }
+
+    fn release(&self, client: Option<u64>, busy: Duration) {
+        let mut state = self.state.lock().expect("scheduler lock");
+        if let Some(stats) = client.and_then(|client| state.stats.get_mut(&client)) {
+            stats.busy += busy;
+        }
+        loop {
+            let prefer_bulk =
+                !state.bulk.is_empty() && state.interactive_streak >= MAX_INTERACTIVE_STREAK;
+            let next = if prefer_bulk {
+                state.interactive_streak = 0;
+                state.bulk.pop_front()
+            } else if let Some(waiter) = state.interactive.pop_front() {
+                state.interactive_streak += 1;
+                Some(waiter)
+            } else {
+                state.interactive_streak = 0;
+                state.bulk.pop_front()
+            };
+            match next {
+                // Skip requests which were cancelled while waiting
+                Some(waiter) => {
+                    if waiter.grant.send(()).is_ok() {
+                        return;
+                    }
+                }
+                None => {
+                    state.busy = false;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Exclusive access to the device, released on drop.
+pub struct Permit {
+    device: Option<OwnedMutexGuard<Device>>,
+    scheduler: Arc<Scheduler>,
+    client: u64,
+    started: Instant,
+}
+
+impl Deref for Permit {
+    type Target = Device;
+
+    fn deref(&self) -> &Device {
+        self.device.as_ref().expect("device guard")
+    }
+}
+
+impl DerefMut for Permit {
+    fn deref_mut(&mut self) -> &mut Device {
+        self.device.as_mut().expect("device guard")
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        // Unlock the device before the next waiter is granted access
+        self.device.take();
+        self.scheduler
+            .release(Some(self.client), self.started.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_interactive_preempts_bulk() {
+        let scheduler = Scheduler::new(Device::new_faked(vec![]));
+        let permit = scheduler.acquire(1, Priority::Bulk).await;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for (client, priority) in [(1, Priority::Bulk), (2, Priority::Interactive)] {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                let _permit = scheduler.acquire(client, priority).await;
+                order.lock().expect("order").push(client);
+            }));
+            tokio::task::yield_now().await;
+        }
+        drop(permit);
+        for task in tasks {
+            task.await.expect("task");
+        }
+
+        assert_eq!(*order.lock().expect("order"), vec![2, 1]);
+        let stats = scheduler.stats();
+        assert_eq!(stats[0].bulk, 2);
+        assert_eq!(stats[1].interactive, 1);
+    }
+}
@@ -0,0 +1,222 @@
+//! SCPI subset for [`super::Server::serve_scpi`].
+
+use std::collections::VecDeque;
+
+use crate::{
+    measurement::{Measurement, ReadingValue},
+    proto::response::Ident,
+};
+
+/// Maximum number of queued errors, newer errors replace the last one.
+const MAX_ERRORS: usize = 16;
+
+const OVERLOAD: f64 = 9.9e37;
+const NOT_A_NUMBER: f64 = 9.91e37;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ScpiCommand {
+    Identify,
+    Measure,
+    Read,
+    SystemError,
+    ClearStatus,
+    OperationComplete,
+    /// Header which is not supported
+    Unknown(String),
+}
+
+/// Split a program message into commands.
+pub(crate) fn parse_line(line: &str) -> Vec<ScpiCommand> {
+    line.split(';')
+        .map(str::trim)
+        .filter(|unit| !unit.is_empty())
+        .map(parse_command)
+        .collect()
+}
+
+fn parse_command(unit: &str) -> ScpiCommand {
+    let header = unit.split_whitespace().next().unwrap_or_default();
+    let (header, query) = match header.strip_suffix('?') {
+        Some(header) => (header, true),
+        None => (header, false),
+    };
+    let header = header.strip_prefix(':').unwrap_or(header);
+    let nodes: Vec<&str> = header.split(':').collect();
+
+    match (nodes.as_slice(), query) {
+        ([node], true) if node.eq_ignore_ascii_case("*IDN") => ScpiCommand::Identify,
+        ([node], false) if node.eq_ignore_ascii_case("*CLS") => ScpiCommand::ClearStatus,
+        ([node], true) if node.eq_ignore_ascii_case("*OPC") => ScpiCommand::OperationComplete,
+        ([first, ..], true) if mnemonic(first, "MEASure") => ScpiCommand::Measure,
+        ([node], true) if mnemonic(node, "READ") => ScpiCommand::Read,
+        ([system, error], true) if mnemonic(system, "SYSTem") && mnemonic(error, "ERRor") => {
+            ScpiCommand::SystemError
+        }
+        ([system, error, next], true)
+            if mnemonic(system, "SYSTem") && mnemonic(error, "ERRor") && mnemonic(next, "NEXT") =>
+        {
+            ScpiCommand::SystemError
+        }
+        _ => ScpiCommand::Unknown(unit.to_string()),
+    }
+}
+
+/// Match `word` against the long form `spec`, its short form are the
+/// upper case letters.
+fn mnemonic(word: &str, spec: &str) -> bool {
+    let short: String = spec.chars().filter(char::is_ascii_uppercase).collect();
+    word.eq_ignore_ascii_case(spec) || word.eq_ignore_ascii_case(&short)
+}
+
+/// Manufacturer and model, e.g. `("FLUKE", "289")` for `Fluke 289`.
+pub(crate) fn manufacturer_model(ident: &Ident) -> (String, &str) {
+    let (manufacturer, model) = ident
+        .model
+        .split_once(' ')
+        .unwrap_or(("FLUKE", ident.model.as_str()));
+    (manufacturer.to_uppercase(), model)
+}
+
+/// `*IDN?` response.
+pub(crate) fn identification(ident: &Ident) -> String {
+    let (manufacturer, model) = manufacturer_model(ident);
+    format!(
+        "{},{},{},{}",
+        manufacturer, model, ident.serial, ident.firmware
+    )
+}
+
+/// Primary reading in NR3 format, with an error for the queue if the
+/// meter has no valid value.
+pub(crate) fn measurement_value(measurement: Option<&Measurement>) -> (String, Option<ScpiError>) {
+    let reading = match measurement.and_then(|m| m.readings.first()) {
+        Some(reading) => reading,
+        None => return (nr3(NOT_A_NUMBER), Some(ScpiError::DATA_STALE)),
+    };
+    match reading.reading_value() {
+        ReadingValue::Value(value) => (nr3(value), None),
+        ReadingValue::Overload => (nr3(OVERLOAD), None),
+        ReadingValue::OverloadNegative => (nr3(-OVERLOAD), None),
+        _ => (nr3(NOT_A_NUMBER), Some(ScpiError::DATA_STALE)),
+    }
+}
+
+fn nr3(value: f64) -> String {
+    let formatted = format!("{:.8E}", value);
+    // Rust omits the exponent sign and padding, SCPI instruments use
+    // `+1.25000000E+00`
+    let (mantissa, exponent) = formatted.split_once('E').expect("exponent");
+    let (exp_sign, exponent) = match exponent.strip_prefix('-') {
+        Some(exponent) => ('-', exponent),
+        None => ('+', exponent),
+    };
+    let sign = if mantissa.starts_with('-') { "" } else { "+" };
+    format!("{}{}E{}{:0>2}", sign, mantissa, exp_sign, exponent)
+}
+
+/// Entry of the SCPI error queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ScpiError {
+    pub code: i16,
+    pub message: &'static str,
+}
+
+impl ScpiError {
+    pub const UNDEFINED_HEADER: Self = Self {
+        code: -113,
+        message: "Undefined header",
+    };
+    pub const DATA_STALE: Self = Self {
+        code: -230,
+        message: "Data corrupt or stale",
+    };
+    pub const HARDWARE: Self = Self {
+        code: -240,
+        message: "Hardware error",
+    };
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ErrorQueue {
+    errors: VecDeque<ScpiError>,
+}
+
+impl ErrorQueue {
+    pub fn push(&mut self, error: ScpiError) {
+        if self.errors.len() >= MAX_ERRORS {
+            self.errors.pop_back();
+            self.errors.push_back(ScpiError {
+                code: -350,
+                message: "Queue overflow",
+            });
+        } else {
+            self.errors.push_back(error);
+        }
+    }
+
+    /// `SYSTem:ERRor?` response.
+    pub fn pop(&mut self) -> String {
+        match self.errors.pop_front() {
+            Some(error) => format!("{},\"{}\"", error.code, error.message),
+            None => String::from("0,\"No error\""),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.errors.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("*idn?; meas:volt:dc?;READ?\n"),
+            [
+                ScpiCommand::Identify,
+                ScpiCommand::Measure,
+                ScpiCommand::Read
+            ]
+        );
+        assert_eq!(
+            parse_line(":SYST:ERR?;SYSTEM:ERROR:NEXT?;*CLS"),
+            [
+                ScpiCommand::SystemError,
+                ScpiCommand::SystemError,
+                ScpiCommand::ClearStatus
+            ]
+        );
+        assert_eq!(
+            parse_line("MEAS;SYS:ERR?"),
+            [
+                ScpiCommand::Unknown("MEAS".into()),
+                ScpiCommand::Unknown("SYS:ERR?".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        assert_eq!(nr3(1.25), "+1.25000000E+00");
+        assert_eq!(nr3(-0.0015), "-1.50000000E-03");
+        assert_eq!(nr3(OVERLOAD), "+9.90000000E+37");
+        assert_eq!(
+            identification(&Ident {
+                model: "Fluke 289".into(),
+                firmware: "V1.16".into(),
+                serial: "12345678".into(),
+            }),
+            "FLUKE,289,12345678,V1.16"
+        );
+
+        let (value, error) = measurement_value(None);
+        assert_eq!(value, "+9.91000000E+37");
+        let mut queue = ErrorQueue::default();
+        queue.push(error.expect("error"));
+        assert_eq!(queue.pop(), "-230,\"Data corrupt or stale\"");
+        assert_eq!(queue.pop(), "0,\"No error\"");
+    }
+}
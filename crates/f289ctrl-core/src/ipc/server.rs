@@ -0,0 +1,846 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use chrono::Utc;
+
+use serde::Serialize;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, ToSocketAddrs},
+    sync::{broadcast, mpsc},
+};
+
+use super::history::{History, HistoryConfig};
+use super::http::{read_request, write_response, HttpRequest};
+use super::protocol::{decode_line, encode_line, Call, Message, Request};
+use super::scheduler::{Permit, Priority, Scheduler};
+use super::scpi::{self, ErrorQueue, ScpiCommand, ScpiError};
+use super::websocket::{self, Opcode};
+use crate::{
+    device::{Device, ValueMaps},
+    measurement::{Measurement, SavedRecordingSessionInfo, SessionRecordReadings},
+    proto::Result,
+    rawmea::RawMeasurement,
+    snapshot::{MemorySnapshot, RecordingSnapshot},
+};
+
+/// Client id used for the server's own live measurement polling.
+const POLL_CLIENT: u64 = 0;
+
+/// Content type of the HTTP API responses.
+const JSON: &str = "application/json";
+
+/// Default interval for polling live measurements while clients are subscribed.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Protocol spoken on a TCP listener, see [`Server::serve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// Newline delimited JSON, see [`super::protocol`]
+    Json,
+    /// See [`Server::serve_http`]
+    Http,
+    /// See [`Server::serve_scpi`]
+    Scpi,
+}
+
+/// Owns the device and serves any number of clients.
+pub struct Server {
+    scheduler: Arc<Scheduler>,
+    next_client: AtomicU64,
+    maps: Arc<ValueMaps>,
+    measurements: broadcast::Sender<Message>,
+    poll_interval: Duration,
+    history: Option<std::sync::Mutex<History>>,
+    observers: std::sync::Mutex<Vec<Observer>>,
+    history_error: std::sync::Mutex<Option<ErrorHandler>>,
+    #[cfg(feature = "mdns")]
+    advertise: bool,
+}
+
+type Observer = Box<dyn FnMut(&Measurement) + Send>;
+type ErrorHandler = Box<dyn FnMut(&std::io::Error) + Send>;
+
+impl Server {
+    pub fn new(device: Device, maps: ValueMaps) -> Self {
+        let (measurements, _) = broadcast::channel(64);
+        Self {
+            scheduler: Scheduler::new(device),
+            next_client: AtomicU64::new(POLL_CLIENT + 1),
+            maps: Arc::new(maps),
+            measurements,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            history: None,
+            observers: Default::default(),
+            history_error: Default::default(),
+            #[cfg(feature = "mdns")]
+            advertise: false,
+        }
+    }
+
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Keep a history of polled measurements which clients can query, e.g. to
+    /// fill a chart on connect. Measurements are polled continuously while enabled.
+    pub fn history(mut self, config: HistoryConfig) -> Result<Self> {
+        self.history = Some(std::sync::Mutex::new(History::open(config)?));
+        Ok(self)
+    }
+
+    /// Call `handler` when a measurement can't be written to the history
+    /// file. Without a handler such errors are ignored and polling continues.
+    pub fn on_history_error(self, handler: impl FnMut(&std::io::Error) + Send + 'static) -> Self {
+        *self.history_error.lock().expect("history error lock") = Some(Box::new(handler));
+        self
+    }
+
+    /// Call `observer` for every polled measurement, e.g. to evaluate alert
+    /// rules. Measurements are polled continuously once an observer is added.
+    pub fn on_measurement(self, observer: impl FnMut(&Measurement) + Send + 'static) -> Self {
+        self.observers
+            .lock()
+            .expect("observers lock")
+            .push(Box::new(observer));
+        self
+    }
+
+    /// Announce the HTTP and SCPI listeners of [`Server::serve`] via mDNS
+    /// (`_lxi._tcp`, `_http._tcp` and `_scpi-raw._tcp`) like LXI instruments,
+    /// and serve the LXI identification document at `/lxi/identification`.
+    #[cfg(feature = "mdns")]
+    pub fn advertise(mut self) -> Self {
+        self.advertise = true;
+        self
+    }
+
+    /// Listen on a Unix domain socket. A stale socket file at `path` is replaced.
+    #[cfg(unix)]
+    pub async fn serve_unix(self, path: impl AsRef<Path>) -> Result<()> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let path = path.as_ref();
+        if let Ok(meta) = std::fs::symlink_metadata(path) {
+            if meta.file_type().is_socket() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        let listener = tokio::net::UnixListener::bind(path)?;
+
+        let server = self.start();
+        loop {
+            let (stream, _) = listener.accept().await?;
+            tokio::spawn(server.clone().handle_connection(stream));
+        }
+    }
+
+    /// Listen on a TCP socket.
+    pub async fn serve_tcp(self, addr: impl ToSocketAddrs) -> Result<()> {
+        self.serve([(Protocol::Json, addr)]).await
+    }
+
+    /// Serve a read-only JSON API over HTTP.
+    ///
+    /// | Path           | Content                                   |
+    /// |----------------|-------------------------------------------|
+    /// | `/ident`       | Device identification                     |
+    /// | `/measurement` | Current live measurement, `null` if none  |
+    /// | `/memory`      | Snapshot of all saved entries (slow)      |
+    /// | `/settings`    | All device settings                       |
+    /// | `/ws`          | WebSocket, every polled measurement as JSON text message |
+    ///
+    /// Errors are returned as `{"error": "..."}`. The WebSocket sends the
+    /// measurement document, or `null` if the device has no data.
+    pub async fn serve_http(self, addr: impl ToSocketAddrs) -> Result<()> {
+        self.serve([(Protocol::Http, addr)]).await
+    }
+
+    /// Accept a subset of SCPI, so the meter can be used by test frameworks
+    /// written for SCPI instruments. Commands are case-insensitive in long
+    /// or short form, several commands in one line are separated by `;`.
+    ///
+    /// | Command                | Response                                     |
+    /// |------------------------|----------------------------------------------|
+    /// | `*IDN?`                | `FLUKE,289,<serial>,<firmware>`              |
+    /// | `MEASure[:...]?`       | Primary reading in base SI unit, e.g. `+1.25000000E+00` |
+    /// | `READ?`                | Same as `MEASure?`                           |
+    /// | `SYSTem:ERRor[:NEXT]?` | Oldest queued error, `0,"No error"` if empty |
+    /// | `*CLS`                 | Clears the error queue                       |
+    /// | `*OPC?`                | `1`                                          |
+    ///
+    /// The function is selected with the rotary switch, a function given
+    /// with `MEASure` (e.g. `MEAS:VOLT:DC?`) is accepted but not checked.
+    /// Overloads are returned as `+9.90000000E+37`, missing data as
+    /// `+9.91000000E+37` (not a number) and an error is queued.
+    pub async fn serve_scpi(self, addr: impl ToSocketAddrs) -> Result<()> {
+        self.serve([(Protocol::Scpi, addr)]).await
+    }
+
+    /// Listen on several TCP sockets sharing the same device, until
+    /// accepting a connection fails.
+    pub async fn serve<A: ToSocketAddrs>(
+        self,
+        listeners: impl IntoIterator<Item = (Protocol, A)>,
+    ) -> Result<()> {
+        let mut bound = Vec::new();
+        for (protocol, addr) in listeners {
+            bound.push((protocol, TcpListener::bind(addr).await?));
+        }
+
+        let server = self.start();
+        #[cfg(feature = "mdns")]
+        let _advertisement = if server.advertise {
+            let mut ports = Vec::with_capacity(bound.len());
+            for (protocol, listener) in &bound {
+                ports.push((*protocol, listener.local_addr()?.port()));
+            }
+            let ident = server.interactive(POLL_CLIENT).await.ident().await?;
+            Some(super::discovery::Advertisement::new(&ident, &ports)?)
+        } else {
+            None
+        };
+        let accept_loops = bound.into_iter().map(|(protocol, listener)| {
+            let server = server.clone();
+            tokio::spawn(async move {
+                loop {
+                    let (stream, _) = listener.accept().await?;
+                    let server = server.clone();
+                    match protocol {
+                        Protocol::Json => tokio::spawn(server.handle_connection(stream)),
+                        Protocol::Http => tokio::spawn(server.handle_http(stream)),
+                        Protocol::Scpi => tokio::spawn(server.handle_scpi(stream)),
+                    };
+                }
+            })
+        });
+        let (result, _, others) = futures::future::select_all(accept_loops).await;
+        for other in others {
+            other.abort();
+        }
+        result.expect("accept loop")
+    }
+
+    fn start(self) -> Arc<Self> {
+        let server = Arc::new(self);
+        tokio::spawn(server.clone().poll_loop());
+        server
+    }
+
+    /// Poll live measurements as long as at least one client is subscribed,
+    /// the history is enabled or measurements are observed.
+    async fn poll_loop(self: Arc<Self>) {
+        let mut ticker = tokio::time::interval(self.poll_interval);
+        let continuous =
+            self.history.is_some() || !self.observers.lock().expect("observers lock").is_empty();
+        loop {
+            ticker.tick().await;
+            if self.measurements.receiver_count() == 0 && !continuous {
+                continue;
+            }
+            let result = self
+                .interactive(POLL_CLIENT)
+                .await
+                .live_measurement()
+                .await
+                .and_then(|raw| self.decode(raw));
+            if let Ok(measurement) = result {
+                if let (Some(history), Some(measurement)) = (&self.history, &measurement) {
+                    if let Err(err) = history
+                        .lock()
+                        .expect("history lock")
+                        .push(measurement.clone())
+                    {
+                        if let Some(handler) = self
+                            .history_error
+                            .lock()
+                            .expect("history error lock")
+                            .as_mut()
+                        {
+                            handler(&err);
+                        }
+                    }
+                }
+                if let Some(measurement) = &measurement {
+                    for observer in self.observers.lock().expect("observers lock").iter_mut() {
+                        observer(measurement);
+                    }
+                }
+                let _ = self.measurements.send(Message::Measurement { measurement });
+            }
+        }
+    }
+
+    async fn handle_connection<S>(self: Arc<Self>, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let client = self.next_client.fetch_add(1, Ordering::Relaxed);
+        let (reader, mut writer) = tokio::io::split(stream);
+        let (tx, mut rx) = mpsc::channel::<Message>(64);
+
+        let writer_task = tokio::spawn(async move {
+            while let Some(msg) = rx.recv().await {
+                let line = match encode_line(&msg) {
+                    Ok(line) => line,
+                    Err(_) => continue,
+                };
+                if writer.write_all(line.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut subscription: Option<tokio::task::JoinHandle<()>> = None;
+        let mut lines = BufReader::new(reader).lines();
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            let request: Request = match decode_line(&line) {
+                Ok(request) => request,
+                Err(err) => {
+                    let _ = tx
+                        .send(Message::Error {
+                            id: 0,
+                            message: format!("Invalid request: {}", err),
+                        })
+                        .await;
+                    continue;
+                }
+            };
+            let id = request.id;
+            match request.call {
+                Call::Subscribe => {
+                    if subscription.is_none() {
+                        subscription = Some(self.forward_measurements(tx.clone()));
+                    }
+                    let _ = tx.send(done(id)).await;
+                }
+                Call::Unsubscribe => {
+                    if let Some(task) = subscription.take() {
+                        task.abort();
+                    }
+                    let _ = tx.send(done(id)).await;
+                }
+                // Execute each call in its own task, so a slow request (e.g. a
+                // memory snapshot) does not hold back replies to other requests.
+                call => {
+                    let server = self.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let msg = match server.execute(client, call).await {
+                            Ok(value) => Message::Reply { id, value },
+                            Err(err) => Message::Error {
+                                id,
+                                message: err.to_string(),
+                            },
+                        };
+                        let _ = tx.send(msg).await;
+                    });
+                }
+            }
+        }
+
+        if let Some(task) = subscription.take() {
+            task.abort();
+        }
+        drop(tx);
+        let _ = writer_task.await;
+        self.scheduler.forget(client);
+    }
+
+    async fn handle_http<S>(self: Arc<Self>, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let client = self.next_client.fetch_add(1, Ordering::Relaxed);
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut reader = BufReader::new(reader);
+
+        let (status, content_type, body) = match read_request(&mut reader).await {
+            Ok(Some(request)) if request.path == "/ws" => {
+                match request.header("Sec-WebSocket-Key") {
+                    Some(key) if request.method == "GET" => {
+                        let handshake = websocket::handshake_response(key);
+                        if writer.write_all(handshake.as_bytes()).await.is_ok() {
+                            self.handle_websocket(reader, writer).await;
+                        }
+                        return;
+                    }
+                    _ => (400, JSON, error_body("WebSocket upgrade expected")),
+                }
+            }
+            Ok(Some(request)) => self.route(client, &request).await,
+            Ok(None) => return,
+            Err(err) => (400, JSON, error_body(&err.to_string())),
+        };
+        let _ = write_response(&mut writer, status, content_type, body.as_bytes()).await;
+        let _ = writer.shutdown().await;
+        self.scheduler.forget(client);
+    }
+
+    async fn handle_scpi<S>(self: Arc<Self>, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let client = self.next_client.fetch_add(1, Ordering::Relaxed);
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut lines = BufReader::new(reader).lines();
+        let mut errors = ErrorQueue::default();
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            let mut responses = Vec::new();
+            for command in scpi::parse_line(&line) {
+                match command {
+                    ScpiCommand::Identify => match self.interactive(client).await.ident().await {
+                        Ok(ident) => responses.push(scpi::identification(&ident)),
+                        Err(_) => errors.push(ScpiError::HARDWARE),
+                    },
+                    ScpiCommand::Measure | ScpiCommand::Read => {
+                        let result = self.interactive(client).await.live_measurement().await;
+                        let (value, error) = match result.and_then(|raw| self.decode(raw)) {
+                            Ok(measurement) => scpi::measurement_value(measurement.as_ref()),
+                            Err(_) => {
+                                let (value, _) = scpi::measurement_value(None);
+                                (value, Some(ScpiError::HARDWARE))
+                            }
+                        };
+                        responses.push(value);
+                        if let Some(error) = error {
+                            errors.push(error);
+                        }
+                    }
+                    ScpiCommand::SystemError => responses.push(errors.pop()),
+                    ScpiCommand::ClearStatus => errors.clear(),
+                    ScpiCommand::OperationComplete => responses.push(String::from("1")),
+                    ScpiCommand::Unknown(_) => errors.push(ScpiError::UNDEFINED_HEADER),
+                }
+            }
+            if !responses.is_empty() {
+                let response = format!("{}\n", responses.join(";"));
+                if writer.write_all(response.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        }
+        self.scheduler.forget(client);
+    }
+
+    /// Push measurements until the client closes the connection.
+    async fn handle_websocket<R, W>(&self, mut reader: R, mut writer: W)
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let (tx, mut rx) = mpsc::channel::<(Opcode, Vec<u8>)>(64);
+
+        let writer_task = tokio::spawn(async move {
+            while let Some((opcode, payload)) = rx.recv().await {
+                if websocket::write_frame(&mut writer, opcode, &payload)
+                    .await
+                    .is_err()
+                    || opcode == Opcode::Close
+                {
+                    break;
+                }
+            }
+            let _ = writer.shutdown().await;
+        });
+
+        let mut events = self.measurements.subscribe();
+        let forward_tx = tx.clone();
+        let forward_task = tokio::spawn(async move {
+            loop {
+                let measurement = match events.recv().await {
+                    Ok(Message::Measurement { measurement }) => measurement,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let payload = match serde_json::to_vec(&measurement) {
+                    Ok(payload) => payload,
+                    Err(_) => continue,
+                };
+                if forward_tx.send((Opcode::Text, payload)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        while let Ok(Some(frame)) = websocket::read_frame(&mut reader).await {
+            match frame.opcode {
+                Opcode::Ping => {
+                    let _ = tx.send((Opcode::Pong, frame.payload)).await;
+                }
+                Opcode::Close => {
+                    let _ = tx.send((Opcode::Close, frame.payload)).await;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        forward_task.abort();
+        drop(tx);
+        let _ = writer_task.await;
+    }
+
+    async fn route(&self, client: u64, request: &HttpRequest) -> (u16, &'static str, String) {
+        let result = match (request.method.as_str(), request.path.as_str()) {
+            #[cfg(feature = "mdns")]
+            ("GET", "/lxi/identification") if self.advertise => {
+                return match self.interactive(client).await.ident().await {
+                    Ok(ident) => (
+                        200,
+                        "text/xml",
+                        super::discovery::identification_xml(&ident),
+                    ),
+                    Err(err) => (500, JSON, error_body(&err.to_string())),
+                };
+            }
+            ("GET", "/ident") => self.execute(client, Call::Ident).await,
+            ("GET", "/measurement") => self.execute(client, Call::LiveMeasurement).await,
+            ("GET", "/memory") => self.execute(client, Call::SnapshotMemory).await,
+            ("GET", "/settings") => self.settings(client).await,
+            (_, "/ident" | "/measurement" | "/memory" | "/settings") => {
+                return (405, JSON, error_body("Method not allowed"))
+            }
+            _ => return (404, JSON, error_body("Not found")),
+        };
+        match result
+            .and_then(|value| Ok(serde_json::to_string(&value).map_err(std::io::Error::from)?))
+        {
+            Ok(body) => (200, JSON, body),
+            Err(err) => (500, JSON, error_body(&err.to_string())),
+        }
+    }
+
+    /// All device settings as one object, keyed like the [`Call`] names.
+    async fn settings(&self, client: u64) -> Result<serde_json::Value> {
+        let calls = [
+            ("backlight", Call::Backlight),
+            ("poweroff", Call::Poweroff),
+            ("operator", Call::Operator),
+            ("company", Call::Company),
+            ("site", Call::Site),
+            ("contact", Call::Contact),
+            ("beeper", Call::Beeper),
+            ("smoothing", Call::Smoothing),
+            ("clock", Call::Clock),
+            ("custom_dbm", Call::CustomDbm),
+            ("dbm_ref", Call::DbmRef),
+            ("temp_offset", Call::TempOffset),
+            ("digit_count", Call::DigitCount),
+            ("autohold_event_threshold", Call::AutoholdEventThreshold),
+            ("recording_event_threshold", Call::RecordingEventThreshold),
+            ("language", Call::Language),
+            ("date_format", Call::DateFormat),
+            ("time_format", Call::TimeFormat),
+            ("numeric_format", Call::NumericFormat),
+        ];
+        let mut settings = serde_json::Map::new();
+        for (name, call) in calls {
+            settings.insert(name.to_string(), self.execute(client, call).await?);
+        }
+        Ok(serde_json::Value::Object(settings))
+    }
+
+    fn forward_measurements(&self, tx: mpsc::Sender<Message>) -> tokio::task::JoinHandle<()> {
+        let mut events = self.measurements.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(msg) => {
+                        if tx.send(msg).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    async fn interactive(&self, client: u64) -> Permit {
+        self.scheduler.acquire(client, Priority::Interactive).await
+    }
+
+    fn decode(&self, raw: Option<RawMeasurement>) -> Result<Option<Measurement>> {
+        raw.map(|raw| Measurement::received(raw, self.maps.as_ref()))
+            .transpose()
+    }
+
+    async fn bulk(&self, client: u64) -> Permit {
+        self.scheduler.acquire(client, Priority::Bulk).await
+    }
+
+    /// Same as [`Device::snapshot_memory`], but the device is acquired for
+    /// each frame, so other clients are served in between.
+    async fn snapshot_memory(&self, client: u64) -> Result<MemorySnapshot> {
+        let maps = self.maps.as_ref();
+        let taken_at = Utc::now();
+        let ident = self.bulk(client).await.ident().await?;
+        let stats = self.bulk(client).await.memory_statistics().await?;
+
+        let mut measurements = Vec::with_capacity(stats.measurement);
+        for i in 0..stats.measurement {
+            let raw = self.bulk(client).await.saved_measurement(i).await?;
+            measurements.push((raw, maps).try_into()?);
+        }
+
+        let mut min_max = Vec::with_capacity(stats.min_max);
+        for i in 0..stats.min_max {
+            let raw = self.bulk(client).await.saved_minmax(i).await?;
+            min_max.push((raw, maps).try_into()?);
+        }
+
+        let mut peak = Vec::with_capacity(stats.peak);
+        for i in 0..stats.peak {
+            let raw = self.bulk(client).await.saved_peak(i).await?;
+            peak.push((raw, maps).try_into()?);
+        }
+
+        let mut recordings = Vec::with_capacity(stats.recordings);
+        for i in 0..stats.recordings {
+            let raw = self.bulk(client).await.saved_recording(i).await?;
+            let info = SavedRecordingSessionInfo::try_from((raw, maps))?;
+            let mut samples = Vec::with_capacity(info.num_samples as usize);
+            for sample in 0..info.num_samples as usize {
+                let raw = self
+                    .bulk(client)
+                    .await
+                    .session_record_reading(info.reading_index as usize, sample)
+                    .await?;
+                samples.push(SessionRecordReadings::try_from((raw, maps))?);
+            }
+            recordings.push(RecordingSnapshot { info, samples });
+        }
+
+        Ok(MemorySnapshot {
+            taken_at,
+            ident,
+            measurements,
+            min_max,
+            peak,
+            recordings,
+        })
+    }
+
+    async fn execute(&self, client: u64, call: Call) -> Result<serde_json::Value> {
+        match call {
+            Call::SnapshotMemory => return value(self.snapshot_memory(client).await?),
+            Call::Statistics => return value(self.scheduler.stats()),
+            Call::History { since } => {
+                return value(
+                    self.history
+                        .as_ref()
+                        .map(|history| history.lock().expect("history lock").since(since))
+                        .unwrap_or_default(),
+                )
+            }
+            _ => {}
+        }
+        let mut device = self.interactive(client).await;
+        match call {
+            Call::Ident => value(device.ident().await?),
+            Call::LiveMeasurement => value(self.decode(device.live_measurement().await?)?),
+            Call::MemoryStatistics => value(device.memory_statistics().await?),
+            Call::Backlight => value(device.backlight().await?),
+            Call::SetBacklight { duration } => value(device.set_backlight(duration).await?),
+            Call::Poweroff => value(device.poweroff().await?),
+            Call::SetPoweroff { duration } => value(device.set_poweroff(duration).await?),
+            Call::Operator => value(device.operator().await?),
+            Call::SetOperator { value: v } => value(device.set_operator(v).await?),
+            Call::Company => value(device.company().await?),
+            Call::SetCompany { value: v } => value(device.set_company(v).await?),
+            Call::Site => value(device.site().await?),
+            Call::SetSite { value: v } => value(device.set_site(v).await?),
+            Call::Contact => value(device.contact().await?),
+            Call::SetContact { value: v } => value(device.set_contact(v).await?),
+            Call::Beeper => value(device.beeper().await?),
+            Call::SetBeeper { state } => value(device.set_beeper(state).await?),
+            Call::Smoothing => value(device.smoothing().await?),
+            Call::SetSmoothing { state } => value(device.set_smoothing(state).await?),
+            Call::Clock => value(device.clock().await?),
+            Call::SetClock { clock } => value(device.set_clock(clock).await?),
+            Call::Clear { mem } => value(device.clear(mem).await?),
+            Call::Reset => value(device.reset().await?),
+            Call::CustomDbm => value(device.custom_dbm().await?),
+            Call::SetCustomDbm { dbm } => value(device.set_custom_dbm(dbm).await?),
+            Call::DbmRef => value(device.dbm_ref().await?),
+            Call::SetDbmRef { dbm } => value(device.set_dbm_ref(dbm).await?),
+            Call::TempOffset => value(device.temp_offset().await?),
+            Call::SetTempOffset { offset } => value(device.set_temp_offset(offset).await?),
+            Call::DigitCount => value(device.digit_count().await?),
+            Call::SetDigitCount { dc } => value(device.set_digit_count(dc).await?),
+            Call::AutoholdEventThreshold => value(device.autohold_event_threshold().await?),
+            Call::SetAutoholdEventThreshold { thd } => {
+                value(device.set_autohold_event_threshold(thd).await?)
+            }
+            Call::RecordingEventThreshold => value(device.recording_event_threshold().await?),
+            Call::SetRecordingEventThreshold { thd } => {
+                value(device.set_recording_event_threshold(thd).await?)
+            }
+            Call::Language => value(device.language().await?),
+            Call::SetLanguage { lang } => value(device.set_language(lang).await?),
+            Call::DateFormat => value(device.date_format().await?),
+            Call::SetDateFormat { fmt } => value(device.set_date_format(fmt).await?),
+            Call::TimeFormat => value(device.time_format().await?),
+            Call::SetTimeFormat { fmt } => value(device.set_time_format(fmt).await?),
+            Call::NumericFormat => value(device.numeric_format().await?),
+            Call::SetNumericFormat { fmt } => value(device.set_numeric_format(fmt).await?),
+            Call::SaveName { slot } => value(device.save_name(slot).await?),
+            Call::SetSaveName { slot, name } => value(device.set_save_name(slot, name).await?),
+            Call::SnapshotMemory
+            | Call::Statistics
+            | Call::History { .. }
+            | Call::Subscribe
+            | Call::Unsubscribe => value(()),
+        }
+    }
+}
+
+fn done(id: u64) -> Message {
+    Message::Reply {
+        id,
+        value: serde_json::Value::Null,
+    }
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+fn value<T: Serialize>(v: T) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(v).map_err(std::io::Error::from)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::Client;
+
+    #[tokio::test]
+    async fn test_ident_roundtrip() {
+        let device = Device::new_faked(vec![
+            '0', '\r', 'F', 'l', 'u', 'k', 'e', ',', 'x', ',', 'x', '\r',
+        ]);
+        let server = Arc::new(Server::new(device, ValueMaps::new()));
+        let (client_io, server_io) = tokio::io::duplex(1024);
+        tokio::spawn(server.handle_connection(server_io));
+
+        let client = Client::new(client_io);
+        let ident = client.ident().await.expect("ident");
+        assert_eq!(ident.model, "Fluke");
+    }
+
+    #[tokio::test]
+    async fn test_http_ident() {
+        use tokio::io::AsyncReadExt;
+
+        let device = Device::new_faked(vec![
+            '0', '\r', 'F', 'l', 'u', 'k', 'e', ',', 'x', ',', 'x', '\r',
+        ]);
+        let server = Arc::new(Server::new(device, ValueMaps::new()));
+        let (mut client_io, server_io) = tokio::io::duplex(4096);
+        tokio::spawn(server.handle_http(server_io));
+
+        client_io
+            .write_all(b"GET /ident HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .expect("request");
+        let mut response = String::new();
+        client_io
+            .read_to_string(&mut response)
+            .await
+            .expect("response");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        let (_, body) = response.split_once("\r\n\r\n").expect("body");
+        let ident: serde_json::Value = serde_json::from_str(body).expect("json");
+        assert_eq!(ident["model"], "Fluke");
+    }
+
+    #[tokio::test]
+    async fn test_scpi() {
+        let device = Device::new_faked(vec![
+            '0', '\r', 'F', 'l', 'u', 'k', 'e', ' ', '2', '8', '9', ',', 'V', '1', ',', '4', '2',
+            '\r',
+        ]);
+        let server = Arc::new(Server::new(device, ValueMaps::new()));
+        let (client_io, server_io) = tokio::io::duplex(1024);
+        tokio::spawn(server.handle_scpi(server_io));
+
+        let (reader, mut writer) = tokio::io::split(client_io);
+        let mut lines = BufReader::new(reader).lines();
+        writer
+            .write_all(b"*IDN?;FOO\nsyst:err?;SYST:ERR?\n")
+            .await
+            .expect("request");
+        assert_eq!(
+            lines.next_line().await.expect("line").as_deref(),
+            Some("FLUKE,289,42,V1")
+        );
+        assert_eq!(
+            lines.next_line().await.expect("line").as_deref(),
+            Some("-113,\"Undefined header\";0,\"No error\"")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_websocket() {
+        use tokio::io::AsyncReadExt;
+
+        let server = Arc::new(Server::new(Device::new_faked(Vec::new()), ValueMaps::new()));
+        let (mut client_io, server_io) = tokio::io::duplex(4096);
+        tokio::spawn(server.clone().handle_http(server_io));
+
+        client_io
+            .write_all(
+                b"GET /ws HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                  Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            )
+            .await
+            .expect("request");
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(client_io.read_u8().await.expect("head"));
+        }
+        let head = String::from_utf8(head).expect("utf8");
+        assert!(head.starts_with("HTTP/1.1 101 "), "{}", head);
+        assert!(head.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+        while server.measurements.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        let _ = server
+            .measurements
+            .send(Message::Measurement { measurement: None });
+        let frame = websocket::read_frame(&mut client_io)
+            .await
+            .expect("frame")
+            .expect("open");
+        assert_eq!(frame.opcode, Opcode::Text);
+        assert_eq!(frame.payload, b"null");
+
+        // Masked close frame without payload
+        client_io
+            .write_all(&[0x88, 0x80, 1, 2, 3, 4])
+            .await
+            .expect("close");
+        let frame = websocket::read_frame(&mut client_io)
+            .await
+            .expect("frame")
+            .expect("close reply");
+        assert_eq!(frame.opcode, Opcode::Close);
+        assert!(websocket::read_frame(&mut client_io)
+            .await
+            .expect("eof")
+            .is_none());
+    }
+}
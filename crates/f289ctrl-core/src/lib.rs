@@ -0,0 +1,95 @@
+//!
+//! This library provides communication with Fluke 287/289 and 28 II digital multimeters.
+//!
+//! <br>
+//!
+//! # Details
+//!
+//! - You need a Fluke IR cable attached to your DMM.
+//!
+//! - Basic setup and connection
+//!
+//!   ```no_run
+//!   use f289ctrl_core::{Device, DEFAULT_BAUDRATE};
+//!   #[tokio::main]
+//!   async fn main() -> f289ctrl_core::Result<()> {
+//!       let path = "/dev/ttyUSB0".to_string();
+//!       let mut device = Device::new(&path, DEFAULT_BAUDRATE)?;
+//!       eprintln!("Connected to: {}\n", device.ident().await?.model);
+//!       Ok(())
+//!   }
+//!   ```
+//!
+//! # Supported devices
+//!
+//!  * Fluke 287
+//!  * Fluke 289
+//!  * Fluke 28 II
+//!
+//! # Features
+//!
+//!  * `cli` - `clap::ValueEnum` for the setting types of [`proto::command`]
+//!  * `serde` - Serialize/Deserialize for decoded measurements and snapshots
+//!  * `schema` - JSON Schema generation (schemars) for all serializable types
+//!  * `ipc` - Daemon server and client to share one device between programs
+//!  * `mdns` - Announce the HTTP and SCPI servers on the LAN like LXI instruments
+//!  * `record` - Compressed recording of the raw byte stream of a session
+//!  * `fuzzing` - `Arbitrary` impls for raw measurements and commands (see `fuzz/`)
+//!  * `proptest` - proptest [`strategy`] for raw measurements and their frames
+//!  * `test-util` - Scripted [`mock`] transport, [`transcript`] replay,
+//!    captured frame [`corpus`] and [`fixture`] builders to test code using
+//!    a [`Device`]
+//!  * `sim` - Simulated meter serving the protocol, see the `f289sim` binary
+//!  * `time` - Conversion of timestamps to the `time` crate, see
+//!    [`proto::conv::ToOffsetDateTime`]
+//!
+
+pub mod analysis;
+pub mod capture;
+#[cfg(any(test, feature = "test-util"))]
+pub mod corpus;
+pub mod device;
+pub mod downsample;
+#[cfg(any(test, feature = "test-util"))]
+pub mod fixture;
+pub mod group;
+#[cfg(feature = "ipc")]
+pub mod ipc;
+pub mod maps;
+pub mod measurement;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
+pub mod model;
+pub mod monitor;
+pub mod probe;
+pub mod progress;
+pub mod proto;
+pub mod quirks;
+pub mod rawmea;
+pub mod ready;
+#[cfg(feature = "record")]
+pub mod record;
+pub mod retry;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod snapshot;
+pub mod stats;
+#[cfg(any(test, feature = "proptest"))]
+pub mod strategy;
+#[cfg(any(test, feature = "test-util"))]
+pub mod transcript;
+pub mod transport;
+
+pub use device::Device;
+pub use proto::client::ProtocolClient;
+pub use proto::Result;
+pub use ready::ReadyDevice;
+pub use transport::DmmTransport;
+
+#[cfg(unix)]
+pub const DEFAULT_TTY: &str = "/dev/ttyUSB0";
+#[cfg(windows)]
+pub const DEFAULT_TTY: &str = "COM1";
+
+/// Default Baudrate for Fluke 287 and 289.
+pub const DEFAULT_BAUDRATE: u32 = 115200;
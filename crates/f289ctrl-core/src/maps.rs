@@ -0,0 +1,264 @@
+//! Built-in value map tables.
+//!
+//! The meter reports the meaning of the numeric fields in binary
+//! measurements through `qemap`. For known firmware revisions the same
+//! tables are compiled in, so measurements can still be decoded when a
+//! query fails or is skipped. Entries taken from these tables are reported
+//! by [`fill_missing`], the device's own maps always take precedence.
+
+use crate::{device::ValueMaps, quirks::Firmware};
+
+type Table = &'static [(&'static str, &'static [(u16, &'static str)])];
+
+/// Value maps of a range of firmware revisions.
+#[derive(Debug, Copy, Clone)]
+pub struct BuiltinMaps {
+    pub firmware: Firmware,
+    pub maps: Table,
+}
+
+/// Registry of all built-in tables, the first matching entry is used.
+pub const BUILTIN_MAPS: &[BuiltinMaps] = &[BuiltinMaps {
+    firmware: Firmware::Prefix("V1."),
+    maps: V1_MAPS,
+}];
+
+const V1_MAPS: Table = &[
+    (
+        "primfunction",
+        &[
+            (0, "LIMBO"),
+            (1, "V_AC"),
+            (2, "MV_AC"),
+            (3, "V_DC"),
+            (4, "MV_DC"),
+            (5, "V_AC_OVER_DC"),
+            (6, "V_DC_OVER_AC"),
+            (7, "V_AC_PLUS_DC"),
+            (8, "MV_AC_OVER_DC"),
+            (9, "MV_DC_OVER_AC"),
+            (10, "MV_AC_PLUS_DC"),
+            (11, "A_AC"),
+            (12, "MA_AC"),
+            (13, "UA_AC"),
+            (14, "A_DC"),
+            (15, "MA_DC"),
+            (16, "UA_DC"),
+            (17, "A_AC_OVER_DC"),
+            (18, "A_DC_OVER_AC"),
+            (19, "A_AC_PLUS_DC"),
+            (20, "MA_AC_OVER_DC"),
+            (21, "MA_DC_OVER_AC"),
+            (22, "MA_AC_PLUS_DC"),
+            (23, "UA_AC_OVER_DC"),
+            (24, "UA_DC_OVER_AC"),
+            (25, "UA_AC_PLUS_DC"),
+            (26, "TEMPERATURE"),
+            (27, "OHMS"),
+            (28, "CONDUCTANCE"),
+            (29, "CONTINUITY"),
+            (30, "CAPACITANCE"),
+            (31, "DIODE_TEST"),
+            (32, "V_AC_LOZ"),
+            (33, "OHMS_LOW"),
+            (34, "CAL_V_DC_LOZ"),
+            (35, "CAL_AD_GAIN_X2"),
+            (36, "CAL_AD_GAIN_X1"),
+            (37, "CAL_RMS"),
+            (38, "CAL_FILT_AMP"),
+            (39, "CAL_DC_AMP_X5"),
+            (40, "CAL_DC_AMP_X10"),
+            (41, "CAL_NINV_AC_AMP"),
+            (42, "CAL_ISRC_500NA"),
+            (43, "CAL_COMP_TRIM_MV_DC"),
+            (44, "CAL_ACDC_AC_COMP"),
+            (45, "CAL_V_AC_LOZ"),
+            (46, "CAL_V_AC_PEAK"),
+            (47, "CAL_MV_AC_PEAK"),
+            (48, "CAL_TEMPERATURE"),
+        ],
+    ),
+    (
+        "secfunction",
+        &[
+            (0, "NONE"),
+            (1, "HERTZ"),
+            (2, "DUTY_CYCLE"),
+            (3, "PULSE_WIDTH"),
+            (4, "DBM"),
+            (5, "DBV"),
+            (6, "DBM_HERTZ"),
+            (7, "DBV_HERTZ"),
+            (8, "CREST_FACTOR"),
+            (9, "PEAK_MIN_MAX"),
+        ],
+    ),
+    ("autorange", &[(0, "MANUAL"), (1, "AUTO")]),
+    (
+        "unit",
+        &[
+            (0, "NONE"),
+            (1, "VDC"),
+            (2, "VAC"),
+            (3, "VAC_PLUS_DC"),
+            (4, "V"),
+            (5, "ADC"),
+            (6, "AAC"),
+            (7, "AAC_PLUS_DC"),
+            (8, "A"),
+            (9, "OHM"),
+            (10, "SIE"),
+            (11, "Hz"),
+            (12, "S"),
+            (13, "F"),
+            (14, "CEL"),
+            (15, "FAR"),
+            (16, "PCT"),
+            (17, "dB"),
+            (18, "dBV"),
+            (19, "dBm"),
+            (20, "CREST_FACTOR"),
+        ],
+    ),
+    ("bolt", &[(0, "OFF"), (1, "ON")]),
+    (
+        "mode",
+        &[
+            (0, "NONE"),
+            (1, "AUTO_HOLD"),
+            (2, "AUTO_SAVE"),
+            (4, "HOLD"),
+            (8, "LOW_PASS_FILTER"),
+            (16, "MIN_MAX_AVG"),
+            (32, "RECORD"),
+            (64, "REL"),
+            (128, "REL_PERCENT"),
+            (256, "CALIBRATION"),
+        ],
+    ),
+    (
+        "state",
+        &[
+            (0, "INACTIVE"),
+            (1, "INVALID"),
+            (2, "NORMAL"),
+            (3, "BLANK"),
+            (4, "DISCHARGE"),
+            (5, "OL"),
+            (6, "OL_MINUS"),
+            (7, "OPEN_TC"),
+        ],
+    ),
+    (
+        "attribute",
+        &[
+            (0, "NONE"),
+            (1, "OPEN_CIRCUIT"),
+            (2, "SHORT_CIRCUIT"),
+            (3, "GLITCH_CIRCUIT"),
+            (4, "GOOD_DIODE"),
+            (5, "LO_OHMS"),
+            (6, "NEGATIVE_EDGE"),
+            (7, "POSITIVE_EDGE"),
+            (8, "HIGH_CURRENT"),
+        ],
+    ),
+    ("recordtype", &[(0, "INPUT"), (1, "INTERVAL")]),
+    ("isstableflag", &[(0, "UNSTABLE"), (1, "STABLE")]),
+    (
+        "transientstate",
+        &[
+            (0, "NON_T"),
+            (1, "RANGE_UP"),
+            (2, "RANGE_DOWN"),
+            (3, "OVERLOAD"),
+            (4, "OPEN_TC"),
+        ],
+    ),
+];
+
+impl BuiltinMaps {
+    pub fn to_value_maps(&self) -> ValueMaps {
+        self.maps
+            .iter()
+            .map(|(key, entries)| {
+                let map = entries
+                    .iter()
+                    .map(|(id, name)| (*id, name.to_string()))
+                    .collect();
+                (key.to_string(), map)
+            })
+            .collect()
+    }
+}
+
+/// Built-in value maps for a firmware revision, `None` if it is unknown.
+pub fn builtin_maps(firmware: Option<&str>) -> Option<ValueMaps> {
+    BUILTIN_MAPS
+        .iter()
+        .find(|b| b.firmware.matches(firmware))
+        .map(BuiltinMaps::to_value_maps)
+}
+
+/// Add maps and entries from `builtin` missing in `maps`.
+///
+/// Returns what was added: the key of a whole map, or `key[id]` for a
+/// single entry.
+pub fn fill_missing(maps: &mut ValueMaps, builtin: &ValueMaps) -> Vec<String> {
+    let mut filled = Vec::new();
+    let mut keys: Vec<&String> = builtin.keys().collect();
+    keys.sort();
+    for key in keys {
+        let fallback = &builtin[key];
+        match maps.get_mut(key) {
+            Some(map) if !map.is_empty() => {
+                let mut ids: Vec<&u16> = fallback.keys().collect();
+                ids.sort();
+                for id in ids {
+                    if !map.contains_key(id) {
+                        map.insert(*id, fallback[id].clone());
+                        filled.push(format!("{}[{}]", key, id));
+                    }
+                }
+            }
+            _ => {
+                maps.insert(key.clone(), fallback.clone());
+                filled.push(key.clone());
+            }
+        }
+    }
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::VALUE_MAP_KEYS;
+
+    #[test]
+    fn test_builtin_maps() {
+        let maps = builtin_maps(Some("V1.16")).expect("V1 maps");
+        for key in VALUE_MAP_KEYS {
+            assert!(!maps[key].is_empty(), "{}", key);
+        }
+        assert_eq!(maps["state"][&2], "NORMAL");
+        assert!(builtin_maps(Some("V2.00")).is_none());
+        assert!(builtin_maps(None).is_none());
+    }
+
+    #[test]
+    fn test_fill_missing() {
+        let builtin = builtin_maps(Some("V1.16")).expect("V1 maps");
+        let mut maps = builtin.clone();
+        maps.remove("bolt");
+        maps.get_mut("unit").expect("unit").remove(&20);
+        maps.get_mut("state")
+            .expect("state")
+            .insert(2, "CUSTOM".into());
+
+        let filled = fill_missing(&mut maps, &builtin);
+        assert_eq!(filled, vec!["bolt".to_string(), "unit[20]".to_string()]);
+        assert_eq!(maps["unit"][&20], "CREST_FACTOR");
+        assert_eq!(maps["state"][&2], "CUSTOM");
+    }
+}
@@ -0,0 +1,2069 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    device::ValueMaps,
+    proto::command::NumericFormat,
+    proto::conv::{timestamp_to_datetime, timestamp_utc_offset, unit_prefix, DstFallback},
+    proto::response::AsciiMeasurement,
+    proto::ProtoError,
+    rawmea::{
+        RawMeasurement, RawReading, RawSavedMeasurement, RawSavedMinMaxMeasurement,
+        RawSavedRecordingSessionInfo, RawSessionRecordReadings,
+    },
+};
+
+/// Name of `index` in the value map `map`.
+fn map_value<'a>(maps: &'a ValueMaps, map: &str, index: u16) -> Result<&'a str, ProtoError> {
+    maps.get(map)
+        .and_then(|values| values.get(&index))
+        .map(String::as_str)
+        .ok_or_else(|| unknown_value(map, index))
+}
+
+fn unknown_value(map: &str, index: u16) -> ProtoError {
+    ProtoError::UnknownMapValue {
+        map: map.to_string(),
+        index,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[allow(non_camel_case_types)]
+pub enum PrimaryFunction {
+    V_DC,
+    TEMPERATURE,
+    A_DC,
+    V_DC_OVER_AC,
+    V_AC_OVER_DC,
+    CAL_ACDC_AC_COMP,
+    CAL_V_AC_LOZ,
+    LIMBO,
+    V_AC_LOZ,
+    OHMS_LOW,
+    CAL_RMS,
+    CAL_TEMPERATURE,
+    CAPACITANCE,
+    OHMS,
+    MA_AC,
+    V_AC_PLUS_DC,
+    MV_AC_PLUS_DC,
+    MA_DC_OVER_AC,
+    CAL_AD_GAIN_X2,
+    CAL_DC_AMP_X5,
+    MV_DC_OVER_AC,
+    A_AC,
+    CONTINUITY,
+    MV_AC,
+    MV_DC,
+    A_DC_OVER_AC,
+    CONDUCTANCE,
+    V_AC,
+    CAL_AD_GAIN_X1,
+    CAL_DC_AMP_X10,
+    UA_AC_PLUS_DC,
+    UA_DC_OVER_AC,
+    CAL_NINV_AC_AMP,
+    CAL_ISRC_500NA,
+    UA_DC,
+    UA_AC_OVER_DC,
+    A_AC_OVER_DC,
+    CAL_FILT_AMP,
+    MA_AC_OVER_DC,
+    MA_AC_PLUS_DC,
+    CAL_MV_AC_PEAK,
+    UA_AC,
+    MV_AC_OVER_DC,
+    CAL_V_DC_LOZ,
+    MA_DC,
+    DIODE_TEST,
+    CAL_COMP_TRIM_MV_DC,
+    CAL_V_AC_PEAK,
+    A_AC_PLUS_DC,
+}
+
+impl fmt::Display for PrimaryFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrimaryFunction::V_DC => f.write_str("V DC"),
+            PrimaryFunction::TEMPERATURE => f.write_str("Temperature"),
+            PrimaryFunction::A_DC => f.write_str("A DC"),
+            PrimaryFunction::V_DC_OVER_AC => f.write_str("V DC,AC"),
+            PrimaryFunction::V_AC_OVER_DC => f.write_str("V AC,DC"),
+            PrimaryFunction::CAL_ACDC_AC_COMP => f.write_str("Calibrate AC/DC AC COMP"),
+            PrimaryFunction::CAL_V_AC_LOZ => f.write_str("Calibrate V AC LOZ"),
+            PrimaryFunction::LIMBO => f.write_str("LIMBO"),
+            PrimaryFunction::V_AC_LOZ => f.write_str("V AC LoZ"),
+            PrimaryFunction::OHMS_LOW => f.write_str("LoΩ"),
+            PrimaryFunction::CAL_RMS => f.write_str("Calibrate RMS"),
+            PrimaryFunction::CAL_TEMPERATURE => f.write_str("Calibrate temperature"),
+            PrimaryFunction::CAPACITANCE => f.write_str("Capacity"),
+            PrimaryFunction::OHMS => f.write_str("Ohms"),
+            PrimaryFunction::MA_AC => f.write_str("mA AC"),
+            PrimaryFunction::V_AC_PLUS_DC => f.write_str("V AC+DC"),
+            PrimaryFunction::MV_AC_PLUS_DC => f.write_str("mV AC+DC"),
+            PrimaryFunction::MA_DC_OVER_AC => f.write_str("mA DC,AC"),
+            PrimaryFunction::CAL_AD_GAIN_X2 => f.write_str("Calibrate AD"),
+            PrimaryFunction::CAL_DC_AMP_X5 => f.write_str("Calibrate DC"),
+            PrimaryFunction::MV_DC_OVER_AC => f.write_str("mV DC,AC"),
+            PrimaryFunction::A_AC => f.write_str("A AC"),
+            PrimaryFunction::CONTINUITY => f.write_str("Continuity"),
+            PrimaryFunction::MV_AC => f.write_str("mV AC"),
+            PrimaryFunction::MV_DC => f.write_str("mv DC"),
+            PrimaryFunction::A_DC_OVER_AC => f.write_str("A DC,AC"),
+            PrimaryFunction::CONDUCTANCE => f.write_str("Conductivity"),
+            PrimaryFunction::V_AC => f.write_str("V AC"),
+            PrimaryFunction::CAL_AD_GAIN_X1 => f.write_str("Calibrate AD"),
+            PrimaryFunction::CAL_DC_AMP_X10 => f.write_str("Calibrate DC"),
+            PrimaryFunction::UA_AC_PLUS_DC => f.write_str("µA AC+DC"),
+            PrimaryFunction::UA_DC_OVER_AC => f.write_str("µA DC,AC"),
+            PrimaryFunction::CAL_NINV_AC_AMP => f.write_str("Calibrate NINV"),
+            PrimaryFunction::CAL_ISRC_500NA => f.write_str("Calibrate ISRC"),
+            PrimaryFunction::UA_DC => f.write_str("µA DC"),
+            PrimaryFunction::UA_AC_OVER_DC => f.write_str("µA AC,DC"),
+            PrimaryFunction::A_AC_OVER_DC => f.write_str("A AC,DC"),
+            PrimaryFunction::CAL_FILT_AMP => f.write_str("Calibrate Filter"),
+            PrimaryFunction::MA_AC_OVER_DC => f.write_str("mA AC,DC"),
+            PrimaryFunction::MA_AC_PLUS_DC => f.write_str("mA AC+DC"),
+            PrimaryFunction::CAL_MV_AC_PEAK => f.write_str("Calibrate MV"),
+            PrimaryFunction::UA_AC => f.write_str("µA AC"),
+            PrimaryFunction::MV_AC_OVER_DC => f.write_str("mV AC,DC"),
+            PrimaryFunction::CAL_V_DC_LOZ => f.write_str("Calibrate V DC LoZ"),
+            PrimaryFunction::MA_DC => f.write_str("mA DC"),
+            PrimaryFunction::DIODE_TEST => f.write_str("Diode test"),
+            PrimaryFunction::CAL_COMP_TRIM_MV_DC => f.write_str("Calibrate COMP"),
+            PrimaryFunction::CAL_V_AC_PEAK => f.write_str("Calibrate V AC Peak"),
+            PrimaryFunction::A_AC_PLUS_DC => f.write_str("A AC+DC"),
+        }
+    }
+}
+
+impl TryFrom<(u16, &ValueMaps)> for PrimaryFunction {
+    type Error = ProtoError;
+    // "primfunction": {3: "V_DC", 26: "TEMPERATURE", 14: "A_DC", 6: "V_DC_OVER_AC",
+    // 5: "V_AC_OVER_DC", 44: "CAL_ACDC_AC_COMP", 45: "CAL_V_AC_LOZ", 0: "LIMBO",
+    // 32: "V_AC_LOZ", 33: "OHMS_LOW", 37: "CAL_RMS", 48: "CAL_TEMPERATURE",
+    // 30: "CAPACITANCE", 27: "OHMS", 12: "MA_AC", 7: "V_AC_PLUS_DC", 10: "MV_AC_PLUS_DC",
+    // 21: "MA_DC_OVER_AC", 35: "CAL_AD_GAIN_X2", 39: "CAL_DC_AMP_X5", 9: "MV_DC_OVER_AC",
+    // 11: "A_AC", 29: "CONTINUITY", 2: "MV_AC", 4: "MV_DC", 18: "A_DC_OVER_AC",
+    // 28: "CONDUCTANCE", 1: "V_AC", 36: "CAL_AD_GAIN_X1", 40: "CAL_DC_AMP_X10",
+    // 25: "UA_AC_PLUS_DC", 24: "UA_DC_OVER_AC", 41: "CAL_NINV_AC_AMP", 42: "CAL_ISRC_500NA",
+    // 16: "UA_DC", 23: "UA_AC_OVER_DC", 17: "A_AC_OVER_DC", 38: "CAL_FILT_AMP",
+    // 20: "MA_AC_OVER_DC", 22: "MA_AC_PLUS_DC", 47: "CAL_MV_AC_PEAK", 13: "UA_AC",
+    // 8: "MV_AC_OVER_DC", 34: "CAL_V_DC_LOZ", 15: "MA_DC", 31: "DIODE_TEST",
+    // 43: "CAL_COMP_TRIM_MV_DC", 46: "CAL_V_AC_PEAK", 19: "A_AC_PLUS_DC"}
+    fn try_from(value: (u16, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+        Ok(match map_value(maps, "primfunction", value.0)? {
+            "V_DC" => Self::V_DC,
+            "TEMPERATURE" => Self::TEMPERATURE,
+            "A_DC" => Self::A_DC,
+            "V_DC_OVER_AC" => Self::V_DC_OVER_AC,
+            "V_AC_OVER_DC" => Self::V_AC_OVER_DC,
+            "CAL_ACDC_AC_COMP" => Self::CAL_ACDC_AC_COMP,
+            "CAL_V_AC_LOZ" => Self::CAL_V_AC_LOZ,
+            "LIMBO" => Self::LIMBO,
+            "V_AC_LOZ" => Self::V_AC_LOZ,
+            "OHMS_LOW" => Self::OHMS_LOW,
+            "CAL_RMS" => Self::CAL_RMS,
+            "CAL_TEMPERATURE" => Self::CAL_TEMPERATURE,
+            "CAPACITANCE" => Self::CAPACITANCE,
+            "OHMS" => Self::OHMS,
+            "MA_AC" => Self::MA_AC,
+            "V_AC_PLUS_DC" => Self::V_AC_PLUS_DC,
+            "MV_AC_PLUS_DC" => Self::MV_AC_PLUS_DC,
+            "MA_DC_OVER_AC" => Self::MA_DC_OVER_AC,
+            "CAL_AD_GAIN_X2" => Self::CAL_AD_GAIN_X2,
+            "CAL_DC_AMP_X5" => Self::CAL_DC_AMP_X5,
+            "MV_DC_OVER_AC" => Self::MV_DC_OVER_AC,
+            "A_AC" => Self::A_AC,
+            "CONTINUITY" => Self::CONTINUITY,
+            "MV_AC" => Self::MV_AC,
+            "MV_DC" => Self::MV_DC,
+            "A_DC_OVER_AC" => Self::A_DC_OVER_AC,
+            "CONDUCTANCE" => Self::CONDUCTANCE,
+            "V_AC" => Self::V_AC,
+            "CAL_AD_GAIN_X1" => Self::CAL_AD_GAIN_X1,
+            "CAL_DC_AMP_X10" => Self::CAL_DC_AMP_X10,
+            "UA_AC_PLUS_DC" => Self::UA_AC_PLUS_DC,
+            "UA_DC_OVER_AC" => Self::UA_DC_OVER_AC,
+            "CAL_NINV_AC_AMP" => Self::CAL_NINV_AC_AMP,
+            "CAL_ISRC_500NA" => Self::CAL_ISRC_500NA,
+            "UA_DC" => Self::UA_DC,
+            "UA_AC_OVER_DC" => Self::UA_AC_OVER_DC,
+            "A_AC_OVER_DC" => Self::A_AC_OVER_DC,
+            "CAL_FILT_AMP" => Self::CAL_FILT_AMP,
+            "MA_AC_OVER_DC" => Self::MA_AC_OVER_DC,
+            "MA_AC_PLUS_DC" => Self::MA_AC_PLUS_DC,
+            "CAL_MV_AC_PEAK" => Self::CAL_MV_AC_PEAK,
+            "UA_AC" => Self::UA_AC,
+            "MV_AC_OVER_DC" => Self::MV_AC_OVER_DC,
+            "CAL_V_DC_LOZ" => Self::CAL_V_DC_LOZ,
+            "MA_DC" => Self::MA_DC,
+            "DIODE_TEST" => Self::DIODE_TEST,
+            "CAL_COMP_TRIM_MV_DC" => Self::CAL_COMP_TRIM_MV_DC,
+            "CAL_V_AC_PEAK" => Self::CAL_V_AC_PEAK,
+            "A_AC_PLUS_DC" => Self::A_AC_PLUS_DC,
+            _ => return Err(unknown_value("primfunction", value.0)),
+        })
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[allow(non_camel_case_types)]
+pub enum SecondaryFunction {
+    DbmHertz,
+    None,
+    Dbm,
+    Hertz,
+    DbvHertz,
+    DutyCycle,
+    CrestFactor,
+    PeakMinMax,
+    Dbv,
+    PulseWidth,
+}
+
+impl fmt::Display for SecondaryFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecondaryFunction::DbmHertz => f.write_str("dBm Hertz"),
+            SecondaryFunction::None => f.write_str("None"),
+            SecondaryFunction::Dbm => f.write_str("dBm"),
+            SecondaryFunction::Hertz => f.write_str("Hertz"),
+            SecondaryFunction::DbvHertz => f.write_str("dBV Hertz"),
+            SecondaryFunction::DutyCycle => f.write_str("Duty Cycle"),
+            SecondaryFunction::CrestFactor => f.write_str("Crest Factor"),
+            SecondaryFunction::PeakMinMax => f.write_str("Peak Min/Max"),
+            SecondaryFunction::Dbv => f.write_str("dBV"),
+            SecondaryFunction::PulseWidth => f.write_str("Pulse width"),
+        }
+    }
+}
+
+impl TryFrom<(u16, &ValueMaps)> for SecondaryFunction {
+    type Error = ProtoError;
+    // "secfunction": {6: "DBM_HERTZ", 0: "NONE", 4: "DBM", 1: "HERTZ"
+    // 7: "DBV_HERTZ", 2: "DUTY_CYCLE", 8: "CREST_FACTOR",
+    // 9: "PEAK_MIN_MAX", 5: "DBV", 3: "PULSE_WIDTH"}
+    fn try_from(value: (u16, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+        Ok(match map_value(maps, "secfunction", value.0)? {
+            "DBM_HERTZ" => Self::DbmHertz,
+            "NONE" => Self::None,
+            "DBM" => Self::Dbm,
+            "HERTZ" => Self::Hertz,
+            "DBV_HERTZ" => Self::DbvHertz,
+            "DUTY_CYCLE" => Self::DutyCycle,
+            "CREST_FACTOR" => Self::CrestFactor,
+            "PEAK_MIN_MAX" => Self::PeakMinMax,
+            "DBV" => Self::Dbv,
+            "PULSE_WIDTH" => Self::PulseWidth,
+            _ => return Err(unknown_value("secfunction", value.0)),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Bolt(pub bool);
+
+impl TryFrom<(u16, &ValueMaps)> for Bolt {
+    type Error = ProtoError;
+    // "bolt": {0: "OFF", 1: "ON"}
+    fn try_from(value: (u16, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+        Ok(match map_value(maps, "bolt", value.0)? {
+            "ON" => Self(true),
+            "OFF" => Self(false),
+            _ => return Err(unknown_value("bolt", value.0)),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Stable(pub bool);
+
+impl TryFrom<(u16, &ValueMaps)> for Stable {
+    type Error = ProtoError;
+    // "isstableflag": {1: "STABLE", 0: "UNSTABLE"}
+    fn try_from(value: (u16, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+        Ok(match map_value(maps, "isstableflag", value.0)? {
+            "STABLE" => Self(true),
+            "UNSTABLE" => Self(false),
+            _ => return Err(unknown_value("isstableflag", value.0)),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AutoRange(pub bool);
+
+impl TryFrom<(u16, &ValueMaps)> for AutoRange {
+    type Error = ProtoError;
+    // "autorange": {1: "AUTO", 0: "MANUAL"}
+    fn try_from(value: (u16, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+        Ok(match map_value(maps, "autorange", value.0)? {
+            "AUTO" => Self(true),
+            "MANUAL" => Self(false),
+            _ => return Err(unknown_value("autorange", value.0)),
+        })
+    }
+}
+
+bitflags::bitflags! {
+    /// Active modes of a measurement.
+    ///
+    /// The bits follow the `mode` value map of known firmware, but device
+    /// values are resolved by name through the value maps. Bits named by
+    /// the device but unknown here are retained, see [`Modes::unknown_names`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct Modes: u16 {
+        const AUTO_HOLD = 1;
+        const AUTO_SAVE = 1 << 1;
+        const HOLD = 1 << 2;
+        const LOW_PASS_FILTER = 1 << 3;
+        const MIN_MAX_AVG = 1 << 4;
+        const RECORD = 1 << 5;
+        const REL = 1 << 6;
+        const REL_PERCENT = 1 << 7;
+        const CALIBRATION = 1 << 8;
+    }
+}
+
+/// Display and iteration order of the known modes
+const MODE_ORDER: [(Mode, Modes); 9] = [
+    (Mode::AutoHold, Modes::AUTO_HOLD),
+    (Mode::AutoSave, Modes::AUTO_SAVE),
+    (Mode::Hold, Modes::HOLD),
+    (Mode::LowPassFilter, Modes::LOW_PASS_FILTER),
+    (Mode::MinMaxAvg, Modes::MIN_MAX_AVG),
+    (Mode::Record, Modes::RECORD),
+    (Mode::Rel, Modes::REL),
+    (Mode::RelPercent, Modes::REL_PERCENT),
+    (Mode::Calibration, Modes::CALIBRATION),
+];
+
+impl Modes {
+    /// True if `mode` is active, [`Mode::None`] if no mode is active.
+    pub fn is(&self, mode: Mode) -> bool {
+        match mode {
+            Mode::None => self.is_empty(),
+            mode => self.contains(mode.into()),
+        }
+    }
+
+    /// Active known modes in a stable order.
+    pub fn modes(&self) -> impl Iterator<Item = Mode> + '_ {
+        MODE_ORDER
+            .iter()
+            .filter(move |(_, flag)| self.contains(*flag))
+            .map(|(mode, _)| *mode)
+    }
+
+    /// Names of active bits unknown to this library, resolved through the
+    /// `mode` value map.
+    pub fn unknown_names<'a>(&self, maps: &'a ValueMaps) -> Vec<&'a str> {
+        let unknown = self.bits() & !Self::all().bits();
+        let mut names: Vec<(u16, &str)> = maps
+            .get("mode")
+            .into_iter()
+            .flatten()
+            .filter(|(flag, _)| **flag != 0 && unknown & **flag == **flag)
+            .map(|(flag, name)| (*flag, name.as_str()))
+            .collect();
+        names.sort();
+        names.into_iter().map(|(_, name)| name).collect()
+    }
+}
+
+impl From<Mode> for Modes {
+    fn from(mode: Mode) -> Self {
+        MODE_ORDER
+            .iter()
+            .find(|(m, _)| *m == mode)
+            .map_or(Modes::empty(), |(_, flag)| *flag)
+    }
+}
+
+impl fmt::Display for Modes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut str = self.modes().map(|m| m.to_string()).collect::<Vec<String>>();
+        let unknown = self.bits() & !Self::all().bits();
+        if unknown != 0 {
+            str.push(format!("Unknown ({:#x})", unknown));
+        }
+        f.write_str(&str.join(", "))
+    }
+}
+
+/// Serialized as the list of active known modes.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Modes {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.modes())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Modes {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let modes = <Vec<Mode>>::deserialize(deserializer)?;
+        Ok(modes.into_iter().map(Modes::from).collect())
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for Modes {
+    fn schema_name() -> String {
+        "Modes".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        <Vec<Mode>>::json_schema(gen)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[allow(non_camel_case_types)]
+pub enum Mode {
+    LowPassFilter,
+    AutoSave,
+    Calibration,
+    None,
+    Hold,
+    AutoHold,
+    MinMaxAvg,
+    Record,
+    Rel,
+    RelPercent,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::LowPassFilter => f.write_str("Lowpass"),
+            Mode::AutoSave => f.write_str("Autosave"),
+            Mode::Calibration => f.write_str("Cal."),
+            Mode::None => f.write_str("None"),
+            Mode::Hold => f.write_str("Hold"),
+            Mode::AutoHold => f.write_str("Autohold"),
+            Mode::MinMaxAvg => f.write_str("Min/Max/Avg"),
+            Mode::Record => f.write_str("Recording"),
+            Mode::Rel => f.write_str("Rel."),
+            Mode::RelPercent => f.write_str("Rel. %"),
+        }
+    }
+}
+
+impl TryFrom<(u16, &ValueMaps)> for Modes {
+    type Error = ProtoError;
+    // "mode": {8: "LOW_PASS_FILTER", 2: "AUTO_SAVE", 256: "CALIBRATION", 0: "NONE",
+    //  4: "HOLD", 1: "AUTO_HOLD", 16: "MIN_MAX_AVG", 32: "RECORD", 64: "REL", 128: "REL_PERCENT"}
+    fn try_from(value: (u16, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+
+        let mut modes = Modes::empty();
+
+        let flags = maps
+            .get("mode")
+            .ok_or_else(|| unknown_value("mode", value.0))?;
+        for (flag, name) in flags {
+            if *flag != 0 && value.0 & *flag == *flag {
+                modes |= match name.as_str() {
+                    "LOW_PASS_FILTER" => Modes::LOW_PASS_FILTER,
+                    "AUTO_SAVE" => Modes::AUTO_SAVE,
+                    "CALIBRATION" => Modes::CALIBRATION,
+                    "HOLD" => Modes::HOLD,
+                    "AUTO_HOLD" => Modes::AUTO_HOLD,
+                    "MIN_MAX_AVG" => Modes::MIN_MAX_AVG,
+                    "RECORD" => Modes::RECORD,
+                    "REL" => Modes::REL,
+                    "REL_PERCENT" => Modes::REL_PERCENT,
+                    _ => Modes::from_bits_retain(*flag),
+                };
+            }
+        }
+        Ok(modes)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[allow(non_camel_case_types)]
+pub enum State {
+    Normal,
+    Discharge,
+    OL_Minus,
+    Invalid,
+    Blank,
+    Inactive,
+    OL,
+    OpenTC,
+}
+
+impl TryFrom<(u16, &ValueMaps)> for State {
+    type Error = ProtoError;
+    // "state": {2: "NORMAL", 4: "DISCHARGE", 6: "OL_MINUS", 1: "INVALID", 3: "BLANK", 0: "INACTIVE", 5: "OL", 7: "OPEN_TC"}
+    fn try_from(value: (u16, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        Self::from_name(map_value(value.1, "state", value.0)?)
+            .ok_or_else(|| unknown_value("state", value.0))
+    }
+}
+
+impl State {
+    /// State of its value map name, e.g. `NORMAL`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "NORMAL" => Self::Normal,
+            "DISCHARGE" => Self::Discharge,
+            "OL_MINUS" => Self::OL_Minus,
+            "INVALID" => Self::Invalid,
+            "BLANK" => Self::Blank,
+            "INACTIVE" => Self::Inactive,
+            "OL" => Self::OL,
+            "OPEN_TC" => Self::OpenTC,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[allow(non_camel_case_types)]
+pub enum TransientState {
+    Overload,
+    RangeUp,
+    NonT,
+    OpenTC,
+    RangeDown,
+}
+
+impl fmt::Display for TransientState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransientState::Overload => f.write_str("Overload"),
+            TransientState::RangeUp => f.write_str("Range UP"),
+            TransientState::NonT => f.write_str("NonT"),
+            TransientState::OpenTC => f.write_str("Open Thermo element"),
+            TransientState::RangeDown => f.write_str("Range DOWN"),
+        }
+    }
+}
+
+impl TryFrom<(u16, &ValueMaps)> for TransientState {
+    type Error = ProtoError;
+    // "transientstate": {3: "OVERLOAD", 1: "RANGE_UP", 0: "NON_T", 4: "OPEN_TC", 2: "RANGE_DOWN"}
+    fn try_from(value: (u16, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+        Ok(match map_value(maps, "transientstate", value.0)? {
+            "OVERLOAD" => Self::Overload,
+            "RANGE_UP" => Self::RangeUp,
+            "NON_T" => Self::NonT,
+            "OPEN_TC" => Self::OpenTC,
+            "RANGE_DOWN" => Self::RangeDown,
+            _ => return Err(unknown_value("transientstate", value.0)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[allow(non_camel_case_types)]
+pub enum Attribute {
+    LoOhms,
+    ShortCircuit,
+    OpenCircuit,
+    GoodDiode,
+    HighCurrent,
+    //None,
+    NegativeEdge,
+    GlitchCircuit,
+    PositiveEdge,
+}
+
+impl fmt::Display for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            match self {
+                Attribute::LoOhms => f.write_str("LoΩ"),
+                Attribute::ShortCircuit => f.write_str("ShortC"),
+                Attribute::OpenCircuit => f.write_str("OpenC"),
+                Attribute::GoodDiode => f.write_str("Diode OK"),
+                Attribute::HighCurrent => f.write_str("High Current"),
+                //Attribute::None => f.write_str("None"),
+                //Attribute::None => Ok(()),
+                Attribute::NegativeEdge => f.write_str("⬎"),
+                Attribute::GlitchCircuit => f.write_str("Glitch Circuit"),
+                Attribute::PositiveEdge => f.write_str("⬏"),
+            }
+        } else {
+            match self {
+                Attribute::LoOhms => f.write_str("LoOhm"),
+                Attribute::ShortCircuit => f.write_str("Short Circuit"),
+                Attribute::OpenCircuit => f.write_str("Open Circuit"),
+                Attribute::GoodDiode => f.write_str("Good Diode"),
+                Attribute::HighCurrent => f.write_str("High Current"),
+                //Attribute::None => f.write_str("None"),
+                //Attribute::None => Ok(()),
+                Attribute::NegativeEdge => f.write_str("Negative Edge"),
+                Attribute::GlitchCircuit => f.write_str("Glitch Circuit"),
+                Attribute::PositiveEdge => f.write_str("Positive Edge"),
+            }
+        }
+    }
+}
+
+impl Attribute {
+    /// Attribute of the value map index `index`, `None` for `NONE`.
+    // "attribute": {5: "LO_OHMS", 2: "SHORT_CIRCUIT", 1: "OPEN_CIRCUIT", 4: "GOOD_DIODE",
+    // 8: "HIGH_CURRENT", 0: "NONE", 6: "NEGATIVE_EDGE", 3: "GLITCH_CIRCUIT", 7: "POSITIVE_EDGE"}
+    pub fn from_map(index: u16, maps: &ValueMaps) -> Result<Option<Self>, ProtoError> {
+        Self::from_name(map_value(maps, "attribute", index)?)
+            .ok_or_else(|| unknown_value("attribute", index))
+    }
+
+    /// Attribute of its value map name, `Some(None)` for `NONE`.
+    pub fn from_name(name: &str) -> Option<Option<Self>> {
+        Some(Some(match name {
+            "LO_OHMS" => Self::LoOhms,
+            "SHORT_CIRCUIT" => Self::ShortCircuit,
+            "OPEN_CIRCUIT" => Self::OpenCircuit,
+            "GOOD_DIODE" => Self::GoodDiode,
+            "HIGH_CURRENT" => Self::HighCurrent,
+            "NONE" => return Some(None),
+            "NEGATIVE_EDGE" => Self::NegativeEdge,
+            "GLITCH_CIRCUIT" => Self::GlitchCircuit,
+            "POSITIVE_EDGE" => Self::PositiveEdge,
+            _ => return None,
+        }))
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[allow(non_camel_case_types)]
+pub enum RecordType {
+    Input,
+    Interval,
+}
+
+impl TryFrom<(u16, &ValueMaps)> for RecordType {
+    type Error = ProtoError;
+    // "recordtype": {0: "INPUT", 1: "INTERVAL"}
+    fn try_from(value: (u16, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+        Ok(match map_value(maps, "recordtype", value.0)? {
+            "INPUT" => Self::Input,
+            "INTERVAL" => Self::Interval,
+            _ => return Err(unknown_value("recordtype", value.0)),
+        })
+    }
+}
+
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordType::Input => f.write_str("Input"),
+            RecordType::Interval => f.write_str("Interval"),
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Unit {
+    Farad,
+    None,
+    Percent,
+    Seconds,
+    AmpereAC,
+    VoltAcPlusDc,
+    CEL,
+    dBV,
+    dBm,
+    dB,
+    AmpereAcPlusDc,
+    VoltDC,
+    Volt,
+    AmpereDC,
+    VoltAC,
+    Fahrenheit,
+    Ohm,
+    Siemens,
+    Hertz,
+    CrestFactor,
+    Ampere,
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::Farad => f.write_str("F"),
+            Unit::None => f.write_str(""),
+            Unit::Percent => f.write_str("%"),
+            Unit::Seconds => f.write_str("S"),
+            Unit::AmpereAC => f.write_str("AAC"),
+            Unit::VoltAcPlusDc => f.write_str("VAC+DC"),
+            Unit::CEL => f.write_str("°C"),
+            Unit::dBV => f.write_str("dBV"),
+            Unit::dBm => f.write_str("dBm"),
+            Unit::dB => f.write_str("db"),
+            Unit::AmpereAcPlusDc => f.write_str("AAC+DC"),
+            Unit::VoltDC => f.write_str("VDC"),
+            Unit::Volt => f.write_str("V"),
+            Unit::AmpereDC => f.write_str("ADC"),
+            Unit::VoltAC => f.write_str("VAC"),
+            Unit::Fahrenheit => f.write_str("°F"),
+            Unit::Ohm => f.write_str("Ω"),
+            Unit::Siemens => f.write_str("S"),
+            Unit::Hertz => f.write_str("Hz"),
+            Unit::CrestFactor => f.write_str("CF"),
+            Unit::Ampere => f.write_str("A"),
+        }
+    }
+}
+
+impl TryFrom<(u16, &ValueMaps)> for Unit {
+    type Error = ProtoError;
+    // "unit": {15: "FAR", 0: "NONE", 16: "PCT", 12: "S", 6: "AAC", 3: "VAC_PLUS_DC",
+    // 14: "CEL", 18: "dBV", 19: "dBm", 17: "dB", 7: "AAC_PLUS_DC", 1: "VDC", 4: "V",
+    // 5: "ADC", 2: "VAC", 13: "F", 9: "OHM", 10: "SIE", 11: "Hz",
+    // 20: "CREST_FACTOR", 8: "A"},
+    fn try_from(value: (u16, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        Self::from_name(map_value(value.1, "unit", value.0)?)
+            .ok_or_else(|| unknown_value("unit", value.0))
+    }
+}
+
+impl Unit {
+    /// Unit of its value map name, e.g. `VDC`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "FAR" => Self::Fahrenheit,
+            "NONE" => Self::None,
+            "PCT" => Self::Percent,
+            "S" => Self::Seconds, // ???
+            "AAC" => Self::AmpereAC,
+            "VAC_PLUS_DC" => Self::VoltAcPlusDc,
+            "CEL" => Self::CEL,
+            "dBV" => Self::dBV,
+            "dBm" => Self::dBm,
+            "dB" => Self::dB,
+            "AAC_PLUS_DC" => Self::AmpereAcPlusDc,
+            "VDC" => Self::VoltDC,
+            "V" => Self::Volt,
+            "ADC" => Self::AmpereDC,
+            "VAC" => Self::VoltAC,
+            "F" => Self::Farad,
+            "OHM" => Self::Ohm,
+            "SIE" => Self::Siemens,
+            "Hz" => Self::Hertz,
+            "CREST_FACTOR" => Self::CrestFactor,
+            "A" => Self::Ampere,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Reading {
+    pub reading_id: u16,
+    pub value: f64,
+    pub unit: Unit,
+    pub unit_multiplier: i16,
+    pub decimals: i16,
+    pub display_digits: i16,
+    pub state: State,
+    pub attribute: Option<Attribute>,
+    pub ts: DateTime<Utc>,
+    /// Raw device timestamp, seconds since 1970 in the meter's local time
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub device_ts: f64,
+    /// UTC offset in seconds assumed for `device_ts` when computing `ts`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub utc_offset: i32,
+}
+
+/// Value of a [`Reading`], see [`Reading::reading_value`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReadingValue {
+    /// Value in the base SI unit
+    Value(f64),
+    Overload,
+    OverloadNegative,
+    OpenTc,
+    Invalid,
+    Blank,
+    Inactive,
+    Discharge,
+}
+
+/// Accuracy in the form of meter specifications, `±(percent % + counts)`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Tolerance {
+    /// Percent of the expected value
+    pub percent: f64,
+    /// Counts of the least significant displayed digit
+    pub counts: u32,
+}
+
+/// Largest `decimals` value used for display, larger values are clamped.
+pub const MAX_DECIMALS: usize = 9;
+
+/// Display options of a [`Reading`] beyond width and precision.
+///
+/// The default shows readings like the meter does.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ReadingFormat {
+    /// Scientific notation, e.g. `1.234e-1 V`
+    pub scientific: bool,
+    /// Show the value with up to 15 significant digits instead of the
+    /// meter's display decimals
+    pub full_resolution: bool,
+    /// Unit multiplier of the shown prefix (e.g. `-3` for mV) instead of
+    /// the one chosen by the meter, see
+    /// [`prefix_multiplier`](crate::proto::conv::prefix_multiplier)
+    pub prefix: Option<i16>,
+    /// Omit prefix and unit
+    pub hide_unit: bool,
+}
+
+impl Reading {
+    /// Decimals used for display, clamped to `0..=MAX_DECIMALS`.
+    pub fn display_decimals(&self) -> usize {
+        self.decimals.clamp(0, MAX_DECIMALS as i16) as usize
+    }
+
+    /// False if the device reported negative or implausibly large decimals.
+    pub fn decimals_valid(&self) -> bool {
+        (0..=MAX_DECIMALS as i16).contains(&self.decimals)
+    }
+
+    /// Value in the base SI unit (V, A, Ohm, ...), `None` unless the
+    /// reading is in [`State::Normal`].
+    pub fn si_value(&self) -> Option<f64> {
+        match self.reading_value() {
+            ReadingValue::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Value and state combined, `value` is only meaningful for
+    /// [`ReadingValue::Value`].
+    pub fn reading_value(&self) -> ReadingValue {
+        match self.state {
+            State::Normal => ReadingValue::Value(self.value),
+            State::OL => ReadingValue::Overload,
+            State::OL_Minus => ReadingValue::OverloadNegative,
+            State::OpenTC => ReadingValue::OpenTc,
+            State::Invalid => ReadingValue::Invalid,
+            State::Blank => ReadingValue::Blank,
+            State::Inactive => ReadingValue::Inactive,
+            State::Discharge => ReadingValue::Discharge,
+        }
+    }
+
+    /// Value scaled by `unit_multiplier`, as shown on the meter (e.g. `1.2`
+    /// for 1.2 mV).
+    pub fn display_value(&self) -> f64 {
+        self.value / 10_f64.powi(self.unit_multiplier as i32)
+    }
+
+    /// Smallest step of the displayed value in the base SI unit, derived
+    /// from `unit_multiplier` and the display decimals.
+    pub fn resolution(&self) -> f64 {
+        10_f64.powi(self.unit_multiplier as i32 - self.display_decimals() as i32)
+    }
+
+    /// True if both readings show the same state and unit and their values
+    /// differ by at most one count of the coarser resolution.
+    pub fn approx_eq(&self, other: &Reading) -> bool {
+        if self.unit != other.unit {
+            return false;
+        }
+        match (self.reading_value(), other.reading_value()) {
+            (ReadingValue::Value(a), ReadingValue::Value(b)) => {
+                let counts = (a - b).abs() / self.resolution().max(other.resolution());
+                // Allow for binary representation errors of the values
+                counts <= 1.0 + 1e-6
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// True if the value is within `tolerance` of `expected` (base SI unit).
+    /// Readings without a value are never within tolerance.
+    pub fn within(&self, expected: f64, tolerance: Tolerance) -> bool {
+        match self.si_value() {
+            Some(value) => {
+                let limit = expected.abs() * tolerance.percent / 100.0
+                    + tolerance.counts as f64 * self.resolution();
+                (value - expected).abs() <= limit * (1.0 + 1e-9)
+            }
+            None => false,
+        }
+    }
+}
+
+/// Arithmetic on readings of the same unit.
+///
+/// Results keep `unit_multiplier`, timestamps and ids of `self` and the
+/// finer resolution of both operands, so they display like a meter reading.
+impl Reading {
+    /// Sum of both values, `None` if the units differ or either reading
+    /// has no value.
+    pub fn checked_add(&self, other: &Reading) -> Option<Reading> {
+        self.combine(other, |a, b| a + b)
+    }
+
+    /// Difference of both values, e.g. the deviation from a reference
+    /// reading. `None` if the units differ or either reading has no value.
+    pub fn checked_sub(&self, other: &Reading) -> Option<Reading> {
+        self.combine(other, |a, b| a - b)
+    }
+
+    /// Value multiplied by `factor`, e.g. for averaging or a probe ratio.
+    ///
+    /// The decimals follow the magnitude of `factor` to keep the number of
+    /// significant digits. Overload states are mirrored for negative
+    /// factors, other states are kept.
+    pub fn scale(&self, factor: f64) -> Reading {
+        let mut scaled = self.clone();
+        match self.state {
+            State::Normal => {
+                scaled.value = self.value * factor;
+                if factor != 0.0 && factor.is_finite() {
+                    let shift = factor.abs().log10().round() as i16;
+                    scaled.decimals =
+                        (self.display_decimals() as i16 - shift).clamp(0, MAX_DECIMALS as i16);
+                }
+            }
+            State::OL if factor < 0.0 => scaled.state = State::OL_Minus,
+            State::OL_Minus if factor < 0.0 => scaled.state = State::OL,
+            _ => {}
+        }
+        scaled
+    }
+
+    fn combine(&self, other: &Reading, op: impl FnOnce(f64, f64) -> f64) -> Option<Reading> {
+        if self.unit != other.unit {
+            return None;
+        }
+        let value = op(self.si_value()?, other.si_value()?);
+        // Exponent of the finest resolution in the base unit
+        let exponent = |r: &Reading| r.unit_multiplier as i32 - r.display_decimals() as i32;
+        let finest = exponent(self).min(exponent(other));
+        Some(Reading {
+            value,
+            decimals: (self.unit_multiplier as i32 - finest).clamp(0, MAX_DECIMALS as i32) as i16,
+            display_digits: self.display_digits.max(other.display_digits),
+            attribute: None,
+            ..self.clone()
+        })
+    }
+}
+
+/// Comparison of the shown values, ignoring resolution, timestamps and ids.
+impl Reading {
+    /// True if both readings show the same unit, state and value.
+    pub fn same_value(&self, other: &Reading) -> bool {
+        self.unit == other.unit && self.reading_value() == other.reading_value()
+    }
+
+    /// Readings of the same unit are ordered by value, with `OL` above and
+    /// `-OL` below all values. `None` for readings of different units or
+    /// without a comparable value (e.g. `OPEN-TC`).
+    pub fn cmp_value(&self, other: &Reading) -> Option<std::cmp::Ordering> {
+        use std::cmp::Ordering;
+
+        if self.unit != other.unit {
+            return None;
+        }
+        let rank = |value: ReadingValue| match value {
+            ReadingValue::OverloadNegative => Some(-1),
+            ReadingValue::Value(_) => Some(0),
+            ReadingValue::Overload => Some(1),
+            _ => None,
+        };
+        let (a, b) = (self.reading_value(), other.reading_value());
+        match (a, b) {
+            (ReadingValue::Value(a), ReadingValue::Value(b)) => a.partial_cmp(&b),
+            _ if a == b => Some(Ordering::Equal),
+            _ => rank(a)?.partial_cmp(&rank(b)?),
+        }
+    }
+}
+
+/// Readings of the same unit are ordered by value, see
+/// [`Reading::cmp_value`]. Readings showing the same value are only equal
+/// if all fields match, e.g. a different resolution makes them unordered;
+/// use [`Reading::same_value`] to compare the shown values only.
+impl PartialOrd for Reading {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match self.cmp_value(other)? {
+            std::cmp::Ordering::Equal if self != other => None,
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl TryFrom<(RawReading, &ValueMaps)> for Reading {
+    type Error = ProtoError;
+    fn try_from(value: (RawReading, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+        Ok(Self {
+            reading_id: value.0.reading_id,
+            value: value.0.value,
+            unit: (value.0.unit, maps).try_into()?,
+            unit_multiplier: value.0.unit_multiplier,
+            decimals: value.0.decimals,
+            display_digits: value.0.display_digits,
+            state: (value.0.state, maps).try_into()?,
+            attribute: Attribute::from_map(value.0.attribute, maps)?,
+            ts: timestamp_to_datetime(value.0.ts, DstFallback::Earliest),
+            device_ts: value.0.ts,
+            utc_offset: timestamp_utc_offset(value.0.ts, DstFallback::Earliest),
+        })
+    }
+}
+
+impl TryFrom<AsciiMeasurement> for Reading {
+    type Error = ProtoError;
+    /// Reading of a `qm` response, timestamped with the host clock as the
+    /// response has no timestamp.
+    fn try_from(value: AsciiMeasurement) -> std::result::Result<Self, Self::Error> {
+        let invalid = |what: &str, name: &str| {
+            ProtoError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unknown {} {} in ASCII measurement", what, name),
+            ))
+        };
+        let ts = Utc::now();
+        Ok(Self {
+            reading_id: 0,
+            value: value.value,
+            unit: Unit::from_name(&value.unit).ok_or_else(|| invalid("unit", &value.unit))?,
+            unit_multiplier: 0,
+            decimals: value.decimals,
+            display_digits: 0,
+            state: State::from_name(&value.state).ok_or_else(|| invalid("state", &value.state))?,
+            attribute: Attribute::from_name(&value.attribute)
+                .ok_or_else(|| invalid("attribute", &value.attribute))?,
+            ts,
+            device_ts: ts.timestamp() as f64,
+            utc_offset: 0,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Measurement {
+    pub pri_function: PrimaryFunction,
+    pub sec_function: SecondaryFunction,
+    pub auto_range: AutoRange,
+    pub unit: Unit,
+    pub range_max: f64,
+    pub unit_multiplier: i16,
+    pub bolt: Bolt,
+    pub ts: Option<DateTime<Utc>>,
+    /// Host clock when the measurement was decoded, live measurements are
+    /// decoded as soon as they are received
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub host_ts: Option<DateTime<Utc>>,
+    pub modes: Modes,
+    pub readings: Vec<Reading>,
+}
+
+impl Measurement {
+    /// Decodes a live measurement which was just received from the meter and
+    /// stamps it with the host clock. The plain conversion leaves
+    /// [`Measurement::host_ts`] empty.
+    pub fn received(raw: RawMeasurement, maps: &ValueMaps) -> Result<Self, ProtoError> {
+        let host_ts = Utc::now();
+        Ok(Self {
+            host_ts: Some(host_ts),
+            ..Self::try_from((raw, maps))?
+        })
+    }
+
+    /// Timestamp of `reading` taken from `source`.
+    ///
+    /// [`TimestampSource::Both`] selects the device clock, callers which
+    /// support two timestamps add [`Measurement::host_ts`] themselves. The
+    /// device clock is used as well if no host time is known.
+    pub fn reading_ts(&self, reading: &Reading, source: TimestampSource) -> DateTime<Utc> {
+        match (source, self.host_ts) {
+            (TimestampSource::Host, Some(host_ts)) => host_ts,
+            _ => reading.ts,
+        }
+    }
+}
+
+/// Clock used for the timestamps of live readings.
+///
+/// The meter clock is set by the user and may drift from the host clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampSource {
+    /// Time of reception on the host
+    Host,
+    /// Timestamps reported by the meter
+    #[default]
+    Device,
+    /// Both, the device clock is the primary timestamp
+    Both,
+}
+
+impl TryFrom<(RawMeasurement, &ValueMaps)> for Measurement {
+    type Error = ProtoError;
+    fn try_from(value: (RawMeasurement, &ValueMaps)) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+
+        let readings = value
+            .0
+            .readings
+            .iter()
+            .map(|rr| Reading::try_from((rr.clone(), maps)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            pri_function: (value.0.pri_function, maps).try_into()?,
+            sec_function: (value.0.sec_function, maps).try_into()?,
+            auto_range: (value.0.auto_range, maps).try_into()?,
+            unit: (value.0.unit, maps).try_into()?,
+            range_max: value.0.range_max,
+            unit_multiplier: value.0.unit_multiplier,
+            bolt: (value.0.bolt, maps).try_into()?,
+            ts: if value.0.ts as isize != 0 && value.0.ts.is_normal() {
+                Some(timestamp_to_datetime(value.0.ts, DstFallback::Earliest))
+            } else {
+                None
+            },
+            host_ts: None,
+            modes: (value.0.modes, maps).try_into()?,
+            readings,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SavedMeasurement {
+    pub seq_no: u16,
+    pub pri_function: PrimaryFunction,
+    pub sec_function: SecondaryFunction,
+    pub auto_range: AutoRange,
+    pub unit: Unit,
+    pub range_max: f64,
+    pub unit_multiplier: i16,
+    pub bolt: Bolt,
+    pub modes: Modes,
+    pub readings: Vec<Reading>,
+    pub name: String,
+}
+
+impl TryFrom<(RawSavedMeasurement, &ValueMaps)> for SavedMeasurement {
+    type Error = ProtoError;
+    fn try_from(
+        value: (RawSavedMeasurement, &ValueMaps),
+    ) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+
+        let readings = value
+            .0
+            .readings
+            .iter()
+            .map(|rr| Reading::try_from((rr.clone(), maps)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            seq_no: value.0.seq_no,
+            pri_function: (value.0.pri_function, maps).try_into()?,
+            sec_function: (value.0.sec_function, maps).try_into()?,
+            auto_range: (value.0.auto_range, maps).try_into()?,
+            unit: (value.0.unit, maps).try_into()?,
+            range_max: value.0.range_max,
+            unit_multiplier: value.0.unit_multiplier,
+            bolt: (value.0.bolt, maps).try_into()?,
+            modes: (value.0.modes, maps).try_into()?,
+            readings,
+            name: value.0.name,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SavedMinMaxMeasurement {
+    pub seq_no: u16,
+    pub ts1: DateTime<Utc>,
+    pub ts2: DateTime<Utc>,
+    pub pri_function: PrimaryFunction,
+    pub sec_function: SecondaryFunction,
+    pub auto_range: AutoRange,
+    pub unit: Unit,
+    pub range_max: f64,
+    pub unit_multiplier: i16,
+    pub bolt: Bolt,
+    pub ts3: DateTime<Utc>,
+    pub modes: Modes,
+
+    pub readings: Vec<Reading>,
+
+    pub name: String,
+}
+
+impl TryFrom<(RawSavedMinMaxMeasurement, &ValueMaps)> for SavedMinMaxMeasurement {
+    type Error = ProtoError;
+    fn try_from(
+        value: (RawSavedMinMaxMeasurement, &ValueMaps),
+    ) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+
+        let readings = value
+            .0
+            .readings
+            .iter()
+            .map(|rr| Reading::try_from((rr.clone(), maps)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            seq_no: value.0.seq_no,
+            ts1: timestamp_to_datetime(value.0.ts1, DstFallback::Earliest),
+            ts2: timestamp_to_datetime(value.0.ts2, DstFallback::Earliest),
+            pri_function: (value.0.pri_function, maps).try_into()?,
+            sec_function: (value.0.sec_function, maps).try_into()?,
+            auto_range: (value.0.auto_range, maps).try_into()?,
+            unit: (value.0.unit, maps).try_into()?,
+            range_max: value.0.range_max,
+            unit_multiplier: value.0.unit_multiplier,
+            bolt: (value.0.bolt, maps).try_into()?,
+            ts3: timestamp_to_datetime(value.0.ts3, DstFallback::Earliest),
+            modes: (value.0.modes, maps).try_into()?,
+            readings,
+            name: value.0.name,
+        })
+    }
+}
+
+pub type SavedPeakMeasurement = SavedMinMaxMeasurement;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SavedRecordingSessionInfo {
+    pub seq_no: u16,
+    pub start_ts: DateTime<Utc>,
+    pub end_ts: DateTime<Utc>,
+    pub sample_interval: f64,
+    pub event_threshold: f64,
+    pub reading_index: u16,
+    pub num_samples: u16,
+    pub pri_function: PrimaryFunction,
+    pub sec_function: SecondaryFunction,
+    pub auto_range: AutoRange,
+    pub unit: Unit,
+    pub range_max: f64,
+    pub unit_multiplier: i16,
+    pub bolt: Bolt,
+    pub modes: Modes,
+
+    pub readings: Vec<Reading>,
+
+    pub name: String,
+}
+
+impl TryFrom<(RawSavedRecordingSessionInfo, &ValueMaps)> for SavedRecordingSessionInfo {
+    type Error = ProtoError;
+    fn try_from(
+        value: (RawSavedRecordingSessionInfo, &ValueMaps),
+    ) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+
+        let readings = value
+            .0
+            .readings
+            .iter()
+            .map(|rr| Reading::try_from((rr.clone(), maps)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            seq_no: value.0.seq_no,
+            start_ts: timestamp_to_datetime(value.0.start_ts, DstFallback::Earliest),
+            end_ts: timestamp_to_datetime(value.0.end_ts, DstFallback::Earliest),
+            sample_interval: value.0.sample_interval,
+            event_threshold: value.0.event_threshold,
+            reading_index: value.0.reading_index,
+            num_samples: value.0.num_samples,
+            pri_function: (value.0.pri_function, maps).try_into()?,
+            sec_function: (value.0.sec_function, maps).try_into()?,
+            auto_range: (value.0.auto_range, maps).try_into()?,
+            unit: (value.0.unit, maps).try_into()?,
+            range_max: value.0.range_max,
+            unit_multiplier: value.0.unit_multiplier,
+            bolt: (value.0.bolt, maps).try_into()?,
+            modes: (value.0.modes, maps).try_into()?,
+            readings,
+            name: value.0.name,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SessionRecordReadings {
+    pub start_ts: DateTime<Utc>,
+    pub end_ts: DateTime<Utc>,
+    pub span_readings: [Reading; 3], // max, min, avg (avg is the sum of all samples taken)
+    pub sampling: u16,               // Count of samples accumulated in this reading
+    pub fixed_reading: Reading,
+    pub record_type: RecordType,
+    pub stable: Stable,
+    pub transient_state: TransientState,
+}
+
+impl SessionRecordReadings {
+    /// Average over the interval, the device only reports the sum of all
+    /// samples.
+    pub fn average_reading(&self) -> Reading {
+        let mut avg = self.span_readings[2].clone();
+        avg.value /= self.sampling.max(1) as f64;
+        avg
+    }
+
+    /// Length of the interval.
+    pub fn duration(&self) -> chrono::Duration {
+        self.end_ts - self.start_ts
+    }
+}
+
+impl TryFrom<(RawSessionRecordReadings, &ValueMaps)> for SessionRecordReadings {
+    type Error = ProtoError;
+    fn try_from(
+        value: (RawSessionRecordReadings, &ValueMaps),
+    ) -> std::result::Result<Self, Self::Error> {
+        let maps = value.1;
+
+        let readings: Vec<Reading> = value
+            .0
+            .span_readings
+            .iter()
+            .map(|rr| Reading::try_from((rr.clone(), maps)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            start_ts: timestamp_to_datetime(value.0.start_ts, DstFallback::Earliest),
+            end_ts: timestamp_to_datetime(value.0.end_ts, DstFallback::Earliest),
+            span_readings: readings.try_into().map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "readings must contain 3 readings",
+                )
+            })?,
+            sampling: value.0.sampling,
+            fixed_reading: Reading::try_from((value.0.fixed_reading.clone(), maps))?,
+            record_type: (value.0.record_type, maps).try_into()?,
+            stable: (value.0.stable, maps).try_into()?,
+            transient_state: (value.0.transient_state, maps).try_into()?,
+        })
+    }
+}
+
+impl fmt::Display for Reading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, NumericFormat::Point, ReadingFormat::default())
+    }
+}
+
+/// Display adapter for a [`Reading`] with a fixed decimal separator and
+/// display options.
+pub struct FormattedReading<'a> {
+    reading: &'a Reading,
+    numeric: NumericFormat,
+    format: ReadingFormat,
+}
+
+impl FormattedReading<'_> {
+    pub fn with_numeric_format(mut self, format: NumericFormat) -> Self {
+        self.numeric = format;
+        self
+    }
+
+    pub fn with_format(mut self, format: ReadingFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+impl fmt::Display for FormattedReading<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.reading.fmt_with(f, self.numeric, self.format)
+    }
+}
+
+impl Reading {
+    /// Display with the given decimal separator, e.g. matching the meter's
+    /// numeric format setting. [`Display`](fmt::Display) uses `.`.
+    pub fn with_numeric_format(&self, format: NumericFormat) -> FormattedReading<'_> {
+        FormattedReading {
+            reading: self,
+            numeric: format,
+            format: ReadingFormat::default(),
+        }
+    }
+
+    /// Display with the given options, [`Display`](fmt::Display) uses the
+    /// [default](ReadingFormat::default).
+    pub fn with_format(&self, format: ReadingFormat) -> FormattedReading<'_> {
+        FormattedReading {
+            reading: self,
+            numeric: NumericFormat::Point,
+            format,
+        }
+    }
+
+    fn fmt_with(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        numeric: NumericFormat,
+        format: ReadingFormat,
+    ) -> fmt::Result {
+        match self.reading_value() {
+            ReadingValue::Value(_) => {
+                let width = f.width().unwrap_or(0);
+                let multiplier = format.prefix.unwrap_or(self.unit_multiplier);
+                let v = self.value / 10_f64.powi(multiplier as i32);
+                // Keep the resolution of the meter when changing the prefix
+                let decimals = self.display_decimals() as i32 + multiplier as i32
+                    - self.unit_multiplier as i32;
+
+                let v = match (f.precision(), format.full_resolution) {
+                    (None, true) => {
+                        // Round off binary representation errors of the scaling
+                        let v = format!("{:.14e}", v).parse::<f64>().unwrap_or(v);
+                        if format.scientific {
+                            format!("{:>width$e}", v)
+                        } else {
+                            format!("{:>width$}", v)
+                        }
+                    }
+                    (Some(prec), _) if format.scientific => format!("{:>width$.prec$e}", v),
+                    (None, _) if format.scientific => {
+                        let exponent = if v == 0.0 || !v.is_finite() {
+                            0
+                        } else {
+                            v.abs().log10().floor() as i32
+                        };
+                        let prec = (exponent + decimals).max(0) as usize;
+                        format!("{:>width$.prec$e}", v)
+                    }
+                    (prec, _) => {
+                        let prec = prec.unwrap_or(decimals.max(0) as usize);
+                        format!("{:>width$.prec$}", v)
+                    }
+                };
+                match numeric {
+                    NumericFormat::Point => f.write_str(&v)?,
+                    NumericFormat::Comma => f.write_str(&v.replace('.', ","))?,
+                }
+                if !format.hide_unit {
+                    f.write_fmt(format_args!(" {}{}", unit_prefix(multiplier), self.unit))?;
+                }
+
+                if f.alternate() {
+                    if let Some(attr) = &self.attribute {
+                        f.write_fmt(format_args!(" {:#}", attr))?;
+                    }
+                    if !self.decimals_valid() {
+                        f.write_fmt(format_args!(" (invalid decimals {})", self.decimals))?;
+                    }
+                }
+                Ok(())
+            }
+            ReadingValue::Discharge => f.write_str("DISCHARGE"),
+            ReadingValue::OverloadNegative => f.write_str("-OL"),
+            ReadingValue::Invalid => f.write_str("INVALID"),
+            ReadingValue::Blank => f.write_str("---"),
+            ReadingValue::Inactive => f.write_str("INACTIVE"),
+            ReadingValue::Overload => f.write_str("OL"),
+            ReadingValue::OpenTc => f.write_str("OPEN-TC"),
+        }
+    }
+}
+
+/// Kind of a saved memory entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MemoryKind {
+    Measurement,
+    MinMax,
+    Peak,
+    Recording,
+}
+
+/// Metadata shared by all saved memory entries.
+pub trait MemoryEntry {
+    fn kind(&self) -> MemoryKind;
+    fn seq_no(&self) -> u16;
+    fn name(&self) -> &str;
+    /// Time the entry was saved, or started for sessions
+    fn ts(&self) -> Option<DateTime<Utc>>;
+    fn pri_function(&self) -> PrimaryFunction;
+    fn sec_function(&self) -> SecondaryFunction;
+}
+
+impl MemoryEntry for SavedMeasurement {
+    fn kind(&self) -> MemoryKind {
+        MemoryKind::Measurement
+    }
+    fn seq_no(&self) -> u16 {
+        self.seq_no
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn ts(&self) -> Option<DateTime<Utc>> {
+        self.readings.first().map(|r| r.ts)
+    }
+    fn pri_function(&self) -> PrimaryFunction {
+        self.pri_function
+    }
+    fn sec_function(&self) -> SecondaryFunction {
+        self.sec_function
+    }
+}
+
+/// Peak sessions share the type, only [`Memory`] reports [`MemoryKind::Peak`].
+impl MemoryEntry for SavedMinMaxMeasurement {
+    fn kind(&self) -> MemoryKind {
+        MemoryKind::MinMax
+    }
+    fn seq_no(&self) -> u16 {
+        self.seq_no
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn ts(&self) -> Option<DateTime<Utc>> {
+        Some(self.ts1)
+    }
+    fn pri_function(&self) -> PrimaryFunction {
+        self.pri_function
+    }
+    fn sec_function(&self) -> SecondaryFunction {
+        self.sec_function
+    }
+}
+
+impl MemoryEntry for SavedRecordingSessionInfo {
+    fn kind(&self) -> MemoryKind {
+        MemoryKind::Recording
+    }
+    fn seq_no(&self) -> u16 {
+        self.seq_no
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn ts(&self) -> Option<DateTime<Utc>> {
+        Some(self.start_ts)
+    }
+    fn pri_function(&self) -> PrimaryFunction {
+        self.pri_function
+    }
+    fn sec_function(&self) -> SecondaryFunction {
+        self.sec_function
+    }
+}
+
+pub enum Memory {
+    Measurement(SavedMeasurement),
+    MinMaxMeasurement(SavedMinMaxMeasurement),
+    PeakMeasurement(SavedPeakMeasurement),
+    Recording(SavedRecordingSessionInfo),
+}
+
+impl Memory {
+    pub fn name(&self) -> &str {
+        self.entry().name()
+    }
+
+    /// The entry behind the variant.
+    pub fn entry(&self) -> &dyn MemoryEntry {
+        match self {
+            Memory::Measurement(m) => m,
+            Memory::MinMaxMeasurement(m) => m,
+            Memory::PeakMeasurement(m) => m,
+            Memory::Recording(m) => m,
+        }
+    }
+}
+
+/// Result of [`Memory::lookup`], positions are 0-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryLookup {
+    Found(usize),
+    /// Several entries match equally well
+    Ambiguous(Vec<usize>),
+    NotFound,
+}
+
+impl Memory {
+    /// Find an entry by `query`, tried in this order: exact name, 1-based
+    /// index into `entries`, case-insensitive name, name prefix and name
+    /// substring. Prefix and substring matching ignore case.
+    pub fn lookup(entries: &[Memory], query: &str) -> MemoryLookup {
+        let names: Vec<&str> = entries.iter().map(Memory::name).collect();
+        lookup_name(&names, query)
+    }
+}
+
+fn lookup_name(names: &[&str], query: &str) -> MemoryLookup {
+    let matching = |pred: &dyn Fn(&str) -> bool| -> Vec<usize> {
+        names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| pred(name))
+            .map(|(i, _)| i)
+            .collect()
+    };
+    let query_lower = query.to_lowercase();
+
+    let mut candidates = matching(&|name| name == query);
+    if candidates.is_empty() {
+        if let Ok(index) = query.trim().parse::<usize>() {
+            if (1..=names.len()).contains(&index) {
+                return MemoryLookup::Found(index - 1);
+            }
+        }
+    }
+    let rules: [&dyn Fn(&str) -> bool; 3] = [
+        &|name| name.to_lowercase() == query_lower,
+        &|name| name.to_lowercase().starts_with(&query_lower),
+        &|name| name.to_lowercase().contains(&query_lower),
+    ];
+    for rule in rules {
+        if !candidates.is_empty() {
+            break;
+        }
+        candidates = matching(rule);
+    }
+    match candidates.len() {
+        0 => MemoryLookup::NotFound,
+        1 => MemoryLookup::Found(candidates[0]),
+        _ => MemoryLookup::Ambiguous(candidates),
+    }
+}
+
+impl MemoryEntry for Memory {
+    fn kind(&self) -> MemoryKind {
+        match self {
+            Memory::PeakMeasurement(_) => MemoryKind::Peak,
+            _ => self.entry().kind(),
+        }
+    }
+    fn seq_no(&self) -> u16 {
+        self.entry().seq_no()
+    }
+    fn name(&self) -> &str {
+        self.entry().name()
+    }
+    fn ts(&self) -> Option<DateTime<Utc>> {
+        self.entry().ts()
+    }
+    fn pri_function(&self) -> PrimaryFunction {
+        self.entry().pri_function()
+    }
+    fn sec_function(&self) -> SecondaryFunction {
+        self.entry().sec_function()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(value: f64, unit: Unit, state: State, decimals: i16) -> Reading {
+        Reading {
+            reading_id: 0,
+            value,
+            unit,
+            unit_multiplier: 0,
+            decimals,
+            display_digits: 5,
+            state,
+            attribute: None,
+            ts: DateTime::default(),
+            device_ts: 0.0,
+            utc_offset: 0,
+        }
+    }
+
+    #[test]
+    fn test_si_value() {
+        let mut r = reading(0.0123, Unit::VoltDC, State::Normal, 1);
+        r.unit_multiplier = -3;
+        assert_eq!(r.reading_value(), ReadingValue::Value(0.0123));
+        assert_eq!(r.si_value(), Some(0.0123));
+        assert!((r.display_value() - 12.3).abs() < 1e-9);
+        assert!((r.resolution() - 1e-4).abs() < 1e-12);
+        assert_eq!(r.to_string(), "12.3 mVDC");
+
+        let r = reading(9.9e37, Unit::Ohm, State::OL, 2);
+        assert_eq!(r.reading_value(), ReadingValue::Overload);
+        assert_eq!(r.si_value(), None);
+        assert!((r.resolution() - 0.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_lookup_name() {
+        let names = ["SAVE 1", "SAVE 12", "Motor", "motor", "Pump A"];
+        assert_eq!(lookup_name(&names, "motor"), MemoryLookup::Found(3));
+        assert_eq!(lookup_name(&names, "2"), MemoryLookup::Found(1));
+        assert_eq!(lookup_name(&names, "save 12"), MemoryLookup::Found(1));
+        assert_eq!(lookup_name(&names, "pump"), MemoryLookup::Found(4));
+        assert_eq!(lookup_name(&names, " a"), MemoryLookup::Found(4));
+        assert_eq!(
+            lookup_name(&names, "save"),
+            MemoryLookup::Ambiguous(vec![0, 1])
+        );
+        assert_eq!(
+            lookup_name(&names, "MOTOR"),
+            MemoryLookup::Ambiguous(vec![2, 3])
+        );
+        assert_eq!(lookup_name(&names, "6"), MemoryLookup::NotFound);
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let a = reading(1.234, Unit::VoltDC, State::Normal, 3);
+        let b = reading(1.235, Unit::VoltDC, State::Normal, 3);
+        let c = reading(1.237, Unit::VoltDC, State::Normal, 3);
+        assert!(a.approx_eq(&b));
+        assert!(!a.approx_eq(&c));
+        assert!(!a.approx_eq(&reading(1.234, Unit::VoltAC, State::Normal, 3)));
+        // Coarser resolution of the other reading decides
+        assert!(a.approx_eq(&reading(1.24, Unit::VoltDC, State::Normal, 2)));
+        assert!(reading(0.0, Unit::Ohm, State::OL, 3).approx_eq(&reading(
+            1.0,
+            Unit::Ohm,
+            State::OL,
+            2
+        )));
+
+        let tolerance = Tolerance {
+            percent: 0.1,
+            counts: 2,
+        };
+        // 0.1% of 1.24 + 2 counts = 0.00324
+        assert!(c.within(1.240, tolerance));
+        assert!(!c.within(1.241, tolerance));
+        assert!(!reading(0.0, Unit::Ohm, State::OL, 3).within(0.0, tolerance));
+    }
+
+    #[test]
+    fn test_numeric_format() {
+        let r = reading(230.4, Unit::VoltAC, State::Normal, 2);
+        assert_eq!(
+            format!("{:8}", r.with_numeric_format(NumericFormat::Comma)),
+            "  230,40 VAC"
+        );
+        assert_eq!(
+            format!("{:8}", r.with_numeric_format(NumericFormat::Point)),
+            "  230.40 VAC"
+        );
+    }
+
+    #[test]
+    fn test_reading_ordering() {
+        use std::cmp::Ordering;
+
+        let low = reading(1.5, Unit::VoltDC, State::Normal, 3);
+        let high = reading(2.5, Unit::VoltDC, State::Normal, 3);
+        assert!(low < high);
+        assert!(reading(0.0, Unit::VoltDC, State::OL, 3) > high);
+        assert!(reading(0.0, Unit::VoltDC, State::OL_Minus, 3) < low);
+        assert_eq!(low.partial_cmp(&low.clone()), Some(Ordering::Equal));
+        assert_eq!(low.cmp_value(&high), Some(Ordering::Less));
+        assert_eq!(
+            reading(0.0, Unit::VoltDC, State::OL, 3).cmp_value(&high),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            reading(0.0, Unit::VoltDC, State::OL_Minus, 3).cmp_value(&low),
+            Some(Ordering::Less)
+        );
+        // Same value at a different resolution
+        let coarse = reading(1.5, Unit::VoltDC, State::Normal, 2);
+        assert!(low.same_value(&coarse));
+        assert_eq!(low.cmp_value(&coarse), Some(Ordering::Equal));
+        assert!(low != coarse);
+        assert_eq!(low.partial_cmp(&coarse), None);
+        assert!(!low.same_value(&high));
+        assert_eq!(
+            low.cmp_value(&reading(1.5, Unit::VoltAC, State::Normal, 3)),
+            None
+        );
+        assert_eq!(
+            low.cmp_value(&reading(0.0, Unit::VoltDC, State::Blank, 3)),
+            None
+        );
+        let open = reading(0.0, Unit::CEL, State::OpenTC, 1);
+        assert!(open.same_value(&open));
+        assert_eq!(open.cmp_value(&open), Some(Ordering::Equal));
+        assert_eq!(open.partial_cmp(&open), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn test_reading_arithmetic() {
+        let mut volts = reading(0.1234, Unit::VoltDC, State::Normal, 1);
+        volts.unit_multiplier = -3;
+        let offset = reading(0.05, Unit::VoltDC, State::Normal, 2);
+
+        let sum = volts.checked_add(&offset).expect("sum");
+        assert!((sum.value - 0.1734).abs() < 1e-12);
+        assert_eq!(sum.unit_multiplier, -3);
+        assert_eq!(sum.decimals, 1);
+        assert_eq!(sum.to_string(), "173.4 mVDC");
+
+        let fine = reading(0.0001, Unit::VoltDC, State::Normal, 6);
+        let delta = volts.checked_sub(&fine).expect("delta");
+        assert_eq!(delta.decimals, 3);
+        assert_eq!(delta.to_string(), "123.300 mVDC");
+
+        assert!(volts
+            .checked_add(&reading(1.0, Unit::VoltAC, State::Normal, 3))
+            .is_none());
+        assert!(volts
+            .checked_sub(&reading(0.0, Unit::VoltDC, State::OL, 3))
+            .is_none());
+
+        let scaled = volts.scale(0.01);
+        assert!((scaled.value - 0.001234).abs() < 1e-12);
+        assert_eq!(scaled.to_string(), "1.234 mVDC");
+        assert_eq!(volts.scale(0.5).to_string(), "61.7 mVDC");
+        let overload = reading(0.0, Unit::VoltDC, State::OL, 3);
+        assert!(matches!(overload.scale(-1.0).state, State::OL_Minus));
+    }
+
+    #[test]
+    fn test_reading_format() {
+        use crate::proto::conv::prefix_multiplier;
+
+        let mut r = reading(0.12345, Unit::VoltDC, State::Normal, 2);
+        r.unit_multiplier = -3;
+        let show = |r: &Reading, format: ReadingFormat| r.with_format(format).to_string();
+        assert_eq!(show(&r, ReadingFormat::default()), "123.45 mVDC");
+        assert_eq!(
+            show(
+                &r,
+                ReadingFormat {
+                    scientific: true,
+                    ..Default::default()
+                }
+            ),
+            "1.2345e2 mVDC"
+        );
+        assert_eq!(
+            show(
+                &r,
+                ReadingFormat {
+                    prefix: prefix_multiplier(""),
+                    ..Default::default()
+                }
+            ),
+            "0.12345 VDC"
+        );
+        assert_eq!(
+            show(
+                &r,
+                ReadingFormat {
+                    scientific: true,
+                    prefix: Some(0),
+                    ..Default::default()
+                }
+            ),
+            "1.2345e-1 VDC"
+        );
+        assert_eq!(
+            show(
+                &r,
+                ReadingFormat {
+                    hide_unit: true,
+                    ..Default::default()
+                }
+            ),
+            "123.45"
+        );
+
+        r.value = 0.123456789;
+        let full = ReadingFormat {
+            full_resolution: true,
+            ..Default::default()
+        };
+        assert_eq!(show(&r, full), "123.456789 mVDC");
+        assert_eq!(
+            format!(
+                "{:.1}",
+                r.with_format(full)
+                    .with_numeric_format(NumericFormat::Comma)
+            ),
+            "123,5 mVDC"
+        );
+        let overload = reading(0.0, Unit::Ohm, State::OL, 2);
+        assert_eq!(overload.with_format(full).to_string(), "OL");
+    }
+
+    #[test]
+    fn test_host_ts() {
+        let maps = crate::maps::builtin_maps(Some("V1.16")).expect("maps");
+        let raw = RawMeasurement {
+            pri_function: 0,
+            sec_function: 0,
+            auto_range: 0,
+            unit: 0,
+            range_max: 0.0,
+            unit_multiplier: 0,
+            bolt: 0,
+            ts: 0.0,
+            modes: 0,
+            un1: 0,
+            readings: Vec::new(),
+        };
+        let decoded = Measurement::try_from((raw.clone(), &maps)).expect("measurement");
+        assert_eq!(decoded.host_ts, None);
+
+        let before = Utc::now();
+        let received = Measurement::received(raw, &maps).expect("measurement");
+        assert!(received.host_ts.expect("host_ts") >= before);
+    }
+
+    #[test]
+    fn test_unknown_map_value() {
+        let mut maps = crate::maps::builtin_maps(Some("V1.16")).expect("maps");
+        assert_eq!(Unit::try_from((4, &maps)).expect("unit"), Unit::Volt);
+        assert!(matches!(
+            Unit::try_from((99, &maps)),
+            Err(ProtoError::UnknownMapValue { ref map, index: 99 }) if map == "unit"
+        ));
+
+        maps.get_mut("unit")
+            .expect("unit")
+            .insert(99, "NEW_UNIT".to_string());
+        assert!(matches!(
+            Unit::try_from((99, &maps)),
+            Err(ProtoError::UnknownMapValue { index: 99, .. })
+        ));
+
+        maps.remove("attribute");
+        assert!(matches!(
+            Attribute::from_map(0, &maps),
+            Err(ProtoError::UnknownMapValue { ref map, index: 0 }) if map == "attribute"
+        ));
+    }
+
+    #[test]
+    fn test_modes() {
+        let mut maps = crate::maps::builtin_maps(Some("V1.16")).expect("maps");
+        maps.get_mut("mode")
+            .expect("mode")
+            .insert(512, "NEW_MODE".to_string());
+
+        let modes = Modes::try_from((64 | 4 | 512, &maps)).expect("modes");
+        assert!(modes.contains(Modes::HOLD | Modes::REL));
+        assert!(modes.is(Mode::Rel) && !modes.is(Mode::None));
+        assert_eq!(
+            modes.modes().collect::<Vec<_>>(),
+            vec![Mode::Hold, Mode::Rel]
+        );
+        assert_eq!(modes.unknown_names(&maps), vec!["NEW_MODE"]);
+        assert_eq!(modes.to_string(), "Hold, Rel., Unknown (0x200)");
+
+        let none = Modes::try_from((0, &maps)).expect("modes");
+        assert!(none.is(Mode::None));
+        assert_eq!(none.to_string(), "");
+    }
+
+    #[test]
+    fn test_memory_entry() {
+        let mea = SavedMinMaxMeasurement {
+            seq_no: 3,
+            ts1: DateTime::default(),
+            ts2: DateTime::default(),
+            pri_function: PrimaryFunction::V_DC,
+            sec_function: SecondaryFunction::None,
+            auto_range: AutoRange(true),
+            unit: Unit::VoltDC,
+            range_max: 5.0,
+            unit_multiplier: 0,
+            bolt: Bolt(false),
+            ts3: DateTime::default(),
+            modes: Modes::empty(),
+            readings: Vec::new(),
+            name: "Peak 1".to_string(),
+        };
+        assert_eq!(mea.kind(), MemoryKind::MinMax);
+
+        let memory = Memory::PeakMeasurement(mea);
+        assert_eq!(memory.kind(), MemoryKind::Peak);
+        assert_eq!(memory.seq_no(), 3);
+        assert_eq!(memory.name(), "Peak 1");
+        assert_eq!(memory.ts(), Some(DateTime::default()));
+        assert_eq!(memory.pri_function(), PrimaryFunction::V_DC);
+    }
+
+    #[test]
+    fn test_negative_decimals() {
+        // OL frames report -1 decimals on V AC and Ohm ranges
+        let r = reading(230.4, Unit::VoltAC, State::Normal, -1);
+        assert_eq!(r.display_decimals(), 0);
+        assert!(!r.decimals_valid());
+        assert_eq!(format!("{}", r), "230 VAC");
+        assert!(format!("{:#}", r).ends_with(" (invalid decimals -1)"));
+
+        let r = reading(1e12, Unit::Ohm, State::OL, -4);
+        assert_eq!(format!("{}", r), "OL");
+    }
+
+    #[test]
+    fn test_out_of_range_decimals() {
+        let r = reading(1.5, Unit::VoltDC, State::Normal, 400);
+        assert_eq!(r.display_decimals(), MAX_DECIMALS);
+        assert_eq!(format!("{}", r), "1.500000000 VDC");
+
+        let r = reading(1.5, Unit::VoltDC, State::Normal, 3);
+        assert!(r.decimals_valid());
+        assert_eq!(format!("{:#}", r), "1.500 VDC");
+    }
+}
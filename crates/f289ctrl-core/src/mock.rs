@@ -0,0 +1,264 @@
+//! Scripted in-memory transport to test code using a [`Device`] without a
+//! meter, available with the `test-util` feature.
+//!
+//! Responses are queued when the command they answer is written, so the
+//! script reads like a conversation with the meter:
+//!
+//! ```
+//! use f289ctrl_core::mock::MockTransport;
+//!
+//! #[tokio::main]
+//! async fn main() -> f289ctrl_core::Result<()> {
+//!     let (mut device, handle) = MockTransport::new()
+//!         .expect("id", "0\rFLUKE 289,V1.16,12345678\r")
+//!         .expect("qmpq operator", "0\r'Jane'\r")
+//!         .into_device();
+//!     assert_eq!(device.ident().await?.model, "FLUKE 289");
+//!     assert_eq!(device.operator().await?, "Jane");
+//!     handle.assert_done();
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::Device;
+
+/// Response of the meter to commands it doesn't know.
+const SYNTAX_ERROR: &[u8] = b"1\r";
+
+#[derive(Debug, Default)]
+struct Script {
+    /// Commands expected in this order, each answered once
+    expected: VecDeque<(String, Vec<u8>)>,
+    /// Commands answered every time they are sent
+    rules: Vec<(String, Vec<u8>)>,
+    /// Bytes not read by the device yet
+    pending: Vec<u8>,
+    /// Written bytes of an unterminated command
+    partial: Vec<u8>,
+    sent: Vec<String>,
+    unexpected: Vec<String>,
+    /// False while only raw responses were added
+    scripted: bool,
+}
+
+impl Script {
+    fn answer(&mut self, command: String) {
+        if !self.scripted {
+            self.sent.push(command);
+            return;
+        }
+        let response = match self.expected.front() {
+            Some((expected, _)) if *expected == command => self.expected.pop_front().map(|e| e.1),
+            _ => self
+                .rules
+                .iter()
+                .find(|(rule, _)| *rule == command)
+                .map(|(_, response)| response.clone()),
+        };
+        match response {
+            Some(response) => self.pending.extend_from_slice(&response),
+            None => {
+                self.unexpected.push(command.clone());
+                self.pending.extend_from_slice(SYNTAX_ERROR);
+            }
+        }
+        self.sent.push(command);
+    }
+}
+
+/// Transport answering commands from a script.
+///
+/// Commands are matched by their complete text as sent to the meter, without
+/// the terminating `\r` (e.g. `"qsrr 2,0"`). Commands neither expected next
+/// nor answered by a rule get a syntax error response, like the meter does
+/// for unknown commands, and are reported by [`MockHandle::unexpected`].
+/// Reading after all responses were consumed returns end of file, which the
+/// device reports as [`ProtoError::Abort`](crate::proto::ProtoError::Abort).
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    script: Arc<Mutex<Script>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect `command` after the previously expected ones and answer it once
+    /// with `response`, which includes the status line (`"0\r..."`).
+    pub fn expect(self, command: impl Into<String>, response: impl AsRef<[u8]>) -> Self {
+        let mut script = self.lock();
+        script.scripted = true;
+        script
+            .expected
+            .push_back((command.into(), response.as_ref().to_vec()));
+        drop(script);
+        self
+    }
+
+    /// Answer `command` with `response` whenever it is sent out of order,
+    /// e.g. for the measurements polled by a live stream.
+    pub fn always(self, command: impl Into<String>, response: impl AsRef<[u8]>) -> Self {
+        let mut script = self.lock();
+        script.scripted = true;
+        script
+            .rules
+            .push((command.into(), response.as_ref().to_vec()));
+        drop(script);
+        self
+    }
+
+    /// Make `bytes` readable immediately, regardless of the commands sent.
+    ///
+    /// Without any [`MockTransport::expect`] or [`MockTransport::always`]
+    /// the commands are only recorded, which replays a captured byte stream.
+    pub fn respond(self, bytes: impl AsRef<[u8]>) -> Self {
+        self.lock().pending.extend_from_slice(bytes.as_ref());
+        self
+    }
+
+    /// Handle to inspect the conversation after the transport was moved
+    /// into a [`Device`].
+    pub fn handle(&self) -> MockHandle {
+        MockHandle {
+            script: self.script.clone(),
+        }
+    }
+
+    /// Device talking to this transport, together with its handle.
+    pub fn into_device(self) -> (Device, MockHandle) {
+        let handle = self.handle();
+        (Device::with_transport(self), handle)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Script> {
+        self.script.lock().expect("mock script lock")
+    }
+}
+
+impl AsyncRead for MockTransport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let mut script = self.lock();
+        let len = buf.remaining().min(script.pending.len());
+        buf.put_slice(&script.pending[..len]);
+        script.pending.drain(..len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for MockTransport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let mut script = self.lock();
+        script.partial.extend_from_slice(buf);
+        while let Some(end) = script.partial.iter().position(|b| *b == b'\r') {
+            let line: Vec<u8> = script.partial.drain(..=end).collect();
+            let command = String::from_utf8_lossy(&line[..end]).into_owned();
+            script.answer(command);
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Access to the conversation of a [`MockTransport`].
+#[derive(Debug, Clone)]
+pub struct MockHandle {
+    script: Arc<Mutex<Script>>,
+}
+
+impl MockHandle {
+    /// All commands sent so far.
+    pub fn sent(&self) -> Vec<String> {
+        self.lock().sent.clone()
+    }
+
+    /// Commands which were neither expected next nor answered by a rule.
+    pub fn unexpected(&self) -> Vec<String> {
+        self.lock().unexpected.clone()
+    }
+
+    /// Expected commands which were not sent yet.
+    pub fn remaining(&self) -> Vec<String> {
+        self.lock()
+            .expected
+            .iter()
+            .map(|(command, _)| command.clone())
+            .collect()
+    }
+
+    /// True if all expected commands and no others were sent.
+    pub fn is_done(&self) -> bool {
+        let script = self.lock();
+        script.expected.is_empty() && script.unexpected.is_empty()
+    }
+
+    /// Panic unless [`MockHandle::is_done`], listing the differences.
+    pub fn assert_done(&self) {
+        assert!(
+            self.is_done(),
+            "mock conversation incomplete, not sent: {:?}, unexpected: {:?}",
+            self.remaining(),
+            self.unexpected()
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Script> {
+        self.script.lock().expect("mock script lock")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::ProtoError;
+
+    #[tokio::test]
+    async fn test_mock_transport() {
+        let (mut device, handle) = MockTransport::new()
+            .expect("id", "0\rFLUKE 289,V1.16,12345678\r")
+            .always("qmp beeper", "0\rOFF\r")
+            .expect("mp beeper,ON", "0\r")
+            .into_device();
+
+        assert!(!device.beeper().await.expect("beeper"));
+        assert_eq!(device.ident().await.expect("ident").serial, "12345678");
+        device.set_beeper(true).await.expect("set beeper");
+        assert!(!device.beeper().await.expect("beeper"));
+        handle.assert_done();
+        assert_eq!(
+            handle.sent(),
+            vec!["qmp beeper", "id", "mp beeper,ON", "qmp beeper"]
+        );
+
+        assert!(matches!(
+            device.operator().await,
+            Err(ProtoError::SyntaxError { .. })
+        ));
+        assert_eq!(handle.unexpected(), vec!["qmpq operator"]);
+        assert!(!handle.is_done());
+    }
+}
@@ -0,0 +1,118 @@
+//! Meter models and the functions they support.
+//!
+//! The Fluke 287 and the rugged 28 II lack some functions of the 289. Commands for them fail
+//! early with [`ProtoError::Unsupported`](crate::proto::ProtoError::Unsupported)
+//! instead of confusing device errors.
+
+use std::fmt;
+
+/// Model reported by `id`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Model {
+    Fluke287,
+    Fluke289,
+    Fluke28II,
+    /// Not identified yet or not known, nothing is gated
+    #[default]
+    Unknown,
+}
+
+impl From<&str> for Model {
+    /// Parse the model of an `id` response, e.g. `Fluke 289` or `FLUKE 28-II`.
+    fn from(model: &str) -> Self {
+        let name: String = model
+            .split_whitespace()
+            .skip_while(|word| word.eq_ignore_ascii_case("fluke"))
+            .flat_map(str::chars)
+            .filter(|c| *c != '-')
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        match name.as_str() {
+            "287" => Self::Fluke287,
+            "289" => Self::Fluke289,
+            "28II" => Self::Fluke28II,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fluke287 => f.write_str("Fluke 287"),
+            Self::Fluke289 => f.write_str("Fluke 289"),
+            Self::Fluke28II => f.write_str("Fluke 28 II"),
+            Self::Unknown => f.write_str("unknown model"),
+        }
+    }
+}
+
+/// Function only some models have.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Capability {
+    /// Low impedance voltage measurement
+    LoZ,
+    /// 50 Ω low resistance range
+    LowOhms,
+    /// Temperature measurement and offset setting
+    Temperature,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LoZ => f.write_str("LoZ"),
+            Self::LowOhms => f.write_str("low ohms"),
+            Self::Temperature => f.write_str("temperature"),
+        }
+    }
+}
+
+impl Model {
+    pub fn supports(&self, capability: Capability) -> bool {
+        match self {
+            Self::Fluke287 => !matches!(
+                capability,
+                Capability::LoZ | Capability::LowOhms | Capability::Temperature
+            ),
+            Self::Fluke28II => !matches!(capability, Capability::LoZ | Capability::LowOhms),
+            Self::Fluke289 | Self::Unknown => true,
+        }
+    }
+
+    /// All capabilities of the model.
+    pub fn capabilities(&self) -> Vec<Capability> {
+        [
+            Capability::LoZ,
+            Capability::LowOhms,
+            Capability::Temperature,
+        ]
+        .into_iter()
+        .filter(|capability| self.supports(*capability))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model() {
+        assert_eq!(Model::from("Fluke 287"), Model::Fluke287);
+        assert_eq!(Model::from("FLUKE 289"), Model::Fluke289);
+        assert_eq!(Model::from("Fluke"), Model::Unknown);
+        assert_eq!(Model::from("FLUKE 28-II"), Model::Fluke28II);
+        assert_eq!(Model::from("Fluke 28 II"), Model::Fluke28II);
+        assert_eq!(Model::from("FLUKE 28"), Model::Unknown);
+        assert_eq!(
+            Model::Fluke28II.capabilities(),
+            vec![Capability::Temperature]
+        );
+        assert!(!Model::Fluke287.supports(Capability::Temperature));
+        assert_eq!(Model::Fluke289.capabilities().len(), 3);
+        assert!(Model::Fluke287.capabilities().is_empty());
+    }
+}
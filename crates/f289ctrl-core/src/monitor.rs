@@ -0,0 +1,197 @@
+//! Background live polling sharing the link with other commands.
+
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::{broadcast, Mutex, MutexGuard},
+    task::JoinHandle,
+};
+
+use crate::{measurement::Measurement, ready::ReadyDevice};
+
+/// Capacity of the event channel, slow subscribers miss older events.
+const EVENT_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone)]
+pub enum MonitorEvent {
+    /// Result of one poll, `None` if the meter has no data (e.g. in setup)
+    Measurement(Option<Measurement>),
+    /// Polling failed, the monitor keeps going
+    Error(String),
+    /// Polling stopped because [`Monitor::exclusive`] was requested
+    Paused,
+    /// All exclusive access ended, polling continues
+    Resumed,
+}
+
+struct Shared {
+    device: Mutex<ReadyDevice>,
+    events: broadcast::Sender<MonitorEvent>,
+    /// Number of exclusive users, waiting or active
+    exclusive: AtomicUsize,
+    running: AtomicBool,
+}
+
+/// Polls live measurements in the background.
+///
+/// Other commands get the link with [`Monitor::exclusive`], which pauses
+/// polling until the returned guard is dropped:
+///
+/// ```no_run
+/// # async fn example(device: f289ctrl_core::ReadyDevice) -> f289ctrl_core::Result<()> {
+/// use f289ctrl_core::monitor::{Monitor, MonitorEvent};
+/// use std::time::Duration;
+///
+/// let monitor = Monitor::start(device, Duration::from_secs(1));
+/// let mut events = monitor.subscribe();
+/// tokio::spawn(async move {
+///     while let Ok(event) = events.recv().await {
+///         if let MonitorEvent::Measurement(Some(m)) = event {
+///             println!("{:?}", m.readings);
+///         }
+///     }
+/// });
+///
+/// let memory = monitor.exclusive().await.all_memory().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Monitor {
+    shared: Arc<Shared>,
+    task: JoinHandle<()>,
+}
+
+impl Monitor {
+    /// Spawn the polling task, one `qddb` every `interval`.
+    pub fn start(device: ReadyDevice, interval: Duration) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let shared = Arc::new(Shared {
+            device: Mutex::new(device),
+            events,
+            exclusive: AtomicUsize::new(0),
+            running: AtomicBool::new(true),
+        });
+        let task = tokio::spawn(poll_loop(shared.clone(), interval));
+        Self { shared, task }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MonitorEvent> {
+        self.shared.events.subscribe()
+    }
+
+    /// Pause polling and get the device, waits for a running poll to finish.
+    pub async fn exclusive(&self) -> ExclusiveGuard<'_> {
+        if self.shared.exclusive.fetch_add(1, Ordering::SeqCst) == 0 {
+            let _ = self.shared.events.send(MonitorEvent::Paused);
+        }
+        ExclusiveGuard {
+            device: self.shared.device.lock().await,
+            shared: &self.shared,
+        }
+    }
+
+    /// Stop polling after the current cycle and return the device.
+    pub async fn stop(self) -> ReadyDevice {
+        self.shared.running.store(false, Ordering::SeqCst);
+        let _ = self.task.await;
+        match Arc::try_unwrap(self.shared) {
+            Ok(shared) => shared.device.into_inner(),
+            Err(_) => unreachable!("poll task ended, no other owner left"),
+        }
+    }
+}
+
+async fn poll_loop(shared: Arc<Shared>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    while shared.running.load(Ordering::SeqCst) {
+        ticker.tick().await;
+        if shared.exclusive.load(Ordering::SeqCst) > 0 || !shared.running.load(Ordering::SeqCst) {
+            continue;
+        }
+        let event = match shared.device.lock().await.live_measurement().await {
+            Ok(measurement) => MonitorEvent::Measurement(measurement),
+            Err(err) => MonitorEvent::Error(err.to_string()),
+        };
+        let _ = shared.events.send(event);
+    }
+}
+
+/// Exclusive access to the device, polling resumes when dropped.
+pub struct ExclusiveGuard<'a> {
+    device: MutexGuard<'a, ReadyDevice>,
+    shared: &'a Shared,
+}
+
+impl Deref for ExclusiveGuard<'_> {
+    type Target = ReadyDevice;
+
+    fn deref(&self) -> &Self::Target {
+        &self.device
+    }
+}
+
+impl DerefMut for ExclusiveGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.device
+    }
+}
+
+impl Drop for ExclusiveGuard<'_> {
+    fn drop(&mut self) {
+        if self.shared.exclusive.fetch_sub(1, Ordering::SeqCst) == 1 {
+            let _ = self.shared.events.send(MonitorEvent::Resumed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{device::VALUE_MAP_KEYS, proto::response::Ident, Device};
+
+    #[tokio::test]
+    async fn test_pause_resume() {
+        let maps = VALUE_MAP_KEYS
+            .iter()
+            .map(|key| (key.to_string(), [(0, "NONE".to_string())].into()))
+            .collect();
+        let ident = Ident {
+            model: "Fluke 289".into(),
+            firmware: "V1.16".into(),
+            serial: "1".into(),
+        };
+        let device = Device::new_faked("5\r".repeat(16).chars().collect());
+        let device = ReadyDevice::new(device, ident, maps).expect("ready");
+
+        let monitor = Monitor::start(device, Duration::from_millis(5));
+        let mut events = monitor.subscribe();
+        assert!(matches!(
+            events.recv().await,
+            Ok(MonitorEvent::Measurement(None))
+        ));
+
+        let mut guard = monitor.exclusive().await;
+        assert!(matches!(guard.live_measurement().await, Ok(None)));
+        drop(guard);
+
+        let mut paused = false;
+        loop {
+            match events.recv().await.expect("event") {
+                MonitorEvent::Paused => paused = true,
+                MonitorEvent::Resumed => break,
+                MonitorEvent::Measurement(_) => {}
+                MonitorEvent::Error(err) => panic!("{}", err),
+            }
+        }
+        assert!(paused);
+        monitor.stop().await;
+    }
+}
@@ -0,0 +1,151 @@
+//! Capability self-test of a connected meter.
+
+use crate::{
+    device::Device,
+    proto::{response::Ident, ProtoError, Result},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "status", content = "error", rename_all = "snake_case")
+)]
+pub enum ProbeOutcome {
+    Supported,
+    /// Command was rejected as syntax error, the firmware does not know it
+    Unsupported,
+    /// Command is known, but could not be executed in the current device state
+    NotExecuted,
+    /// Response could not be decoded or the connection failed
+    Failed(String),
+    /// Not tried, because the connection was lost before
+    Skipped,
+}
+
+impl ProbeOutcome {
+    fn from_result<T>(result: &Result<T>) -> Self {
+        match result {
+            Ok(_) => Self::Supported,
+            Err(ProtoError::SyntaxError { .. } | ProtoError::Unsupported { .. }) => {
+                Self::Unsupported
+            }
+            Err(ProtoError::ExecutionError { .. } | ProtoError::DeviceLocked { .. }) => {
+                Self::NotExecuted
+            }
+            Err(err) => Self::Failed(err.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProbeResult {
+    pub command: String,
+    pub outcome: ProbeOutcome,
+}
+
+/// Result of [`Device::probe`], suitable for submitting to the capability matrix.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CapabilityReport {
+    /// Version of this library
+    pub library_version: String,
+    pub ident: Option<Ident>,
+    pub results: Vec<ProbeResult>,
+}
+
+impl CapabilityReport {
+    fn record<T>(&mut self, command: &str, result: Result<T>) -> Option<T> {
+        let aborted = self.results.last().map_or(false, |r| {
+            matches!(r.outcome, ProbeOutcome::Failed(_) | ProbeOutcome::Skipped)
+        }) && matches!(result, Err(ProtoError::Abort));
+        let outcome = if aborted {
+            ProbeOutcome::Skipped
+        } else {
+            ProbeOutcome::from_result(&result)
+        };
+        self.results.push(ProbeResult {
+            command: command.to_string(),
+            outcome,
+        });
+        result.ok()
+    }
+}
+
+impl Device {
+    /// Try every known query command and record which ones the meter supports.
+    ///
+    /// With `writes` enabled, setting commands are probed as well by writing
+    /// back the value that was just read, so the device configuration is not
+    /// changed. Destructive commands (clear, reset) are never probed.
+    pub async fn probe(&mut self, writes: bool) -> CapabilityReport {
+        let mut report = CapabilityReport {
+            library_version: env!("CARGO_PKG_VERSION").to_string(),
+            ident: None,
+            results: Vec::new(),
+        };
+
+        report.ident = report.record("id", self.ident().await);
+        report.record("qemap", self.value_maps().await);
+        report.record("qsls", self.memory_statistics().await);
+        report.record("qddb", self.live_measurement().await);
+        report.record("qsavname", self.save_name(1).await);
+        report.record("qmp clock", self.clock().await);
+
+        macro_rules! setting {
+            ($name:expr, $get:ident, $set:ident) => {
+                let value = report.record(concat!("qmp ", $name), self.$get().await);
+                if let (true, Some(value)) = (writes, value) {
+                    report.record(concat!("mp ", $name), self.$set(value).await);
+                }
+            };
+        }
+
+        setting!("ablto", backlight, set_backlight);
+        setting!("apoffto", poweroff, set_poweroff);
+        setting!("operator", operator, set_operator);
+        setting!("company", company, set_company);
+        setting!("site", site, set_site);
+        setting!("contact", contact, set_contact);
+        setting!("beeper", beeper, set_beeper);
+        setting!("acsmooth", smoothing, set_smoothing);
+        setting!("cusDBm", custom_dbm, set_custom_dbm);
+        setting!("dBmRef", dbm_ref, set_dbm_ref);
+        setting!("tempOs", temp_offset, set_temp_offset);
+        setting!("digits", digit_count, set_digit_count);
+        setting!(
+            "ahEventTh",
+            autohold_event_threshold,
+            set_autohold_event_threshold
+        );
+        setting!(
+            "recEventTh",
+            recording_event_threshold,
+            set_recording_event_threshold
+        );
+        setting!("lang", language, set_language);
+        setting!("dateFmt", date_format, set_date_format);
+        setting!("timeFmt", time_format, set_time_format);
+        setting!("numFmt", numeric_format, set_numeric_format);
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probe_lost_connection() {
+        let mut device = Device::new_faked(vec![
+            '0', '\r', 'F', 'l', 'u', 'k', 'e', ',', 'x', ',', 'x', '\r', '1', '\r',
+        ]);
+        let report = device.probe(false).await;
+        assert_eq!(report.results[0].outcome, ProbeOutcome::Supported);
+        assert_eq!(report.results[1].outcome, ProbeOutcome::Unsupported);
+        assert!(matches!(report.results[2].outcome, ProbeOutcome::Failed(_)));
+        assert_eq!(report.results[3].outcome, ProbeOutcome::Skipped);
+    }
+}
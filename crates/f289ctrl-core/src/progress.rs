@@ -0,0 +1,89 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Bulk operation a [`Progress`] event belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Measurements,
+    MinMax,
+    Peak,
+    Recordings,
+    RecordingSamples,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase::Measurements => f.write_str("Measurements"),
+            Phase::MinMax => f.write_str("Min/Max"),
+            Phase::Peak => f.write_str("Peak"),
+            Phase::Recordings => f.write_str("Recordings"),
+            Phase::RecordingSamples => f.write_str("Samples"),
+        }
+    }
+}
+
+/// Progress event emitted by bulk operations after each transferred item.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    pub phase: Phase,
+    /// Items transferred so far
+    pub done: usize,
+    /// Total items for this phase
+    pub total: usize,
+    /// Bytes received from the device in this phase
+    pub bytes: u64,
+    /// Estimated time until the phase is complete,
+    /// `None` until at least one item was transferred.
+    pub eta: Option<Duration>,
+}
+
+impl Progress {
+    /// Fraction of completed items, between 0.0 and 1.0.
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.done as f64 / self.total as f64
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.done >= self.total
+    }
+}
+
+/// Builds [`Progress`] events for one phase and estimates the remaining time.
+pub(crate) struct ProgressTracker {
+    phase: Phase,
+    total: usize,
+    started: Instant,
+    rx_start: u64,
+}
+
+impl ProgressTracker {
+    pub(crate) fn new(phase: Phase, total: usize, rx_start: u64) -> Self {
+        Self {
+            phase,
+            total,
+            started: Instant::now(),
+            rx_start,
+        }
+    }
+
+    pub(crate) fn update(&self, done: usize, rx_now: u64) -> Progress {
+        let eta = if done > 0 {
+            let per_item = self.started.elapsed() / done as u32;
+            Some(per_item * self.total.saturating_sub(done) as u32)
+        } else {
+            None
+        };
+        Progress {
+            phase: self.phase,
+            done,
+            total: self.total,
+            bytes: rx_now.saturating_sub(self.rx_start),
+            eta,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use self::command::Command;
+use self::response::Response;
+
+pub mod client;
+pub mod codec;
+pub mod command;
+pub mod conv;
+pub mod observer;
+pub mod response;
+
+use thiserror::Error;
+
+/// Errors of the meter protocol.
+///
+/// Errors caused by a command carry the command if it is known, see
+/// [`ProtoError::command`]. More variants may be added in the future.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ProtoError {
+    #[error("I/O error: {:?}", _0)]
+    Io(#[from] std::io::Error),
+
+    #[error("Serial I/O error: {:?}", _0)]
+    Serial(#[from] tokio_serial::Error),
+
+    #[error("Command{} was invalid or contains syntax errors", context(command))]
+    SyntaxError { command: Option<Command> },
+    #[error("Execution error{}", context(command))]
+    ExecutionError { command: Option<Command> },
+    #[error(
+        "Command{} was rejected because of the screen shown by the device",
+        context(command)
+    )]
+    DeviceLocked { command: Option<Command> },
+    #[error("Device did not respond{}", context(command))]
+    Timeout { command: Option<Command> },
+    #[error("Connection was closed")]
+    Abort,
+    #[error("Unexpected response: {:?}", _0)]
+    Unexpected(Box<Response>),
+    #[error("Remote error: {}", _0)]
+    Remote(String),
+    #[error("Lost frame synchronization, skipped {} garbage bytes", skipped)]
+    FramingError { skipped: usize },
+    #[error("Invalid argument: {}", _0)]
+    InvalidArgument(String),
+    #[error("The {} does not support {}", model, capability)]
+    Unsupported {
+        model: crate::model::Model,
+        capability: crate::model::Capability,
+    },
+    #[error(transparent)]
+    InvalidSaveName(#[from] command::SaveNameError),
+    #[error("Unknown value {} of value map {}", index, map)]
+    UnknownMapValue { map: String, index: u16 },
+}
+
+fn context(command: &Option<Command>) -> String {
+    command
+        .as_ref()
+        .map(|command| format!(" {:?}", command))
+        .unwrap_or_default()
+}
+
+impl ProtoError {
+    /// Command which caused the error, if known.
+    pub fn command(&self) -> Option<&Command> {
+        match self {
+            Self::SyntaxError { command }
+            | Self::ExecutionError { command }
+            | Self::DeviceLocked { command }
+            | Self::Timeout { command } => command.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Attach `command` to errors caused by a command which don't know it yet.
+    pub fn with_command(mut self, command: Command) -> Self {
+        if let Self::SyntaxError { command: slot }
+        | Self::ExecutionError { command: slot }
+        | Self::DeviceLocked { command: slot }
+        | Self::Timeout { command: slot } = &mut self
+        {
+            slot.get_or_insert(command);
+        }
+        self
+    }
+}
+
+impl From<Response> for ProtoError {
+    fn from(value: Response) -> Self {
+        match value {
+            Response::SyntaxError => Self::SyntaxError { command: None },
+            Response::ExecutionError => Self::ExecutionError { command: None },
+            Response::Success(_) => Self::Unexpected(value.into()),
+            Response::NoData => Self::Unexpected(value.into()),
+            Response::FramingError(skipped) => Self::FramingError { skipped },
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ProtoError>;
@@ -0,0 +1,112 @@
+use futures::{SinkExt, StreamExt};
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+use tokio_util::codec::Framed;
+
+use super::{
+    codec::{FrameLimits, ProtocolCodec},
+    command::Command,
+    observer::{ObserverSlot, ProtocolObserver},
+    response::Response,
+};
+use crate::{quirks::Quirks, transport::DmmTransport};
+
+trait AsyncReadWrite<S>: futures::Sink<S> + futures::Stream {}
+
+impl<T, S> AsyncReadWrite<S> for T where T: futures::Sink<S> + futures::Stream {}
+
+/// Low-level access to the meter protocol.
+///
+/// Commands are encoded and responses decoded, but nothing else is done:
+/// no error mapping, no retries and no tolerance for garbage between frames
+/// (reported as [`Response::FramingError`]). Several commands may be sent
+/// before reading their responses, they are answered in order.
+///
+/// ```no_run
+/// # async fn example() -> std::io::Result<()> {
+/// use f289ctrl_core::proto::{client::ProtocolClient, command::Command};
+///
+/// let stream = tokio::net::TcpStream::connect("10.0.0.5:4001").await?;
+/// let mut client = ProtocolClient::new(stream);
+/// client.send(Command::Id).await?;
+/// client.send(Command::GetBeeper).await?;
+/// let ident = client.recv().await;
+/// let beeper = client.recv().await;
+/// # Ok(())
+/// # }
+/// ```
+#[allow(clippy::type_complexity)]
+pub struct ProtocolClient {
+    stream: Pin<
+        Box<
+            dyn AsyncReadWrite<
+                    Command,
+                    Error = io::Error,
+                    Item = std::result::Result<Response, io::Error>,
+                > + Send,
+        >,
+    >,
+    rx_bytes: Arc<AtomicU64>,
+    observer: ObserverSlot,
+    quirks: Arc<RwLock<Quirks>>,
+}
+
+impl ProtocolClient {
+    pub fn new(transport: impl DmmTransport + 'static) -> Self {
+        Self::with_limits(transport, FrameLimits::default())
+    }
+
+    /// Client with bounded decoder memory, see [`FrameLimits`].
+    pub fn with_limits(transport: impl DmmTransport + 'static, limits: FrameLimits) -> Self {
+        let codec = ProtocolCodec::with_limits(limits);
+        let rx_bytes = codec.rx_counter();
+        let observer = codec.observer_slot();
+        let quirks = codec.quirks_slot();
+
+        Self {
+            stream: Box::pin(Framed::with_capacity(transport, codec, limits.read_buffer)),
+            rx_bytes,
+            observer,
+            quirks,
+        }
+    }
+
+    /// Encode and flush a command.
+    pub async fn send(&mut self, command: Command) -> io::Result<()> {
+        self.stream.send(command).await
+    }
+
+    /// Decode the next response, `None` if the transport was closed.
+    pub async fn recv(&mut self) -> Option<io::Result<Response>> {
+        self.stream.next().await
+    }
+
+    /// Total bytes received since the connection was opened.
+    pub fn rx_bytes(&self) -> u64 {
+        self.rx_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Trace all commands and responses, replacing a previous observer.
+    pub fn set_observer(&mut self, observer: impl ProtocolObserver + 'static) {
+        *self.observer.lock().expect("observer lock") = Some(Box::new(observer));
+    }
+
+    pub fn clear_observer(&mut self) {
+        *self.observer.lock().expect("observer lock") = None;
+    }
+
+    /// Firmware quirks applied while decoding.
+    pub fn quirks(&self) -> Quirks {
+        self.quirks.read().expect("quirks lock").clone()
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        *self.quirks.write().expect("quirks lock") = quirks;
+    }
+}
@@ -1,41 +1,138 @@
 use bytes::BytesMut;
+use chrono::Utc;
 use std::{
+    collections::VecDeque,
     fmt::{self, Write},
     io::{self},
     str,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
     time::Duration,
 };
 use tokio_util::codec::{Decoder, Encoder};
 
 use crate::proto::command::Command;
+use crate::proto::observer::ObserverSlot;
+use crate::quirks::Quirks;
 use crate::{
     device::ValueMap,
-    proto::response::{Ident, MemoryStat, Response, ResponsePayload},
-    rawmea::{RawMeasurement, RawSavedMeasurement},
+    proto::response::{AsciiMeasurement, Ident, MemoryStat, Response, ResponsePayload},
     rawmea::{
-        RawSavedMinMaxMeasurement, RawSavedPeakMeasurement, RawSavedRecordingSessionInfo,
-        RawSessionRecordReadings, BIN_MARKER_LEN, MEA_METADATA_LEN, READING_LEN,
+        readings_len, RawSavedMinMaxMeasurement, RawSavedPeakMeasurement,
+        RawSavedRecordingSessionInfo, RawSessionRecordReadings, BIN_MARKER_LEN, MAX_FRAME_LEN,
+        MAX_MEASUREMENT_READINGS, MAX_READINGS, MEA_METADATA_LEN, READING_LEN,
+        SAVED_RECORDING_METADATA_LEN,
     },
+    rawmea::{RawMeasurement, RawSavedMeasurement},
 };
 
 use super::command::{
-    ClearMemory, DateFormat, DezibelReference, DigitCount, Language, NumericFormat, TimeFormat,
+    ClearMemory, DateFormat, DezibelReference, DigitCount, Key, Language, NumericFormat, TimeFormat,
 };
 
 const STATUS_LEN: usize = 2;
 const EOL_LEN: usize = 1; // one byte for '\r'
 
+/// Status codes a response frame can start with
+const STATUS_CODES: &[u8] = b"0125";
+
+/// Memory bounds for decoding.
+///
+/// The read buffer starts at `read_buffer` bytes and grows only while a frame
+/// is incomplete; a frame longer than `max_frame_len` is rejected with an
+/// error. Worst-case memory per connection is therefore about
+/// `2 * max(read_buffer, max_frame_len)` for the read buffer, plus one
+/// scratch buffer of `max_frame_len` bytes while an observer is installed.
+/// With the defaults this stays below 16 KiB.
+///
+/// The largest regular responses (saved measurements with many readings)
+/// take about 2 KiB, lower `max_frame_len` values make those commands fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameLimits {
+    pub max_frame_len: usize,
+    pub read_buffer: usize,
+}
+
+impl Default for FrameLimits {
+    fn default() -> Self {
+        Self {
+            max_frame_len: MAX_FRAME_LEN,
+            read_buffer: 1024,
+        }
+    }
+}
+
+impl FrameLimits {
+    /// Smallest limits still able to decode every response.
+    pub fn compact() -> Self {
+        Self {
+            max_frame_len: STATUS_LEN
+                + BIN_MARKER_LEN
+                + SAVED_RECORDING_METADATA_LEN
+                + MAX_READINGS * READING_LEN
+                + 64,
+            read_buffer: 256,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct ProtocolCodec {
-    last_cmd: Option<Command>,
+    /// Commands sent but not yet answered, oldest first. Responses arrive in
+    /// the order the commands were sent, so several commands can be in flight.
+    pending: VecDeque<Command>,
+    rx_bytes: Arc<AtomicU64>,
+    observer: ObserverSlot,
+    quirks: Arc<RwLock<Quirks>>,
+    limits: FrameLimits,
+    /// Copy of the received bytes for the observer, reused across decodes
+    scratch: Vec<u8>,
 }
 
 impl ProtocolCodec {
+    pub fn with_limits(limits: FrameLimits) -> Self {
+        Self {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    pub fn limits(&self) -> FrameLimits {
+        self.limits
+    }
+
+    /// Shared counter of all bytes consumed by the decoder.
+    pub fn rx_counter(&self) -> Arc<AtomicU64> {
+        self.rx_bytes.clone()
+    }
+
+    pub(crate) fn quirks_slot(&self) -> Arc<RwLock<Quirks>> {
+        self.quirks.clone()
+    }
+
+    pub(crate) fn observer_slot(&self) -> ObserverSlot {
+        self.observer.clone()
+    }
+
+    /// Number of commands still waiting for a response.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
     pub(crate) fn get_payload(src: &BytesMut) -> Option<Vec<u8>> {
         let offset = src.as_ref().iter().skip(2).position(|b| *b == b'\r');
         offset.map(|n| Vec::from(&src[2..n + 2]))
     }
 
+    /// Setting value with firmware quirks applied.
+    fn setting_value(&self, setting: &str, payload: impl AsRef<[u8]>) -> std::io::Result<String> {
+        let line = Self::convert_string(payload)?;
+        let quirks = self.quirks.read().expect("quirks lock");
+        Ok(quirks.setting_value(setting, &line).to_string())
+    }
+
     fn convert_string(payload: impl AsRef<[u8]>) -> std::io::Result<String> {
         Ok(str::from_utf8(payload.as_ref())
             .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
@@ -52,6 +149,47 @@ impl Decoder for ProtocolCodec {
     type Error = io::Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let available = src.len();
+        let observer = self.observer.clone();
+        let mut observer = observer.lock().expect("observer lock");
+        if observer.is_some() {
+            self.scratch.clear();
+            self.scratch.extend_from_slice(src);
+        }
+        let result = if !src.is_empty() && !is_frame_start(src, 0) {
+            // Stray bytes (noise, rest of an earlier response), skip to the
+            // next plausible frame start instead of failing the stream.
+            let skipped = resync_offset(src);
+            let _ = src.split_to(skipped);
+            Ok(Some(Response::FramingError(skipped)))
+        } else {
+            let result = match self.decode_frame(src) {
+                // A frame can't grow this large, don't buffer forever
+                Ok(None) if src.len() > self.limits.max_frame_len => Err(invalid_data(format!(
+                    "Response frame exceeds maximum length of {} bytes",
+                    self.limits.max_frame_len
+                ))),
+                result => result,
+            };
+            // A complete (or broken) frame answers the oldest pending command
+            if !matches!(result, Ok(None)) {
+                self.pending.pop_front();
+            }
+            result
+        };
+        let consumed = available - src.len();
+        self.rx_bytes.fetch_add(consumed as u64, Ordering::Relaxed);
+        if let Some(observer) = observer.as_mut() {
+            if consumed > 0 {
+                observer.on_rx(Utc::now(), &self.scratch[..consumed]);
+            }
+        }
+        result
+    }
+}
+
+impl ProtocolCodec {
+    fn decode_frame(&mut self, src: &mut BytesMut) -> Result<Option<Response>, io::Error> {
         if src.len() >= 2 {
             if (src[1] as char) != '\r' {
                 return Err(io::Error::new(
@@ -63,7 +201,7 @@ impl Decoder for ProtocolCodec {
                 '0' => {
                     // Success
 
-                    match self.last_cmd {
+                    match self.pending.front() {
                         Some(Command::SetBacklightTimeout(_))
                         | Some(Command::SetDevicePowerOff(_))
                         | Some(Command::SetOperator(_))
@@ -85,7 +223,8 @@ impl Decoder for ProtocolCodec {
                         | Some(Command::SetNumFormat(_))
                         | Some(Command::SetDbmRef(_))
                         | Some(Command::SetTempOffset(_))
-                        | Some(Command::SetClock(_)) => {
+                        | Some(Command::SetClock(_))
+                        | Some(Command::PressKey(_)) => {
                             let _ = src.split_to(2);
                             Ok(Some(Response::Success(None)))
                         }
@@ -109,7 +248,6 @@ impl Decoder for ProtocolCodec {
                                     .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
                                     .to_string();
                                 let mut values: Vec<&str> = value.split(',').collect();
-                                assert!(!values.is_empty());
 
                                 let c = values[0]
                                     .parse::<usize>()
@@ -124,7 +262,13 @@ impl Decoder for ProtocolCodec {
                                     value_map.insert(id, name.to_string());
                                 }
 
-                                assert_eq!(c, value_map.len());
+                                if c != value_map.len() {
+                                    return Err(invalid_data(format!(
+                                        "Map entry count mismatch: announced {}, received {}",
+                                        c,
+                                        value_map.len()
+                                    )));
+                                }
 
                                 Ok(Some(Response::Success(Some(ResponsePayload::Map(
                                     value_map,
@@ -221,7 +365,7 @@ impl Decoder for ProtocolCodec {
 
                         Some(Command::GetBeeper) => {
                             if let Some(payload) = Self::get_payload(src) {
-                                let line = Self::convert_string(&payload)?;
+                                let line = self.setting_value("beeper", &payload)?;
                                 let state = line.eq("ON");
                                 let _ = src.split_to(2 + payload.len() + 1);
                                 Ok(Some(Response::Success(Some(ResponsePayload::Beeper(
@@ -234,7 +378,7 @@ impl Decoder for ProtocolCodec {
 
                         Some(Command::GetSmoothing) => {
                             if let Some(payload) = Self::get_payload(src) {
-                                let line = Self::convert_string(&payload)?;
+                                let line = self.setting_value("acsmooth", &payload)?;
                                 let state = line.eq("ON");
                                 let _ = src.split_to(2 + payload.len() + 1);
                                 Ok(Some(Response::Success(Some(ResponsePayload::Smoothing(
@@ -262,7 +406,7 @@ impl Decoder for ProtocolCodec {
 
                         Some(Command::GetDigitCount) => {
                             if let Some(payload) = Self::get_payload(src) {
-                                let line = Self::convert_string(&payload)?;
+                                let line = self.setting_value("digits", &payload)?;
                                 let digits = line
                                     .parse::<u8>()
                                     .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
@@ -270,7 +414,7 @@ impl Decoder for ProtocolCodec {
                                 let d = match digits {
                                     4 => DigitCount::Digit4,
                                     5 => DigitCount::Digit5,
-                                    _ => unimplemented!(),
+                                    x => return Err(unknown_value("digit count", x)),
                                 };
                                 Ok(Some(Response::Success(Some(ResponsePayload::DigitCount(
                                     d,
@@ -282,17 +426,17 @@ impl Decoder for ProtocolCodec {
 
                         Some(Command::GetLanguage) => {
                             if let Some(payload) = Self::get_payload(src) {
-                                let line = Self::convert_string(&payload)?;
+                                let line = self.setting_value("lang", &payload)?;
                                 let _ = src.split_to(2 + payload.len() + 1);
                                 let lang = match line.as_str() {
                                     "GERMAN" => Language::German,
-                                    "ENLISH" => Language::English,
+                                    "ENGLISH" => Language::English,
                                     "SPANISH" => Language::Spanish,
                                     "ITALIAN" => Language::Italian,
                                     "FRENCH" => Language::French,
                                     "JAPANESE" => Language::Japanese,
                                     "CHINESE" => Language::Chinese,
-                                    _ => unimplemented!(),
+                                    x => return Err(unknown_value("language", x)),
                                 };
                                 Ok(Some(Response::Success(Some(ResponsePayload::Language(
                                     lang,
@@ -304,12 +448,12 @@ impl Decoder for ProtocolCodec {
 
                         Some(Command::GetDateFormat) => {
                             if let Some(payload) = Self::get_payload(src) {
-                                let line = Self::convert_string(&payload)?;
+                                let line = self.setting_value("dateFmt", &payload)?;
                                 let _ = src.split_to(2 + payload.len() + 1);
                                 let fmt = match line.as_str() {
                                     "MM_DD" => DateFormat::MM_DD,
                                     "DD_MM" => DateFormat::DD_MM,
-                                    _ => unimplemented!(),
+                                    x => return Err(unknown_value("date format", x)),
                                 };
                                 Ok(Some(Response::Success(Some(ResponsePayload::DateFormat(
                                     fmt,
@@ -321,7 +465,7 @@ impl Decoder for ProtocolCodec {
 
                         Some(Command::GetTimeFormat) => {
                             if let Some(payload) = Self::get_payload(src) {
-                                let line = Self::convert_string(&payload)?;
+                                let line = self.setting_value("timeFmt", &payload)?;
                                 let _ = src.split_to(2 + payload.len() + 1);
                                 let v = line
                                     .parse::<u8>()
@@ -329,7 +473,7 @@ impl Decoder for ProtocolCodec {
                                 let fmt = match v {
                                     12 => TimeFormat::Time12,
                                     24 => TimeFormat::Time24,
-                                    _ => unimplemented!(),
+                                    x => return Err(unknown_value("time format", x)),
                                 };
                                 Ok(Some(Response::Success(Some(ResponsePayload::TimeFormat(
                                     fmt,
@@ -341,12 +485,12 @@ impl Decoder for ProtocolCodec {
 
                         Some(Command::GetNumFormat) => {
                             if let Some(payload) = Self::get_payload(src) {
-                                let line = Self::convert_string(&payload)?;
+                                let line = self.setting_value("numFmt", &payload)?;
                                 let _ = src.split_to(2 + payload.len() + 1);
                                 let fmt = match line.as_str() {
                                     "COMMA" => NumericFormat::Comma,
                                     "POINT" => NumericFormat::Point,
-                                    _ => unimplemented!(),
+                                    x => return Err(unknown_value("numeric format", x)),
                                 };
                                 Ok(Some(Response::Success(Some(
                                     ResponsePayload::NumericFormat(fmt),
@@ -358,14 +502,23 @@ impl Decoder for ProtocolCodec {
 
                         Some(Command::GetDbmRef) => {
                             if let Some(payload) = Self::get_payload(src) {
-                                let line = Self::convert_string(&payload)?;
+                                let line = self.setting_value("dBmRef", &payload)?;
                                 let d_bm = line
                                     .parse::<u16>()
                                     .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
                                 let _ = src.split_to(2 + payload.len() + 1);
                                 let x = match d_bm {
                                     0 => DezibelReference::Custom,
-                                    _ => unimplemented!(),
+                                    4 => DezibelReference::Ref4,
+                                    8 => DezibelReference::Ref8,
+                                    16 => DezibelReference::Ref16,
+                                    25 => DezibelReference::Ref25,
+                                    32 => DezibelReference::Ref32,
+                                    50 => DezibelReference::Ref50,
+                                    75 => DezibelReference::Ref75,
+                                    600 => DezibelReference::Ref600,
+                                    1000 => DezibelReference::Ref1000,
+                                    x => return Err(unknown_value("dBm reference", x)),
                                 };
                                 Ok(Some(Response::Success(Some(ResponsePayload::DbmRef(x)))))
                             } else {
@@ -441,6 +594,19 @@ impl Decoder for ProtocolCodec {
                             }
                         }
 
+                        Some(Command::GetMeasurementAscii) => {
+                            if let Some(payload) = Self::get_payload(src) {
+                                let _ = src.split_to(2 + payload.len() + 1);
+                                AsciiMeasurement::try_from(payload.as_slice()).map(|mea| {
+                                    Some(Response::Success(Some(
+                                        ResponsePayload::MeasurementAscii(mea),
+                                    )))
+                                })
+                            } else {
+                                Ok(None)
+                            }
+                        }
+
                         Some(Command::GetMeasurementBinary) => {
                             if src.len() >= STATUS_LEN + BIN_MARKER_LEN + MEA_METADATA_LEN {
                                 let readings: u16 = u16::from_le_bytes([
@@ -450,7 +616,11 @@ impl Decoder for ProtocolCodec {
                                 let total = STATUS_LEN
                                     + BIN_MARKER_LEN
                                     + MEA_METADATA_LEN
-                                    + (readings as usize * READING_LEN)
+                                    + readings_len(
+                                        readings,
+                                        MAX_MEASUREMENT_READINGS,
+                                        "Measurement",
+                                    )?
                                     + EOL_LEN;
                                 if src.len() >= total {
                                     let m = RawMeasurement::try_from(&src[2..total])?; // Skip STATUS
@@ -591,7 +761,9 @@ impl Decoder for ProtocolCodec {
                             }
                         }
 
-                        None => panic!("No command called"),
+                        None => Err(invalid_data(
+                            "Received a response without a pending command",
+                        )),
                     }
                 }
                 '1' => {
@@ -625,22 +797,50 @@ fn write_fmt_guarded(dst: &mut BytesMut, args: fmt::Arguments<'_>) -> Result<(),
         .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
 }
 
+/// Remove the quotes added by [`enclose_string`].
 fn strip_string(s: impl AsRef<str>) -> String {
     s.as_ref()
         .chars()
         .skip(1)
-        .take(s.as_ref().len() - 2)
-        .collect()
+        .take(s.as_ref().chars().count().saturating_sub(2))
+        .collect::<String>()
+        .replace("''", "'")
+}
+
+/// A frame starts with a status code followed by '\r'. If the buffer ends
+/// after the status code, the position is assumed to be a frame start.
+fn is_frame_start(src: &[u8], pos: usize) -> bool {
+    STATUS_CODES.contains(&src[pos]) && src.get(pos + 1).map_or(true, |b| *b == b'\r')
+}
+
+/// Offset of the next plausible frame start, or the whole buffer if there is none.
+/// Status codes inside a payload (preceded by printable characters) are ignored.
+fn resync_offset(src: &[u8]) -> usize {
+    (1..src.len())
+        .find(|&pos| {
+            is_frame_start(src, pos) && (src[pos - 1] == b'\r' || !src[pos - 1].is_ascii_graphic())
+        })
+        .unwrap_or(src.len())
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+fn unknown_value(what: &str, value: impl fmt::Display) -> io::Error {
+    invalid_data(format!("Unknown {} in device response: {}", what, value))
 }
 
+/// Quote a string argument, embedded quotes are doubled.
 fn enclose_string(s: impl AsRef<str>) -> String {
-    format!("'{}'", s.as_ref()).to_string()
+    format!("'{}'", s.as_ref().replace('\'', "''"))
 }
 
 impl Encoder<Command> for ProtocolCodec {
     type Error = io::Error;
 
     fn encode(&mut self, item: Command, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let start = dst.len();
         match &item {
             Command::Id => write_fmt_guarded(dst, format_args!("id"))?,
             Command::QueryMap(name) => write_fmt_guarded(dst, format_args!("qemap {}", name))?,
@@ -668,7 +868,7 @@ impl Encoder<Command> for ProtocolCodec {
             }
             Command::GetContact => write_fmt_guarded(dst, format_args!("qmpq contact"))?,
             Command::SetContact(contact) => {
-                write_fmt_guarded(dst, format_args!("mpq contact,{}", contact))?
+                write_fmt_guarded(dst, format_args!("mpq contact,{}", enclose_string(contact)))?
             }
             Command::GetClock => write_fmt_guarded(dst, format_args!("qmp clock"))?,
             Command::SetClock(clock) => write_fmt_guarded(dst, format_args!("mp clock,{}", clock))?,
@@ -681,6 +881,7 @@ impl Encoder<Command> for ProtocolCodec {
             )?,
             Command::GetMemoryStat => write_fmt_guarded(dst, format_args!("qsls"))?,
             Command::GetMeasurementBinary => write_fmt_guarded(dst, format_args!("qddb"))?,
+            Command::GetMeasurementAscii => write_fmt_guarded(dst, format_args!("qm"))?,
             Command::QuerySavedMeasurement(idx) => {
                 write_fmt_guarded(dst, format_args!("qsmr {}", idx))?
             }
@@ -707,6 +908,14 @@ impl Encoder<Command> for ProtocolCodec {
                 write_fmt_guarded(dst, format_args!("csd {}", s))?;
             }
             Command::ResetDevice => write_fmt_guarded(dst, format_args!("rmp"))?,
+            Command::PressKey(key) => {
+                let s = match key {
+                    Key::Backlight => "BACKLIGHT",
+                    Key::F4 => "F4",
+                    Key::Hold => "HOLD",
+                };
+                write_fmt_guarded(dst, format_args!("press {}", s))?;
+            }
             Command::GetBeeper => write_fmt_guarded(dst, format_args!("qmp beeper"))?,
             Command::SetBeeper(state) => {
                 if *state {
@@ -807,7 +1016,10 @@ impl Encoder<Command> for ProtocolCodec {
         }
         dst.write_str("\r")
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-        self.last_cmd = Some(item);
+        if let Some(observer) = self.observer.lock().expect("observer lock").as_mut() {
+            observer.on_tx(Utc::now(), &item, &dst[start..]);
+        }
+        self.pending.push_back(item);
         Ok(())
     }
 }
@@ -1,6 +1,10 @@
 use std::{fmt::Display, time::Duration};
 
+use thiserror::Error;
+
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub enum ClearMemory {
     All,
     Measurements,
@@ -10,6 +14,8 @@ pub enum ClearMemory {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub enum DezibelReference {
     Ref4,
     Ref8,
@@ -40,6 +46,7 @@ impl Display for DezibelReference {
     }
 }
 
+#[cfg(feature = "cli")]
 impl clap::ValueEnum for DezibelReference {
     fn value_variants<'a>() -> &'a [Self] {
         &[
@@ -72,6 +79,7 @@ impl clap::ValueEnum for DezibelReference {
     }
 }
 
+#[cfg(feature = "cli")]
 impl clap::ValueEnum for ClearMemory {
     fn value_variants<'a>() -> &'a [Self] {
         &[
@@ -95,11 +103,14 @@ impl clap::ValueEnum for ClearMemory {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub enum DigitCount {
     Digit4,
     Digit5,
 }
 
+#[cfg(feature = "cli")]
 impl clap::ValueEnum for DigitCount {
     fn value_variants<'a>() -> &'a [Self] {
         &[Self::Digit4, Self::Digit5]
@@ -114,6 +125,8 @@ impl clap::ValueEnum for DigitCount {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub enum Language {
     German,
     English,
@@ -124,6 +137,7 @@ pub enum Language {
     Chinese,
 }
 
+#[cfg(feature = "cli")]
 impl clap::ValueEnum for Language {
     fn value_variants<'a>() -> &'a [Self] {
         &[Self::German, Self::English]
@@ -143,12 +157,15 @@ impl clap::ValueEnum for Language {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 #[allow(non_camel_case_types)]
 pub enum DateFormat {
     DD_MM,
     MM_DD,
 }
 
+#[cfg(feature = "cli")]
 impl clap::ValueEnum for DateFormat {
     fn value_variants<'a>() -> &'a [Self] {
         &[Self::DD_MM, Self::MM_DD]
@@ -163,11 +180,14 @@ impl clap::ValueEnum for DateFormat {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub enum TimeFormat {
     Time12,
     Time24,
 }
 
+#[cfg(feature = "cli")]
 impl clap::ValueEnum for TimeFormat {
     fn value_variants<'a>() -> &'a [Self] {
         &[Self::Time12, Self::Time24]
@@ -182,11 +202,14 @@ impl clap::ValueEnum for TimeFormat {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub enum NumericFormat {
     Point,
     Comma,
 }
 
+#[cfg(feature = "cli")]
 impl clap::ValueEnum for NumericFormat {
     fn value_variants<'a>() -> &'a [Self] {
         &[Self::Point, Self::Comma]
@@ -200,7 +223,63 @@ impl clap::ValueEnum for NumericFormat {
     }
 }
 
+/// Longest operator, company, site or contact string stored by the meter.
+pub const MAX_OWNER_LEN: usize = 32;
+/// Longest name of a save slot.
+pub const MAX_SAVE_NAME_LEN: usize = 16;
+/// Characters of save names besides ASCII letters and digits.
+const SAVE_NAME_SYMBOLS: &str = " -_./+#";
+
+/// Check a string argument before it is sent: at most `max_len` printable
+/// ASCII characters. `what` names the argument in the error.
+pub fn validate_string(what: &str, value: &str, max_len: usize) -> Result<(), String> {
+    if let Some(c) = value.chars().find(|c| !(' '..='~').contains(c)) {
+        return Err(format!("{} contains unsupported character {:?}", what, c));
+    }
+    if value.len() > max_len {
+        return Err(format!("{} is longer than {} characters", what, max_len));
+    }
+    Ok(())
+}
+
+/// Save name the meter does not accept.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SaveNameError {
+    #[error("Save name is longer than {} characters", MAX_SAVE_NAME_LEN)]
+    TooLong,
+    #[error("Save name contains unsupported character {:?}", _0)]
+    UnsupportedChar(char),
+}
+
+/// Check a save name against the limits of the meter: up to
+/// [`MAX_SAVE_NAME_LEN`] ASCII letters, digits and ` -_./+#`.
+pub fn validate_save_name(name: &str) -> Result<(), SaveNameError> {
+    if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !SAVE_NAME_SYMBOLS.contains(*c))
+    {
+        return Err(SaveNameError::UnsupportedChar(c));
+    }
+    if name.len() > MAX_SAVE_NAME_LEN {
+        return Err(SaveNameError::TooLong);
+    }
+    Ok(())
+}
+
+/// Front panel key, pressed remotely with [`Command::PressKey`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum Key {
+    /// Steps the backlight through off, low and high
+    Backlight,
+    /// Rightmost soft key, Close or Back in menus and setup screens
+    F4,
+    /// Toggles the hold screen
+    Hold,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub enum Command {
     Id,
     // Maps
@@ -235,6 +314,8 @@ pub enum Command {
     // Measurements
     GetMemoryStat,
     GetMeasurementBinary,
+    /// Primary reading in ASCII (`qm`), for meters without `qddb`
+    GetMeasurementAscii,
     QuerySavedMeasurement(usize),
     QueryMinMaxSessionInfo(usize),
     QueryPeakSessionInfo(usize),
@@ -271,4 +352,6 @@ pub enum Command {
 
     GetTempOffset,
     SetTempOffset(i16),
+
+    PressKey(Key),
 }
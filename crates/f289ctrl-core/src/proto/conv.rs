@@ -0,0 +1,339 @@
+use chrono::{DateTime, Duration, Local, LocalResult, NaiveDateTime, Offset, TimeZone, Utc};
+use thiserror::Error;
+
+/// Instant chosen for a local device time which is ambiguous (the hour
+/// repeated when DST ends) or nonexistent (the hour skipped when DST starts).
+///
+/// Nonexistent times are interpreted with the UTC offsets before and after
+/// the transition, which also gives two instants.
+///
+/// Decoded readings and measurements use the default,
+/// [`DstFallback::Earliest`]; convert
+/// [`Reading::device_ts`](crate::measurement::Reading::device_ts) with
+/// [`timestamp_to_datetime`] to choose the other instant.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DstFallback {
+    #[default]
+    Earliest,
+    Latest,
+}
+
+/// Local device time without a single matching instant.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LocalTimeError {
+    #[error("Local time {} is ambiguous, it occurs twice around a DST change", _0)]
+    Ambiguous(NaiveDateTime),
+    #[error("Local time {} does not exist, it is skipped by a DST change", _0)]
+    Nonexistent(NaiveDateTime),
+}
+
+/// Device timestamps are seconds since the epoch in local time.
+fn device_naive(ts: f64) -> NaiveDateTime {
+    Utc.timestamp_nanos((ts * 1000000000.0) as i64).naive_utc()
+}
+
+/// Instants `naive` may denote in `tz`, the same instant twice if unique.
+fn local_candidates<Tz: TimeZone>(
+    tz: &Tz,
+    naive: &NaiveDateTime,
+) -> (DateTime<Utc>, DateTime<Utc>, Option<LocalTimeError>) {
+    match tz.from_local_datetime(naive) {
+        LocalResult::Single(dt) => {
+            let dt = dt.with_timezone(&Utc);
+            (dt, dt, None)
+        }
+        LocalResult::Ambiguous(a, b) => {
+            let (a, b) = (a.with_timezone(&Utc), b.with_timezone(&Utc));
+            (a.min(b), a.max(b), Some(LocalTimeError::Ambiguous(*naive)))
+        }
+        LocalResult::None => {
+            // Offsets a day before and after the gap, DST changes are
+            // much farther apart
+            let with_offset_at = |utc: NaiveDateTime| {
+                let offset = tz.offset_from_utc_datetime(&utc).fix();
+                Utc.from_utc_datetime(
+                    &(*naive - Duration::seconds(offset.local_minus_utc().into())),
+                )
+            };
+            let before = with_offset_at(*naive - Duration::days(1));
+            let after = with_offset_at(*naive + Duration::days(1));
+            (
+                before.min(after),
+                before.max(after),
+                Some(LocalTimeError::Nonexistent(*naive)),
+            )
+        }
+    }
+}
+
+fn local_to_utc<Tz: TimeZone>(
+    tz: &Tz,
+    naive: &NaiveDateTime,
+    fallback: DstFallback,
+) -> DateTime<Utc> {
+    let (earliest, latest, _) = local_candidates(tz, naive);
+    match fallback {
+        DstFallback::Earliest => earliest,
+        DstFallback::Latest => latest,
+    }
+}
+
+/// Convert a device timestamp in local time, `fallback` chooses the instant
+/// for times around DST changes.
+pub fn timestamp_to_datetime(ts: f64, fallback: DstFallback) -> DateTime<Utc> {
+    local_to_utc(&Local, &device_naive(ts), fallback)
+}
+
+/// Like [`timestamp_to_datetime`], but fails for times which are ambiguous
+/// or nonexistent because of a DST change.
+pub fn try_timestamp_to_datetime(ts: f64) -> Result<DateTime<Utc>, LocalTimeError> {
+    match local_candidates(&Local, &device_naive(ts)) {
+        (dt, _, None) => Ok(dt),
+        (_, _, Some(err)) => Err(err),
+    }
+}
+
+/// UTC offset in seconds [`timestamp_to_datetime`] assumes for a device timestamp.
+pub fn timestamp_utc_offset(ts: f64, fallback: DstFallback) -> i32 {
+    let naive = device_naive(ts);
+    let utc = local_to_utc(&Local, &naive, fallback);
+    (naive - utc.naive_utc()).num_seconds() as i32
+}
+
+/// Convert a device timestamp with an explicit UTC offset, e.g. to re-derive
+/// [`Reading::ts`](crate::measurement::Reading) for a meter set to another timezone.
+pub fn timestamp_to_datetime_with_offset(ts: f64, utc_offset: i32) -> DateTime<Utc> {
+    Utc.timestamp_nanos(((ts - utc_offset as f64) * 1000000000.0) as i64)
+}
+
+pub type DeviceDateTime = NaiveDateTime;
+
+/// Conversion of the chrono timestamps of this crate (e.g.
+/// [`Reading::ts`](crate::measurement::Reading)) to the `time` crate,
+/// available with the `time` feature.
+///
+/// ```
+/// use f289ctrl_core::proto::conv::{timestamp_to_datetime_with_offset, ToOffsetDateTime};
+///
+/// let ts = timestamp_to_datetime_with_offset(1672574400.5, 0);
+/// let ts = ts.to_offset_date_time().expect("in range");
+/// assert_eq!(ts.unix_timestamp(), 1672574400);
+/// assert_eq!(ts.millisecond(), 500);
+/// ```
+#[cfg(feature = "time")]
+pub trait ToOffsetDateTime {
+    /// Fails for years outside of -9999 to 9999, which chrono supports.
+    fn to_offset_date_time(&self) -> Result<time::OffsetDateTime, time::error::ComponentRange>;
+}
+
+#[cfg(feature = "time")]
+impl<Tz: TimeZone> ToOffsetDateTime for DateTime<Tz> {
+    fn to_offset_date_time(&self) -> Result<time::OffsetDateTime, time::error::ComponentRange> {
+        let offset = time::UtcOffset::from_whole_seconds(self.offset().fix().local_minus_utc())?;
+        let ts = time::OffsetDateTime::from_unix_timestamp(self.timestamp())?
+            .replace_nanosecond(self.timestamp_subsec_nanos().min(999_999_999))?;
+        Ok(ts.to_offset(offset))
+    }
+}
+
+/// Convert a `time` timestamp for the APIs of this crate, e.g.
+/// [`Device::set_clock`](crate::Device::set_clock).
+#[cfg(feature = "time")]
+pub fn from_offset_date_time(ts: time::OffsetDateTime) -> DateTime<Utc> {
+    // The time range of the time crate is a subset of chrono's
+    Utc.timestamp_nanos(0)
+        + Duration::seconds(ts.unix_timestamp())
+        + Duration::nanoseconds(ts.nanosecond().into())
+}
+
+pub fn unit_prefix(unit_multiplier: i16) -> &'static str {
+    match unit_multiplier {
+        -12 => "p",
+        -9 => "n",
+        -6 => "u",
+        -3 => "m",
+        -2 => "c",
+        -1 => "d",
+        0 => "",
+        1 => "D",
+        2 => "h",
+        3 => "k",
+        6 => "M",
+        9 => "G",
+        12 => "T",
+        _ => "?",
+    }
+}
+
+/// Multiplier of an SI prefix as shown by [`unit_prefix`], `µ` is accepted
+/// for micro.
+pub fn prefix_multiplier(prefix: &str) -> Option<i16> {
+    match prefix {
+        "\u{b5}" => return Some(-6),
+        "?" => return None,
+        _ => {}
+    }
+    (-12..=12).find(|multiplier| unit_prefix(*multiplier) == prefix)
+}
+
+pub fn pretty_ts(&ts: &DateTime<Utc>) -> String {
+    let local: DateTime<Local> = ts.into();
+    local.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Duration as `HH:MM:SS.s`, negative durations are shown as zero.
+pub fn pretty_duration(duration: &chrono::Duration) -> String {
+    let millis = duration.num_milliseconds().max(0);
+    let seconds = (millis % 60_000) as f64 / 1000.0;
+    let minutes = (millis / 60_000) % 60;
+    let hours = millis / 3_600_000;
+    format!("{:02}:{:02}:{:04.1}", hours, minutes, seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn test_pretty_duration() {
+        let d = chrono::Duration::milliseconds(3_723_400);
+        assert_eq!(pretty_duration(&d), "01:02:03.4");
+        assert_eq!(pretty_duration(&-d), "00:00:00.0");
+    }
+
+    /// CET/CEST with the 2023 transitions: 02:00 to 03:00 local on March 26
+    /// is skipped, 02:00 to 03:00 local on October 29 repeated.
+    #[derive(Debug, Clone)]
+    struct Cet;
+
+    impl Cet {
+        fn start() -> NaiveDateTime {
+            Utc.with_ymd_and_hms(2023, 3, 26, 1, 0, 0)
+                .unwrap()
+                .naive_utc()
+        }
+        fn end() -> NaiveDateTime {
+            Utc.with_ymd_and_hms(2023, 10, 29, 1, 0, 0)
+                .unwrap()
+                .naive_utc()
+        }
+        fn winter() -> FixedOffset {
+            FixedOffset::east_opt(3600).expect("offset")
+        }
+        fn summer() -> FixedOffset {
+            FixedOffset::east_opt(7200).expect("offset")
+        }
+    }
+
+    impl TimeZone for Cet {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self {
+            Cet
+        }
+
+        fn offset_from_local_date(&self, local: &chrono::NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(12, 0, 0).expect("noon"))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let candidates: Vec<FixedOffset> = [Self::winter(), Self::summer()]
+                .into_iter()
+                .filter(|offset| {
+                    let utc = *local - Duration::seconds(offset.local_minus_utc().into());
+                    self.offset_from_utc_datetime(&utc) == *offset
+                })
+                .collect();
+            match candidates[..] {
+                [offset] => LocalResult::Single(offset),
+                [a, b] => LocalResult::Ambiguous(a, b),
+                _ => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &chrono::NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(12, 0, 0).expect("noon"))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            if *utc >= Self::start() && *utc < Self::end() {
+                Self::summer()
+            } else {
+                Self::winter()
+            }
+        }
+    }
+
+    #[test]
+    fn test_dst_fallback() {
+        let local = |h, m| {
+            chrono::NaiveDate::from_ymd_opt(2023, 3, 26)
+                .and_then(|d| d.and_hms_opt(h, m, 0))
+                .expect("time")
+        };
+        let utc = |dt: DateTime<Utc>| dt.format("%m-%d %H:%M").to_string();
+
+        let (earliest, latest, err) = local_candidates(&Cet, &local(2, 30));
+        assert_eq!(utc(earliest), "03-26 00:30");
+        assert_eq!(utc(latest), "03-26 01:30");
+        assert_eq!(err, Some(LocalTimeError::Nonexistent(local(2, 30))));
+        assert_eq!(
+            utc(local_to_utc(&Cet, &local(1, 30), DstFallback::Latest)),
+            "03-26 00:30"
+        );
+
+        let repeated = local(2, 30) + Duration::days(217);
+        let (earliest, latest, err) = local_candidates(&Cet, &repeated);
+        assert_eq!(utc(earliest), "10-29 00:30");
+        assert_eq!(utc(latest), "10-29 01:30");
+        assert_eq!(err, Some(LocalTimeError::Ambiguous(repeated)));
+        assert_eq!(
+            utc(local_to_utc(&Cet, &repeated, DstFallback::Latest)),
+            "10-29 01:30"
+        );
+    }
+
+    #[test]
+    fn test_prefix_multiplier() {
+        assert_eq!(prefix_multiplier("m"), Some(-3));
+        assert_eq!(prefix_multiplier("\u{b5}"), Some(-6));
+        assert_eq!(prefix_multiplier(""), Some(0));
+        assert_eq!(prefix_multiplier("k"), Some(3));
+        assert_eq!(prefix_multiplier("?"), None);
+        assert_eq!(prefix_multiplier("x"), None);
+    }
+
+    #[test]
+    fn test_timestamp_offset() {
+        let ts = 1672574400.5;
+        let offset = timestamp_utc_offset(ts, DstFallback::Latest);
+        assert_eq!(
+            timestamp_to_datetime_with_offset(ts, offset),
+            timestamp_to_datetime(ts, DstFallback::Latest)
+        );
+        assert_eq!(
+            timestamp_to_datetime_with_offset(ts, 3600).timestamp(),
+            1672570800
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_offset_date_time() {
+        let ts = Utc.timestamp_opt(1672574400, 250_000_000).unwrap();
+        let converted = ts.to_offset_date_time().expect("time");
+        assert_eq!(converted.unix_timestamp(), 1672574400);
+        assert_eq!(converted.nanosecond(), 250_000_000);
+        assert_eq!(from_offset_date_time(converted), ts);
+
+        let local = ts.with_timezone(&FixedOffset::east_opt(3600).expect("offset"));
+        let converted = local.to_offset_date_time().expect("time");
+        assert_eq!(converted.offset().whole_seconds(), 3600);
+        assert_eq!(converted.hour(), 13);
+        assert_eq!(from_offset_date_time(converted), ts);
+
+        let far = Utc.with_ymd_and_hms(20000, 1, 1, 0, 0, 0).unwrap();
+        assert!(far.to_offset_date_time().is_err());
+    }
+}
@@ -0,0 +1,18 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+
+use super::command::Command;
+
+/// Receives all raw bytes exchanged with the device, e.g. to implement a
+/// protocol log.
+pub trait ProtocolObserver: Send {
+    /// `bytes` is exactly what is sent to the device for `command`.
+    fn on_tx(&mut self, _ts: DateTime<Utc>, _command: &Command, _bytes: &[u8]) {}
+
+    /// `frame` holds the bytes consumed for one decoded response, including
+    /// garbage skipped while resynchronizing.
+    fn on_rx(&mut self, _ts: DateTime<Utc>, _frame: &[u8]) {}
+}
+
+pub(crate) type ObserverSlot = Arc<Mutex<Option<Box<dyn ProtocolObserver>>>>;
@@ -2,6 +2,7 @@ use std::{io, str, time::Duration};
 
 use crate::{
     device::ValueMap,
+    model::Model,
     rawmea::{
         RawMeasurement, RawSavedMeasurement, RawSavedMinMaxMeasurement, RawSavedPeakMeasurement,
         RawSavedRecordingSessionInfo, RawSessionRecordReadings,
@@ -22,6 +23,8 @@ pub enum Response {
     SyntaxError,                      // 1
     ExecutionError,                   // 2
     NoData,                           // 5
+    /// Garbage bytes were discarded to find the next frame start
+    FramingError(usize),
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +43,7 @@ pub enum ResponsePayload {
     SaveName(String),
     MemoryStat(MemoryStat),
     MeasurementBinary(RawMeasurement),
+    MeasurementAscii(AsciiMeasurement),
     SavedMeasurement(RawSavedMeasurement),
 
     MinMaxSessionInfo(RawSavedMinMaxMeasurement),
@@ -61,12 +65,20 @@ pub enum ResponsePayload {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Ident {
     pub model: String,
     pub firmware: String,
     pub serial: String,
 }
 
+impl Ident {
+    pub fn device_model(&self) -> Model {
+        Model::from(self.model.as_str())
+    }
+}
+
 impl TryFrom<&[u8]> for Ident {
     type Error = io::Error;
 
@@ -91,6 +103,8 @@ impl TryFrom<&[u8]> for Ident {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MemoryStat {
     pub recordings: usize,
     pub min_max: usize,
@@ -129,3 +143,75 @@ impl TryFrom<&[u8]> for MemoryStat {
         }
     }
 }
+
+/// Primary reading of a `qm` response, e.g. `+1.2345E+0,VDC,NORMAL,NONE`.
+///
+/// Unit, state and attribute are value map names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsciiMeasurement {
+    pub value: f64,
+    pub unit: String,
+    pub state: String,
+    pub attribute: String,
+    /// Digits after the decimal point of the displayed value
+    pub decimals: i16,
+}
+
+impl TryFrom<&[u8]> for AsciiMeasurement {
+    type Error = io::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let value = str::from_utf8(value)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+            .to_string();
+        let values: Vec<&str> = value.split(',').map(str::trim).collect();
+        if values.len() == 4 {
+            Ok(Self {
+                value: values[0]
+                    .parse::<f64>()
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?,
+                unit: values[1].to_string(),
+                state: values[2].to_string(),
+                attribute: values[3].to_string(),
+                decimals: ascii_decimals(values[0]),
+            })
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("Invalid data for qm response: {}", value),
+            ))
+        }
+    }
+}
+
+/// Decimals of a number in scientific notation, `1.2345E-1` has 5.
+fn ascii_decimals(number: &str) -> i16 {
+    let (mantissa, exponent) = match number.find(['E', 'e']) {
+        Some(pos) => (
+            &number[..pos],
+            number[pos + 1..].parse::<i16>().unwrap_or(0),
+        ),
+        None => (number, 0),
+    };
+    let fraction = mantissa
+        .split_once('.')
+        .map(|(_, fraction)| fraction.len() as i16)
+        .unwrap_or(0);
+    (fraction - exponent).max(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_measurement() {
+        let mea = AsciiMeasurement::try_from(&b"+1.2345E+0,VDC,NORMAL,NONE"[..]).expect("qm");
+        assert_eq!(mea.value, 1.2345);
+        assert_eq!(mea.unit, "VDC");
+        assert_eq!(mea.decimals, 4);
+        assert_eq!(ascii_decimals("-2.20E2"), 0);
+        assert_eq!(ascii_decimals("1.5E-3"), 4);
+        assert!(AsciiMeasurement::try_from(&b"1.0,VDC"[..]).is_err());
+    }
+}
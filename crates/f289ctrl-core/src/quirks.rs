@@ -0,0 +1,168 @@
+//! Known firmware deviations, patched at runtime.
+//!
+//! Each entry in [`KNOWN_QUIRKS`] describes one difference between what a
+//! firmware sends and what the parsers expect. Supporting a new firmware
+//! revision usually only requires a new entry plus a test, the parsers
+//! themselves stay untouched.
+
+/// Firmware revisions a quirk applies to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Firmware {
+    Any,
+    /// Firmware string as reported by `id`, e.g. `V1.16`
+    Exact(&'static str),
+    /// All firmware strings starting with the prefix
+    Prefix(&'static str),
+}
+
+impl Firmware {
+    pub(crate) fn matches(&self, firmware: Option<&str>) -> bool {
+        match (self, firmware) {
+            (Self::Any, _) => true,
+            (Self::Exact(v), Some(firmware)) => *v == firmware,
+            (Self::Prefix(p), Some(firmware)) => firmware.starts_with(p),
+            (_, None) => false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Patch {
+    /// The device answers `sent` for a setting value the parser knows as `expected`
+    SettingAlias {
+        setting: &'static str,
+        sent: &'static str,
+        expected: &'static str,
+    },
+    /// Entry missing from a value map reported by `qemap`
+    MapEntry {
+        map: &'static str,
+        id: u16,
+        name: &'static str,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Quirk {
+    pub name: &'static str,
+    pub firmware: Firmware,
+    pub patch: Patch,
+}
+
+/// Registry of all known quirks.
+pub const KNOWN_QUIRKS: &[Quirk] = &[Quirk {
+    name: "lang-enlish-typo",
+    firmware: Firmware::Any,
+    patch: Patch::SettingAlias {
+        setting: "lang",
+        sent: "ENLISH",
+        expected: "ENGLISH",
+    },
+}];
+
+/// Quirks active for a connected device.
+#[derive(Debug, Clone)]
+pub struct Quirks {
+    active: Vec<Quirk>,
+}
+
+impl Default for Quirks {
+    /// Quirks applying to every firmware, used until the firmware is known.
+    fn default() -> Self {
+        Self::select(KNOWN_QUIRKS, None)
+    }
+}
+
+impl Quirks {
+    pub fn for_firmware(firmware: &str) -> Self {
+        Self::select(KNOWN_QUIRKS, Some(firmware))
+    }
+
+    fn select(registry: &[Quirk], firmware: Option<&str>) -> Self {
+        Self {
+            active: registry
+                .iter()
+                .filter(|q| q.firmware.matches(firmware))
+                .copied()
+                .collect(),
+        }
+    }
+
+    pub fn active(&self) -> &[Quirk] {
+        &self.active
+    }
+
+    /// Map a setting value as sent by the device to the one the parser expects.
+    pub fn setting_value<'a>(&self, setting: &str, value: &'a str) -> &'a str {
+        for quirk in &self.active {
+            if let Patch::SettingAlias {
+                setting: s,
+                sent,
+                expected,
+            } = quirk.patch
+            {
+                if s == setting && sent == value {
+                    return expected;
+                }
+            }
+        }
+        value
+    }
+
+    /// Add missing value map entries, existing entries are kept.
+    pub fn patch_maps(&self, maps: &mut crate::device::ValueMaps) {
+        for quirk in &self.active {
+            if let Patch::MapEntry { map, id, name } = quirk.patch {
+                maps.entry(map.to_string())
+                    .or_default()
+                    .entry(id)
+                    .or_insert_with(|| name.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGISTRY: &[Quirk] = &[
+        Quirk {
+            name: "all",
+            firmware: Firmware::Any,
+            patch: Patch::SettingAlias {
+                setting: "lang",
+                sent: "ENLISH",
+                expected: "ENGLISH",
+            },
+        },
+        Quirk {
+            name: "v2-map",
+            firmware: Firmware::Prefix("V2."),
+            patch: Patch::MapEntry {
+                map: "unit",
+                id: 99,
+                name: "NEW_UNIT",
+            },
+        },
+    ];
+
+    #[test]
+    fn test_select_by_firmware() {
+        assert_eq!(Quirks::select(REGISTRY, None).active().len(), 1);
+        assert_eq!(Quirks::select(REGISTRY, Some("V1.16")).active().len(), 1);
+        assert_eq!(Quirks::select(REGISTRY, Some("V2.01")).active().len(), 2);
+    }
+
+    #[test]
+    fn test_patches() {
+        let quirks = Quirks::select(REGISTRY, Some("V2.01"));
+        assert_eq!(quirks.setting_value("lang", "ENLISH"), "ENGLISH");
+        assert_eq!(quirks.setting_value("lang", "GERMAN"), "GERMAN");
+        assert_eq!(quirks.setting_value("numFmt", "ENLISH"), "ENLISH");
+
+        let mut maps = crate::device::ValueMaps::new();
+        quirks.patch_maps(&mut maps);
+        assert_eq!(maps["unit"][&99], "NEW_UNIT");
+    }
+}
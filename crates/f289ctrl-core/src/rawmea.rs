@@ -0,0 +1,1062 @@
+pub(crate) const BIN_MARKER_LEN: usize = 2;
+
+pub(crate) const MEA_METADATA_LEN: usize = 34;
+pub(crate) const SAVED_MEA_METADATA_LEN: usize = 38;
+pub(crate) const SAVED_MINMAX_METADATA_LEN: usize = 54;
+//pub(crate) const SAVED_PEAK_METADATA_LEN: usize = SAVED_MINMAX_METADATA_LEN;
+pub(crate) const SAVED_RECORDING_METADATA_LEN: usize = 78;
+pub(crate) const SAVED_RECORD_READINGS_LEN: usize = 26 + READING_LEN + (3 * READING_LEN); // = 146
+
+pub(crate) const READING_LEN: usize = 30;
+
+pub(crate) const EOL_LEN: usize = 1;
+
+// QSRR returns fixed length
+const _: () = assert!(BIN_MARKER_LEN + SAVED_RECORD_READINGS_LEN + EOL_LEN == 149);
+
+/// Upper bounds for the readings count of binary responses, larger counts
+/// are corrupted. Live and saved measurements carry the displayed readings,
+/// min/max and peak sessions additionally the session statistics.
+pub(crate) const MAX_MEASUREMENT_READINGS: usize = 16;
+pub(crate) const MAX_MINMAX_READINGS: usize = 32;
+pub(crate) const MAX_RECORDING_READINGS: usize = 16;
+
+/// Upper bound for the readings count of any binary response
+pub(crate) const MAX_READINGS: usize = MAX_MINMAX_READINGS;
+
+/// Upper bound for the length of any response frame
+pub(crate) const MAX_FRAME_LEN: usize = 4096;
+
+/// Length of `readings` binary readings of a `what` frame, fails if the
+/// count exceeds `max`.
+pub(crate) fn readings_len(readings: u16, max: usize, what: &str) -> std::io::Result<usize> {
+    if readings as usize > max {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "{} readings count {} exceeds maximum of {}",
+                what, readings, max
+            ),
+        ));
+    }
+    Ok(readings as usize * READING_LEN)
+}
+
+/// Parse `count` readings, the count is checked before allocating.
+fn read_readings(
+    cur: &mut FrameReader<'_>,
+    count: u16,
+    max: usize,
+    what: &str,
+) -> std::io::Result<Vec<RawReading>> {
+    readings_len(count, max, what)?;
+    let mut readings = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        readings.push(RawReading::try_from(cur.take(READING_LEN)?)?);
+    }
+    Ok(readings)
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct RawMeasurement {
+    pub pri_function: u16,
+    pub sec_function: u16,
+    pub auto_range: u16,
+    pub unit: u16,
+    pub range_max: f64,
+    pub unit_multiplier: i16,
+    pub bolt: u16,
+    pub ts: f64,
+    pub modes: u16,
+    pub un1: u16,
+    pub readings: Vec<RawReading>,
+}
+
+impl TryFrom<&[u8]> for RawMeasurement {
+    type Error = std::io::Error;
+
+    fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
+        check_len(value, BIN_MARKER_LEN + MEA_METADATA_LEN, "Measurement")?;
+
+        if value[0..2] == [b'#', b'0'] {
+            let mut cur = FrameReader::new(&value[2..]);
+
+            let pri_function = cur.u16()?;
+            let sec_function = cur.u16()?;
+            let auto_range = cur.u16()?;
+            let unit = cur.u16()?;
+            let range_max = cur.double()?;
+            let unit_multiplier = cur.i16()?;
+            let bolt = cur.u16()?;
+            let ts = cur.f64()?;
+            let mode = cur.u16()?;
+            let un1 = cur.u16()?;
+            let readings_cnt = cur.u16()?;
+
+            let expected =
+                readings_len(readings_cnt, MAX_MEASUREMENT_READINGS, "Measurement")? + EOL_LEN;
+            if cur.remaining() != expected {
+                return Err(invalid_data(format!(
+                    "Measurement frame has {} bytes after metadata, expected {} for {} readings",
+                    cur.remaining(),
+                    expected,
+                    readings_cnt
+                )));
+            }
+
+            let readings = read_readings(
+                &mut cur,
+                readings_cnt,
+                MAX_MEASUREMENT_READINGS,
+                "Measurement",
+            )?;
+
+            Ok(RawMeasurement {
+                pri_function,
+                sec_function,
+                auto_range,
+                unit,
+                range_max,
+                unit_multiplier,
+                bolt,
+                ts,
+                modes: mode,
+                un1,
+                readings,
+            })
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Binary data expected but not #0 marker found",
+            ))
+        }
+    }
+}
+
+fn invalid_data(msg: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Fails if `frame` is shorter than `min_len`.
+fn check_len(frame: &[u8], min_len: usize, what: &str) -> std::io::Result<()> {
+    if frame.len() < min_len {
+        return Err(invalid_data(format!(
+            "{} frame too short: {} bytes, expected at least {}",
+            what,
+            frame.len(),
+            min_len
+        )));
+    }
+    Ok(())
+}
+
+/// Reads the fields of a binary frame, borrowing from the decoder buffer
+/// instead of copying.
+struct FrameReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> FrameReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn remaining(&self) -> usize {
+        self.buf.len()
+    }
+
+    fn has_remaining(&self) -> bool {
+        !self.buf.is_empty()
+    }
+
+    /// The next `len` bytes.
+    fn take(&mut self, len: usize) -> std::io::Result<&'a [u8]> {
+        if self.buf.len() < len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Binary frame is too short",
+            ));
+        }
+        let (head, tail) = self.buf.split_at(len);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    /// Bytes up to and including `delim`, or all remaining bytes.
+    fn take_until(&mut self, delim: u8) -> &'a [u8] {
+        let len = self
+            .buf
+            .iter()
+            .position(|b| *b == delim)
+            .map_or(self.buf.len(), |idx| idx + 1);
+        let (head, tail) = self.buf.split_at(len);
+        self.buf = tail;
+        head
+    }
+
+    fn array<const N: usize>(&mut self) -> std::io::Result<[u8; N]> {
+        let mut data = [0_u8; N];
+        data.copy_from_slice(self.take(N)?);
+        Ok(data)
+    }
+
+    fn u16(&mut self) -> std::io::Result<u16> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn i16(&mut self) -> std::io::Result<i16> {
+        self.array().map(i16::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> std::io::Result<f64> {
+        self.array().map(f64::from_le_bytes)
+    }
+
+    /// Double with swapped byte order in each 32 bit word.
+    fn double(&mut self) -> std::io::Result<f64> {
+        let mut data: [u8; 8] = self.array()?;
+        data.swap(0, 3);
+        data.swap(1, 2);
+        data.swap(4, 7);
+        data.swap(5, 6);
+        Ok(f64::from_be_bytes(data))
+    }
+}
+
+/// Writes the fields of a binary frame, the inverse of [`FrameReader`].
+struct FrameWriter {
+    buf: Vec<u8>,
+}
+
+impl FrameWriter {
+    /// Frame starting with the binary marker.
+    fn new() -> Self {
+        Self {
+            buf: b"#0".to_vec(),
+        }
+    }
+
+    fn u16(&mut self, value: u16) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn i16(&mut self, value: i16) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn f64(&mut self, value: f64) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    /// Double with swapped byte order in each 32 bit word.
+    fn double(&mut self, value: f64) -> &mut Self {
+        let mut data = value.to_be_bytes();
+        data.swap(0, 3);
+        data.swap(1, 2);
+        data.swap(4, 7);
+        data.swap(5, 6);
+        self.buf.extend_from_slice(&data);
+        self
+    }
+
+    fn reading(&mut self, reading: &RawReading) -> &mut Self {
+        self.u16(reading.reading_id)
+            .double(reading.value)
+            .u16(reading.unit)
+            .i16(reading.unit_multiplier)
+            .i16(reading.decimals)
+            .i16(reading.display_digits)
+            .u16(reading.state)
+            .u16(reading.attribute)
+            .double(reading.ts)
+    }
+
+    /// Readings preceded by their count.
+    fn readings(&mut self, readings: &[RawReading]) -> &mut Self {
+        self.u16(readings.len() as u16);
+        for reading in readings {
+            self.reading(reading);
+        }
+        self
+    }
+
+    /// Terminated name of a saved entry.
+    fn name(&mut self, name: &str) -> &mut Self {
+        self.buf.extend_from_slice(name.as_bytes());
+        self
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        self.buf.push(b'\r');
+        std::mem::take(&mut self.buf)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct RawReading {
+    pub reading_id: u16,
+    pub value: f64,
+    pub unit: u16,
+    pub unit_multiplier: i16,
+    pub decimals: i16,
+    pub display_digits: i16,
+    pub state: u16,
+    pub attribute: u16,
+    pub ts: f64,
+}
+
+impl TryFrom<&[u8]> for RawReading {
+    type Error = std::io::Error;
+
+    fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
+        let mut cur = FrameReader::new(value);
+
+        let reading_id = cur.u16()?;
+        let value = cur.double()?;
+        let unit = cur.u16()?;
+        let unit_multiplier = cur.i16()?;
+        let decimals = cur.i16()?;
+        let display_digits = cur.i16()?;
+        let state = cur.u16()?;
+        let attribute = cur.u16()?;
+        let ts = cur.double()?;
+
+        Ok(RawReading {
+            reading_id,
+            value,
+            unit,
+            unit_multiplier,
+            decimals,
+            display_digits,
+            state,
+            attribute,
+            ts,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct RawSavedMeasurement {
+    pub seq_no: u16,
+    pub un1: u16,
+    pub pri_function: u16,
+    pub sec_function: u16,
+    pub auto_range: u16,
+    pub unit: u16,
+    pub range_max: f64,
+    pub unit_multiplier: i16,
+    pub bolt: u16,
+    pub un2: u16,
+    pub un3: u16,
+    pub un4: u16,
+    pub un5: u16,
+    pub modes: u16,
+    pub un6: u16,
+
+    pub readings: Vec<RawReading>,
+
+    pub name: String,
+}
+
+impl RawSavedMeasurement {
+    pub fn can_parse(buf: &[u8]) -> std::io::Result<Option<usize>> {
+        if buf.len() >= BIN_MARKER_LEN + SAVED_MEA_METADATA_LEN {
+            // readings count is on last two bytes
+            let readings: u16 = u16::from_le_bytes([
+                buf[BIN_MARKER_LEN + SAVED_MEA_METADATA_LEN - 2],
+                buf[BIN_MARKER_LEN + SAVED_MEA_METADATA_LEN - 1],
+            ]);
+            // how many bytes total before ASCII data
+            let total = BIN_MARKER_LEN
+                + SAVED_MEA_METADATA_LEN
+                + readings_len(readings, MAX_MEASUREMENT_READINGS, "Saved measurement")?;
+
+            if buf.len() > total {
+                if let Some(idx) = buf[total..].iter().position(|b| *b == b'\r') {
+                    return Ok(Some(total + idx + EOL_LEN));
+                }
+            }
+        }
+        Ok(None) // Not enough data yet
+    }
+}
+
+fn read_saved_name(cur: &mut FrameReader<'_>) -> std::io::Result<String> {
+    if !cur.has_remaining() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "Name of saved entry is missing",
+        ));
+    }
+    let name = cur.take_until(b'\r');
+    if name.last() != Some(&b'\r') {
+        return Err(invalid_data("Name of saved entry is not terminated"));
+    }
+    // without delimiter
+    Ok(String::from_utf8_lossy(&name[..name.len() - 1]).into_owned())
+}
+
+impl TryFrom<&[u8]> for RawSavedMeasurement {
+    type Error = std::io::Error;
+
+    fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
+        check_len(
+            value,
+            BIN_MARKER_LEN + SAVED_MEA_METADATA_LEN,
+            "Saved measurement",
+        )?;
+
+        if value[0..2] == [b'#', b'0'] {
+            let mut cur = FrameReader::new(&value[2..]);
+
+            let seq_no = cur.u16()?;
+            let un1 = cur.u16()?;
+            let pri_function = cur.u16()?;
+            let sec_function = cur.u16()?;
+            let auto_range = cur.u16()?;
+            let unit = cur.u16()?;
+            let range_max = cur.double()?;
+            let unit_multiplier = cur.i16()?;
+            let bolt = cur.u16()?;
+
+            let un2 = cur.u16()?;
+            let un3 = cur.u16()?;
+            let un4 = cur.u16()?;
+            let un5 = cur.u16()?;
+
+            let mode = cur.u16()?;
+
+            let un6 = cur.u16()?;
+
+            let readings_cnt = cur.u16()?;
+            let readings = read_readings(
+                &mut cur,
+                readings_cnt,
+                MAX_MEASUREMENT_READINGS,
+                "Saved measurement",
+            )?;
+
+            let name = read_saved_name(&mut cur)?;
+
+            Ok(RawSavedMeasurement {
+                seq_no,
+                un1,
+                pri_function,
+                sec_function,
+                auto_range,
+                unit,
+                range_max,
+                unit_multiplier,
+                bolt,
+                un2,
+                un3,
+                un4,
+                un5,
+                modes: mode,
+                un6,
+                readings,
+                name,
+            })
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Binary data expected but not #0 marker found",
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct RawSavedMinMaxMeasurement {
+    pub seq_no: u16,
+    pub un1: u16,
+    pub ts1: f64,
+    pub ts2: f64,
+    pub pri_function: u16,
+    pub sec_function: u16,
+    pub auto_range: u16,
+    pub unit: u16,
+    pub range_max: f64,
+    pub unit_multiplier: i16,
+    pub bolt: u16,
+    pub ts3: f64,
+    pub modes: u16,
+    pub un2: u16,
+
+    pub readings: Vec<RawReading>,
+
+    pub name: String,
+}
+
+impl RawSavedMinMaxMeasurement {
+    pub fn can_parse(buf: &[u8]) -> std::io::Result<Option<usize>> {
+        if buf.len() >= BIN_MARKER_LEN + SAVED_MINMAX_METADATA_LEN {
+            // readings count is on last two bytes
+            let readings: u16 = u16::from_le_bytes([
+                buf[BIN_MARKER_LEN + SAVED_MINMAX_METADATA_LEN - 2],
+                buf[BIN_MARKER_LEN + SAVED_MINMAX_METADATA_LEN - 1],
+            ]);
+            // how many bytes total before ASCII data
+            let total = BIN_MARKER_LEN
+                + SAVED_MINMAX_METADATA_LEN
+                + readings_len(readings, MAX_MINMAX_READINGS, "Saved min/max measurement")?;
+
+            if buf.len() > total {
+                if let Some(idx) = buf[total..].iter().position(|b| *b == b'\r') {
+                    return Ok(Some(total + idx + EOL_LEN));
+                }
+            }
+        }
+        Ok(None) // Not enough data yet
+    }
+}
+
+impl TryFrom<&[u8]> for RawSavedMinMaxMeasurement {
+    type Error = std::io::Error;
+
+    fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
+        check_len(
+            value,
+            BIN_MARKER_LEN + SAVED_MINMAX_METADATA_LEN,
+            "Saved min/max measurement",
+        )?;
+
+        if value[0..2] == [b'#', b'0'] {
+            let mut cur = FrameReader::new(&value[2..]);
+
+            let seq_no = cur.u16()?;
+            let un1 = cur.u16()?;
+            let ts1 = cur.double()?;
+            let ts2 = cur.double()?;
+            let pri_function = cur.u16()?;
+            let sec_function = cur.u16()?;
+            let auto_range = cur.u16()?;
+            let unit = cur.u16()?;
+            let range_max = cur.double()?;
+            let unit_multiplier = cur.i16()?;
+            let bolt = cur.u16()?;
+            let ts3 = cur.double()?;
+            let mode = cur.u16()?;
+            let un2 = cur.u16()?;
+
+            let readings_cnt = cur.u16()?;
+            let readings = read_readings(
+                &mut cur,
+                readings_cnt,
+                MAX_MINMAX_READINGS,
+                "Saved min/max measurement",
+            )?;
+
+            let name = read_saved_name(&mut cur)?;
+
+            Ok(RawSavedMinMaxMeasurement {
+                seq_no,
+                un1,
+                ts1,
+                ts2,
+                pri_function,
+                sec_function,
+                auto_range,
+                unit,
+                range_max,
+                unit_multiplier,
+                bolt,
+                ts3,
+                modes: mode,
+                un2,
+                readings,
+                name,
+            })
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Binary data expected but not #0 marker found",
+            ))
+        }
+    }
+}
+
+// Same structure
+pub type RawSavedPeakMeasurement = RawSavedMinMaxMeasurement;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct RawSavedRecordingSessionInfo {
+    pub seq_no: u16,
+    pub un1: u16,
+    pub start_ts: f64,
+    pub end_ts: f64,
+    pub sample_interval: f64,
+    pub event_threshold: f64,
+    pub reading_index: u16,
+    pub un2: u16,
+    pub num_samples: u16,
+    pub un3: u16,
+    pub pri_function: u16,
+    pub sec_function: u16,
+    pub auto_range: u16,
+    pub unit: u16,
+    pub range_max: f64,
+    pub unit_multiplier: i16,
+    pub bolt: u16,
+    pub un4: u16,
+    pub un5: u16,
+    pub un6: u16,
+    pub un7: u16,
+    pub modes: u16,
+    pub un8: u16,
+
+    pub readings: Vec<RawReading>,
+
+    pub name: String,
+}
+
+impl TryFrom<&[u8]> for RawSavedRecordingSessionInfo {
+    type Error = std::io::Error;
+
+    fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
+        check_len(
+            value,
+            BIN_MARKER_LEN + SAVED_RECORDING_METADATA_LEN,
+            "Saved recording",
+        )?;
+
+        if value[0..2] == [b'#', b'0'] {
+            let mut cur = FrameReader::new(&value[2..]);
+
+            let seq_no = cur.u16()?;
+            let un1 = cur.u16()?;
+            let start_ts = cur.double()?;
+            let end_ts = cur.double()?;
+            let sample_interval = cur.double()?;
+            let event_threshold = cur.double()?;
+            let reading_index = cur.u16()?;
+            let un2 = cur.u16()?;
+            let num_samples = cur.u16()?;
+            let un3 = cur.u16()?;
+            let pri_function = cur.u16()?;
+            let sec_function = cur.u16()?;
+            let auto_range = cur.u16()?;
+            let unit = cur.u16()?;
+            let range_max = cur.double()?;
+            let unit_multiplier = cur.i16()?;
+            let bolt = cur.u16()?;
+            let un4 = cur.u16()?;
+            let un5 = cur.u16()?;
+            let un6 = cur.u16()?;
+            let un7 = cur.u16()?;
+            let mode = cur.u16()?;
+            let un8 = cur.u16()?;
+
+            let readings_cnt = cur.u16()?;
+            let readings = read_readings(
+                &mut cur,
+                readings_cnt,
+                MAX_RECORDING_READINGS,
+                "Saved recording",
+            )?;
+
+            let name = read_saved_name(&mut cur)?;
+
+            Ok(RawSavedRecordingSessionInfo {
+                seq_no,
+                un1,
+                start_ts,
+                end_ts,
+                sample_interval,
+                event_threshold,
+                reading_index,
+                un2,
+                num_samples,
+                un3,
+                pri_function,
+                sec_function,
+                auto_range,
+                unit,
+                range_max,
+                unit_multiplier,
+                bolt,
+                un4,
+                un5,
+                un6,
+                un7,
+                modes: mode,
+                un8,
+                readings,
+                name,
+            })
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Binary data expected but not #0 marker found",
+            ))
+        }
+    }
+}
+
+impl RawSavedRecordingSessionInfo {
+    pub fn can_parse(buf: &[u8]) -> std::io::Result<Option<usize>> {
+        if buf.len() >= BIN_MARKER_LEN + SAVED_RECORDING_METADATA_LEN {
+            // readings count is on last two bytes
+            let readings: u16 = u16::from_le_bytes([
+                buf[BIN_MARKER_LEN + SAVED_RECORDING_METADATA_LEN - 2],
+                buf[BIN_MARKER_LEN + SAVED_RECORDING_METADATA_LEN - 1],
+            ]);
+            // how many bytes total before ASCII data
+            let total = BIN_MARKER_LEN
+                + SAVED_RECORDING_METADATA_LEN
+                + readings_len(readings, MAX_RECORDING_READINGS, "Saved recording")?;
+
+            if buf.len() > total {
+                if let Some(idx) = buf[total..].iter().position(|b| *b == b'\r') {
+                    return Ok(Some(total + idx + EOL_LEN));
+                }
+            }
+        }
+        Ok(None) // Not enough data yet
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct RawSessionRecordReadings {
+    pub start_ts: f64,
+    pub end_ts: f64,
+    pub span_readings: [RawReading; 3],
+    pub sampling: u16,
+    pub un2: u16,
+    pub fixed_reading: RawReading,
+    pub record_type: u16,
+    pub stable: u16,
+    pub transient_state: u16,
+}
+
+impl TryFrom<&[u8]> for RawSessionRecordReadings {
+    type Error = std::io::Error;
+
+    fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
+        check_len(
+            value,
+            BIN_MARKER_LEN + SAVED_RECORD_READINGS_LEN,
+            "Recording interval",
+        )?;
+
+        if value[0..2] == [b'#', b'0'] {
+            let mut cur = FrameReader::new(&value[2..]);
+
+            let start_ts = cur.double()?;
+            let end_ts = cur.double()?;
+
+            let span_readings = [
+                RawReading::try_from(cur.take(READING_LEN)?)?,
+                RawReading::try_from(cur.take(READING_LEN)?)?,
+                RawReading::try_from(cur.take(READING_LEN)?)?,
+            ];
+
+            let sampling = cur.u16()?;
+            let un2 = cur.u16()?;
+
+            let reading2 = RawReading::try_from(cur.take(READING_LEN)?)?;
+
+            let record_type = cur.u16()?;
+
+            let stable = cur.u16()?;
+            let transient_state = cur.u16()?;
+
+            Ok(RawSessionRecordReadings {
+                start_ts,
+                end_ts,
+                span_readings,
+                sampling,
+                un2,
+                fixed_reading: reading2,
+                record_type,
+                stable,
+                transient_state,
+            })
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Binary data expected but not #0 marker found",
+            ))
+        }
+    }
+}
+
+impl RawSessionRecordReadings {
+    pub fn can_parse(buf: &[u8]) -> std::io::Result<Option<usize>> {
+        //const STATUS_LEN: usize = 2;
+        const EOL_LEN: usize = 1;
+
+        let total = BIN_MARKER_LEN + SAVED_RECORD_READINGS_LEN;
+
+        if buf.len() >= total + EOL_LEN {
+            return Ok(Some(total + EOL_LEN));
+        }
+        Ok(None) // Not enough data yet
+    }
+}
+
+impl RawMeasurement {
+    /// Frame as sent by the meter after the status line, parsing it yields
+    /// the same measurement.
+    pub fn to_frame(&self) -> Vec<u8> {
+        FrameWriter::new()
+            .u16(self.pri_function)
+            .u16(self.sec_function)
+            .u16(self.auto_range)
+            .u16(self.unit)
+            .double(self.range_max)
+            .i16(self.unit_multiplier)
+            .u16(self.bolt)
+            .f64(self.ts)
+            .u16(self.modes)
+            .u16(self.un1)
+            .readings(&self.readings)
+            .finish()
+    }
+}
+
+impl RawSavedMeasurement {
+    /// Frame as sent by the meter after the status line.
+    pub fn to_frame(&self) -> Vec<u8> {
+        FrameWriter::new()
+            .u16(self.seq_no)
+            .u16(self.un1)
+            .u16(self.pri_function)
+            .u16(self.sec_function)
+            .u16(self.auto_range)
+            .u16(self.unit)
+            .double(self.range_max)
+            .i16(self.unit_multiplier)
+            .u16(self.bolt)
+            .u16(self.un2)
+            .u16(self.un3)
+            .u16(self.un4)
+            .u16(self.un5)
+            .u16(self.modes)
+            .u16(self.un6)
+            .readings(&self.readings)
+            .name(&self.name)
+            .finish()
+    }
+}
+
+impl RawSavedMinMaxMeasurement {
+    /// Frame as sent by the meter after the status line.
+    pub fn to_frame(&self) -> Vec<u8> {
+        FrameWriter::new()
+            .u16(self.seq_no)
+            .u16(self.un1)
+            .double(self.ts1)
+            .double(self.ts2)
+            .u16(self.pri_function)
+            .u16(self.sec_function)
+            .u16(self.auto_range)
+            .u16(self.unit)
+            .double(self.range_max)
+            .i16(self.unit_multiplier)
+            .u16(self.bolt)
+            .double(self.ts3)
+            .u16(self.modes)
+            .u16(self.un2)
+            .readings(&self.readings)
+            .name(&self.name)
+            .finish()
+    }
+}
+
+impl RawSavedRecordingSessionInfo {
+    /// Frame as sent by the meter after the status line.
+    pub fn to_frame(&self) -> Vec<u8> {
+        FrameWriter::new()
+            .u16(self.seq_no)
+            .u16(self.un1)
+            .double(self.start_ts)
+            .double(self.end_ts)
+            .double(self.sample_interval)
+            .double(self.event_threshold)
+            .u16(self.reading_index)
+            .u16(self.un2)
+            .u16(self.num_samples)
+            .u16(self.un3)
+            .u16(self.pri_function)
+            .u16(self.sec_function)
+            .u16(self.auto_range)
+            .u16(self.unit)
+            .double(self.range_max)
+            .i16(self.unit_multiplier)
+            .u16(self.bolt)
+            .u16(self.un4)
+            .u16(self.un5)
+            .u16(self.un6)
+            .u16(self.un7)
+            .u16(self.modes)
+            .u16(self.un8)
+            .readings(&self.readings)
+            .name(&self.name)
+            .finish()
+    }
+}
+
+impl RawSessionRecordReadings {
+    /// Frame as sent by the meter after the status line.
+    pub fn to_frame(&self) -> Vec<u8> {
+        let mut frame = FrameWriter::new();
+        frame.double(self.start_ts).double(self.end_ts);
+        for reading in &self.span_readings {
+            frame.reading(reading);
+        }
+        frame
+            .u16(self.sampling)
+            .u16(self.un2)
+            .reading(&self.fixed_reading)
+            .u16(self.record_type)
+            .u16(self.stable)
+            .u16(self.transient_state)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_reader() {
+        let frame = [0x34, 0x12, 0xfe, 0xff, b'a', b'b', b'\r', b'c'];
+        let mut cur = FrameReader::new(&frame);
+        assert_eq!(cur.u16().expect("u16"), 0x1234);
+        assert_eq!(cur.i16().expect("i16"), -2);
+        assert_eq!(cur.take_until(b'\r'), b"ab\r");
+        assert_eq!(cur.remaining(), 1);
+        assert_eq!(
+            cur.u16().expect_err("too short").kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+
+        let reading = [0_u8; READING_LEN - 1];
+        assert!(RawReading::try_from(&reading[..]).is_err());
+    }
+
+    #[test]
+    fn test_corrupted_frames() {
+        let mut frame = vec![b'#', b'0'];
+        frame.extend([0; MEA_METADATA_LEN - 2]);
+        let err = RawMeasurement::try_from(&frame[..]).expect_err("too short");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "Measurement frame too short: 34 bytes, expected at least 36"
+        );
+
+        // One reading announced, none sent
+        frame.extend([1, 0, b'\r']);
+        let err = RawMeasurement::try_from(&frame[..]).expect_err("readings missing");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut frame = vec![b'#', b'0'];
+        frame.extend([0; SAVED_MEA_METADATA_LEN]);
+        frame.extend(b"name");
+        let err = RawSavedMeasurement::try_from(&frame[..]).expect_err("unterminated");
+        assert_eq!(err.to_string(), "Name of saved entry is not terminated");
+        frame.truncate(BIN_MARKER_LEN + SAVED_MEA_METADATA_LEN);
+        assert!(RawSavedMeasurement::try_from(&frame[..]).is_err());
+
+        assert!(RawSessionRecordReadings::try_from(&[b'#', b'0'][..]).is_err());
+    }
+
+    #[test]
+    fn test_to_frame() {
+        let reading = RawReading {
+            reading_id: 2,
+            value: -1.234,
+            unit: 1,
+            unit_multiplier: -3,
+            decimals: 4,
+            display_digits: 5,
+            state: 2,
+            attribute: 0,
+            ts: 1_700_000_000.5,
+        };
+        let mea = RawSavedMinMaxMeasurement {
+            seq_no: 3,
+            un1: 0,
+            ts1: 1_700_000_000.0,
+            ts2: 1_700_000_060.0,
+            pri_function: 3,
+            sec_function: 0,
+            auto_range: 1,
+            unit: 1,
+            range_max: 5.0,
+            unit_multiplier: 0,
+            bolt: 0,
+            ts3: 1_700_000_030.0,
+            modes: 16,
+            un2: 0,
+            readings: vec![reading.clone(), reading],
+            name: "Save 3".to_string(),
+        };
+        let frame = mea.to_frame();
+        assert_eq!(
+            RawSavedMinMaxMeasurement::can_parse(&frame).expect("count"),
+            Some(frame.len())
+        );
+        let parsed = RawSavedMinMaxMeasurement::try_from(&frame[..]).expect("parse");
+        assert_eq!(parsed.name, "Save 3");
+        assert_eq!(parsed.readings[1].value, -1.234);
+        assert_eq!(parsed.to_frame(), frame);
+
+        let rec = RawSessionRecordReadings {
+            start_ts: 1_700_000_000.0,
+            end_ts: 1_700_000_001.0,
+            span_readings: [
+                parsed.readings[0].clone(),
+                parsed.readings[0].clone(),
+                parsed.readings[0].clone(),
+            ],
+            sampling: 10,
+            un2: 0,
+            fixed_reading: parsed.readings[1].clone(),
+            record_type: 1,
+            stable: 1,
+            transient_state: 0,
+        };
+        let frame = rec.to_frame();
+        assert_eq!(
+            frame.len(),
+            BIN_MARKER_LEN + SAVED_RECORD_READINGS_LEN + EOL_LEN
+        );
+        let parsed = RawSessionRecordReadings::try_from(&frame[..]).expect("parse");
+        assert_eq!(parsed.to_frame(), frame);
+    }
+
+    #[test]
+    fn test_readings_count_limits() {
+        let mut frame = vec![b'#', b'0'];
+        frame.extend([0; SAVED_RECORDING_METADATA_LEN - 2]);
+        frame.extend((MAX_RECORDING_READINGS as u16 + 1).to_le_bytes());
+        let err = RawSavedRecordingSessionInfo::can_parse(&frame).expect_err("too many");
+        assert_eq!(
+            err.to_string(),
+            "Saved recording readings count 17 exceeds maximum of 16"
+        );
+        let err = RawSavedRecordingSessionInfo::try_from(&frame[..]).expect_err("too many");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut frame = vec![b'#', b'0'];
+        frame.extend([0; SAVED_MINMAX_METADATA_LEN - 2]);
+        frame.extend((MAX_RECORDING_READINGS as u16 + 1).to_le_bytes());
+        assert_eq!(
+            RawSavedMinMaxMeasurement::can_parse(&frame).expect("count"),
+            None
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Device with identification and value maps loaded.
+//!
+//! Decoding measurements requires the value maps of the connected meter and
+//! panics on missing entries. A [`ReadyDevice`] can only be created after the
+//! maps were loaded and checked, so all its measurement APIs are safe to use:
+//!
+//! ```no_run
+//! # async fn example() -> f289ctrl_core::Result<()> {
+//! let mut device = f289ctrl_core::Device::new("/dev/ttyUSB0", 115200)?
+//!     .ready()
+//!     .await?;
+//! if let Some(measurement) = device.live_measurement().await? {
+//!     println!("{:?}", measurement.pri_function);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io,
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
+
+use futures::{Stream, StreamExt};
+
+use crate::{
+    device::{Device, ValueMaps, VALUE_MAP_KEYS},
+    maps,
+    measurement::{Measurement, Memory},
+    progress::Progress,
+    proto::{response::Ident, ProtoError, Result},
+    snapshot::MemorySnapshot,
+};
+
+/// A [`Device`] upgraded by [`Device::ready`].
+///
+/// Dereferences to the underlying [`Device`] for settings and raw access.
+pub struct ReadyDevice {
+    device: Device,
+    ident: Ident,
+    maps: ValueMaps,
+    /// Maps and entries taken from the built-in tables
+    builtin: Vec<String>,
+}
+
+impl Device {
+    /// Load identification and value maps, required for decoding measurements.
+    ///
+    /// Maps the device fails to report are taken from the built-in tables
+    /// if the firmware is known, see [`ReadyDevice::builtin_maps`].
+    pub async fn ready(mut self) -> Result<ReadyDevice> {
+        let ident = self.ident().await?;
+        let (maps, builtin) = self.value_maps_with_fallback(Some(&ident.firmware)).await?;
+        let mut ready = ReadyDevice::new(self, ident, maps)?;
+        ready.builtin = builtin;
+        Ok(ready)
+    }
+}
+
+impl ReadyDevice {
+    /// Use previously loaded value maps, e.g. from a cache.
+    pub fn new(device: Device, ident: Ident, maps: ValueMaps) -> Result<Self> {
+        if let Some(key) = VALUE_MAP_KEYS
+            .iter()
+            .find(|key| maps.get(**key).map_or(true, |map| map.is_empty()))
+        {
+            return Err(ProtoError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Value map '{}' is missing or empty", key),
+            )));
+        }
+        Ok(Self {
+            device,
+            ident,
+            maps,
+            builtin: Vec::new(),
+        })
+    }
+
+    /// Skip `qemap` and use the built-in value maps for the firmware.
+    pub fn with_builtin_maps(device: Device, ident: Ident) -> Result<Self> {
+        let maps = maps::builtin_maps(Some(&ident.firmware)).ok_or_else(|| {
+            ProtoError::Io(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("No built-in value maps for firmware {}", ident.firmware),
+            ))
+        })?;
+        let mut ready = Self::new(device, ident, maps)?;
+        ready.builtin = VALUE_MAP_KEYS.iter().map(|k| k.to_string()).collect();
+        Ok(ready)
+    }
+
+    /// Maps (by key) and entries (`key[id]`) not reported by the device but
+    /// taken from the built-in tables.
+    pub fn builtin_maps(&self) -> &[String] {
+        &self.builtin
+    }
+
+    pub fn ident(&self) -> &Ident {
+        &self.ident
+    }
+
+    pub fn maps(&self) -> &ValueMaps {
+        &self.maps
+    }
+
+    pub fn into_inner(self) -> Device {
+        self.device
+    }
+
+    pub async fn live_measurement(&mut self) -> Result<Option<Measurement>> {
+        let maps = &self.maps;
+        self.device
+            .live_measurement()
+            .await?
+            .map(|raw| Measurement::received(raw, maps))
+            .transpose()
+    }
+
+    /// Decoded variant of [`Device::live_measurements`].
+    pub fn live_measurements(
+        &mut self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<Option<Measurement>>> + '_ {
+        let maps = &self.maps;
+        self.device.live_measurements(interval).map(move |result| {
+            result.and_then(|raw| raw.map(|raw| Measurement::received(raw, maps)).transpose())
+        })
+    }
+
+    pub async fn all_memory(&mut self) -> Result<Vec<Memory>> {
+        self.device.all_memory(&self.maps).await
+    }
+
+    pub async fn all_memory_with_progress(
+        &mut self,
+        progress: impl FnMut(&Progress),
+    ) -> Result<Vec<Memory>> {
+        self.device
+            .all_memory_with_progress(&self.maps, progress)
+            .await
+    }
+
+    pub async fn snapshot_memory(&mut self) -> Result<MemorySnapshot> {
+        self.device.snapshot_memory(&self.maps).await
+    }
+
+    pub async fn snapshot_memory_with_progress(
+        &mut self,
+        progress: impl FnMut(&Progress),
+    ) -> Result<MemorySnapshot> {
+        self.device
+            .snapshot_memory_with_progress(&self.maps, progress)
+            .await
+    }
+}
+
+impl Deref for ReadyDevice {
+    type Target = Device;
+
+    fn deref(&self) -> &Self::Target {
+        &self.device
+    }
+}
+
+impl DerefMut for ReadyDevice {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.device
+    }
+}
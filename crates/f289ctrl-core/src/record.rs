@@ -0,0 +1,353 @@
+//! Recording and replay of the raw byte stream of a session.
+//!
+//! [`SessionRecorder`] wraps a transport and writes every byte sent and
+//! received to a gzip compressed file, together with a timestamp. The
+//! recording can be read back with [`SessionReader`] to verify decoding
+//! against the original bytes, or fed through the codec again with
+//! [`ReplayTransport`].
+//!
+//! File format (inside gzip): the magic `F289REC1`, followed by records of
+//! direction (`T` sent, `R` received), timestamp in microseconds since the
+//! Unix epoch (i64 LE), length (u32 LE) and the bytes.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use chrono::{DateTime, TimeZone, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+const MAGIC: &[u8; 8] = b"F289REC1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub direction: Direction,
+    pub ts: DateTime<Utc>,
+    pub bytes: Vec<u8>,
+}
+
+/// Transport wrapper recording all bytes to a compressed file.
+pub struct SessionRecorder<T> {
+    inner: T,
+    sink: GzEncoder<BufWriter<File>>,
+}
+
+impl<T> SessionRecorder<T> {
+    pub fn create(inner: T, path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut sink = GzEncoder::new(file, Compression::default());
+        sink.write_all(MAGIC)?;
+        Ok(Self { inner, sink })
+    }
+
+    fn record(&mut self, direction: Direction, bytes: &[u8]) -> io::Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.sink.write_u8(match direction {
+            Direction::Sent => b'T',
+            Direction::Received => b'R',
+        })?;
+        self.sink
+            .write_i64::<LittleEndian>(Utc::now().timestamp_micros())?;
+        self.sink.write_u32::<LittleEndian>(bytes.len() as u32)?;
+        self.sink.write_all(bytes)
+    }
+
+    /// Complete the gzip stream and return the wrapped transport.
+    pub fn finish(self) -> io::Result<T> {
+        self.sink.finish()?.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for SessionRecorder<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        match Pin::new(&mut this.inner).poll_read(cx, buf) {
+            Poll::Ready(Ok(())) => {
+                Poll::Ready(this.record(Direction::Received, &buf.filled()[before..]))
+            }
+            other => other,
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for SessionRecorder<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.inner).poll_write(cx, buf) {
+            Poll::Ready(Ok(n)) => Poll::Ready(this.record(Direction::Sent, &buf[..n]).map(|_| n)),
+            other => other,
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        // Keep the recording readable up to here if the process dies
+        if let Err(err) = this.sink.flush() {
+            return Poll::Ready(Err(err));
+        }
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if let Err(err) = this.sink.try_finish() {
+            return Poll::Ready(Err(err));
+        }
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+/// Iterator over the records of a recording file.
+pub struct SessionReader<R> {
+    source: GzDecoder<R>,
+}
+
+impl SessionReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> SessionReader<R> {
+    pub fn new(source: R) -> io::Result<Self> {
+        let mut source = GzDecoder::new(source);
+        let mut magic = [0; 8];
+        source.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a session recording",
+            ));
+        }
+        Ok(Self { source })
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<Record>> {
+        let direction = match self.source.read_u8() {
+            Ok(b'T') => Direction::Sent,
+            Ok(b'R') => Direction::Received,
+            Ok(other) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unknown record direction: {:#x}", other),
+                ))
+            }
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let micros = self.source.read_i64::<LittleEndian>()?;
+        let len = self.source.read_u32::<LittleEndian>()?;
+        let mut bytes = vec![0; len as usize];
+        self.source.read_exact(&mut bytes)?;
+        let ts = Utc
+            .timestamp_opt(
+                micros.div_euclid(1_000_000),
+                (micros.rem_euclid(1_000_000) * 1000) as u32,
+            )
+            .single()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid timestamp"))?;
+        Ok(Some(Record {
+            direction,
+            ts,
+            bytes,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for SessionReader<R> {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Transport answering from a recording, for reproducing decoding issues offline.
+///
+/// Received bytes are only handed out after the commands recorded before
+/// them were written, so the codec sees the same exchange as in the
+/// original session. Writes that differ from the recording fail.
+pub struct ReplayTransport {
+    records: std::collections::VecDeque<Record>,
+    /// Bytes of the front record already written or read
+    offset: usize,
+    waker: Option<std::task::Waker>,
+}
+
+impl ReplayTransport {
+    pub fn new(records: impl IntoIterator<Item = Record>) -> Self {
+        Self {
+            records: records
+                .into_iter()
+                .filter(|r| !r.bytes.is_empty())
+                .collect(),
+            offset: 0,
+            waker: None,
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(
+            SessionReader::open(path)?.collect::<io::Result<Vec<_>>>()?,
+        ))
+    }
+
+    fn advance(&mut self, n: usize) {
+        self.offset += n;
+        if self.offset == self.records[0].bytes.len() {
+            self.records.pop_front();
+            self.offset = 0;
+        }
+    }
+}
+
+impl AsyncRead for ReplayTransport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.records.front() {
+            // End of the recording
+            None => Poll::Ready(Ok(())),
+            Some(record) if record.direction == Direction::Received => {
+                let n = buf.remaining().min(record.bytes.len() - this.offset);
+                buf.put_slice(&record.bytes[this.offset..this.offset + n]);
+                this.advance(n);
+                Poll::Ready(Ok(()))
+            }
+            // Wait for the command to be written
+            Some(_) => {
+                this.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl AsyncWrite for ReplayTransport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let expected = match this.records.front() {
+            Some(record) if record.direction == Direction::Sent => &record.bytes[this.offset..],
+            _ => &[],
+        };
+        let n = buf.len().min(expected.len());
+        if n == 0 || buf[..n] != expected[..n] {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Replay diverged: sent {:?}, recording expects {:?}",
+                    buf.escape_ascii().to_string(),
+                    expected.escape_ascii().to_string()
+                ),
+            )));
+        }
+        this.advance(n);
+        if let Some(waker) = this.waker.take() {
+            waker.wake();
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Device;
+
+    #[tokio::test]
+    async fn test_record_session() {
+        let path = std::env::temp_dir().join(format!("f289rec-{}.gz", std::process::id()));
+        let (transport, mut meter) = tokio::io::duplex(64);
+        let recorder = SessionRecorder::create(transport, &path).expect("create");
+        let mut device = Device::with_transport(recorder);
+
+        let meter = tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let mut cmd = [0; 3];
+            meter.read_exact(&mut cmd).await.expect("read");
+            meter
+                .write_all(b"0\rFluke 289,V1.16,1\r")
+                .await
+                .expect("write");
+            cmd
+        });
+        device.ident().await.expect("ident");
+        assert_eq!(&meter.await.expect("meter"), b"id\r");
+        drop(device);
+
+        let records: Vec<Record> = SessionReader::open(&path)
+            .expect("open")
+            .collect::<io::Result<_>>()
+            .expect("records");
+        std::fs::remove_file(&path).ok();
+        assert_eq!(records[0].direction, Direction::Sent);
+        assert_eq!(records[0].bytes, b"id\r");
+        let received: Vec<u8> = records[1..]
+            .iter()
+            .flat_map(|r| r.bytes.iter().copied())
+            .collect();
+        assert_eq!(received, b"0\rFluke 289,V1.16,1\r");
+    }
+
+    #[tokio::test]
+    async fn test_replay() {
+        let record = |direction, bytes: &[u8]| Record {
+            direction,
+            ts: Utc::now(),
+            bytes: bytes.to_vec(),
+        };
+        let records = vec![
+            record(Direction::Sent, b"id\r"),
+            record(Direction::Received, b"0\rFluke 289,"),
+            record(Direction::Received, b"V1.16,1\r"),
+            record(Direction::Sent, b"qmp beeper\r"),
+            record(Direction::Received, b"0\rOFF\r"),
+        ];
+
+        let mut device = Device::with_transport(ReplayTransport::new(records.clone()));
+        assert_eq!(device.ident().await.expect("ident").firmware, "V1.16");
+        assert!(!device.beeper().await.expect("beeper"));
+
+        let mut device = Device::with_transport(ReplayTransport::new(records));
+        assert!(device.beeper().await.is_err());
+    }
+}
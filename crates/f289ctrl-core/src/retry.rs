@@ -0,0 +1,128 @@
+//! Retry policies for transient protocol failures.
+
+use std::time::Duration;
+
+use crate::proto::{
+    command::{Command, Key},
+    response::Response,
+};
+
+/// Decides whether a command is repeated after a transient failure.
+///
+/// Transient failures are the responses [`Response::NoData`] (e.g. a live
+/// measurement requested while the meter is still settling),
+/// [`Response::ExecutionError`] (meter busy) and [`Response::FramingError`].
+pub trait RetryPolicy: Send + Sync {
+    /// Delay before repeating `command` after its `attempt`th failure
+    /// (counting from 1), or `None` to hand the response to the caller.
+    fn retry(&self, command: &Command, attempt: u32, response: &Response) -> Option<Duration>;
+}
+
+/// Never retry, every failure is returned to the caller (the default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoRetry;
+
+impl RetryPolicy for NoRetry {
+    fn retry(&self, _command: &Command, _attempt: u32, _response: &Response) -> Option<Duration> {
+        None
+    }
+}
+
+/// Retry up to `max_attempts` times in total, with exponential backoff.
+///
+/// Destructive commands (clear memory, reset) are never repeated.
+/// [`Response::NoData`] is only retried for live measurements, for other
+/// queries it means there is nothing to report (e.g. an empty memory).
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub factor: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(100),
+            factor: 2,
+        }
+    }
+}
+
+impl RetryPolicy for Backoff {
+    fn retry(&self, command: &Command, attempt: u32, response: &Response) -> Option<Duration> {
+        if attempt >= self.max_attempts
+            || matches!(command, Command::Clear(_) | Command::ResetDevice)
+            || (matches!(response, Response::NoData)
+                && !matches!(
+                    command,
+                    Command::GetMeasurementBinary | Command::GetMeasurementAscii
+                ))
+        {
+            return None;
+        }
+        Some(self.initial_delay * self.factor.saturating_pow(attempt - 1))
+    }
+}
+
+/// Reaction on commands the meter rejects because a setup, hold or other
+/// modal screen is open, see [`Device::set_locked_recovery`].
+///
+/// [`Device::set_locked_recovery`]: crate::device::Device::set_locked_recovery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockedRecovery {
+    /// Fail with [`ProtoError::DeviceLocked`](crate::proto::ProtoError::DeviceLocked)
+    #[default]
+    Fail,
+    /// Press `key` to leave the screen and repeat the command, at most
+    /// `attempts` times
+    PressKey { key: Key, attempts: u32 },
+    /// Repeat the command after `delay`, e.g. until the user closed the
+    /// screen, at most `attempts` times
+    Wait { delay: Duration, attempts: u32 },
+}
+
+impl LockedRecovery {
+    /// Close menus and setup screens with the soft key labeled Close/Back.
+    pub fn close_screen() -> Self {
+        Self::PressKey {
+            key: Key::F4,
+            attempts: 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = Backoff::default();
+        let command = Command::GetMeasurementBinary;
+        assert_eq!(
+            policy.retry(&command, 1, &Response::ExecutionError),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.retry(&command, 2, &Response::ExecutionError),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(policy.retry(&command, 3, &Response::ExecutionError), None);
+        assert_eq!(
+            policy.retry(&Command::ResetDevice, 1, &Response::ExecutionError),
+            None
+        );
+        assert_eq!(
+            policy.retry(&command, 1, &Response::NoData),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.retry(&Command::GetMeasurementAscii, 2, &Response::NoData),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(policy.retry(&command, 3, &Response::NoData), None);
+        assert_eq!(policy.retry(&Command::Id, 1, &Response::NoData), None);
+    }
+}
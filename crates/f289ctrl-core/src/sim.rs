@@ -0,0 +1,684 @@
+//! Simulated meter speaking the remote protocol, available with the `sim`
+//! feature and used by the `f289sim` binary.
+//!
+//! The [`Simulator`] answers settings, value map, live measurement and
+//! memory commands from configurable fake data, so tools can be developed
+//! and tested without a meter. [`Faults`] inject the errors seen on real
+//! links: busy and syntax error responses, missing data, line noise,
+//! dropped and delayed responses.
+//!
+//! ```
+//! use f289ctrl_core::{sim::{SimConfig, Simulator}, Device};
+//!
+//! #[tokio::main]
+//! async fn main() -> f289ctrl_core::Result<()> {
+//!     let (client, server) = tokio::io::duplex(4096);
+//!     let mut simulator = Simulator::new(SimConfig::default())?;
+//!     tokio::spawn(async move { simulator.serve(server).await });
+//!     let mut device = Device::with_transport(client);
+//!     assert_eq!(device.ident().await?.model, "FLUKE 289");
+//!     Ok(())
+//! }
+//! ```
+
+use std::{collections::HashMap, io, time::Duration};
+
+use chrono::{Local, TimeZone, Utc};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    device::ValueMaps,
+    maps,
+    proto::{ProtoError, Result},
+    rawmea::{
+        RawMeasurement, RawReading, RawSavedMeasurement, RawSavedMinMaxMeasurement,
+        RawSavedRecordingSessionInfo, RawSessionRecordReadings,
+    },
+};
+
+/// Settings of `qmp`/`mp` with their values after a reset.
+const DEFAULT_SETTINGS: [(&str, &str); 14] = [
+    ("ablto", "900"),
+    ("apoffto", "1800"),
+    ("beeper", "ON"),
+    ("acsmooth", "OFF"),
+    ("digits", "5"),
+    ("lang", "ENGLISH"),
+    ("dateFmt", "MM_DD"),
+    ("timeFmt", "24"),
+    ("numFmt", "POINT"),
+    ("ahEventTh", "4"),
+    ("recEventTh", "4"),
+    ("cusDBm", "600"),
+    ("dBmRef", "600"),
+    ("tempOs", "0"),
+];
+
+/// String settings of `qmpq`/`mpq`.
+const STRING_SETTINGS: [&str; 4] = ["operator", "company", "site", "contact"];
+
+/// Reading id of the primary display.
+const PRIMARY_READING: u16 = 2;
+
+/// Errors injected into the responses, each rate is a probability per
+/// command between 0 and 1.
+#[derive(Debug, Clone, Default)]
+pub struct Faults {
+    /// Answer with an execution error, like a meter busy in a menu
+    pub busy: f64,
+    /// Answer with a syntax error
+    pub syntax_error: f64,
+    /// Answer `qddb` with no data
+    pub no_data: f64,
+    /// Prefix the response with garbage bytes
+    pub garbage: f64,
+    /// Don't answer at all
+    pub drop: f64,
+    /// Wait before each response
+    pub delay: Duration,
+}
+
+/// Identity and fake data of a [`Simulator`].
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    pub model: String,
+    pub firmware: String,
+    pub serial: String,
+    /// Primary function as named in the `primfunction` map, e.g. `V_DC`
+    pub function: String,
+    /// Unit as named in the `unit` map, e.g. `VDC`
+    pub unit: String,
+    /// Center of the simulated readings
+    pub value: f64,
+    /// Maximum deviation of a reading from `value`
+    pub noise: f64,
+    pub decimals: i16,
+    /// Number of saved measurements, min/max, peak and recording sessions each
+    pub memory: u16,
+    /// Samples of each recording session
+    pub samples: u16,
+    pub faults: Faults,
+    /// Seed of the noise and fault injection, runs with the same seed and
+    /// commands get the same responses
+    pub seed: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            model: "FLUKE 289".to_string(),
+            firmware: "V1.16".to_string(),
+            serial: "12345678".to_string(),
+            function: "V_DC".to_string(),
+            unit: "VDC".to_string(),
+            value: 12.0,
+            noise: 0.05,
+            decimals: 4,
+            memory: 2,
+            samples: 10,
+            faults: Faults::default(),
+            seed: 1,
+        }
+    }
+}
+
+/// Simulated meter state.
+#[derive(Debug)]
+pub struct Simulator {
+    config: SimConfig,
+    maps: ValueMaps,
+    pri_function: u16,
+    unit: u16,
+    state: u16,
+    settings: Vec<(&'static str, String)>,
+    strings: Vec<(&'static str, String)>,
+    save_names: HashMap<u16, String>,
+    /// Seconds between the simulated and the host clock
+    clock_offset: i64,
+    measurements: Vec<RawSavedMeasurement>,
+    min_max: Vec<RawSavedMinMaxMeasurement>,
+    peak: Vec<RawSavedMinMaxMeasurement>,
+    recordings: Vec<RawSavedRecordingSessionInfo>,
+    rng: u64,
+}
+
+impl Simulator {
+    /// Simulator with built-in value maps of the configured firmware.
+    pub fn new(config: SimConfig) -> Result<Self> {
+        let maps = maps::builtin_maps(Some(&config.firmware)).ok_or_else(|| {
+            ProtoError::InvalidArgument(format!("No value maps for firmware {}", config.firmware))
+        })?;
+        let pri_function = map_index(&maps, "primfunction", &config.function)?;
+        let unit = map_index(&maps, "unit", &config.unit)?;
+        let state = map_index(&maps, "state", "NORMAL")?;
+        let mut simulator = Self {
+            rng: config.seed ^ 0x9e37_79b9_7f4a_7c15,
+            config,
+            maps,
+            pri_function,
+            unit,
+            state,
+            settings: Vec::new(),
+            strings: Vec::new(),
+            save_names: HashMap::new(),
+            clock_offset: 0,
+            measurements: Vec::new(),
+            min_max: Vec::new(),
+            peak: Vec::new(),
+            recordings: Vec::new(),
+        };
+        if simulator.rng == 0 {
+            simulator.rng = 1;
+        }
+        simulator.reset();
+        simulator.fill_memory();
+        Ok(simulator)
+    }
+
+    /// Response to one command line without the terminating `\r`, `None`
+    /// if it is dropped.
+    pub fn respond(&mut self, line: &str) -> Option<Vec<u8>> {
+        let faults = self.config.faults.clone();
+        if self.chance(faults.drop) {
+            return None;
+        }
+        let mut response = Vec::new();
+        if self.chance(faults.garbage) {
+            let len = 1 + self.next() % 4;
+            for _ in 0..len {
+                response.push(0x80 | (self.next() & 0x7f) as u8);
+            }
+        }
+        let (command, args) = match line.trim().split_once(' ') {
+            Some((command, args)) => (command.to_ascii_lowercase(), args.trim()),
+            None => (line.trim().to_ascii_lowercase(), ""),
+        };
+        if self.chance(faults.busy) {
+            response.extend_from_slice(b"2\r");
+        } else if self.chance(faults.syntax_error) {
+            response.extend_from_slice(b"1\r");
+        } else if command == "qddb" && self.chance(faults.no_data) {
+            response.extend_from_slice(b"5\r");
+        } else {
+            match self.answer(&command, args) {
+                Some(payload) => {
+                    response.extend_from_slice(b"0\r");
+                    response.extend_from_slice(&payload);
+                }
+                None => response.extend_from_slice(b"1\r"),
+            }
+        }
+        Some(response)
+    }
+
+    /// Answer commands read from `stream` until it is closed.
+    pub async fn serve<S>(&mut self, mut stream: S) -> io::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut line = Vec::new();
+        let mut buf = [0u8; 256];
+        loop {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            for byte in &buf[..n] {
+                match byte {
+                    b'\r' => {
+                        let command = String::from_utf8_lossy(&line).into_owned();
+                        line.clear();
+                        if command.trim().is_empty() {
+                            continue;
+                        }
+                        if let Some(response) = self.respond(&command) {
+                            if !self.config.faults.delay.is_zero() {
+                                tokio::time::sleep(self.config.faults.delay).await;
+                            }
+                            stream.write_all(&response).await?;
+                            stream.flush().await?;
+                        }
+                    }
+                    b'\n' => {}
+                    byte => line.push(*byte),
+                }
+            }
+        }
+    }
+
+    /// Payload of a successful response, `None` for a syntax error.
+    fn answer(&mut self, command: &str, args: &str) -> Option<Vec<u8>> {
+        match command {
+            "id" => Some(
+                format!(
+                    "{},{},{}\r",
+                    self.config.model, self.config.firmware, self.config.serial
+                )
+                .into_bytes(),
+            ),
+            "qemap" => {
+                let map = self.maps.get(&args.to_ascii_lowercase())?;
+                let mut entries: Vec<(&u16, &String)> = map.iter().collect();
+                entries.sort();
+                let mut line = entries.len().to_string();
+                for (id, name) in entries {
+                    line.push_str(&format!(",{},{}", id, name));
+                }
+                line.push('\r');
+                Some(line.into_bytes())
+            }
+            "qmp" if args.eq_ignore_ascii_case("clock") => {
+                Some(format!("{}\r", self.clock()).into_bytes())
+            }
+            "qmp" => self
+                .settings
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(args))
+                .map(|(_, value)| format!("{}\r", value).into_bytes()),
+            "mp" => {
+                let (name, value) = args.split_once(',')?;
+                if name.eq_ignore_ascii_case("clock") {
+                    let clock: i64 = value.trim().parse().ok()?;
+                    self.clock_offset = clock - local_now();
+                    return Some(Vec::new());
+                }
+                let setting = self
+                    .settings
+                    .iter_mut()
+                    .find(|(setting, _)| setting.eq_ignore_ascii_case(name))?;
+                setting.1 = value.trim().to_string();
+                Some(Vec::new())
+            }
+            "qmpq" => self
+                .strings
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(args))
+                .map(|(_, value)| format!("'{}'\r", value.replace('\'', "''")).into_bytes()),
+            "mpq" => {
+                let (name, value) = args.split_once(',')?;
+                let value = unquote(value)?;
+                let setting = self
+                    .strings
+                    .iter_mut()
+                    .find(|(setting, _)| setting.eq_ignore_ascii_case(name))?;
+                setting.1 = value;
+                Some(Vec::new())
+            }
+            "qsavname" => {
+                let slot: u16 = args.parse().ok()?;
+                let name = self
+                    .save_names
+                    .get(&slot)
+                    .cloned()
+                    .unwrap_or_else(|| format!("SAVE{}", slot));
+                Some(format!("{}\r", name).into_bytes())
+            }
+            "savname" => {
+                let (slot, name) = args.split_once(',')?;
+                let slot: u16 = slot.trim().parse().ok()?;
+                let name = unquote(name)?;
+                self.save_names.insert(slot, name);
+                Some(Vec::new())
+            }
+            "qsls" => Some(
+                format!(
+                    "{},{},{},{}\r",
+                    self.recordings.len(),
+                    self.min_max.len(),
+                    self.peak.len(),
+                    self.measurements.len()
+                )
+                .into_bytes(),
+            ),
+            "qddb" => Some(self.live().to_frame()),
+            "qm" => {
+                let reading = self.reading(self.clock() as f64);
+                let unit = self.maps.get("unit")?.get(&self.unit)?;
+                Some(format!("{:E},{},NORMAL,NONE\r", reading.value, unit).into_bytes())
+            }
+            "qsmr" => Some(self.measurements.get(index(args)?)?.to_frame()),
+            "qmmsi" => Some(self.min_max.get(index(args)?)?.to_frame()),
+            "qpsi" => Some(self.peak.get(index(args)?)?.to_frame()),
+            "qrsi" => Some(self.recordings.get(index(args)?)?.to_frame()),
+            "qsrr" => {
+                let (reading, sample) = args.split_once(',')?;
+                let reading: u16 = reading.trim().parse().ok()?;
+                let sample: u16 = sample.trim().parse().ok()?;
+                let session = self
+                    .recordings
+                    .iter()
+                    .find(|session| session.reading_index == reading)?;
+                if sample >= session.num_samples {
+                    return None;
+                }
+                let start = session.start_ts + f64::from(sample) * session.sample_interval;
+                Some(self.sample(start, session.sample_interval).to_frame())
+            }
+            "csd" => {
+                match args.to_ascii_uppercase().as_str() {
+                    "ALL" => {
+                        self.measurements.clear();
+                        self.min_max.clear();
+                        self.peak.clear();
+                        self.recordings.clear();
+                    }
+                    "MEASUREMENT" => self.measurements.clear(),
+                    "MIN_MAX" => self.min_max.clear(),
+                    "PEAK" => self.peak.clear(),
+                    "RECORDED" => self.recordings.clear(),
+                    _ => return None,
+                }
+                Some(Vec::new())
+            }
+            "rmp" => {
+                self.reset();
+                Some(Vec::new())
+            }
+            "press" => match args.to_ascii_uppercase().as_str() {
+                "BACKLIGHT" | "F4" | "HOLD" => Some(Vec::new()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Restore the default settings.
+    fn reset(&mut self) {
+        self.settings = DEFAULT_SETTINGS
+            .iter()
+            .map(|(name, value)| (*name, value.to_string()))
+            .collect();
+        self.strings = STRING_SETTINGS
+            .iter()
+            .map(|name| (*name, String::new()))
+            .collect();
+    }
+
+    /// Saved entries and recording sessions in memory at start.
+    fn fill_memory(&mut self) {
+        let now = self.clock() as f64;
+        for i in 0..self.config.memory {
+            let ts = now - 3600.0 * f64::from(self.config.memory - i);
+            let reading = self.reading(ts);
+            self.measurements.push(RawSavedMeasurement {
+                seq_no: i,
+                un1: 0,
+                pri_function: self.pri_function,
+                sec_function: 0,
+                auto_range: 1,
+                unit: self.unit,
+                range_max: range_max(self.config.value),
+                unit_multiplier: 0,
+                bolt: 0,
+                un2: 0,
+                un3: 0,
+                un4: 0,
+                un5: 0,
+                modes: 0,
+                un6: 0,
+                readings: vec![reading.clone()],
+                name: format!("Measurement {}", i + 1),
+            });
+            let session = RawSavedMinMaxMeasurement {
+                seq_no: i,
+                un1: 0,
+                ts1: ts,
+                ts2: ts + 60.0,
+                pri_function: self.pri_function,
+                sec_function: 0,
+                auto_range: 1,
+                unit: self.unit,
+                range_max: range_max(self.config.value),
+                unit_multiplier: 0,
+                bolt: 0,
+                ts3: ts + 60.0,
+                modes: 0,
+                un2: 0,
+                readings: (0..4).map(|_| self.reading(ts)).collect(),
+                name: format!("Min Max {}", i + 1),
+            };
+            self.min_max.push(session.clone());
+            self.peak.push(RawSavedMinMaxMeasurement {
+                name: format!("Peak {}", i + 1),
+                ..session
+            });
+            let interval = 1.0;
+            self.recordings.push(RawSavedRecordingSessionInfo {
+                seq_no: i,
+                un1: 0,
+                start_ts: ts,
+                end_ts: ts + interval * f64::from(self.config.samples),
+                sample_interval: interval,
+                event_threshold: 0.0,
+                reading_index: i,
+                un2: 0,
+                num_samples: self.config.samples,
+                un3: 0,
+                pri_function: self.pri_function,
+                sec_function: 0,
+                auto_range: 1,
+                unit: self.unit,
+                range_max: range_max(self.config.value),
+                unit_multiplier: 0,
+                bolt: 0,
+                un4: 0,
+                un5: 0,
+                un6: 0,
+                un7: 0,
+                modes: 0,
+                un8: 0,
+                readings: vec![reading],
+                name: format!("Recording {}", i + 1),
+            });
+        }
+    }
+
+    fn live(&mut self) -> RawMeasurement {
+        let ts = self.clock() as f64;
+        RawMeasurement {
+            pri_function: self.pri_function,
+            sec_function: 0,
+            auto_range: 1,
+            unit: self.unit,
+            range_max: range_max(self.config.value),
+            unit_multiplier: 0,
+            bolt: 0,
+            ts,
+            modes: 0,
+            un1: 0,
+            readings: vec![self.reading(ts)],
+        }
+    }
+
+    fn sample(&mut self, start_ts: f64, interval: f64) -> RawSessionRecordReadings {
+        let end_ts = start_ts + interval;
+        RawSessionRecordReadings {
+            start_ts,
+            end_ts,
+            span_readings: [
+                self.reading(end_ts),
+                self.reading(end_ts),
+                self.reading(end_ts),
+            ],
+            sampling: 1,
+            un2: 0,
+            fixed_reading: self.reading(end_ts),
+            record_type: 1,
+            stable: 1,
+            transient_state: 0,
+        }
+    }
+
+    fn reading(&mut self, ts: f64) -> RawReading {
+        let value = self.config.value + self.config.noise * self.uniform();
+        let scale = 10f64.powi(i32::from(self.config.decimals));
+        RawReading {
+            reading_id: PRIMARY_READING,
+            value: (value * scale).round() / scale,
+            unit: self.unit,
+            unit_multiplier: 0,
+            decimals: self.config.decimals,
+            display_digits: 5,
+            state: self.state,
+            attribute: 0,
+            ts,
+        }
+    }
+
+    /// Device clock in local wall-clock seconds.
+    fn clock(&self) -> i64 {
+        local_now() + self.clock_offset
+    }
+
+    /// Next value of a xorshift64* generator.
+    fn next(&mut self) -> u64 {
+        let mut x = self.rng;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.rng = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform value in `[0, 1)`.
+    fn unit_interval(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform value in `[-1, 1)`.
+    fn uniform(&mut self) -> f64 {
+        self.unit_interval() * 2.0 - 1.0
+    }
+
+    fn chance(&mut self, rate: f64) -> bool {
+        rate > 0.0 && self.unit_interval() < rate
+    }
+}
+
+fn map_index(maps: &ValueMaps, map: &str, name: &str) -> Result<u16> {
+    maps.get(map)
+        .and_then(|entries| {
+            entries
+                .iter()
+                .find(|(_, entry)| entry.eq_ignore_ascii_case(name))
+                .map(|(id, _)| *id)
+        })
+        .ok_or_else(|| ProtoError::InvalidArgument(format!("Unknown {} {}", map, name)))
+}
+
+/// Smallest range of the meter covering `value`.
+fn range_max(value: f64) -> f64 {
+    let mut range = 5.0;
+    while range < value.abs() && range < 5e9 {
+        range *= 10.0;
+    }
+    range
+}
+
+fn index(args: &str) -> Option<usize> {
+    args.trim().parse().ok()
+}
+
+/// Contents of a quoted string argument, embedded quotes are doubled.
+fn unquote(value: &str) -> Option<String> {
+    let value = value.trim();
+    let inner = value.strip_prefix('\'')?.strip_suffix('\'')?;
+    Some(inner.replace("''", "'"))
+}
+
+fn local_now() -> i64 {
+    Utc.from_utc_datetime(&Local::now().naive_local())
+        .timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Device;
+
+    fn device(config: SimConfig) -> Device {
+        let (client, server) = tokio::io::duplex(4096);
+        let mut simulator = Simulator::new(config).expect("simulator");
+        tokio::spawn(async move { simulator.serve(server).await });
+        Device::with_transport(client)
+    }
+
+    #[tokio::test]
+    async fn test_simulator() {
+        let mut device = device(SimConfig::default());
+        let ident = device.ident().await.expect("ident");
+        assert_eq!(ident.model, "FLUKE 289");
+        assert_eq!(ident.serial, "12345678");
+
+        device.set_beeper(false).await.expect("set beeper");
+        assert!(!device.beeper().await.expect("beeper"));
+        device.set_operator("O'Neil").await.expect("set operator");
+        assert_eq!(device.operator().await.expect("operator"), "O'Neil");
+
+        let maps = device.value_maps().await.expect("maps");
+        let raw = device
+            .live_measurement()
+            .await
+            .expect("qddb")
+            .expect("data");
+        let measurement =
+            crate::measurement::Measurement::try_from((raw, &maps)).expect("measurement");
+        assert!(matches!(
+            measurement.pri_function,
+            crate::measurement::PrimaryFunction::V_DC
+        ));
+        let value = measurement.readings[0].value;
+        assert!((11.95..=12.05).contains(&value), "{}", value);
+
+        let reading = device.ascii_reading().await.expect("qm").expect("data");
+        assert!(
+            (11.95..=12.05).contains(&reading.value),
+            "{}",
+            reading.value
+        );
+
+        let stat = device.memory_statistics().await.expect("qsls");
+        assert_eq!(stat.recordings, 2);
+        let session = device.saved_recording(1).await.expect("qrsi");
+        assert_eq!(session.name, "Recording 2");
+        let samples = device
+            .session_record_reading_all(
+                usize::from(session.reading_index),
+                usize::from(session.num_samples),
+            )
+            .await
+            .expect("qsrr");
+        assert_eq!(samples.len(), 10);
+        device
+            .clear(crate::proto::command::ClearMemory::Recordings)
+            .await
+            .expect("csd");
+        assert_eq!(
+            device.memory_statistics().await.expect("qsls").recordings,
+            0
+        );
+    }
+
+    #[test]
+    fn test_simulator_faults() {
+        let config = SimConfig {
+            faults: Faults {
+                busy: 1.0,
+                ..Faults::default()
+            },
+            ..SimConfig::default()
+        };
+        let mut simulator = Simulator::new(config).expect("simulator");
+        assert_eq!(simulator.respond("id"), Some(b"2\r".to_vec()));
+        assert_eq!(simulator.respond("qemap foo"), Some(b"2\r".to_vec()));
+
+        let mut simulator = Simulator::new(SimConfig::default()).expect("simulator");
+        assert_eq!(simulator.respond("qemap foo"), Some(b"1\r".to_vec()));
+        assert_eq!(simulator.respond("qsrr 0,10"), Some(b"1\r".to_vec()));
+        assert!(Simulator::new(SimConfig {
+            function: "FOO".to_string(),
+            ..SimConfig::default()
+        })
+        .is_err());
+    }
+}
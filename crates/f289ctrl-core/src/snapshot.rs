@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+    measurement::{
+        SavedMeasurement, SavedMinMaxMeasurement, SavedPeakMeasurement, SavedRecordingSessionInfo,
+        SessionRecordReadings,
+    },
+    proto::response::Ident,
+};
+
+/// Complete copy of the device memory, created by [`crate::Device::snapshot_memory`].
+///
+/// With the `serde` feature enabled, the snapshot can be serialized
+/// to persist a full meter state in one file.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MemorySnapshot {
+    /// Host time when the download was started
+    pub taken_at: DateTime<Utc>,
+    pub ident: Ident,
+    pub measurements: Vec<SavedMeasurement>,
+    pub min_max: Vec<SavedMinMaxMeasurement>,
+    pub peak: Vec<SavedPeakMeasurement>,
+    pub recordings: Vec<RecordingSnapshot>,
+}
+
+/// A saved recording together with all of its samples.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RecordingSnapshot {
+    pub info: SavedRecordingSessionInfo,
+    pub samples: Vec<SessionRecordReadings>,
+}
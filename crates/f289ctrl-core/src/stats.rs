@@ -0,0 +1,173 @@
+//! Statistics over series of readings.
+//!
+//! Only readings carrying a value ([`ReadingValue::Value`]) enter the
+//! numbers, overloads and other states are counted separately so a series
+//! with `OL` readings doesn't silently produce a misleading mean.
+
+use crate::measurement::{Reading, ReadingValue, SessionRecordReadings};
+
+/// Summary of a series, numbers are in the base SI unit.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Stats {
+    /// Values included in the numbers below
+    pub count: usize,
+    /// Readings in overload (`OL` or `-OL`)
+    pub overloads: usize,
+    /// Readings without a value for other reasons (blank, invalid, ...)
+    pub skipped: usize,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    /// Population standard deviation
+    pub stddev: Option<f64>,
+}
+
+impl Stats {
+    fn count_state(&mut self, reading: &Reading) -> Option<f64> {
+        match reading.reading_value() {
+            ReadingValue::Value(value) => Some(value),
+            ReadingValue::Overload | ReadingValue::OverloadNegative => {
+                self.overloads += 1;
+                None
+            }
+            _ => {
+                self.skipped += 1;
+                None
+            }
+        }
+    }
+
+    /// Fill the numbers from `(value, weight)` pairs.
+    fn summarize(&mut self, values: &[(f64, f64)]) {
+        let weight: f64 = values.iter().map(|(_, w)| w).sum();
+        if values.is_empty() || weight <= 0.0 {
+            return;
+        }
+        let mean = values.iter().map(|(v, w)| v * w).sum::<f64>() / weight;
+        let variance = values
+            .iter()
+            .map(|(v, w)| (v - mean).powi(2) * w)
+            .sum::<f64>()
+            / weight;
+        self.mean = Some(mean);
+        self.stddev = Some(variance.sqrt());
+    }
+}
+
+/// Statistics over single readings.
+pub fn reading_stats(readings: &[Reading]) -> Stats {
+    let mut stats = Stats::default();
+    let values: Vec<(f64, f64)> = readings
+        .iter()
+        .filter_map(|r| stats.count_state(r))
+        .map(|v| (v, 1.0))
+        .collect();
+    stats.count = values.len();
+    stats.min = values.iter().map(|(v, _)| *v).reduce(f64::min);
+    stats.max = values.iter().map(|(v, _)| *v).reduce(f64::max);
+    stats.summarize(&values);
+    stats
+}
+
+/// Statistics over recorded intervals.
+///
+/// Min and max are taken from the interval extremes, the mean is weighted by
+/// the samples per interval. The device doesn't record single samples, so
+/// the standard deviation is that of the interval averages. Intervals with
+/// an overloaded or missing average are counted but not included.
+pub fn recording_stats(records: &[SessionRecordReadings]) -> Stats {
+    let mut stats = Stats::default();
+    let mut values = Vec::with_capacity(records.len());
+    for rec in records {
+        let avg = rec.average_reading();
+        if let Some(value) = stats.count_state(&avg) {
+            values.push((value, rec.sampling.max(1) as f64));
+            for extreme in [&rec.span_readings[0], &rec.span_readings[1]] {
+                if let Some(v) = extreme.si_value() {
+                    stats.min = Some(stats.min.map_or(v, |min| min.min(v)));
+                    stats.max = Some(stats.max.map_or(v, |max| max.max(v)));
+                }
+            }
+        }
+    }
+    stats.count = values.len();
+    stats.summarize(&values);
+    stats
+}
+
+/// Percentile `p` (0 to 100) of the reading values, linearly interpolated.
+pub fn percentile(readings: &[Reading], p: f64) -> Option<f64> {
+    let mut values: Vec<f64> = readings.iter().filter_map(Reading::si_value).collect();
+    percentile_of(&mut values, p)
+}
+
+/// Percentile `p` (0 to 100) of the interval averages.
+pub fn recording_percentile(records: &[SessionRecordReadings], p: f64) -> Option<f64> {
+    let mut values: Vec<f64> = records
+        .iter()
+        .filter_map(|rec| rec.average_reading().si_value())
+        .collect();
+    percentile_of(&mut values, p)
+}
+
+fn percentile_of(values: &mut [f64], p: f64) -> Option<f64> {
+    if values.is_empty() || !(0.0..=100.0).contains(&p) {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let rank = p / 100.0 * (values.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(values[lower] + (values[upper] - values[lower]) * (rank - lower as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::fixture::reading;
+    use crate::measurement::{RecordType, Stable, State, TransientState};
+
+    #[test]
+    fn test_reading_stats() {
+        let readings = [
+            reading(1.0, State::Normal),
+            reading(3.0, State::Normal),
+            reading(9.9e37, State::OL),
+            reading(0.0, State::Blank),
+        ];
+        let stats = reading_stats(&readings);
+        assert_eq!((stats.count, stats.overloads, stats.skipped), (2, 1, 1));
+        assert_eq!((stats.min, stats.max), (Some(1.0), Some(3.0)));
+        assert_eq!((stats.mean, stats.stddev), (Some(2.0), Some(1.0)));
+        assert_eq!(percentile(&readings, 50.0), Some(2.0));
+        assert_eq!(percentile(&readings, 100.0), Some(3.0));
+        assert_eq!(reading_stats(&[]).mean, None);
+    }
+
+    #[test]
+    fn test_recording_stats() {
+        let rec = |min: f64, max: f64, sum: f64, sampling: u16| SessionRecordReadings {
+            start_ts: DateTime::default(),
+            end_ts: DateTime::default(),
+            span_readings: [
+                reading(max, State::Normal),
+                reading(min, State::Normal),
+                reading(sum, State::Normal),
+            ],
+            sampling,
+            fixed_reading: reading(0.0, State::Normal),
+            record_type: RecordType::Interval,
+            stable: Stable(true),
+            transient_state: TransientState::NonT,
+        };
+        let stats = recording_stats(&[rec(0.5, 2.0, 3.0, 3), rec(2.0, 4.0, 3.0, 1)]);
+        assert_eq!(stats.count, 2);
+        assert_eq!((stats.min, stats.max), (Some(0.5), Some(4.0)));
+        assert_eq!(stats.mean, Some(1.5));
+        assert_eq!(
+            recording_percentile(&[rec(0.0, 0.0, 6.0, 2)], 50.0),
+            Some(3.0)
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! [proptest](https://docs.rs/proptest) support for raw measurements and
+//! their binary frames, available with the `proptest` feature.
+//!
+//! [`Arbitrary`] covers the whole value range of every field, including
+//! negative multipliers, NaN values and measurements without readings.
+//! The frame strategies encode such values like the meter does.
+//!
+//! ```
+//! use f289ctrl_core::{rawmea::RawMeasurement, strategy};
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     fn parses(frame in strategy::measurement_frame()) {
+//!         prop_assert!(RawMeasurement::try_from(&frame[..]).is_ok());
+//!     }
+//! }
+//! # parses();
+//! ```
+
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    collection::vec,
+    strategy::{BoxedStrategy, Strategy},
+};
+
+use crate::rawmea::{RawMeasurement, RawReading, RawSavedMeasurement, MAX_MEASUREMENT_READINGS};
+
+/// Printable names without the terminating `\r`.
+fn name() -> impl Strategy<Value = String> {
+    vec(0x20u8..0x7f, 0..32).prop_map(|bytes| bytes.into_iter().map(char::from).collect())
+}
+
+fn readings() -> impl Strategy<Value = Vec<RawReading>> {
+    vec(any::<RawReading>(), 0..=MAX_MEASUREMENT_READINGS)
+}
+
+impl Arbitrary for RawReading {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        (
+            any::<u16>(),
+            any::<f64>(),
+            any::<u16>(),
+            any::<i16>(),
+            any::<i16>(),
+            any::<i16>(),
+            any::<u16>(),
+            any::<u16>(),
+            any::<f64>(),
+        )
+            .prop_map(
+                |(
+                    reading_id,
+                    value,
+                    unit,
+                    unit_multiplier,
+                    decimals,
+                    display_digits,
+                    state,
+                    attribute,
+                    ts,
+                )| RawReading {
+                    reading_id,
+                    value,
+                    unit,
+                    unit_multiplier,
+                    decimals,
+                    display_digits,
+                    state,
+                    attribute,
+                    ts,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for RawMeasurement {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        (
+            any::<[u16; 4]>(),
+            any::<f64>(),
+            any::<i16>(),
+            any::<u16>(),
+            any::<f64>(),
+            any::<[u16; 2]>(),
+            readings(),
+        )
+            .prop_map(
+                |(
+                    [pri_function, sec_function, auto_range, unit],
+                    range_max,
+                    unit_multiplier,
+                    bolt,
+                    ts,
+                    [modes, un1],
+                    readings,
+                )| RawMeasurement {
+                    pri_function,
+                    sec_function,
+                    auto_range,
+                    unit,
+                    range_max,
+                    unit_multiplier,
+                    bolt,
+                    ts,
+                    modes,
+                    un1,
+                    readings,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for RawSavedMeasurement {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        (
+            any::<[u16; 6]>(),
+            any::<f64>(),
+            any::<i16>(),
+            any::<[u16; 7]>(),
+            readings(),
+            name(),
+        )
+            .prop_map(
+                |(
+                    [seq_no, un1, pri_function, sec_function, auto_range, unit],
+                    range_max,
+                    unit_multiplier,
+                    [bolt, un2, un3, un4, un5, modes, un6],
+                    readings,
+                    name,
+                )| RawSavedMeasurement {
+                    seq_no,
+                    un1,
+                    pri_function,
+                    sec_function,
+                    auto_range,
+                    unit,
+                    range_max,
+                    unit_multiplier,
+                    bolt,
+                    un2,
+                    un3,
+                    un4,
+                    un5,
+                    modes,
+                    un6,
+                    readings,
+                    name,
+                },
+            )
+            .boxed()
+    }
+}
+
+/// Frames of live measurements as answered to `qddb`, without the status line.
+pub fn measurement_frame() -> impl Strategy<Value = Vec<u8>> {
+    any::<RawMeasurement>().prop_map(|m| m.to_frame())
+}
+
+/// Frames of saved measurements as answered to `qsmr`, without the status line.
+pub fn saved_measurement_frame() -> impl Strategy<Value = Vec<u8>> {
+    any::<RawSavedMeasurement>().prop_map(|m| m.to_frame())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockTransport, rawmea::RawSavedMinMaxMeasurement};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn prop_measurement_roundtrip(frame in measurement_frame()) {
+            let parsed = RawMeasurement::try_from(&frame[..]).expect("parse");
+            prop_assert_eq!(parsed.to_frame(), frame);
+        }
+
+        #[test]
+        fn prop_saved_measurement_roundtrip(frame in saved_measurement_frame()) {
+            prop_assert_eq!(
+                RawSavedMeasurement::can_parse(&frame).expect("count"),
+                Some(frame.len())
+            );
+            let parsed = RawSavedMeasurement::try_from(&frame[..]).expect("parse");
+            prop_assert_eq!(parsed.to_frame(), frame);
+        }
+
+        #[test]
+        fn prop_parse_garbage(bytes in vec(any::<u8>(), 0..256)) {
+            let mut frame = b"#0".to_vec();
+            frame.extend_from_slice(&bytes);
+            let _ = RawMeasurement::try_from(&frame[..]);
+            let _ = RawSavedMeasurement::can_parse(&frame);
+            let _ = RawSavedMeasurement::try_from(&frame[..]);
+            let _ = RawSavedMinMaxMeasurement::can_parse(&frame);
+            let _ = RawSavedMinMaxMeasurement::try_from(&frame[..]);
+        }
+
+        #[test]
+        fn prop_live_measurement(measurement in any::<RawMeasurement>()) {
+            let mut response = b"0\r".to_vec();
+            response.extend_from_slice(&measurement.to_frame());
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .expect("runtime");
+            let decoded = runtime.block_on(async {
+                let (mut device, _) = MockTransport::new().expect("qddb", response).into_device();
+                device.live_measurement().await
+            });
+            let decoded = decoded.expect("qddb").expect("data");
+            prop_assert_eq!(decoded.to_frame(), measurement.to_frame());
+        }
+    }
+}
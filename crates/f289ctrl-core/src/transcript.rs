@@ -0,0 +1,251 @@
+//! Request/response transcripts for regression tests, available with the
+//! `test-util` feature.
+//!
+//! A transcript is a text file listing each command sent to the meter,
+//! followed by the bytes it answered. Replaying it with
+//! [`Transcript::into_device`] drives a [`Device`] through a
+//! [`MockTransport`], so a decoding bug reported with a capture of the
+//! meter's responses becomes a fixture asserting the commands sent and the
+//! decoded result. The fixtures of this crate are in `tests/transcripts`.
+//!
+//! ```text
+//! # Comments and empty lines are ignored
+//! > id
+//! < 0\rFLUKE 289,V1.16,12345678\r
+//! > qddb
+//! < 0\r#0\x1b\x00\x00\x00
+//! < \x01\x00\x09\x00...
+//! ```
+//!
+//! Lines starting with `>` are commands without the terminating `\r`, lines
+//! starting with `<` are appended to the response of the previous command.
+//! Responses use the escapes `\r`, `\n`, `\\` and `\xNN`.
+
+use std::{fs, io, path::Path};
+
+use crate::{
+    mock::{MockHandle, MockTransport},
+    Device,
+};
+
+/// A command and the complete response of the meter, status line included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exchange {
+    pub command: String,
+    pub response: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transcript {
+    pub exchanges: Vec<Exchange>,
+}
+
+impl Transcript {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut exchanges: Vec<Exchange> = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let invalid = |msg: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {}", n + 1, msg),
+                )
+            };
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(command) = line.strip_prefix('>') {
+                exchanges.push(Exchange {
+                    command: command.trim().to_string(),
+                    response: Vec::new(),
+                });
+            } else if let Some(response) = line.strip_prefix('<') {
+                let exchange = exchanges
+                    .last_mut()
+                    .ok_or_else(|| invalid("response without command"))?;
+                let bytes = unescape(response.strip_prefix(' ').unwrap_or(response))
+                    .map_err(|err| invalid(&err))?;
+                exchange.response.extend(bytes);
+            } else {
+                return Err(invalid("expected '>' or '<'"));
+            }
+        }
+        Ok(Self { exchanges })
+    }
+
+    /// Transcript of a session recording, e.g. `f289cmd --record`.
+    ///
+    /// Received bytes are attributed to the last command sent before them,
+    /// so pipelined responses stay in order when replayed.
+    #[cfg(feature = "record")]
+    pub fn from_records(records: impl IntoIterator<Item = crate::record::Record>) -> Self {
+        use crate::record::Direction;
+
+        let mut exchanges: Vec<Exchange> = Vec::new();
+        let mut partial = Vec::new();
+        for record in records {
+            match record.direction {
+                Direction::Sent => {
+                    partial.extend_from_slice(&record.bytes);
+                    while let Some(end) = partial.iter().position(|b| *b == b'\r') {
+                        let line: Vec<u8> = partial.drain(..=end).collect();
+                        exchanges.push(Exchange {
+                            command: String::from_utf8_lossy(&line[..end]).into_owned(),
+                            response: Vec::new(),
+                        });
+                    }
+                }
+                Direction::Received => {
+                    if let Some(exchange) = exchanges.last_mut() {
+                        exchange.response.extend_from_slice(&record.bytes);
+                    }
+                }
+            }
+        }
+        Self { exchanges }
+    }
+
+    /// Text form as read by [`Transcript::parse`].
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for exchange in &self.exchanges {
+            text.push_str("> ");
+            text.push_str(&exchange.command);
+            text.push('\n');
+            // One line per response line keeps binary frames readable
+            for chunk in exchange.response.split_inclusive(|b| *b == b'\r') {
+                text.push_str("< ");
+                text.push_str(&escape(chunk));
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Commands in the order they have to be sent.
+    pub fn commands(&self) -> Vec<&str> {
+        self.exchanges
+            .iter()
+            .map(|exchange| exchange.command.as_str())
+            .collect()
+    }
+
+    /// Transport expecting the commands in order.
+    pub fn into_mock(self) -> MockTransport {
+        self.exchanges
+            .into_iter()
+            .fold(MockTransport::new(), |mock, exchange| {
+                mock.expect(exchange.command, exchange.response)
+            })
+    }
+
+    /// Device replaying this transcript, check the commands sent with
+    /// [`MockHandle::assert_done`] afterwards.
+    pub fn into_device(self) -> (Device, MockHandle) {
+        self.into_mock().into_device()
+    }
+}
+
+fn escape(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for byte in bytes {
+        match byte {
+            b'\r' => text.push_str("\\r"),
+            b'\n' => text.push_str("\\n"),
+            b'\\' => text.push_str("\\\\"),
+            0x20..=0x7e => text.push(*byte as char),
+            _ => text.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    text
+}
+
+fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('r') => bytes.push(b'\r'),
+            Some('n') => bytes.push(b'\n'),
+            Some('\\') => bytes.push(b'\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16)
+                    .map_err(|_| format!("invalid escape \\x{}", hex))?;
+                bytes.push(byte);
+            }
+            Some(other) => return Err(format!("invalid escape \\{}", other)),
+            None => return Err("escape at end of line".to_string()),
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measurement::{Measurement, PrimaryFunction, Unit};
+
+    const OHMS_LIVE: &str = include_str!("../tests/transcripts/ohms_live.txt");
+    const SETTINGS: &str = include_str!("../tests/transcripts/settings.txt");
+
+    #[test]
+    fn test_transcript_text() {
+        let transcript =
+            Transcript::parse("# x\n> qddb\n< 0\\r#0\\x1b\\\\\n< \\x00\\r\n").expect("transcript");
+        assert_eq!(transcript.commands(), vec!["qddb"]);
+        assert_eq!(transcript.exchanges[0].response, b"0\r#0\x1b\\\x00\r");
+        assert_eq!(
+            Transcript::parse(&transcript.to_text()).expect("reparse"),
+            transcript
+        );
+        assert!(Transcript::parse("< 0\\r").is_err());
+        assert!(Transcript::parse("> id\n< \\q").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ohms_live() {
+        let (mut device, handle) = Transcript::parse(OHMS_LIVE)
+            .expect("transcript")
+            .into_device();
+        let maps = device.value_maps().await.expect("maps");
+        let raw = device
+            .live_measurement()
+            .await
+            .expect("qddb")
+            .expect("data");
+        handle.assert_done();
+
+        let measurement = Measurement::try_from((raw, &maps)).expect("measurement");
+        assert!(matches!(measurement.pri_function, PrimaryFunction::OHMS));
+        assert!(matches!(measurement.unit, Unit::Ohm));
+        assert_eq!(measurement.readings.len(), 2);
+        assert!(matches!(measurement.readings[0].unit, Unit::Ohm));
+    }
+
+    #[tokio::test]
+    async fn test_settings() {
+        let (mut device, handle) = Transcript::parse(SETTINGS)
+            .expect("transcript")
+            .into_device();
+        assert_eq!(device.ident().await.expect("ident").model, "FLUKE 289");
+        assert!(device.beeper().await.expect("beeper"));
+        assert_eq!(device.operator().await.expect("operator"), "O'Neil");
+        assert_eq!(
+            device.backlight().await.expect("backlight"),
+            std::time::Duration::from_secs(900)
+        );
+        let stat = device.memory_statistics().await.expect("qsls");
+        assert_eq!(stat.measurement, 3);
+        handle.assert_done();
+    }
+}
@@ -0,0 +1,174 @@
+use std::{io, time::Duration};
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_serial::{SerialPort, SerialPortBuilderExt};
+
+use crate::Result;
+
+#[cfg(unix)]
+mod pty;
+mod rfc2217;
+
+#[cfg(unix)]
+pub use pty::PtyStream;
+pub use rfc2217::Rfc2217Stream;
+
+/// Byte stream a [`crate::Device`] can talk to.
+///
+/// Implemented for every `AsyncRead + AsyncWrite` type, so serial ports,
+/// TCP streams, pipes or in-memory mocks can be used as transport:
+///
+/// ```no_run
+/// # async fn example() -> f289ctrl_core::Result<()> {
+/// let stream = tokio::net::TcpStream::connect("10.0.0.5:4001").await?;
+/// let mut device = f289ctrl_core::Device::with_transport(stream);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Terminal servers (ser2net and the like) can also be used by passing a
+/// `tcp://host:port` (raw) or `rfc2217://host:port` address to [`crate::Device::new`].
+/// Simulators and test rigs can attach with `unix:///path/to/socket` or
+/// `pty:///dev/pts/N`, no serial port setup is done for those.
+pub trait DmmTransport: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T> DmmTransport for T where T: AsyncRead + AsyncWrite + Send + Unpin {}
+
+/// USB IDs of the serial converters used in Fluke IR cables.
+pub const KNOWN_CABLES: &[(u16, u16, &str)] = &[
+    (0x0403, 0x6001, "FTDI FT232R (Fluke IR189USB)"),
+    (0x0403, 0x6015, "FTDI FT231X"),
+    (0x067b, 0x2303, "Prolific PL2303"),
+];
+
+/// Serial port attached to a known IR cable, see [`candidate_ports`].
+#[derive(Debug, Clone)]
+pub struct CablePort {
+    pub path: String,
+    pub vid: u16,
+    pub pid: u16,
+    pub cable: &'static str,
+    pub serial_number: Option<String>,
+}
+
+/// Name of the cable with the given USB IDs, if it is a known IR cable.
+pub fn known_cable(vid: u16, pid: u16) -> Option<&'static str> {
+    KNOWN_CABLES
+        .iter()
+        .find(|(v, p, _)| *v == vid && *p == pid)
+        .map(|(_, _, name)| *name)
+}
+
+/// List serial ports that belong to a known IR cable, judged by USB vendor
+/// and product ID only. No data is sent to the ports.
+pub fn candidate_ports() -> Result<Vec<CablePort>> {
+    Ok(tokio_serial::available_ports()?
+        .into_iter()
+        .filter_map(|port| match port.port_type {
+            tokio_serial::SerialPortType::UsbPort(usb) => {
+                known_cable(usb.vid, usb.pid).map(|cable| CablePort {
+                    path: port.port_name,
+                    vid: usb.vid,
+                    pid: usb.pid,
+                    cable,
+                    serial_number: usb.serial_number,
+                })
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Modem control lines set after opening a serial port, for IR adapters
+/// powered from DTR/RTS. Ignored for network and pty transports.
+#[derive(Debug, Clone, Default)]
+pub struct LineControl {
+    pub dtr: Option<bool>,
+    pub rts: Option<bool>,
+    /// Delay after setting the lines so the adapter can power up.
+    pub settle: Duration,
+}
+
+/// Open a serial port or a transport given as URL, see [`crate::Device::new`].
+pub fn open(address: &str, baudrate: u32) -> Result<Box<dyn DmmTransport>> {
+    open_port(address, baudrate, &LineControl::default())
+}
+
+/// Like [`open`], setting the modem control lines of serial ports.
+pub async fn open_with_lines(
+    address: &str,
+    baudrate: u32,
+    lines: &LineControl,
+) -> Result<Box<dyn DmmTransport>> {
+    let transport = open_port(address, baudrate, lines)?;
+    if !lines.settle.is_zero() {
+        tokio::time::sleep(lines.settle).await;
+    }
+    Ok(transport)
+}
+
+fn open_port(address: &str, baudrate: u32, lines: &LineControl) -> Result<Box<dyn DmmTransport>> {
+    if let Some(transport) = connect(address, baudrate) {
+        return Ok(transport?);
+    }
+
+    let mut port = tokio_serial::new(address, baudrate).open_native_async()?;
+
+    #[cfg(unix)]
+    port.set_exclusive(false)
+        .expect("Unable to set serial port exclusive to false");
+
+    if let Some(dtr) = lines.dtr {
+        port.write_data_terminal_ready(dtr)?;
+    }
+    if let Some(rts) = lines.rts {
+        port.write_request_to_send(rts)?;
+    }
+    Ok(Box::new(port))
+}
+
+/// Open a transport given as URL (`tcp://`, `rfc2217://`, `unix://`, `pty://`).
+///
+/// Returns `None` if `address` is not an URL, e.g. a serial port path.
+fn connect(address: &str, baudrate: u32) -> Option<io::Result<Box<dyn DmmTransport>>> {
+    let (scheme, target) = address.split_once("://")?;
+    let tcp = |target: &str| -> io::Result<tokio::net::TcpStream> {
+        let stream = std::net::TcpStream::connect(target)?;
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        tokio::net::TcpStream::from_std(stream)
+    };
+
+    let transport = match scheme {
+        "tcp" => tcp(target).map(|s| Box::new(s) as Box<dyn DmmTransport>),
+        "rfc2217" => {
+            tcp(target).map(|s| Box::new(Rfc2217Stream::new(s, baudrate)) as Box<dyn DmmTransport>)
+        }
+        #[cfg(unix)]
+        "unix" => std::os::unix::net::UnixStream::connect(target).and_then(|s| {
+            s.set_nonblocking(true)?;
+            Ok(Box::new(tokio::net::UnixStream::from_std(s)?) as Box<dyn DmmTransport>)
+        }),
+        #[cfg(unix)]
+        "pty" => PtyStream::open(target).map(|s| Box::new(s) as Box<dyn DmmTransport>),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Unsupported transport: {}://", scheme),
+        )),
+    };
+    Some(transport)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_cable() {
+        assert_eq!(
+            known_cable(0x0403, 0x6001),
+            Some("FTDI FT232R (Fluke IR189USB)")
+        );
+        assert_eq!(known_cable(0x0403, 0x6010), None);
+    }
+}
@@ -0,0 +1,203 @@
+//! Pseudo-terminals and other character devices without serial port setup.
+
+use std::{
+    ffi::CStr,
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    os::unix::{
+        fs::OpenOptionsExt,
+        io::{AsRawFd, FromRawFd, RawFd},
+    },
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::ready;
+use tokio::io::{unix::AsyncFd, AsyncRead, AsyncWrite, ReadBuf};
+
+/// Byte stream to a pseudo-terminal, e.g. created by a meter simulator.
+///
+/// Unlike a serial port, no baudrate or line settings are applied. Terminals
+/// are only switched to raw mode so `\r` is not translated.
+pub struct PtyStream {
+    fd: AsyncFd<File>,
+}
+
+impl PtyStream {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+            .open(path)?;
+
+        let raw = file.as_raw_fd();
+        // SAFETY: `raw` is a valid, open descriptor and termios is plain data
+        unsafe {
+            if libc::isatty(raw) == 1 {
+                let mut termios: libc::termios = std::mem::zeroed();
+                if libc::tcgetattr(raw, &mut termios) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                libc::cfmakeraw(&mut termios);
+                if libc::tcsetattr(raw, libc::TCSANOW, &termios) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+        }
+
+        Ok(Self {
+            fd: AsyncFd::new(file)?,
+        })
+    }
+
+    /// Create a new pseudo-terminal and return its master side together
+    /// with the path of the slave side, which clients open.
+    pub fn create() -> io::Result<(Self, String)> {
+        // SAFETY: `fd` is checked and owned by `file` before any early return
+        // besides the first, `name` is a NUL terminated string of the libc
+        let (file, slave) = unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY | libc::O_NONBLOCK);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let file = File::from_raw_fd(fd);
+            if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            (file, slave_name(fd)?)
+        };
+
+        // The master side must not echo or translate either
+        // SAFETY: as in `open`
+        unsafe {
+            let raw = file.as_raw_fd();
+            let mut termios: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(raw, &mut termios) == 0 {
+                libc::cfmakeraw(&mut termios);
+                libc::tcsetattr(raw, libc::TCSANOW, &termios);
+            }
+        }
+
+        Ok((
+            Self {
+                fd: AsyncFd::new(file)?,
+            },
+            slave,
+        ))
+    }
+}
+
+/// Path of the slave side of the master `fd`.
+#[cfg(target_os = "linux")]
+unsafe fn slave_name(fd: RawFd) -> io::Result<String> {
+    let mut name = [0 as std::os::raw::c_char; 64];
+    if libc::ptsname_r(fd, name.as_mut_ptr(), name.len()) != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned())
+}
+
+/// Path of the slave side of the master `fd`.
+#[cfg(not(target_os = "linux"))]
+unsafe fn slave_name(fd: RawFd) -> io::Result<String> {
+    let name = libc::ptsname(fd);
+    if name.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(CStr::from_ptr(name).to_string_lossy().into_owned())
+}
+
+impl AsyncRead for PtyStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            let mut guard = ready!(self.fd.poll_read_ready(cx))?;
+            match guard.try_io(|fd| fd.get_ref().read(buf.initialize_unfilled())) {
+                Ok(Ok(n)) => {
+                    buf.advance(n);
+                    return Poll::Ready(Ok(()));
+                }
+                Ok(Err(err)) => return Poll::Ready(Err(err)),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+impl AsyncWrite for PtyStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let mut guard = ready!(self.fd.poll_write_ready(cx))?;
+            match guard.try_io(|fd| fd.get_ref().write(buf)) {
+                Ok(result) => return Poll::Ready(result),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::Device;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_pty_create() {
+        let (mut master, slave) = PtyStream::create().expect("create pty");
+        let mut device = Device::new(format!("pty://{}", slave), 115200).expect("open pty");
+        master
+            .write_all(b"0\rFluke 289,V1.16,1\r")
+            .await
+            .expect("write");
+        assert_eq!(device.ident().await.expect("ident").serial, "1");
+
+        let mut sent = [0; 3];
+        master.read_exact(&mut sent).await.expect("read");
+        assert_eq!(&sent, b"id\r");
+    }
+
+    #[tokio::test]
+    async fn test_pty_roundtrip() {
+        // SAFETY: plain libc pty setup, the master fd is owned by `master`
+        let (mut master, slave) = unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            assert!(fd >= 0);
+            assert_eq!(libc::grantpt(fd), 0);
+            assert_eq!(libc::unlockpt(fd), 0);
+            let mut name = [0 as libc::c_char; 64];
+            assert_eq!(libc::ptsname_r(fd, name.as_mut_ptr(), name.len()), 0);
+            let slave = CStr::from_ptr(name.as_ptr())
+                .to_str()
+                .expect("pty name")
+                .to_string();
+            (File::from_raw_fd(fd), slave)
+        };
+
+        let mut device = Device::new(format!("pty://{}", slave), 115200).expect("open pty");
+        master.write_all(b"0\rFluke 289,V1.16,1\r").expect("write");
+        let ident = device.ident().await.expect("ident");
+        assert_eq!(ident.firmware, "V1.16");
+
+        let mut sent = [0; 3];
+        master.read_exact(&mut sent).expect("read");
+        assert_eq!(&sent, b"id\r");
+    }
+}
@@ -0,0 +1,272 @@
+//! Telnet COM port control (RFC 2217) client side.
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+const OPT_BINARY: u8 = 0;
+const OPT_SGA: u8 = 3;
+const OPT_COM_PORT: u8 = 44;
+
+const SET_BAUDRATE: u8 = 1;
+const SET_DATASIZE: u8 = 2;
+const SET_PARITY: u8 = 3;
+const SET_STOPSIZE: u8 = 4;
+
+const PARITY_NONE: u8 = 1;
+const STOPSIZE_ONE: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Data,
+    Iac,
+    Negotiation(u8),
+    Sub,
+    SubIac,
+}
+
+/// Byte stream to a RFC 2217 terminal server.
+///
+/// The serial line is configured to `baudrate` 8N1 on connect. Telnet
+/// commands from the server are answered and removed from the data stream,
+/// `0xFF` data bytes are escaped in both directions.
+pub struct Rfc2217Stream<S> {
+    inner: S,
+    state: State,
+    /// Bytes not yet written to `inner`: escaped data and negotiation replies
+    tx: Vec<u8>,
+    /// Raw receive buffer, reused across reads
+    rx: Vec<u8>,
+}
+
+impl<S> Rfc2217Stream<S> {
+    pub fn new(inner: S, baudrate: u32) -> Self {
+        let mut tx = vec![IAC, WILL, OPT_BINARY, IAC, DO, OPT_BINARY];
+        tx.extend_from_slice(&[IAC, WILL, OPT_COM_PORT]);
+        let mut sub = |command: u8, value: &[u8]| {
+            tx.extend_from_slice(&[IAC, SB, OPT_COM_PORT, command]);
+            escape_into(&mut tx, value);
+            tx.extend_from_slice(&[IAC, SE]);
+        };
+        sub(SET_BAUDRATE, &baudrate.to_be_bytes());
+        sub(SET_DATASIZE, &[8]);
+        sub(SET_PARITY, &[PARITY_NONE]);
+        sub(SET_STOPSIZE, &[STOPSIZE_ONE]);
+
+        Self {
+            inner,
+            state: State::Data,
+            tx,
+            rx: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Strip telnet commands from `raw`, appending data bytes to `out`.
+    fn filter(&mut self, raw: &[u8], out: &mut ReadBuf<'_>) {
+        for &byte in raw {
+            self.state = match (self.state, byte) {
+                (State::Data, IAC) => State::Iac,
+                (State::Data, _) => {
+                    out.put_slice(&[byte]);
+                    State::Data
+                }
+                (State::Iac, IAC) => {
+                    out.put_slice(&[IAC]);
+                    State::Data
+                }
+                (State::Iac, DO | DONT | WILL | WONT) => State::Negotiation(byte),
+                (State::Iac, SB) => State::Sub,
+                (State::Iac, _) => State::Data,
+                (State::Negotiation(verb), option) => {
+                    self.negotiate(verb, option);
+                    State::Data
+                }
+                // Notifications of the COM port option are ignored
+                (State::Sub, IAC) => State::SubIac,
+                (State::Sub, _) => State::Sub,
+                (State::SubIac, SE) => State::Data,
+                (State::SubIac, _) => State::Sub,
+            };
+        }
+    }
+
+    fn negotiate(&mut self, verb: u8, option: u8) {
+        let reply = match verb {
+            DO if matches!(option, OPT_BINARY | OPT_COM_PORT) => WILL,
+            DO => WONT,
+            WILL if matches!(option, OPT_BINARY | OPT_SGA | OPT_COM_PORT) => DO,
+            WILL => DONT,
+            // DONT/WONT need no reply for options we never enabled
+            _ => return,
+        };
+        self.tx.extend_from_slice(&[IAC, reply, option]);
+    }
+}
+
+impl<S: AsyncWrite + Unpin> Rfc2217Stream<S> {
+    /// Write pending bytes, `Ready` once all were written.
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.tx.is_empty() {
+            match Pin::new(&mut self.inner).poll_write(cx, &self.tx) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Poll::Ready(Ok(n)) => {
+                    self.tx.drain(..n);
+                }
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn escape_into(dst: &mut Vec<u8>, data: &[u8]) {
+    for &byte in data {
+        if byte == IAC {
+            dst.push(IAC);
+        }
+        dst.push(byte);
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for Rfc2217Stream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            // Negotiation replies go out as soon as possible
+            if let Poll::Ready(Err(err)) = this.poll_drain(cx) {
+                return Poll::Ready(Err(err));
+            }
+
+            let mut raw = std::mem::take(&mut this.rx);
+            raw.resize(buf.remaining(), 0);
+            let mut raw_buf = ReadBuf::new(&mut raw);
+            let result = Pin::new(&mut this.inner).poll_read(cx, &mut raw_buf);
+            let filled = raw_buf.filled().len();
+            let before = buf.filled().len();
+            if let Poll::Ready(Ok(())) = result {
+                this.filter(&raw[..filled], buf);
+            }
+            this.rx = raw;
+
+            match result {
+                // EOF
+                Poll::Ready(Ok(())) if filled == 0 => return Poll::Ready(Ok(())),
+                Poll::Ready(Ok(())) => {
+                    if let Poll::Ready(Err(err)) = this.poll_drain(cx) {
+                        return Poll::Ready(Err(err));
+                    }
+                    if buf.filled().len() > before {
+                        return Poll::Ready(Ok(()));
+                    }
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Rfc2217Stream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        match this.poll_drain(cx) {
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        }
+        escape_into(&mut this.tx, buf);
+        // Accepted, the rest is written by the next write or flush
+        if let Poll::Ready(Err(err)) = this.poll_drain(cx) {
+            return Poll::Ready(Err(err));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_drain(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_flush(cx),
+            other => other,
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_drain(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_shutdown(cx),
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_negotiation_and_escaping() {
+        let (client, mut server) = tokio::io::duplex(256);
+        let mut stream = Rfc2217Stream::new(client, 115200);
+
+        stream.write_all(b"id\r\xff").await.expect("write");
+        stream.flush().await.expect("flush");
+        let mut sent = vec![0; 256];
+        let n = server.read(&mut sent).await.expect("read");
+        let sent = &sent[..n];
+        let baudrate = [IAC, SB, OPT_COM_PORT, SET_BAUDRATE, 0, 1, 0xc2, 0, IAC, SE];
+        assert!(sent.windows(baudrate.len()).any(|w| w == baudrate));
+        assert!(sent.ends_with(b"id\r\xff\xff"));
+
+        server
+            .write_all(&[
+                IAC,
+                DO,
+                OPT_SGA,
+                b'0',
+                IAC,
+                IAC,
+                IAC,
+                SB,
+                OPT_COM_PORT,
+                101,
+                0,
+                IAC,
+                SE,
+                b'\r',
+            ])
+            .await
+            .expect("write");
+        let mut received = [0; 4];
+        stream.read_exact(&mut received[..3]).await.expect("read");
+        assert_eq!(&received[..3], b"0\xff\r");
+
+        let mut reply = [0; 3];
+        server.read_exact(&mut reply).await.expect("read");
+        assert_eq!(reply, [IAC, WONT, OPT_SGA]);
+    }
+}
@@ -0,0 +1,463 @@
+//!
+//! gRPC service for remote control of Fluke 287/289 digital multimeters.
+//!
+//! The service is defined in `proto/f289ctrl.proto`, [`MeterService`]
+//! implements it on top of a [`ReadyDevice`]. Calls are serialized, a
+//! running measurement stream holds the device only while polling.
+//!
+
+// `tonic::Status` is the error type of the generated service trait
+#![allow(clippy::result_large_err)]
+
+use std::{pin::Pin, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+use f289ctrl_core::{
+    measurement::{
+        self, Memory, MemoryEntry, MemoryKind, Reading, SavedRecordingSessionInfo,
+        SessionRecordReadings,
+    },
+    proto::{
+        command::{DateFormat, DezibelReference, DigitCount, Language, NumericFormat, TimeFormat},
+        ProtoError,
+    },
+    ReadyDevice,
+};
+use futures::Stream;
+use tokio::sync::Mutex;
+use tonic::{Request, Response, Status};
+
+pub mod pb {
+    tonic::include_proto!("f289ctrl.v1");
+}
+
+pub use pb::meter_server::MeterServer;
+
+/// Default interval of [`pb::meter_server::Meter::stream_measurements`].
+pub const DEFAULT_STREAM_INTERVAL: Duration = Duration::from_millis(1000);
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Implementation of the `Meter` service.
+#[derive(Clone)]
+pub struct MeterService {
+    device: Arc<Mutex<ReadyDevice>>,
+}
+
+impl MeterService {
+    pub fn new(device: ReadyDevice) -> Self {
+        Self {
+            device: Arc::new(Mutex::new(device)),
+        }
+    }
+
+    /// Service to register with a `tonic` server.
+    pub fn into_server(self) -> MeterServer<Self> {
+        MeterServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl pb::meter_server::Meter for MeterService {
+    async fn get_ident(
+        &self,
+        _request: Request<pb::GetIdentRequest>,
+    ) -> Result<Response<pb::Ident>, Status> {
+        let device = self.device.lock().await;
+        let ident = device.ident();
+        Ok(Response::new(pb::Ident {
+            model: ident.model.clone(),
+            firmware: ident.firmware.clone(),
+            serial: ident.serial.clone(),
+        }))
+    }
+
+    async fn get_measurement(
+        &self,
+        _request: Request<pb::GetMeasurementRequest>,
+    ) -> Result<Response<pb::GetMeasurementResponse>, Status> {
+        let measurement = self
+            .device
+            .lock()
+            .await
+            .live_measurement()
+            .await
+            .map_err(status)?;
+        Ok(Response::new(pb::GetMeasurementResponse {
+            measurement: measurement.as_ref().map(to_measurement),
+        }))
+    }
+
+    type StreamMeasurementsStream = ResponseStream<pb::Measurement>;
+
+    async fn stream_measurements(
+        &self,
+        request: Request<pb::StreamMeasurementsRequest>,
+    ) -> Result<Response<Self::StreamMeasurementsStream>, Status> {
+        let interval = match request.into_inner().interval_ms {
+            0 => DEFAULT_STREAM_INTERVAL,
+            ms => Duration::from_millis(ms.into()),
+        };
+        let ticker = tokio::time::interval(interval);
+        let stream = futures::stream::unfold(
+            (self.device.clone(), ticker),
+            |(device, mut ticker)| async move {
+                loop {
+                    ticker.tick().await;
+                    let result = device.lock().await.live_measurement().await;
+                    match result {
+                        Ok(Some(mea)) => return Some((Ok(to_measurement(&mea)), (device, ticker))),
+                        Ok(None) => continue,
+                        Err(err) => return Some((Err(status(err)), (device, ticker))),
+                    }
+                }
+            },
+        );
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn list_memory(
+        &self,
+        _request: Request<pb::ListMemoryRequest>,
+    ) -> Result<Response<pb::ListMemoryResponse>, Status> {
+        let memory = self
+            .device
+            .lock()
+            .await
+            .all_memory()
+            .await
+            .map_err(status)?;
+        Ok(Response::new(pb::ListMemoryResponse {
+            entries: memory.iter().map(to_memory_entry).collect(),
+        }))
+    }
+
+    type DownloadRecordingStream = ResponseStream<pb::RecordingInterval>;
+
+    async fn download_recording(
+        &self,
+        request: Request<pb::DownloadRecordingRequest>,
+    ) -> Result<Response<Self::DownloadRecordingStream>, Status> {
+        let selector = request
+            .into_inner()
+            .recording
+            .ok_or_else(|| Status::invalid_argument("Recording seq_no or name required"))?;
+        let (info, maps) = {
+            let mut device = self.device.lock().await;
+            let info = find_recording(&mut device, &selector).await?;
+            (info, device.maps().clone())
+        };
+
+        let stream = futures::stream::unfold(
+            (self.device.clone(), maps, info, 0),
+            |(device, maps, info, sample)| async move {
+                if sample >= info.num_samples as usize {
+                    return None;
+                }
+                let raw = device
+                    .lock()
+                    .await
+                    .session_record_reading(info.reading_index as usize, sample)
+                    .await;
+                let result = match raw {
+                    Ok(raw) => SessionRecordReadings::try_from((raw, &maps))
+                        .map(|rec| to_interval(&rec))
+                        .map_err(status),
+                    Err(err) => Err(status(err)),
+                };
+                Some((result, (device, maps, info, sample + 1)))
+            },
+        );
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn get_setting(
+        &self,
+        request: Request<pb::GetSettingRequest>,
+    ) -> Result<Response<pb::Setting>, Status> {
+        let name = request.into_inner().name;
+        let mut device = self.device.lock().await;
+        let value = get_setting(&mut device, &name).await?;
+        Ok(Response::new(pb::Setting { name, value }))
+    }
+
+    async fn set_setting(
+        &self,
+        request: Request<pb::Setting>,
+    ) -> Result<Response<pb::Setting>, Status> {
+        let pb::Setting { name, value } = request.into_inner();
+        let mut device = self.device.lock().await;
+        set_setting(&mut device, &name, &value).await?;
+        let value = get_setting(&mut device, &name).await?;
+        Ok(Response::new(pb::Setting { name, value }))
+    }
+}
+
+async fn find_recording(
+    device: &mut ReadyDevice,
+    selector: &pb::download_recording_request::Recording,
+) -> Result<SavedRecordingSessionInfo, Status> {
+    use pb::download_recording_request::Recording;
+
+    let count = device.memory_statistics().await.map_err(status)?.recordings;
+    let maps = device.maps().clone();
+    for idx in 0..count {
+        let raw = device.saved_recording(idx).await.map_err(status)?;
+        let info = SavedRecordingSessionInfo::try_from((raw, &maps)).map_err(status)?;
+        let matches = match selector {
+            Recording::SeqNo(seq_no) => u32::from(info.seq_no) == *seq_no,
+            Recording::Name(name) => info.name == *name,
+        };
+        if matches {
+            return Ok(info);
+        }
+    }
+    Err(Status::not_found("No such recording"))
+}
+
+async fn get_setting(device: &mut ReadyDevice, name: &str) -> Result<String, Status> {
+    Ok(match name {
+        "backlight_minutes" => {
+            (device.backlight().await.map_err(status)?.as_secs() / 60).to_string()
+        }
+        "poweroff_minutes" => (device.poweroff().await.map_err(status)?.as_secs() / 60).to_string(),
+        "operator" => device.operator().await.map_err(status)?,
+        "company" => device.company().await.map_err(status)?,
+        "site" => device.site().await.map_err(status)?,
+        "contact" => device.contact().await.map_err(status)?,
+        "beeper" => device.beeper().await.map_err(status)?.to_string(),
+        "smoothing" => device.smoothing().await.map_err(status)?.to_string(),
+        "custom_dbm" => device.custom_dbm().await.map_err(status)?.to_string(),
+        "dbm_reference" => enum_name(&device.dbm_ref().await.map_err(status)?),
+        "temp_offset" => device.temp_offset().await.map_err(status)?.to_string(),
+        "digits" => enum_name(&device.digit_count().await.map_err(status)?),
+        "numeric_format" => enum_name(&device.numeric_format().await.map_err(status)?),
+        "date_format" => enum_name(&device.date_format().await.map_err(status)?),
+        "time_format" => enum_name(&device.time_format().await.map_err(status)?),
+        "language" => enum_name(&device.language().await.map_err(status)?),
+        "autohold_event_threshold" => device
+            .autohold_event_threshold()
+            .await
+            .map_err(status)?
+            .to_string(),
+        "recording_event_threshold" => device
+            .recording_event_threshold()
+            .await
+            .map_err(status)?
+            .to_string(),
+        "clock" => {
+            let secs = device.clock().await.map_err(status)?;
+            let clock: DateTime<Utc> = (std::time::UNIX_EPOCH + Duration::from_secs(secs)).into();
+            clock.to_rfc3339()
+        }
+        _ => return Err(unknown_setting(name)),
+    })
+}
+
+async fn set_setting(device: &mut ReadyDevice, name: &str, value: &str) -> Result<(), Status> {
+    match name {
+        "backlight_minutes" => device.set_backlight(minutes(value)?).await,
+        "poweroff_minutes" => device.set_poweroff(minutes(value)?).await,
+        "operator" => device.set_operator(value).await,
+        "company" => device.set_company(value).await,
+        "site" => device.set_site(value).await,
+        "contact" => device.set_contact(value).await,
+        "beeper" => device.set_beeper(parse(value)?).await,
+        "smoothing" => device.set_smoothing(parse(value)?).await,
+        "custom_dbm" => device.set_custom_dbm(parse(value)?).await,
+        "dbm_reference" => {
+            device
+                .set_dbm_ref(parse_enum::<DezibelReference>(value)?)
+                .await
+        }
+        "temp_offset" => device.set_temp_offset(parse(value)?).await,
+        "digits" => {
+            device
+                .set_digit_count(parse_enum::<DigitCount>(value)?)
+                .await
+        }
+        "numeric_format" => {
+            device
+                .set_numeric_format(parse_enum::<NumericFormat>(value)?)
+                .await
+        }
+        "date_format" => {
+            device
+                .set_date_format(parse_enum::<DateFormat>(value)?)
+                .await
+        }
+        "time_format" => {
+            device
+                .set_time_format(parse_enum::<TimeFormat>(value)?)
+                .await
+        }
+        "language" => device.set_language(parse_enum::<Language>(value)?).await,
+        "autohold_event_threshold" => device.set_autohold_event_threshold(parse(value)?).await,
+        "recording_event_threshold" => device.set_recording_event_threshold(parse(value)?).await,
+        "clock" if value == "now" => device.set_clock(Local::now()).await,
+        "clock" => {
+            return Err(Status::invalid_argument(
+                "The clock can only be set to 'now'",
+            ))
+        }
+        _ => return Err(unknown_setting(name)),
+    }
+    .map_err(status)
+}
+
+fn unknown_setting(name: &str) -> Status {
+    Status::invalid_argument(format!("Unknown setting '{}'", name))
+}
+
+fn minutes(value: &str) -> Result<Duration, Status> {
+    match value.to_lowercase().as_str() {
+        "off" | "0" => Ok(Duration::ZERO),
+        value => Ok(Duration::from_secs(parse::<u64>(value)? * 60)),
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, Status> {
+    value
+        .parse()
+        .map_err(|_| Status::invalid_argument(format!("Invalid value '{}'", value)))
+}
+
+fn parse_enum<T: ValueEnum>(value: &str) -> Result<T, Status> {
+    T::from_str(value, true).map_err(Status::invalid_argument)
+}
+
+fn enum_name<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Map protocol errors to gRPC status codes.
+fn status(err: ProtoError) -> Status {
+    match err {
+        ProtoError::Abort => Status::unavailable(err.to_string()),
+        ProtoError::DeviceLocked { .. } => Status::failed_precondition(err.to_string()),
+        ProtoError::Unsupported { .. } => Status::unimplemented(err.to_string()),
+        ProtoError::InvalidArgument(_) | ProtoError::InvalidSaveName(_) => {
+            Status::invalid_argument(err.to_string())
+        }
+        err => Status::internal(err.to_string()),
+    }
+}
+
+fn timestamp(ts: &DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: ts.timestamp(),
+        nanos: ts.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn to_reading(reading: &Reading) -> pb::Reading {
+    pb::Reading {
+        reading_id: reading.reading_id.into(),
+        value: reading.si_value(),
+        unit: reading.unit.to_string(),
+        state: format!("{:?}", reading.state),
+        timestamp: Some(timestamp(&reading.ts)),
+    }
+}
+
+fn to_measurement(mea: &measurement::Measurement) -> pb::Measurement {
+    pb::Measurement {
+        primary_function: format!("{:?}", mea.pri_function),
+        secondary_function: format!("{:?}", mea.sec_function),
+        modes: mea.modes.modes().map(|m| format!("{:?}", m)).collect(),
+        readings: mea.readings.iter().map(to_reading).collect(),
+        host_timestamp: mea.host_ts.as_ref().map(timestamp),
+    }
+}
+
+fn to_memory_entry(memory: &Memory) -> pb::MemoryEntry {
+    let kind = match memory.kind() {
+        MemoryKind::Measurement => pb::MemoryKind::Measurement,
+        MemoryKind::MinMax => pb::MemoryKind::MinMax,
+        MemoryKind::Peak => pb::MemoryKind::Peak,
+        MemoryKind::Recording => pb::MemoryKind::Recording,
+    };
+    pb::MemoryEntry {
+        kind: kind.into(),
+        seq_no: memory.seq_no().into(),
+        name: memory.name().to_string(),
+        timestamp: memory.ts().as_ref().map(timestamp),
+        primary_function: format!("{:?}", memory.pri_function()),
+        secondary_function: format!("{:?}", memory.sec_function()),
+    }
+}
+
+fn to_interval(rec: &SessionRecordReadings) -> pb::RecordingInterval {
+    pb::RecordingInterval {
+        start: Some(timestamp(&rec.start_ts)),
+        end: Some(timestamp(&rec.end_ts)),
+        average: Some(to_reading(&rec.average_reading())),
+        min: Some(to_reading(&rec.span_readings[1])),
+        max: Some(to_reading(&rec.span_readings[0])),
+        samples: rec.sampling.into(),
+        stable: rec.stable.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use f289ctrl_core::{
+        fixture,
+        measurement::{AutoRange, Bolt, Modes, PrimaryFunction, SecondaryFunction, State, Unit},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_to_measurement() {
+        let reading = |value: f64, state: State| Reading {
+            unit_multiplier: -3,
+            ..fixture::reading(value, state)
+        };
+        let mea = measurement::Measurement {
+            pri_function: PrimaryFunction::V_DC,
+            sec_function: SecondaryFunction::None,
+            auto_range: AutoRange(true),
+            unit: Unit::VoltDC,
+            range_max: 5.0,
+            unit_multiplier: -3,
+            bolt: Bolt(false),
+            ts: None,
+            host_ts: Some(DateTime::default() + chrono::Duration::milliseconds(1500)),
+            modes: Modes::HOLD,
+            readings: vec![reading(1.25, State::Normal), reading(0.0, State::OL)],
+        };
+
+        let pb = to_measurement(&mea);
+        assert_eq!(pb.primary_function, "V_DC");
+        assert_eq!(pb.modes, ["Hold"]);
+        assert_eq!(pb.readings[0].value, Some(1.25));
+        assert_eq!(pb.readings[0].unit, "VDC");
+        assert_eq!(pb.readings[1].value, None);
+        assert_eq!(pb.readings[1].state, "OL");
+        assert_eq!(
+            pb.host_timestamp,
+            Some(prost_types::Timestamp {
+                seconds: 1,
+                nanos: 500_000_000
+            })
+        );
+    }
+
+    #[test]
+    fn test_setting_values() {
+        assert_eq!(minutes("off").expect("off"), Duration::ZERO);
+        assert_eq!(minutes("15").expect("15"), Duration::from_secs(900));
+        assert!(minutes("x").is_err());
+        assert_eq!(enum_name(&DigitCount::Digit5), "5");
+        assert!(matches!(
+            parse_enum::<NumericFormat>("COMMA"),
+            Ok(NumericFormat::Comma)
+        ));
+        assert!(parse_enum::<Language>("klingon").is_err());
+    }
+}
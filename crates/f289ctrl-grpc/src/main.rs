@@ -0,0 +1,50 @@
+use std::{net::SocketAddr, process::exit};
+
+use clap::{arg, command, value_parser};
+use f289ctrl_core::{Device, DEFAULT_BAUDRATE, DEFAULT_TTY};
+use f289ctrl_grpc::MeterService;
+
+#[tokio::main]
+async fn main() {
+    let matches = command!()
+        .about("gRPC server for Fluke 287/289 digital multimeters")
+        .arg(arg!(-p --device <DEVICE> "Device").default_value(DEFAULT_TTY))
+        .arg(
+            arg!(--baudrate <BAUDRATE> "Baudrate")
+                .default_value(DEFAULT_BAUDRATE.to_string())
+                .value_parser(value_parser!(u32)),
+        )
+        .arg(
+            arg!(--listen <ADDR> "Listen address")
+                .default_value("127.0.0.1:50289")
+                .value_parser(value_parser!(SocketAddr)),
+        )
+        .get_matches();
+
+    let port = matches.get_one::<String>("device").expect("device");
+    let baud_rate = matches.get_one::<u32>("baudrate").expect("baudrate");
+    let addr = matches.get_one::<SocketAddr>("listen").expect("listen");
+
+    let device = match Device::new(port, *baud_rate) {
+        Ok(device) => device.ready().await,
+        Err(err) => Err(err),
+    };
+    let device = match device {
+        Ok(device) => device,
+        Err(err) => {
+            eprintln!("{}: {}", port, err);
+            exit(-1);
+        }
+    };
+    eprintln!("Connected to: {} ({})", port, device.ident().model);
+
+    eprintln!("Listening on: {}", addr);
+    if let Err(err) = tonic::transport::Server::builder()
+        .add_service(MeterService::new(device).into_server())
+        .serve(*addr)
+        .await
+    {
+        eprintln!("Server error: {}", err);
+        exit(-1);
+    }
+}
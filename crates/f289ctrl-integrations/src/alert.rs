@@ -0,0 +1,180 @@
+//! Alert rules evaluated against live measurements.
+//!
+//! Rules and actions are loaded from a TOML file:
+//!
+//! ```toml
+//! [[rule]]
+//! name = "overvoltage"
+//! kind = "threshold"
+//! above = 250.0
+//! hysteresis = 5.0
+//! cooldown_secs = 300
+//! actions = ["hook"]
+//!
+//! [[rule]]
+//! name = "function changed"
+//! kind = "state"
+//! field = "function"
+//! actions = ["log"]
+//!
+//! [action.hook]
+//! type = "webhook"
+//! url = "http://localhost:8080/alert"
+//!
+//! [action.log]
+//! type = "syslog"
+//! ```
+
+mod action;
+mod rule;
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::Path,
+    sync::Arc,
+};
+
+use chrono::{DateTime, Utc};
+use f289ctrl_core::measurement::Measurement;
+use serde::{Deserialize, Serialize};
+
+pub use action::ActionConfig;
+pub use rule::{Condition, RuleConfig, StateField};
+
+use rule::RuleState;
+
+/// Contents of an alert configuration file.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AlertConfig {
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<RuleConfig>,
+    #[serde(default, rename = "action")]
+    pub actions: HashMap<String, ActionConfig>,
+}
+
+impl AlertConfig {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+/// A fired rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub rule: String,
+    pub message: String,
+    /// Evaluated value, if the rule is value based
+    pub value: Option<f64>,
+    pub ts: DateTime<Utc>,
+}
+
+/// Evaluates all rules and runs the configured actions for fired alerts.
+pub struct AlertEngine {
+    rules: Vec<RuleState>,
+    actions: HashMap<String, ActionConfig>,
+    on_error: Option<ErrorHandler>,
+}
+
+type ErrorHandler = Arc<dyn Fn(&str, &Alert, &io::Error) + Send + Sync>;
+
+impl AlertEngine {
+    /// Fails if two rules have the same name or a rule refers to an
+    /// undefined action.
+    pub fn new(config: AlertConfig) -> io::Result<Self> {
+        let mut names = HashSet::new();
+        for rule in &config.rules {
+            if !names.insert(rule.name.as_str()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Duplicate rule name '{}'", rule.name),
+                ));
+            }
+            if let Some(name) = rule
+                .actions
+                .iter()
+                .find(|name| !config.actions.contains_key(*name))
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Rule '{}' refers to unknown action '{}'", rule.name, name),
+                ));
+            }
+        }
+        Ok(Self {
+            rules: config.rules.into_iter().map(RuleState::new).collect(),
+            actions: config.actions,
+            on_error: None,
+        })
+    }
+
+    /// Call `handler` with the action name, the alert and the error when an
+    /// action run by [`AlertEngine::observe`] fails. Without a handler
+    /// failures are ignored.
+    pub fn on_error(
+        mut self,
+        handler: impl Fn(&str, &Alert, &io::Error) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_error = Some(Arc::new(handler));
+        self
+    }
+
+    /// Update all rules with a new measurement and return the fired alerts.
+    pub fn evaluate(&mut self, measurement: &Measurement, now: DateTime<Utc>) -> Vec<Alert> {
+        self.rules
+            .iter_mut()
+            .filter_map(|rule| rule.evaluate(measurement, now))
+            .collect()
+    }
+
+    /// Evaluate a measurement and run the actions of all fired alerts in the
+    /// background. Failed actions are reported to the
+    /// [error handler](AlertEngine::on_error).
+    pub fn observe(&mut self, measurement: &Measurement) {
+        for alert in self.evaluate(measurement, Utc::now()) {
+            let rule = self
+                .rules
+                .iter()
+                .find(|rule| rule.config.name == alert.rule)
+                .expect("rule of fired alert");
+            for name in &rule.config.actions {
+                let action = self.actions[name].clone();
+                let alert = alert.clone();
+                let name = name.clone();
+                let on_error = self.on_error.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = action.execute(&alert).await {
+                        if let Some(on_error) = on_error {
+                            on_error(&name, &alert, &err);
+                        }
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_rule_names() {
+        let config: AlertConfig = toml::from_str(
+            r#"
+            [[rule]]
+            name = "high"
+            kind = "threshold"
+            above = 1.0
+
+            [[rule]]
+            name = "high"
+            kind = "threshold"
+            above = 2.0
+            "#,
+        )
+        .expect("config");
+        assert!(AlertEngine::new(config).is_err());
+    }
+}
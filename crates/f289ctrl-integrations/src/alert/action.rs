@@ -0,0 +1,63 @@
+use std::io;
+
+use serde::Deserialize;
+
+use super::Alert;
+use crate::net::{http, mqtt, syslog};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActionConfig {
+    /// POST the alert as JSON to a plain http:// URL
+    Webhook { url: String },
+    /// Publish the alert as JSON to an MQTT broker
+    Mqtt {
+        host: String,
+        #[serde(default = "default_mqtt_port")]
+        port: u16,
+        topic: String,
+        #[serde(default = "default_client_id")]
+        client_id: String,
+    },
+    /// Log the alert to syslog, over UDP if `address` is given
+    Syslog { address: Option<String> },
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_client_id() -> String {
+    String::from("f289ctrl")
+}
+
+impl ActionConfig {
+    pub async fn execute(&self, alert: &Alert) -> io::Result<()> {
+        match self {
+            Self::Webhook { url } => {
+                let url = http::Url::parse(url)?;
+                let body = serde_json::to_vec(alert)?;
+                http::post(&url, "application/json", &[], &body).await
+            }
+            Self::Mqtt {
+                host,
+                port,
+                topic,
+                client_id,
+            } => {
+                let body = serde_json::to_vec(alert)?;
+                let options = mqtt::Options {
+                    client_id,
+                    ..Default::default()
+                };
+                let mut conn = mqtt::Connection::connect(host, *port, &options).await?;
+                conn.publish(topic, &body, false).await?;
+                conn.disconnect().await
+            }
+            Self::Syslog { address } => {
+                let message = format!("[{}] {}", alert.rule, alert.message);
+                syslog::send(address.as_deref(), "f289ctrl", &message).await
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use f289ctrl_core::measurement::Measurement;
+use serde::Deserialize;
+
+use super::Alert;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// Names of the actions run when the rule fires
+    #[serde(default)]
+    pub actions: Vec<String>,
+    /// Index of the evaluated reading, 0 is the primary display
+    #[serde(default)]
+    pub reading: usize,
+    /// Distance the value must move back before the rule can fire again
+    #[serde(default)]
+    pub hysteresis: f64,
+    /// Minimum time between two alerts of this rule
+    #[serde(default)]
+    pub cooldown_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    /// Value leaves the range given by `above` and/or `below`
+    Threshold {
+        above: Option<f64>,
+        below: Option<f64>,
+    },
+    /// Value changes faster than `max_per_sec` in either direction
+    Rate { max_per_sec: f64 },
+    /// A field of the measurement changes, optionally only into state `to`
+    State {
+        field: StateField,
+        to: Option<String>,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateField {
+    Function,
+    Modes,
+    Unit,
+}
+
+impl StateField {
+    fn value(&self, measurement: &Measurement) -> String {
+        match self {
+            Self::Function => measurement.pri_function.to_string(),
+            Self::Modes => measurement.modes.to_string(),
+            Self::Unit => measurement.unit.to_string(),
+        }
+    }
+}
+
+pub(crate) struct RuleState {
+    pub config: RuleConfig,
+    active: bool,
+    last_fired: Option<DateTime<Utc>>,
+    last_value: Option<(f64, DateTime<Utc>)>,
+    last_state: Option<String>,
+}
+
+impl RuleState {
+    pub fn new(config: RuleConfig) -> Self {
+        Self {
+            config,
+            active: false,
+            last_fired: None,
+            last_value: None,
+            last_state: None,
+        }
+    }
+
+    pub fn evaluate(&mut self, measurement: &Measurement, now: DateTime<Utc>) -> Option<Alert> {
+        let (triggered, value, message) = match &self.config.condition {
+            Condition::Threshold { above, below } => {
+                let value = self.reading(measurement)?;
+                let h = if self.active {
+                    self.config.hysteresis
+                } else {
+                    0.0
+                };
+                let high = above.map_or(false, |above| value > above - h);
+                let low = below.map_or(false, |below| value < below + h);
+                let message = match (high, above, below) {
+                    (true, Some(above), _) => format!("value {} above {}", value, above),
+                    (_, _, Some(below)) => format!("value {} below {}", value, below),
+                    _ => String::new(),
+                };
+                (high || low, Some(value), message)
+            }
+            Condition::Rate { max_per_sec } => {
+                let value = self.reading(measurement)?;
+                let previous = self.last_value.replace((value, now));
+                let rate = match previous {
+                    Some((prev, ts)) if now > ts => {
+                        (value - prev) / (now - ts).to_std().ok()?.as_secs_f64()
+                    }
+                    _ => return None,
+                };
+                let h = if self.active {
+                    self.config.hysteresis
+                } else {
+                    0.0
+                };
+                let message = format!("value changing by {:.3}/s, limit {}", rate, max_per_sec);
+                (rate.abs() > max_per_sec - h, Some(rate), message)
+            }
+            Condition::State { field, to } => {
+                let state = field.value(measurement);
+                let previous = self.last_state.replace(state.clone());
+                // State changes are events, there is no active phase to leave
+                self.active = false;
+                let changed = previous.as_ref().map_or(false, |prev| *prev != state)
+                    && to.as_ref().map_or(true, |to| *to == state);
+                let message = format!(
+                    "{:?} changed from '{}' to '{}'",
+                    field,
+                    previous.unwrap_or_default(),
+                    state
+                );
+                (changed, None, message)
+            }
+        };
+
+        let fire = triggered && !self.active && !self.cooling_down(now);
+        // Stay active while triggered, so the alert fires once per excursion
+        self.active = triggered && (self.active || fire);
+        if !fire {
+            return None;
+        }
+        self.last_fired = Some(now);
+        Some(Alert {
+            rule: self.config.name.clone(),
+            message,
+            value,
+            ts: now,
+        })
+    }
+
+    fn cooling_down(&self, now: DateTime<Utc>) -> bool {
+        self.last_fired.map_or(false, |last| {
+            (now - last).to_std().unwrap_or_default()
+                < Duration::from_secs(self.config.cooldown_secs)
+        })
+    }
+
+    /// Value of the configured reading, `None` for overload or missing readings.
+    fn reading(&self, measurement: &Measurement) -> Option<f64> {
+        measurement
+            .readings
+            .get(self.config.reading)
+            .and_then(|r| r.si_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use f289ctrl_core::fixture::measurement;
+
+    use super::*;
+
+    fn rule(toml: &str) -> RuleState {
+        RuleState::new(toml::from_str(toml).expect("rule config"))
+    }
+
+    #[test]
+    fn test_threshold_hysteresis() {
+        let mut rule = rule(
+            r#"
+            name = "over"
+            kind = "threshold"
+            above = 10.0
+            hysteresis = 1.0
+            "#,
+        );
+        let now = Utc.timestamp_opt(0, 0).unwrap();
+        let fired: Vec<bool> = [5.0, 11.0, 12.0, 9.5, 10.5, 8.0, 10.5]
+            .into_iter()
+            .map(|v| rule.evaluate(&measurement(&[v]), now).is_some())
+            .collect();
+        assert_eq!(
+            fired,
+            vec![false, true, false, false, false, false, true],
+            "fires once per excursion, re-arms below 9.0"
+        );
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let mut rule = rule(
+            r#"
+            name = "over"
+            kind = "threshold"
+            above = 10.0
+            cooldown_secs = 60
+            "#,
+        );
+        let t0 = Utc.timestamp_opt(0, 0).unwrap();
+        assert!(rule.evaluate(&measurement(&[11.0]), t0).is_some());
+        assert!(rule.evaluate(&measurement(&[5.0]), t0).is_none());
+        let t1 = Utc.timestamp_opt(30, 0).unwrap();
+        assert!(rule.evaluate(&measurement(&[11.0]), t1).is_none());
+        let t2 = Utc.timestamp_opt(90, 0).unwrap();
+        assert!(rule.evaluate(&measurement(&[5.0]), t2).is_none());
+        assert!(rule.evaluate(&measurement(&[11.0]), t2).is_some());
+    }
+
+    #[test]
+    fn test_rate() {
+        let mut rule = rule(
+            r#"
+            name = "fast"
+            kind = "rate"
+            max_per_sec = 1.0
+            "#,
+        );
+        let t0 = Utc.timestamp_opt(0, 0).unwrap();
+        let t1 = Utc.timestamp_opt(1, 0).unwrap();
+        let t2 = Utc.timestamp_opt(2, 0).unwrap();
+        assert!(rule.evaluate(&measurement(&[0.0]), t0).is_none());
+        assert!(rule.evaluate(&measurement(&[0.5]), t1).is_none());
+        assert!(rule.evaluate(&measurement(&[5.0]), t2).is_some());
+    }
+}
@@ -0,0 +1,28 @@
+#[cfg(any(feature = "csv", feature = "influx", feature = "parquet"))]
+use f289ctrl_core::measurement::State;
+
+#[cfg(feature = "json")]
+pub mod archive;
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "influx")]
+pub mod influx;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+
+/// State names as used in the device value maps
+#[cfg(any(feature = "csv", feature = "influx", feature = "parquet"))]
+pub(crate) fn state_name(state: &State) -> &'static str {
+    match state {
+        State::Normal => "NORMAL",
+        State::Discharge => "DISCHARGE",
+        State::OL_Minus => "OL_MINUS",
+        State::Invalid => "INVALID",
+        State::Blank => "BLANK",
+        State::Inactive => "INACTIVE",
+        State::OL => "OL",
+        State::OpenTC => "OPEN_TC",
+    }
+}
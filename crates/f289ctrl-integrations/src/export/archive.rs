@@ -0,0 +1,235 @@
+//! Backup of the complete device memory into a directory.
+//!
+//! Every saved entry is written to its own file, named after its kind,
+//! sequence number and name (e.g. `recording-002-Motor_1.json`). A
+//! `manifest.json` lists all files together with the meter identity and
+//! the time of the download.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use f289ctrl_core::{
+    measurement::{MemoryEntry, MemoryKind},
+    proto::response::Ident,
+    snapshot::MemorySnapshot,
+};
+use serde::{Deserialize, Serialize};
+
+use super::json::Versioned;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// File format of the entries, the manifest is always JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// The complete entry as versioned JSON document
+    Json,
+    /// The readings only, see [`super::csv`]
+    #[cfg(feature = "csv")]
+    Csv,
+}
+
+impl ArchiveFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Json => "json",
+            #[cfg(feature = "csv")]
+            ArchiveFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Content of `manifest.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub taken_at: DateTime<Utc>,
+    pub ident: Ident,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub kind: MemoryKind,
+    pub seq_no: u16,
+    pub name: String,
+    /// File name relative to the archive directory
+    pub file: String,
+    /// Readings, or samples for recordings
+    pub readings: usize,
+}
+
+/// Write all entries of `snapshot` into `dir`, creating it if needed.
+pub fn write_archive(
+    dir: impl AsRef<Path>,
+    snapshot: &MemorySnapshot,
+    format: ArchiveFormat,
+) -> io::Result<Manifest> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+
+    let mut manifest = Manifest {
+        taken_at: snapshot.taken_at,
+        ident: snapshot.ident.clone(),
+        entries: Vec::new(),
+    };
+    let mut add = |entry: &dyn MemoryEntry, kind: MemoryKind, readings: usize| {
+        let file = file_name(kind, entry.seq_no(), entry.name(), format);
+        manifest.entries.push(ManifestEntry {
+            kind,
+            seq_no: entry.seq_no(),
+            name: entry.name().to_string(),
+            file: file.clone(),
+            readings,
+        });
+        File::create(dir.join(file)).map(BufWriter::new)
+    };
+
+    for mea in &snapshot.measurements {
+        let mut out = add(mea, MemoryKind::Measurement, mea.readings.len())?;
+        match format {
+            ArchiveFormat::Json => write_json(&mut out, mea)?,
+            #[cfg(feature = "csv")]
+            ArchiveFormat::Csv => super::csv::write_readings(&mut out, &mea.readings)?,
+        }
+        out.flush()?;
+    }
+    for (kind, entries) in [
+        (MemoryKind::MinMax, &snapshot.min_max),
+        (MemoryKind::Peak, &snapshot.peak),
+    ] {
+        for mea in entries {
+            let mut out = add(mea, kind, mea.readings.len())?;
+            match format {
+                ArchiveFormat::Json => write_json(&mut out, mea)?,
+                #[cfg(feature = "csv")]
+                ArchiveFormat::Csv => super::csv::write_readings(&mut out, &mea.readings)?,
+            }
+            out.flush()?;
+        }
+    }
+    for rec in &snapshot.recordings {
+        let mut out = add(&rec.info, MemoryKind::Recording, rec.samples.len())?;
+        match format {
+            ArchiveFormat::Json => write_json(&mut out, rec)?,
+            #[cfg(feature = "csv")]
+            ArchiveFormat::Csv => super::csv::write_recordings(
+                &mut out,
+                [(rec.info.name.as_str(), rec.samples.as_slice())],
+            )?,
+        }
+        out.flush()?;
+    }
+
+    let mut out = BufWriter::new(File::create(dir.join(MANIFEST_FILE))?);
+    write_json(&mut out, &manifest)?;
+    out.flush()?;
+    Ok(manifest)
+}
+
+fn write_json(writer: impl Write, data: &impl Serialize) -> io::Result<()> {
+    serde_json::to_writer_pretty(writer, &Versioned::new(data)).map_err(io::Error::from)
+}
+
+fn file_name(kind: MemoryKind, seq_no: u16, name: &str, format: ArchiveFormat) -> String {
+    let kind = match kind {
+        MemoryKind::Measurement => "measurement",
+        MemoryKind::MinMax => "minmax",
+        MemoryKind::Peak => "peak",
+        MemoryKind::Recording => "recording",
+    };
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}-{:03}-{}.{}", kind, seq_no, name, format.extension())
+}
+
+#[cfg(test)]
+mod tests {
+    use f289ctrl_core::{
+        measurement::{
+            AutoRange, Bolt, Modes, PrimaryFunction, SavedMeasurement, SavedRecordingSessionInfo,
+            SecondaryFunction, Unit,
+        },
+        snapshot::RecordingSnapshot,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_write_archive() {
+        let snapshot = MemorySnapshot {
+            taken_at: DateTime::default(),
+            ident: Ident {
+                model: "FLUKE 289".into(),
+                firmware: "V1.16".into(),
+                serial: "12345678".into(),
+            },
+            measurements: vec![SavedMeasurement {
+                seq_no: 3,
+                pri_function: PrimaryFunction::V_DC,
+                sec_function: SecondaryFunction::None,
+                auto_range: AutoRange(true),
+                unit: Unit::VoltDC,
+                range_max: 5.0,
+                unit_multiplier: 0,
+                bolt: Bolt(false),
+                modes: Modes::empty(),
+                readings: Vec::new(),
+                name: "Save 3".into(),
+            }],
+            min_max: Vec::new(),
+            peak: Vec::new(),
+            recordings: vec![RecordingSnapshot {
+                info: SavedRecordingSessionInfo {
+                    seq_no: 1,
+                    start_ts: DateTime::default(),
+                    end_ts: DateTime::default(),
+                    sample_interval: 1.0,
+                    event_threshold: 4.0,
+                    reading_index: 0,
+                    num_samples: 0,
+                    pri_function: PrimaryFunction::V_DC,
+                    sec_function: SecondaryFunction::None,
+                    auto_range: AutoRange(true),
+                    unit: Unit::VoltDC,
+                    range_max: 5.0,
+                    unit_multiplier: 0,
+                    bolt: Bolt(false),
+                    modes: Modes::empty(),
+                    readings: Vec::new(),
+                    name: "Rec 1/2".into(),
+                },
+                samples: Vec::new(),
+            }],
+        };
+        let dir = std::env::temp_dir().join(format!("f289-archive-{}", std::process::id()));
+
+        let manifest = write_archive(&dir, &snapshot, ArchiveFormat::Json).expect("archive");
+        let files: Vec<&str> = manifest.entries.iter().map(|e| e.file.as_str()).collect();
+        assert_eq!(
+            files,
+            ["measurement-003-Save_3.json", "recording-001-Rec_1_2.json"]
+        );
+        for file in files {
+            assert!(dir.join(file).is_file(), "{}", file);
+        }
+        let read: Manifest =
+            crate::export::json::from_reader(File::open(dir.join(MANIFEST_FILE)).expect("open"))
+                .expect("manifest");
+        assert_eq!(read.ident.serial, "12345678");
+        assert_eq!(read.entries[1].kind, MemoryKind::Recording);
+
+        fs::remove_dir_all(&dir).expect("cleanup");
+    }
+}
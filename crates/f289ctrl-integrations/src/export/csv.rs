@@ -0,0 +1,156 @@
+use std::io::Write;
+
+use f289ctrl_core::measurement::{Measurement, Reading, SavedMeasurement, SessionRecordReadings};
+
+use super::state_name;
+
+/// Columns describing a single reading, shared by all CSV exports.
+///
+/// `value` is in the base SI unit with a point as decimal separator and
+/// empty unless `state` is `NORMAL`.
+pub const READING_COLUMNS: [&str; 5] = ["timestamp", "value", "unit", "state", "attribute"];
+
+/// Write a live measurement series, one row per measurement with its
+/// primary reading.
+pub fn write_series(writer: impl Write, measurements: &[Measurement]) -> std::io::Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(READING_COLUMNS)?;
+    for reading in measurements.iter().filter_map(|m| m.readings.first()) {
+        csv.write_record(reading_fields(reading))?;
+    }
+    csv.flush()
+}
+
+/// Write plain readings, one row each.
+pub fn write_readings(writer: impl Write, readings: &[Reading]) -> std::io::Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(READING_COLUMNS)?;
+    for reading in readings {
+        csv.write_record(reading_fields(reading))?;
+    }
+    csv.flush()
+}
+
+/// Write saved measurements, one row per reading prefixed by the name.
+pub fn write_saved(writer: impl Write, measurements: &[SavedMeasurement]) -> std::io::Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(["name"].iter().chain(READING_COLUMNS.iter()))?;
+    for mea in measurements {
+        for reading in &mea.readings {
+            csv.write_record(
+                [mea.name.clone()]
+                    .into_iter()
+                    .chain(reading_fields(reading)),
+            )?;
+        }
+    }
+    csv.flush()
+}
+
+/// Write downloaded recordings, one row per interval.
+///
+/// `timestamp` is the interval start, `value` and state the interval
+/// average, `min`/`max` the extremes in the same unit.
+pub fn write_recordings<'a>(
+    writer: impl Write,
+    recordings: impl IntoIterator<Item = (&'a str, &'a [SessionRecordReadings])>,
+) -> std::io::Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(
+        ["name"]
+            .iter()
+            .chain(READING_COLUMNS.iter())
+            .chain(["end_timestamp", "min", "max", "samples"].iter()),
+    )?;
+    for (name, records) in recordings {
+        for rec in records {
+            let avg = rec.average_reading();
+            csv.write_record(
+                [name.to_string()]
+                    .into_iter()
+                    .chain(reading_fields(&avg))
+                    .chain([
+                        rec.end_ts.to_rfc3339(),
+                        value_field(&rec.span_readings[1]),
+                        value_field(&rec.span_readings[0]),
+                        rec.sampling.to_string(),
+                    ]),
+            )?;
+        }
+    }
+    csv.flush()
+}
+
+fn reading_fields(reading: &Reading) -> [String; 5] {
+    [
+        reading.ts.to_rfc3339(),
+        value_field(reading),
+        reading.unit.to_string(),
+        state_name(&reading.state).to_string(),
+        reading
+            .attribute
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+    ]
+}
+
+fn value_field(reading: &Reading) -> String {
+    reading
+        .si_value()
+        .map(|v| v.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use f289ctrl_core::measurement::{Attribute, RecordType, Stable, State, TransientState};
+
+    use super::*;
+    use f289ctrl_core::fixture::reading;
+
+    #[test]
+    fn test_recordings() {
+        let mut ol = reading(0.0, State::OL);
+        ol.attribute = Some(Attribute::HighCurrent);
+        let records = [
+            SessionRecordReadings {
+                start_ts: Default::default(),
+                end_ts: Default::default(),
+                span_readings: [
+                    reading(0.5, State::Normal),
+                    reading(0.25, State::Normal),
+                    reading(1.5, State::Normal),
+                ],
+                sampling: 4,
+                fixed_reading: reading(0.5, State::Normal),
+                record_type: RecordType::Interval,
+                stable: Stable(true),
+                transient_state: TransientState::NonT,
+            },
+            SessionRecordReadings {
+                start_ts: Default::default(),
+                end_ts: Default::default(),
+                span_readings: [ol.clone(), ol.clone(), ol.clone()],
+                sampling: 1,
+                fixed_reading: ol,
+                record_type: RecordType::Interval,
+                stable: Stable(false),
+                transient_state: TransientState::Overload,
+            },
+        ];
+
+        let mut out = Vec::new();
+        write_recordings(&mut out, [("Rec 1", &records[..])]).expect("csv");
+        let ts = "1970-01-01T00:00:00+00:00";
+        assert_eq!(
+            String::from_utf8(out).expect("utf8"),
+            format!(
+                "name,timestamp,value,unit,state,attribute,end_timestamp,min,max,samples\n\
+                 Rec 1,{ts},0.375,VDC,NORMAL,,{ts},0.25,0.5,4\n\
+                 Rec 1,{ts},,VDC,OL,High Current,{ts},,,1\n",
+                ts = ts
+            )
+        );
+    }
+}
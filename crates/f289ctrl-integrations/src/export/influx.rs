@@ -0,0 +1,214 @@
+//! InfluxDB line protocol.
+//!
+//! Every reading becomes one line, tagged with the primary function and
+//! unit:
+//!
+//! ```text
+//! fluke289,function=V_DC,unit=VDC,reading=0,serial=12345678 value=1.25,state="NORMAL" 1672531200000000000
+//! ```
+//!
+//! `value` is in the base SI unit and left out unless the reading is in
+//! state `NORMAL`. Timestamps have nanosecond precision, the default of the
+//! InfluxDB write API. With [`TimestampSource::Both`] the line carries the
+//! device time and the host time is added as integer field `host_ts`.
+
+use std::io::{self, Write};
+
+use chrono::{DateTime, Utc};
+use f289ctrl_core::measurement::{Measurement, TimestampSource};
+
+use super::state_name;
+use crate::net::http;
+
+/// Formats measurements as InfluxDB line protocol.
+#[derive(Debug, Clone)]
+pub struct LineProtocol {
+    measurement: String,
+    tags: Vec<(String, String)>,
+    timestamps: TimestampSource,
+}
+
+impl LineProtocol {
+    pub fn new(measurement: impl Into<String>) -> Self {
+        Self {
+            measurement: measurement.into(),
+            tags: Vec::new(),
+            timestamps: TimestampSource::Device,
+        }
+    }
+
+    /// Clock used for the line timestamps, the device clock by default.
+    pub fn timestamps(mut self, timestamps: TimestampSource) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Add a tag to every line, e.g. the serial number of the meter.
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.push((key.into(), value.into()));
+        self
+    }
+
+    /// One line per reading of the measurement, without line terminator.
+    pub fn lines(&self, mea: &Measurement) -> Vec<String> {
+        let function = format!("{:?}", mea.pri_function);
+        mea.readings
+            .iter()
+            .map(|r| {
+                let mut line = escape(&self.measurement, &[',', ' ']);
+                let reading = r.reading_id.to_string();
+                let unit = r.unit.to_string();
+                let tags = [
+                    ("function", function.as_str()),
+                    ("unit", unit.as_str()),
+                    ("reading", reading.as_str()),
+                ]
+                .into_iter()
+                .chain(self.tags.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+                for (key, value) in tags.filter(|(_, v)| !v.is_empty()) {
+                    line.push(',');
+                    line.push_str(&escape(key, &[',', '=', ' ']));
+                    line.push('=');
+                    line.push_str(&escape(value, &[',', '=', ' ']));
+                }
+                line.push(' ');
+                if let Some(value) = r.si_value() {
+                    line.push_str(&format!("value={:?},", value));
+                }
+                line.push_str(&format!("state=\"{}\"", state_name(&r.state)));
+                if let (TimestampSource::Both, Some(host_ts)) = (self.timestamps, mea.host_ts) {
+                    line.push_str(&format!(",host_ts={}i", nanos(&host_ts)));
+                }
+                let ts = mea.reading_ts(r, self.timestamps);
+                line.push_str(&format!(" {}", nanos(&ts)));
+                line
+            })
+            .collect()
+    }
+
+    /// Write the lines of a measurement, each terminated by a newline.
+    pub fn write(&self, mut writer: impl Write, mea: &Measurement) -> io::Result<()> {
+        for line in self.lines(mea) {
+            writeln!(writer, "{}", line)?;
+        }
+        writer.flush()
+    }
+}
+
+fn nanos(ts: &DateTime<Utc>) -> i128 {
+    ts.timestamp() as i128 * 1_000_000_000 + ts.timestamp_subsec_nanos() as i128
+}
+
+/// Escape special characters of names and tag values with a backslash.
+fn escape(s: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Writes measurements to the HTTP API of InfluxDB.
+#[derive(Debug, Clone)]
+pub struct InfluxWriter {
+    url: http::Url,
+    token: Option<String>,
+    format: LineProtocol,
+}
+
+impl InfluxWriter {
+    /// `url` is the full write endpoint, e.g.
+    /// `http://localhost:8086/api/v2/write?org=lab&bucket=dmm` for InfluxDB 2
+    /// or `http://localhost:8086/write?db=dmm` for InfluxDB 1.
+    pub fn new(url: &str, format: LineProtocol) -> io::Result<Self> {
+        Ok(Self {
+            url: http::Url::parse(url)?,
+            token: None,
+            format,
+        })
+    }
+
+    /// API token sent as `Authorization: Token <token>`.
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub async fn write(&self, mea: &Measurement) -> io::Result<()> {
+        let mut body = Vec::new();
+        self.format.write(&mut body, mea)?;
+        let auth = self.token.as_ref().map(|t| format!("Token {}", t));
+        let headers: Vec<(&str, &str)> = auth
+            .as_deref()
+            .map(|auth| ("Authorization", auth))
+            .into_iter()
+            .collect();
+        http::post(&self.url, "text/plain; charset=utf-8", &headers, &body).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use f289ctrl_core::measurement::{
+        AutoRange, Bolt, Modes, PrimaryFunction, Reading, SecondaryFunction, State, Unit,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let reading = |reading_id: u16, value: f64, state: State| Reading {
+            reading_id,
+            value,
+            unit: Unit::VoltDC,
+            unit_multiplier: -3,
+            decimals: 3,
+            display_digits: 5,
+            state,
+            attribute: None,
+            ts: Default::default(),
+            device_ts: 0.0,
+            utc_offset: 0,
+        };
+        let mea = Measurement {
+            pri_function: PrimaryFunction::V_DC,
+            sec_function: SecondaryFunction::None,
+            auto_range: AutoRange(true),
+            unit: Unit::VoltDC,
+            range_max: 5.0,
+            unit_multiplier: -3,
+            bolt: Bolt(false),
+            ts: None,
+            host_ts: None,
+            modes: Modes::empty(),
+            readings: vec![reading(0, 1.25, State::Normal), reading(1, 0.0, State::OL)],
+        };
+
+        let lines = LineProtocol::new("fluke 289")
+            .tag("site", "lab, bench=2")
+            .lines(&mea);
+        assert_eq!(
+            lines,
+            [
+                "fluke\\ 289,function=V_DC,unit=VDC,reading=0,site=lab\\,\\ bench\\=2 value=1.25,state=\"NORMAL\" 0",
+                "fluke\\ 289,function=V_DC,unit=VDC,reading=1,site=lab\\,\\ bench\\=2 state=\"OL\" 0",
+            ]
+        );
+
+        let mea = Measurement {
+            host_ts: Some(DateTime::default() + chrono::Duration::seconds(2)),
+            ..mea
+        };
+        let lines = LineProtocol::new("f289")
+            .timestamps(TimestampSource::Host)
+            .lines(&mea);
+        assert!(lines[0].ends_with(" 2000000000"), "{}", lines[0]);
+        let lines = LineProtocol::new("f289")
+            .timestamps(TimestampSource::Both)
+            .lines(&mea);
+        assert!(lines[0].ends_with(",host_ts=2000000000i 0"), "{}", lines[0]);
+    }
+}
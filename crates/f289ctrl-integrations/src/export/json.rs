@@ -0,0 +1,170 @@
+use std::io::{Read, Write};
+
+use chrono::{DateTime, Utc};
+use f289ctrl_core::measurement::{Measurement, PrimaryFunction, State, TimestampSource, Unit};
+use f289ctrl_core::snapshot::MemorySnapshot;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Version of the JSON document layout.
+///
+/// Bumped on every incompatible change of an exported structure,
+/// see the schema files in `schema/`.
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// JSON document with an embedded `schema_version` field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Versioned<T> {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub data: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            data,
+        }
+    }
+}
+
+/// Serialize any value into a single JSON line with `schema_version` attached.
+pub fn to_line<T: Serialize>(data: &T) -> std::io::Result<String> {
+    serde_json::to_string(&Versioned::new(data)).map_err(std::io::Error::from)
+}
+
+/// Parse a versioned document, rejecting documents from a newer schema.
+pub fn from_reader<T: DeserializeOwned>(reader: impl Read) -> std::io::Result<T> {
+    let doc: Versioned<T> = serde_json::from_reader(reader).map_err(std::io::Error::from)?;
+    if doc.schema_version > SCHEMA_VERSION {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Unsupported schema version {}, expected <= {}",
+                doc.schema_version, SCHEMA_VERSION
+            ),
+        ));
+    }
+    Ok(doc.data)
+}
+
+/// Write a memory snapshot as pretty printed JSON.
+pub fn write_snapshot(writer: impl Write, snapshot: &MemorySnapshot) -> std::io::Result<()> {
+    serde_json::to_writer_pretty(writer, &Versioned::new(snapshot)).map_err(std::io::Error::from)
+}
+
+/// Read a memory snapshot previously written by [`write_snapshot`].
+pub fn read_snapshot(reader: impl Read) -> std::io::Result<MemorySnapshot> {
+    from_reader(reader)
+}
+
+/// A single live reading, flattened for line based streams.
+///
+/// `value` is in the base SI unit and `null` unless `state` is `Normal`.
+/// `host_timestamp` is only present with [`TimestampSource::Both`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingLine {
+    pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_timestamp: Option<DateTime<Utc>>,
+    pub function: PrimaryFunction,
+    pub reading_id: u16,
+    pub value: Option<f64>,
+    pub unit: Unit,
+    pub state: State,
+}
+
+impl ReadingLine {
+    /// One line per reading of the measurement.
+    pub fn from_measurement(mea: &Measurement, timestamps: TimestampSource) -> Vec<Self> {
+        let host_timestamp = match timestamps {
+            TimestampSource::Both => mea.host_ts,
+            _ => None,
+        };
+        mea.readings
+            .iter()
+            .map(|r| Self {
+                timestamp: mea.reading_ts(r, timestamps),
+                host_timestamp,
+                function: mea.pri_function,
+                reading_id: r.reading_id,
+                value: r.si_value(),
+                unit: r.unit.clone(),
+                state: r.state.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Write the readings of a measurement as newline delimited JSON.
+pub fn write_ndjson(
+    mut writer: impl Write,
+    mea: &Measurement,
+    timestamps: TimestampSource,
+) -> std::io::Result<()> {
+    for line in ReadingLine::from_measurement(mea, timestamps) {
+        serde_json::to_writer(&mut writer, &line).map_err(std::io::Error::from)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use f289ctrl_core::measurement::{AutoRange, Bolt, Modes, Reading, SecondaryFunction};
+
+    use super::*;
+
+    #[test]
+    fn test_ndjson() {
+        let reading = |reading_id: u16, value: f64, state: State| Reading {
+            reading_id,
+            value,
+            unit: Unit::VoltDC,
+            unit_multiplier: -3,
+            decimals: 3,
+            display_digits: 5,
+            state,
+            attribute: None,
+            ts: DateTime::default(),
+            device_ts: 0.0,
+            utc_offset: 0,
+        };
+        let mea = Measurement {
+            pri_function: PrimaryFunction::V_DC,
+            sec_function: SecondaryFunction::None,
+            auto_range: AutoRange(true),
+            unit: Unit::VoltDC,
+            range_max: 5.0,
+            unit_multiplier: -3,
+            bolt: Bolt(false),
+            ts: None,
+            host_ts: Some(DateTime::default() + chrono::Duration::seconds(2)),
+            modes: Modes::empty(),
+            readings: vec![reading(0, 1.25, State::Normal), reading(1, 0.0, State::OL)],
+        };
+
+        let mut out = Vec::new();
+        write_ndjson(&mut out, &mea, TimestampSource::Device).expect("ndjson");
+        let out = String::from_utf8(out).expect("utf8");
+        let lines: Vec<serde_json::Value> = out
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("json line"))
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["value"], 1.25);
+        assert_eq!(lines[0]["function"], "V_DC");
+        assert_eq!(lines[1]["reading_id"], 1);
+        assert!(lines[1]["value"].is_null());
+        assert_eq!(lines[0]["timestamp"], "1970-01-01T00:00:00Z");
+        assert!(lines[0].get("host_timestamp").is_none());
+
+        let host = ReadingLine::from_measurement(&mea, TimestampSource::Host);
+        assert_eq!(host[0].timestamp, mea.host_ts.expect("host_ts"));
+        assert_eq!(host[0].host_timestamp, None);
+        let both = ReadingLine::from_measurement(&mea, TimestampSource::Both);
+        assert_eq!(both[0].timestamp, DateTime::<Utc>::default());
+        assert_eq!(both[0].host_timestamp, mea.host_ts);
+    }
+}
@@ -0,0 +1,226 @@
+//! Apache Parquet export.
+//!
+//! Column layout follows the CSV export: values are in the base SI unit and
+//! null unless the state is `NORMAL`, timestamps are UTC with millisecond
+//! precision. Files are Snappy compressed.
+
+use std::{io::Write, sync::Arc};
+
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, TimestampMillisecondArray,
+    UInt16Array, UInt32Array,
+};
+use arrow_schema::{DataType, Field, Schema};
+use f289ctrl_core::measurement::{Measurement, Reading, SessionRecordReadings};
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+
+use super::state_name;
+
+/// Write a live measurement series, one row per reading.
+pub fn write_series(
+    writer: impl Write + Send,
+    measurements: &[Measurement],
+) -> std::io::Result<()> {
+    let rows: Vec<(&Measurement, &Reading)> = measurements
+        .iter()
+        .flat_map(|m| m.readings.iter().map(move |r| (m, r)))
+        .collect();
+    let columns: Vec<(&str, ArrayRef)> = vec![
+        ("timestamp", timestamps(rows.iter().map(|(_, r)| *r))),
+        (
+            "function",
+            strings(rows.iter().map(|(m, _)| format!("{:?}", m.pri_function))),
+        ),
+        (
+            "reading_id",
+            Arc::new(UInt16Array::from_iter_values(
+                rows.iter().map(|(_, r)| r.reading_id),
+            )),
+        ),
+        ("value", values(rows.iter().map(|(_, r)| *r))),
+        (
+            "unit",
+            strings(rows.iter().map(|(_, r)| r.unit.to_string())),
+        ),
+        (
+            "state",
+            strings(rows.iter().map(|(_, r)| state_name(&r.state).to_string())),
+        ),
+    ];
+    write_batch(writer, columns)
+}
+
+/// Write downloaded recordings, one row per interval.
+///
+/// `timestamp` is the interval start, `value` and `state` the interval
+/// average, `min`/`max` the extremes in the same unit.
+pub fn write_recordings<'a>(
+    writer: impl Write + Send,
+    recordings: impl IntoIterator<Item = (&'a str, &'a [SessionRecordReadings])>,
+) -> std::io::Result<()> {
+    let rows: Vec<(&str, &SessionRecordReadings, Reading)> = recordings
+        .into_iter()
+        .flat_map(|(name, records)| records.iter().map(move |rec| (name, rec)))
+        .map(|(name, rec)| (name, rec, rec.average_reading()))
+        .collect();
+    let columns: Vec<(&str, ArrayRef)> = vec![
+        (
+            "name",
+            strings(rows.iter().map(|(name, _, _)| name.to_string())),
+        ),
+        ("timestamp", timestamps(rows.iter().map(|(_, _, avg)| avg))),
+        ("value", values(rows.iter().map(|(_, _, avg)| avg))),
+        (
+            "unit",
+            strings(rows.iter().map(|(_, _, avg)| avg.unit.to_string())),
+        ),
+        (
+            "state",
+            strings(
+                rows.iter()
+                    .map(|(_, _, avg)| state_name(&avg.state).to_string()),
+            ),
+        ),
+        (
+            "end_timestamp",
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    rows.iter().map(|(_, rec, _)| rec.end_ts.timestamp_millis()),
+                )
+                .with_timezone("UTC"),
+            ),
+        ),
+        (
+            "min",
+            values(rows.iter().map(|(_, rec, _)| &rec.span_readings[1])),
+        ),
+        (
+            "max",
+            values(rows.iter().map(|(_, rec, _)| &rec.span_readings[0])),
+        ),
+        (
+            "samples",
+            Arc::new(UInt32Array::from_iter_values(
+                rows.iter().map(|(_, rec, _)| u32::from(rec.sampling)),
+            )),
+        ),
+        (
+            "stable",
+            Arc::new(BooleanArray::from_iter(
+                rows.iter().map(|(_, rec, _)| Some(rec.stable.0)),
+            )),
+        ),
+    ];
+    write_batch(writer, columns)
+}
+
+fn timestamps<'a>(readings: impl Iterator<Item = &'a Reading>) -> ArrayRef {
+    Arc::new(
+        TimestampMillisecondArray::from_iter_values(readings.map(|r| r.ts.timestamp_millis()))
+            .with_timezone("UTC"),
+    )
+}
+
+fn values<'a>(readings: impl Iterator<Item = &'a Reading>) -> ArrayRef {
+    Arc::new(Float64Array::from_iter(readings.map(Reading::si_value)))
+}
+
+fn strings(values: impl Iterator<Item = String>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values))
+}
+
+fn write_batch(writer: impl Write + Send, columns: Vec<(&str, ArrayRef)>) -> std::io::Result<()> {
+    let fields: Vec<Field> = columns
+        .iter()
+        .map(|(name, array)| {
+            let nullable = matches!(array.data_type(), DataType::Float64);
+            Field::new(*name, array.data_type().clone(), nullable)
+        })
+        .collect();
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        columns.into_iter().map(|(_, array)| array).collect(),
+    )
+    .map_err(to_io)?;
+
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(writer, schema, Some(props)).map_err(to_io)?;
+    writer.write(&batch).map_err(to_io)?;
+    writer.close().map_err(to_io)?;
+    Ok(())
+}
+
+fn to_io(err: impl std::error::Error + Send + Sync + 'static) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Float64Type;
+    use arrow_array::Array;
+    use f289ctrl_core::measurement::{RecordType, Stable, State, TransientState};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+    use f289ctrl_core::fixture::reading;
+
+    #[test]
+    fn test_recordings() {
+        let ol = reading(0.0, State::OL);
+        let records = [
+            SessionRecordReadings {
+                start_ts: Default::default(),
+                end_ts: Default::default(),
+                span_readings: [
+                    reading(0.5, State::Normal),
+                    reading(0.25, State::Normal),
+                    reading(1.5, State::Normal),
+                ],
+                sampling: 4,
+                fixed_reading: reading(0.5, State::Normal),
+                record_type: RecordType::Interval,
+                stable: Stable(true),
+                transient_state: TransientState::NonT,
+            },
+            SessionRecordReadings {
+                start_ts: Default::default(),
+                end_ts: Default::default(),
+                span_readings: [ol.clone(), ol.clone(), ol.clone()],
+                sampling: 1,
+                fixed_reading: ol,
+                record_type: RecordType::Interval,
+                stable: Stable(false),
+                transient_state: TransientState::Overload,
+            },
+        ];
+
+        let mut out = Vec::new();
+        write_recordings(&mut out, [("Rec 1", &records[..])]).expect("parquet");
+
+        let batches: Vec<RecordBatch> =
+            ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(out))
+                .expect("reader")
+                .build()
+                .expect("reader")
+                .collect::<Result<_, _>>()
+                .expect("batches");
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        let value = batch
+            .column_by_name("value")
+            .expect("value")
+            .as_primitive::<Float64Type>();
+        assert_eq!(value.value(0), 0.375);
+        assert!(value.is_null(1));
+        let state = batch
+            .column_by_name("state")
+            .expect("state")
+            .as_string::<i32>();
+        assert_eq!(state.value(1), "OL");
+    }
+}
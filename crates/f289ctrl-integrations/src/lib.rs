@@ -0,0 +1,36 @@
+//!
+//! Export formats and service integrations for f289ctrl.
+//!
+//! Every integration is gated behind its own cargo feature, so library users
+//! only compile the dependencies they actually need.
+//!
+//! # Features
+//!
+//!  * `csv` - CSV export of measurement series, saved measurements and recordings
+//!  * `json` - JSON export with an embedded `schema_version`, NDJSON reading streams
+//!  * `influx` - InfluxDB line protocol, optionally written over HTTP
+//!  * `mqtt` - Publish live measurements to an MQTT broker, `mqtt-tls` adds TLS
+//!  * `parquet` - Apache Parquet export of measurement series and recordings
+//!  * `plot` - SVG charts of recordings
+//!  * `schema` - JSON Schema of all JSON exports
+//!  * `alerts` - Alert rules with webhook, MQTT and syslog actions
+//!  * `profiles` - Named device profiles of the `f289cmd` configuration file
+//!
+//! FlukeView Forms (`.fvf`) files are not supported. The format is
+//! undocumented, so a reader or writer could not be verified against the
+//! vendor tool; exchange data with it through the CSV export instead.
+//!
+
+#[cfg(feature = "alerts")]
+pub mod alert;
+pub mod export;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+#[cfg(any(feature = "alerts", feature = "influx", feature = "mqtt"))]
+mod net;
+#[cfg(feature = "plot")]
+pub mod plot;
+#[cfg(feature = "profiles")]
+pub mod profile;
+#[cfg(feature = "schema")]
+pub mod schema;
@@ -0,0 +1,11 @@
+//! Minimal clients for the network protocols used by integrations.
+//!
+//! Only the small subset of each protocol needed to deliver a message is
+//! implemented, so no heavy client libraries are pulled in.
+
+#[cfg(any(feature = "alerts", feature = "influx"))]
+pub(crate) mod http;
+#[cfg(any(feature = "alerts", feature = "mqtt"))]
+pub(crate) mod mqtt;
+#[cfg(feature = "alerts")]
+pub(crate) mod syslog;
@@ -0,0 +1,301 @@
+use std::io;
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+};
+
+const KEEP_ALIVE_SECS: u16 = 60;
+
+/// Wait for a PUBACK before the publish is sent again with the DUP flag.
+#[cfg(feature = "mqtt")]
+const ACK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Sends of a QoS 1 publish before giving up.
+#[cfg(feature = "mqtt")]
+const PUBLISH_ATTEMPTS: usize = 3;
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// Connection parameters beyond the broker address.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Options<'a> {
+    pub client_id: &'a str,
+    pub username: Option<&'a str>,
+    pub password: Option<&'a str>,
+    /// Connect over TLS, requires the `mqtt-tls` feature
+    pub tls: bool,
+}
+
+/// MQTT 3.1.1 connection, supporting QoS 0 and 1 publishes.
+pub(crate) struct Connection {
+    stream: Box<dyn Stream>,
+    #[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+    packet_id: u16,
+    /// Received bytes of an incomplete packet
+    #[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+    rx: Vec<u8>,
+}
+
+impl Connection {
+    pub async fn connect(host: &str, port: u16, options: &Options<'_>) -> io::Result<Self> {
+        let tcp = TcpStream::connect((host, port)).await?;
+        let mut stream: Box<dyn Stream> = if options.tls {
+            tls(host, tcp).await?
+        } else {
+            Box::new(tcp)
+        };
+
+        let mut flags = 0x02; // clean session
+        if options.username.is_some() {
+            flags |= 0x80;
+        }
+        if options.password.is_some() {
+            flags |= 0x40;
+        }
+        let mut body = Vec::new();
+        put_str(&mut body, "MQTT");
+        body.push(4); // protocol level 3.1.1
+        body.push(flags);
+        body.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+        put_str(&mut body, options.client_id);
+        for field in [options.username, options.password].iter().flatten() {
+            put_str(&mut body, field);
+        }
+        stream.write_all(&packet(0x10, &body)).await?;
+
+        let mut connack = [0u8; 4];
+        stream.read_exact(&mut connack).await?;
+        if connack[0] != 0x20 || connack[3] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("MQTT connection refused, return code {}", connack[3]),
+            ));
+        }
+        Ok(Self {
+            stream,
+            packet_id: 0,
+            rx: Vec::new(),
+        })
+    }
+
+    /// Publish with QoS 0.
+    pub async fn publish(&mut self, topic: &str, payload: &[u8], retain: bool) -> io::Result<()> {
+        let mut body = Vec::new();
+        put_str(&mut body, topic);
+        body.extend_from_slice(payload);
+        let flags = if retain { 0x01 } else { 0x00 };
+        self.stream.write_all(&packet(0x30 | flags, &body)).await
+    }
+
+    /// Publish with QoS 1 and wait for the broker's acknowledgement.
+    ///
+    /// Sent again with the DUP flag if no PUBACK arrives in time.
+    #[cfg(feature = "mqtt")]
+    pub async fn publish_acked(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        retain: bool,
+    ) -> io::Result<()> {
+        self.packet_id = self.packet_id.wrapping_add(1).max(1);
+        let mut body = Vec::new();
+        put_str(&mut body, topic);
+        body.extend_from_slice(&self.packet_id.to_be_bytes());
+        body.extend_from_slice(payload);
+        let flags = if retain { 0x03 } else { 0x02 };
+
+        for attempt in 0..PUBLISH_ATTEMPTS {
+            let dup = if attempt > 0 { 0x08 } else { 0x00 };
+            self.stream
+                .write_all(&packet(0x30 | dup | flags, &body))
+                .await?;
+            match tokio::time::timeout(ACK_TIMEOUT, self.wait_puback()).await {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(err)) => return Err(err),
+                Err(_elapsed) => continue,
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("No MQTT PUBACK for packet {}", self.packet_id),
+        ))
+    }
+
+    /// Wait for the PUBACK of the current packet id, skipping other packets
+    /// such as acknowledgements of earlier attempts.
+    #[cfg(feature = "mqtt")]
+    async fn wait_puback(&mut self) -> io::Result<()> {
+        loop {
+            let (header, body) = self.read_packet().await?;
+            if header & 0xF0 == 0x40 && body[..] == self.packet_id.to_be_bytes() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Read the next packet, returns the first header byte and the body.
+    ///
+    /// Partial packets stay buffered, so waiting can be cancelled.
+    #[cfg(feature = "mqtt")]
+    async fn read_packet(&mut self) -> io::Result<(u8, Vec<u8>)> {
+        loop {
+            if let Some((header, len, total)) = parse_header(&self.rx)? {
+                if self.rx.len() >= total {
+                    let body = self.rx[total - len..total].to_vec();
+                    self.rx.drain(..total);
+                    return Ok((header, body));
+                }
+            }
+            let mut buf = [0u8; 256];
+            let read = self.stream.read(&mut buf).await?;
+            if read == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            self.rx.extend_from_slice(&buf[..read]);
+        }
+    }
+
+    pub async fn disconnect(mut self) -> io::Result<()> {
+        self.stream.write_all(&[0xE0, 0x00]).await
+    }
+}
+
+#[cfg(feature = "mqtt-tls")]
+async fn tls(host: &str, tcp: TcpStream) -> io::Result<Box<dyn Stream>> {
+    use tokio_native_tls::native_tls;
+    let tls_err = |e: native_tls::Error| io::Error::new(io::ErrorKind::Other, e);
+    let connector = native_tls::TlsConnector::new().map_err(tls_err)?;
+    let stream = tokio_native_tls::TlsConnector::from(connector)
+        .connect(host, tcp)
+        .await
+        .map_err(tls_err)?;
+    Ok(Box::new(stream))
+}
+
+#[cfg(not(feature = "mqtt-tls"))]
+async fn tls(_host: &str, _tcp: TcpStream) -> io::Result<Box<dyn Stream>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "MQTT over TLS requires the mqtt-tls feature",
+    ))
+}
+
+fn put_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+/// Decode a fixed header, returns the first byte, the body length and the
+/// packet length. `None` if the header is incomplete.
+#[cfg(feature = "mqtt")]
+fn parse_header(buf: &[u8]) -> io::Result<Option<(u8, usize, usize)>> {
+    let mut len = 0;
+    for (i, byte) in buf.iter().skip(1).take(4).enumerate() {
+        len |= ((byte & 0x7F) as usize) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(Some((buf[0], len, 2 + i + len)));
+        }
+    }
+    if buf.len() > 5 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Invalid MQTT remaining length",
+        ));
+    }
+    Ok(None)
+}
+
+/// Fixed header with variable length encoding followed by `body`.
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut buf = vec![header];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        buf.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    buf.extend_from_slice(body);
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remaining_length() {
+        assert_eq!(packet(0x30, &[0; 2])[..2], [0x30, 2]);
+        assert_eq!(packet(0x30, &[0; 321])[..3], [0x30, 0xC1, 0x02]);
+    }
+
+    #[cfg(feature = "mqtt")]
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(parse_header(&[]).expect("header"), None);
+        assert_eq!(parse_header(&[0x40]).expect("header"), None);
+        assert_eq!(
+            parse_header(&[0x40, 0x02, 0x00]).expect("header"),
+            Some((0x40, 2, 4))
+        );
+        assert_eq!(
+            parse_header(&packet(0x30, &[0; 321])).expect("header"),
+            Some((0x30, 321, 324))
+        );
+        assert!(parse_header(&[0x30, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]).is_err());
+    }
+
+    #[cfg(feature = "mqtt")]
+    #[tokio::test(start_paused = true)]
+    async fn test_publish_acked_resend() {
+        let (client, mut broker) = tokio::io::duplex(1024);
+        let mut conn = Connection {
+            stream: Box::new(client),
+            packet_id: 0,
+            rx: Vec::new(),
+        };
+        let publish = tokio::spawn(async move { conn.publish_acked("t", b"x", false).await });
+
+        // First send is not acknowledged
+        let mut buf = [0u8; 8];
+        broker.read_exact(&mut buf).await.expect("publish");
+        assert_eq!(buf, [0x32, 6, 0, 1, b't', 0, 1, b'x']);
+        broker.read_exact(&mut buf).await.expect("resend");
+        assert_eq!(buf, [0x3A, 6, 0, 1, b't', 0, 1, b'x']);
+
+        // PINGRESP and a PUBACK of another packet are skipped, the PUBACK
+        // arrives in two parts
+        broker
+            .write_all(&[0xD0, 0x00, 0x40, 0x02, 0x00, 0x07, 0x40])
+            .await
+            .expect("write");
+        broker.write_all(&[0x02, 0x00, 0x01]).await.expect("write");
+        publish.await.expect("task").expect("acknowledged");
+    }
+
+    #[cfg(feature = "mqtt")]
+    #[tokio::test(start_paused = true)]
+    async fn test_publish_acked_closed() {
+        let (client, mut broker) = tokio::io::duplex(1024);
+        let mut conn = Connection {
+            stream: Box::new(client),
+            packet_id: 0,
+            rx: Vec::new(),
+        };
+        let publish = tokio::spawn(async move { conn.publish_acked("t", b"x", false).await });
+
+        let mut buf = [0u8; 8];
+        broker.read_exact(&mut buf).await.expect("publish");
+        drop(broker);
+        let err = publish.await.expect("task").expect_err("not acknowledged");
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
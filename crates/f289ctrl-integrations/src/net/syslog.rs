@@ -0,0 +1,28 @@
+use std::io;
+
+/// Facility `user`, severity `warning`
+const PRIORITY: u8 = 8 + 4;
+
+/// Send a message to syslog, either over UDP to `address` or to the local
+/// `/dev/log` socket.
+pub(crate) async fn send(address: Option<&str>, tag: &str, message: &str) -> io::Result<()> {
+    let line = format!("<{}>{}: {}", PRIORITY, tag, message);
+    match address {
+        Some(address) => {
+            let socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+            socket.send_to(line.as_bytes(), address).await?;
+            Ok(())
+        }
+        #[cfg(unix)]
+        None => {
+            let socket = tokio::net::UnixDatagram::unbound()?;
+            socket.send_to(line.as_bytes(), "/dev/log").await?;
+            Ok(())
+        }
+        #[cfg(not(unix))]
+        None => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Local syslog requires a unix system, configure an address",
+        )),
+    }
+}
@@ -0,0 +1,197 @@
+//! SVG charts of downloaded recordings.
+//!
+//! Every recording gets its own panel, stacked vertically. The interval
+//! average is drawn as line over the interval start time, the range between
+//! interval minimum and maximum as shaded band. Intervals without a valid
+//! value (e.g. `OL`) leave a gap.
+
+use std::io::{self, Write};
+
+use chrono::{DateTime, Duration, Utc};
+use f289ctrl_core::measurement::SessionRecordReadings;
+use plotters::prelude::*;
+
+const PANEL_WIDTH: u32 = 1024;
+const PANEL_HEIGHT: u32 = 360;
+
+/// One interval in chart coordinates, `x` in seconds since recording start.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Point {
+    x: f64,
+    avg: f64,
+    min: f64,
+    max: f64,
+}
+
+/// Render recordings as SVG document into `writer`.
+pub fn write_recordings<'a>(
+    mut writer: impl Write,
+    recordings: impl IntoIterator<Item = (&'a str, &'a [SessionRecordReadings])>,
+) -> io::Result<()> {
+    let recordings: Vec<(&str, &[SessionRecordReadings])> = recordings.into_iter().collect();
+    let height = PANEL_HEIGHT * recordings.len().max(1) as u32;
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, (PANEL_WIDTH, height)).into_drawing_area();
+        root.fill(&WHITE).map_err(to_io)?;
+        let panels = root.split_evenly((recordings.len().max(1), 1));
+        for ((name, records), panel) in recordings.iter().zip(panels.iter()) {
+            draw_recording(panel, name, records).map_err(to_io)?;
+        }
+        root.present().map_err(to_io)?;
+    }
+    writer.write_all(svg.as_bytes())?;
+    writer.flush()
+}
+
+fn draw_recording<DB: DrawingBackend>(
+    area: &DrawingArea<DB, plotters::coord::Shift>,
+    name: &str,
+    records: &[SessionRecordReadings],
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let start = records.first().map(|r| r.start_ts).unwrap_or_default();
+    let segments = segments(start, records);
+
+    let x_end = records
+        .last()
+        .map(|r| seconds(start, r.end_ts))
+        .unwrap_or_default()
+        .max(1.0);
+    let (y_min, y_max) = segments
+        .iter()
+        .flatten()
+        .fold(None, |range: Option<(f64, f64)>, p| match range {
+            Some((lo, hi)) => Some((lo.min(p.min), hi.max(p.max))),
+            None => Some((p.min, p.max)),
+        })
+        .unwrap_or((0.0, 1.0));
+    let pad = if y_max > y_min {
+        (y_max - y_min) * 0.05
+    } else {
+        y_max.abs().max(1.0) * 0.05
+    };
+    let unit = records
+        .first()
+        .map(|r| r.span_readings[2].unit.to_string())
+        .unwrap_or_default();
+
+    let mut chart = ChartBuilder::on(area)
+        .caption(format!("{} [{}]", name, unit), ("sans-serif", 18))
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(70)
+        .build_cartesian_2d(0.0..x_end, (y_min - pad)..(y_max + pad))?;
+    chart
+        .configure_mesh()
+        .x_labels(8)
+        .x_label_formatter(&|x| {
+            (start + Duration::milliseconds((*x * 1000.0) as i64))
+                .format("%H:%M:%S")
+                .to_string()
+        })
+        .y_desc(unit.as_str())
+        .draw()?;
+
+    let band = BLUE.mix(0.2);
+    for segment in &segments {
+        let outline: Vec<(f64, f64)> = segment
+            .iter()
+            .map(|p| (p.x, p.max))
+            .chain(segment.iter().rev().map(|p| (p.x, p.min)))
+            .collect();
+        chart.draw_series(std::iter::once(Polygon::new(outline, band.filled())))?;
+        chart.draw_series(LineSeries::new(segment.iter().map(|p| (p.x, p.avg)), &BLUE))?;
+    }
+    Ok(())
+}
+
+/// Split the intervals into runs of valid values.
+fn segments(start: DateTime<Utc>, records: &[SessionRecordReadings]) -> Vec<Vec<Point>> {
+    let mut segments = vec![Vec::new()];
+    for rec in records {
+        let point = match (
+            rec.average_reading().si_value(),
+            rec.span_readings[1].si_value(),
+            rec.span_readings[0].si_value(),
+        ) {
+            (Some(avg), Some(min), Some(max)) => Some(Point {
+                x: seconds(start, rec.start_ts),
+                avg,
+                min,
+                max,
+            }),
+            _ => None,
+        };
+        match point {
+            Some(point) => segments.last_mut().expect("segment").push(point),
+            None if segments.last().map_or(false, |s| !s.is_empty()) => segments.push(Vec::new()),
+            None => {}
+        }
+    }
+    segments.retain(|s| !s.is_empty());
+    segments
+}
+
+fn seconds(start: DateTime<Utc>, ts: DateTime<Utc>) -> f64 {
+    (ts - start).num_milliseconds() as f64 / 1000.0
+}
+
+fn to_io(err: impl std::error::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use f289ctrl_core::measurement::{RecordType, Stable, State, TransientState};
+
+    use super::*;
+    use f289ctrl_core::fixture::reading;
+
+    fn record(secs: i64, min: f64, max: f64, sum: f64, state: State) -> SessionRecordReadings {
+        let start_ts = DateTime::<Utc>::default() + Duration::seconds(secs);
+        SessionRecordReadings {
+            start_ts,
+            end_ts: start_ts + Duration::seconds(1),
+            span_readings: [
+                reading(max, state.clone()),
+                reading(min, state.clone()),
+                reading(sum, state.clone()),
+            ],
+            sampling: 2,
+            fixed_reading: reading(sum / 2.0, state),
+            record_type: RecordType::Interval,
+            stable: Stable(true),
+            transient_state: TransientState::NonT,
+        }
+    }
+
+    #[test]
+    fn test_plot() {
+        let records = [
+            record(0, 1.0, 2.0, 3.0, State::Normal),
+            record(1, 1.0, 3.0, 4.0, State::Normal),
+            record(2, 0.0, 0.0, 0.0, State::OL),
+            record(3, 2.0, 2.0, 4.0, State::Normal),
+        ];
+
+        let segments = segments(records[0].start_ts, &records);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(
+            segments[0][1],
+            Point {
+                x: 1.0,
+                avg: 2.0,
+                min: 1.0,
+                max: 3.0
+            }
+        );
+        assert_eq!(segments[1][0].x, 3.0);
+
+        let mut out = Vec::new();
+        write_recordings(&mut out, [("Rec 1", &records[..]), ("Empty", &[][..])]).expect("plot");
+        let svg = String::from_utf8(out).expect("utf8");
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("Rec 1 [VDC]"));
+        assert!(svg.contains("<polyline"));
+    }
+}
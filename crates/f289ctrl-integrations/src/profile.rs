@@ -0,0 +1,147 @@
+//! Named device profiles of the `f289cmd` configuration file.
+//!
+//! ```toml
+//! # Used if no profile is selected on the command line
+//! default_profile = "lab-bench-2"
+//!
+//! [profile.lab-bench-2]
+//! device = "/dev/ttyUSB1"
+//! baudrate = 115200
+//! timezone = "+01:00"
+//! operator = "J. Doe"
+//! company = "ACME"
+//!
+//! [profile.field]
+//! device = "tcp://192.168.1.20:4001"
+//! ```
+//!
+//! All fields of a profile are optional, command line options take
+//! precedence.
+
+use std::{
+    collections::BTreeMap,
+    env, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+/// Contents of the configuration file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub default_profile: Option<String>,
+    #[serde(default, rename = "profile")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Connection settings and owner defaults for one meter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Port or transport URL
+    pub device: Option<String>,
+    pub baudrate: Option<u32>,
+    /// UTC offset of the meter clock, e.g. `+01:00`
+    pub timezone: Option<String>,
+    pub operator: Option<String>,
+    pub company: Option<String>,
+    pub site: Option<String>,
+    pub contact: Option<String>,
+}
+
+impl Config {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        toml::from_str(text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Profile `name`, or the default profile if `name` is `None`.
+    ///
+    /// Fails if the profile is not defined, the message lists all defined
+    /// profiles.
+    pub fn profile(&self, name: Option<&str>) -> io::Result<Option<&Profile>> {
+        let name = match name.or(self.default_profile.as_deref()) {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        match self.profiles.get(name) {
+            Some(profile) => Ok(Some(profile)),
+            None => {
+                let defined: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "Unknown profile '{}', defined profiles: {}",
+                        name,
+                        if defined.is_empty() {
+                            String::from("none")
+                        } else {
+                            defined.join(", ")
+                        }
+                    ),
+                ))
+            }
+        }
+    }
+}
+
+/// `$F289CTRL_CONFIG`, otherwise `f289ctrl/config.toml` in the user
+/// configuration directory.
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("F289CTRL_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+    };
+    dir.map(|dir| dir.join("f289ctrl").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let config = Config::parse(
+            r#"
+            default_profile = "bench"
+
+            [profile.bench]
+            device = "/dev/ttyUSB1"
+            baudrate = 9600
+            timezone = "+01:00"
+
+            [profile.field]
+            operator = "J. Doe"
+            "#,
+        )
+        .expect("config");
+
+        let bench = config.profile(None).expect("profile").expect("default");
+        assert_eq!(bench.device.as_deref(), Some("/dev/ttyUSB1"));
+        assert_eq!(bench.baudrate, Some(9600));
+        let field = config.profile(Some("field")).expect("profile");
+        assert_eq!(field.and_then(|p| p.operator.as_deref()), Some("J. Doe"));
+
+        let err = config.profile(Some("lab")).expect_err("unknown profile");
+        assert_eq!(
+            err.to_string(),
+            "Unknown profile 'lab', defined profiles: bench, field"
+        );
+        assert!(Config::default()
+            .profile(None)
+            .expect("no profile")
+            .is_none());
+        assert!(Config::parse("[profile.x]\nport = \"COM3\"").is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use f289ctrl_core::{measurement::Measurement, snapshot::MemorySnapshot};
+use schemars::{schema::RootSchema, schema_for};
+
+use crate::export::json::Versioned;
+
+/// JSON Schema of the document written by [`crate::export::json::write_snapshot`].
+pub fn snapshot_schema() -> RootSchema {
+    schema_for!(Versioned<MemorySnapshot>)
+}
+
+/// JSON Schema of a single live measurement document.
+pub fn measurement_schema() -> RootSchema {
+    schema_for!(Versioned<Measurement>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::json::SCHEMA_VERSION;
+
+    // If one of these tests fails, an exported structure was changed.
+    // Bump SCHEMA_VERSION, add the new schema file and keep the old one.
+    fn assert_published(schema: RootSchema, published: &str) {
+        let generated = serde_json::to_value(schema).expect("Schema serializes");
+        let published: serde_json::Value =
+            serde_json::from_str(published).expect("Published schema is valid JSON");
+        assert_eq!(generated, published);
+    }
+
+    #[test]
+    fn snapshot_schema_is_published() {
+        assert_eq!(SCHEMA_VERSION, 3);
+        assert_published(
+            snapshot_schema(),
+            include_str!("../schema/memory-snapshot.v3.json"),
+        );
+    }
+
+    #[test]
+    fn measurement_schema_is_published() {
+        assert_eq!(SCHEMA_VERSION, 3);
+        assert_published(
+            measurement_schema(),
+            include_str!("../schema/measurement.v3.json"),
+        );
+    }
+}
@@ -12,7 +12,7 @@ async fn main() -> f289ctrl::Result<()> {
         let raw = device.live_measurement().await?;
         match raw {
             Some(data) => {
-                let mea = Measurement::from((data, &maps));
+                let mea = Measurement::try_from((data, &maps))?;
                 // Each measurement contains one or more readings.
                 mea.readings.iter().for_each(|r| {
                     println!("Value: {}", r);
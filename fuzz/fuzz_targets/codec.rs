@@ -0,0 +1,36 @@
+//! Codec state machine: pending commands answered by arbitrary chunks of data.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use bytes::BytesMut;
+use f289ctrl_core::proto::{codec::ProtocolCodec, command::Command};
+use libfuzzer_sys::fuzz_target;
+use tokio_util::codec::{Decoder, Encoder};
+
+#[derive(Debug, Arbitrary)]
+struct Session {
+    commands: Vec<Command>,
+    chunks: Vec<Vec<u8>>,
+}
+
+fuzz_target!(|session: Session| {
+    let mut codec = ProtocolCodec::default();
+    let mut tx = BytesMut::new();
+    for command in session.commands {
+        let _ = codec.encode(command, &mut tx);
+    }
+
+    let mut rx = BytesMut::new();
+    for chunk in session.chunks {
+        rx.extend_from_slice(&chunk);
+        loop {
+            match codec.decode(&mut rx) {
+                Ok(Some(_)) => continue,
+                Ok(None) => break,
+                Err(_) => return,
+            }
+        }
+    }
+    let _ = codec.decode_eof(&mut rx);
+});
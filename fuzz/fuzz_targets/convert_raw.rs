@@ -0,0 +1,51 @@
+//! Conversion of arbitrary raw measurements with arbitrary value maps.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use f289ctrl_core::{
+    device::ValueMaps,
+    measurement::{
+        Measurement, SavedMeasurement, SavedMinMaxMeasurement, SavedRecordingSessionInfo,
+        SessionRecordReadings,
+    },
+    rawmea::{
+        RawMeasurement, RawSavedMeasurement, RawSavedMinMaxMeasurement,
+        RawSavedRecordingSessionInfo, RawSessionRecordReadings,
+    },
+};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+enum Raw {
+    Measurement(RawMeasurement),
+    Saved(RawSavedMeasurement),
+    MinMax(RawSavedMinMaxMeasurement),
+    Recording(RawSavedRecordingSessionInfo),
+    RecordReadings(RawSessionRecordReadings),
+}
+
+fuzz_target!(|input: (Raw, ValueMaps)| {
+    let (raw, maps) = input;
+    match raw {
+        Raw::Measurement(raw) => {
+            if let Ok(measurement) = Measurement::try_from((raw, &maps)) {
+                for reading in &measurement.readings {
+                    let _ = reading.to_string();
+                }
+            }
+        }
+        Raw::Saved(raw) => {
+            let _ = SavedMeasurement::try_from((raw, &maps));
+        }
+        Raw::MinMax(raw) => {
+            let _ = SavedMinMaxMeasurement::try_from((raw, &maps));
+        }
+        Raw::Recording(raw) => {
+            let _ = SavedRecordingSessionInfo::try_from((raw, &maps));
+        }
+        Raw::RecordReadings(raw) => {
+            let _ = SessionRecordReadings::try_from((raw, &maps));
+        }
+    }
+});
@@ -0,0 +1,39 @@
+//! Binary parsers fed with arbitrary device data.
+
+#![no_main]
+
+use f289ctrl_core::rawmea::{
+    RawMeasurement, RawReading, RawSavedMeasurement, RawSavedMinMaxMeasurement,
+    RawSavedRecordingSessionInfo, RawSessionRecordReadings,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((kind, data)) = data.split_first() else {
+        return;
+    };
+    match kind % 6 {
+        0 => {
+            let _ = RawMeasurement::try_from(data);
+        }
+        1 => {
+            let _ = RawReading::try_from(data);
+        }
+        2 => {
+            if let Ok(Some(len)) = RawSavedMeasurement::can_parse(data) {
+                let _ = RawSavedMeasurement::try_from(&data[..len]);
+            }
+        }
+        3 => {
+            if let Ok(Some(len)) = RawSavedMinMaxMeasurement::can_parse(data) {
+                let _ = RawSavedMinMaxMeasurement::try_from(&data[..len]);
+            }
+        }
+        4 => {
+            let _ = RawSavedRecordingSessionInfo::try_from(data);
+        }
+        _ => {
+            let _ = RawSessionRecordReadings::try_from(data);
+        }
+    }
+});
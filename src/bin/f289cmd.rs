@@ -1,199 +1,48 @@
 #![deny(clippy::unwrap_used)]
 
-use chrono::{DateTime, Local};
+use chrono::{DateTime, FixedOffset, Local, TimeZone, Utc};
 use clap::builder::BoolishValueParser;
 use clap::{arg, command, value_parser};
-use f289ctrl::device::ValueMaps;
-use f289ctrl::measurement::Reading;
+use f289ctrl::device::{Backend, ValueMaps, MEASUREMENT_MAP_KEYS, VALUE_MAP_KEYS};
+use f289ctrl::measurement::{FormattedReading, Reading, ReadingFormat};
 use f289ctrl::proto::command::{
-    ClearMemory, DateFormat, DezibelReference, DigitCount, Language, NumericFormat, TimeFormat,
+    validate_save_name, ClearMemory, Command, DateFormat, DezibelReference, DigitCount, Language,
+    NumericFormat, TimeFormat,
 };
 use f289ctrl::{proto, DEFAULT_BAUDRATE, DEFAULT_TTY};
+use std::collections::BTreeMap;
 use std::io::{ErrorKind, Write};
 use std::process::exit;
 use std::{env, path::PathBuf, str, time::Duration};
 
+use f289ctrl::analysis::{self, EventOptions, RecordingEvent};
+use f289ctrl::capture::{Capture, Trigger};
 use f289ctrl::device::Device;
 use f289ctrl::measurement::{
-    Measurement, Memory, Mode, PrimaryFunction, SavedMeasurement, SavedMinMaxMeasurement,
-    SavedRecordingSessionInfo, SecondaryFunction, SessionRecordReadings,
+    Measurement, Memory, MemoryEntry, MemoryKind, MemoryLookup, Mode, PrimaryFunction,
+    ReadingValue, SavedMeasurement, SavedMinMaxMeasurement, SavedRecordingSessionInfo,
+    SecondaryFunction, SessionRecordReadings, State, TimestampSource,
 };
-use f289ctrl::proto::conv::pretty_ts;
+use f289ctrl::probe::ProbeOutcome;
+use f289ctrl::progress::Progress;
+use f289ctrl::proto::conv::{prefix_multiplier, pretty_duration, pretty_ts};
+use f289ctrl::proto::observer::ProtocolObserver;
+use f289ctrl::proto::response::MemoryStat;
 use f289ctrl::proto::Result;
+use f289ctrl::retry::{Backoff, LockedRecovery};
+use f289ctrl::stats;
+use f289ctrl::transport::{self, LineControl};
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
 
 #[tokio::main]
 async fn main() -> tokio_serial::Result<()> {
-    let matches =
-        command!() // requires `cargo` feature
-            .arg(
-                arg!(
-                    -p --device <PORT> "Port for USB adapter"
-                )
-                .default_value(DEFAULT_TTY)
-                .required(false)
-                .value_parser(value_parser!(PathBuf)),
-            )
-            .arg(arg!(
-                -d --debug ... "Turn debugging information on"
-            ))
-            .arg(
-                arg!(
-                    -b --baudrate <BAUDRATE> "Baudrate"
-                )
-                .default_value(DEFAULT_BAUDRATE.to_string())
-                .value_parser(value_parser!(u32)),
-            )
-            .subcommand(
-                clap::Command::new("backlight")
-                    .about("Auto Backlight Timeout")
-                    .arg(
-                        arg!([minutes] "Set time in minutes for auto backlight timeout")
-                            .value_parser(["5", "10", "15", "20", "25", "30", "off"]),
-                    ),
-            )
-            .subcommand(
-                clap::Command::new("poweroff").about("Auto Power Off").arg(
-                    arg!([minutes] "Set time in minutes for auto power off")
-                        .value_parser(["15", "25", "35", "45", "60", "off"]),
-                ),
-            )
-            .subcommand(clap::Command::new("reset-device").about("Reset device"))
-            .subcommand(
-                clap::Command::new("custom-dBm")
-                    .about("Custom dBm reference in Ohm")
-                    .arg(arg!([reference] "Set custom reference").value_parser(value_parser!(u16))),
-            )
-            .subcommand(
-                clap::Command::new("temp-offset")
-                    .about("Temperature offset")
-                    .arg(arg!([offset] "Set custom offset").value_parser(value_parser!(i16))),
-            )
-            .subcommand(clap::Command::new("digits").about("Digit count").arg(
-                arg!([digits] "Set display digit count").value_parser(value_parser!(DigitCount)),
-            ))
-            .subcommand(
-                clap::Command::new("language")
-                    .about("Multimeter language")
-                    .arg(arg!([language] "Set language").value_parser(value_parser!(Language))),
-            )
-            .subcommand(
-                clap::Command::new("date-format")
-                    .about("Date format")
-                    .arg(arg!([fmt] "Set format").value_parser(value_parser!(DateFormat))),
-            )
-            .subcommand(
-                clap::Command::new("time-format")
-                    .about("Time format")
-                    .arg(arg!([fmt] "Set format").value_parser(value_parser!(TimeFormat))),
-            )
-            .subcommand(
-                clap::Command::new("numeric-format")
-                    .about("Numeric format")
-                    .arg(arg!([fmt] "Set format").value_parser(value_parser!(NumericFormat))),
-            )
-            .subcommand(
-                clap::Command::new("autohold-event-thd")
-                    .about("Autohold event threshold in %")
-                    .arg(arg!([percent] "Set threshold").value_parser(value_parser!(u8))),
-            )
-            .subcommand(
-                clap::Command::new("recording-event-thd")
-                    .about("Recording event threshold in %")
-                    .arg(arg!([percent] "Set threshold").value_parser(value_parser!(u8))),
-            )
-            .subcommand(
-                clap::Command::new("dBm-reference")
-                    .about("dBm reference in Ohm")
-                    .arg(
-                        arg!([reference] "Set dBm reference")
-                            .value_parser(value_parser!(DezibelReference)),
-                    ),
-            )
-            .subcommand(
-                clap::Command::new("smoothing")
-                    .about("Smoothing (AC)")
-                    .arg(arg!([state] "Set smoothing").value_parser(BoolishValueParser::new())),
-            )
-            .subcommand(clap::Command::new("ident").about("Device identification"))
-            .subcommand(
-                clap::Command::new("beeper")
-                    .about("Beeper")
-                    .arg(arg!([state] "Set beeper").value_parser(BoolishValueParser::new())),
-            )
-            .subcommand(
-                clap::Command::new("clock")
-                    .about("Internal clock")
-                    .arg(arg!(
-                        --"sync-with-host" "Sync DMM clock with local host"
-                    )),
-            )
-            .subcommand(
-                clap::Command::new("operator")
-                    .about("Operator name")
-                    .arg(arg!([name] "Set operator name")),
-            )
-            .subcommand(
-                clap::Command::new("company")
-                    .about("Company name")
-                    .arg(arg!([name] "Set company name")),
-            )
-            .subcommand(
-                clap::Command::new("site")
-                    .about("Site name")
-                    .arg(arg!([name] "Set site name")),
-            )
-            .subcommand(
-                clap::Command::new("contact")
-                    .about("Contact")
-                    .arg(arg!([name] "Set contact")),
-            )
-            .subcommand(
-                clap::Command::new("mea")
-                    //.alias("mea")
-                    .about("Get current measurement")
-                    .arg(arg!(
-                        --"watch" "Poll current measurement forever"
-                    )),
-            )
-            .subcommand(
-                clap::Command::new("memory-name")
-                    .about("Get/set memory slot name")
-                    .arg(arg!(<slot> "Slot").value_parser(clap::value_parser!(u16).range(1..=8)))
-                    .arg(arg!([name] "Set name (max 16 chars)")),
-            )
-            .subcommand(
-                clap::Command::new("clear").about("Clear memory").arg(
-                    arg!(--"memory" <memory> "Memory type")
-                        .value_parser(value_parser!(ClearMemory))
-                        .default_missing_value("all")
-                        .default_value("all"),
-                ),
-            )
-            .subcommand(
-                clap::Command::new("dump-measurements")
-                    .about("Dump memory measurements")
-                    .alias("dump-mea"),
-            )
-            .subcommand(clap::Command::new("dump-minmax").about("Dump memory min/max measurements"))
-            .subcommand(clap::Command::new("dump-peak").about("Dump memory peak measurement"))
-            .subcommand(
-                clap::Command::new("dump-recordings")
-                    .about("Dump memory recordings")
-                    .alias("dump-rec"),
-            )
-            .subcommand(clap::Command::new("memory").about("List all memory entries"))
-            .subcommand(
-                clap::Command::new("get-memory")
-                    .about("Query memory saving by name")
-                    .arg(
-                        arg!(
-                            [name] "Name of saving"
-                        )
-                        .required(true),
-                    ),
-            )
-            .subcommand_required(true)
-            .get_matches();
+    let matches = cli().get_matches();
+    // Profile values are defaults of the options, so parse again with them
+    let matches = match load_profile(&matches) {
+        Some(command) => command.get_matches(),
+        None => matches,
+    };
 
     match handle_args(&matches).await {
         Ok(()) => {}
@@ -228,18 +77,26 @@ async fn main() -> tokio_serial::Result<()> {
                     }
                     exit(-1);
                 }
-                proto::ProtoError::SyntaxError => {
+                proto::ProtoError::SyntaxError { .. } => {
                     eprintln!("Command was not recognized by device, aborting!");
                     exit(-1);
                 }
-                proto::ProtoError::ExecutionError => {
+                proto::ProtoError::ExecutionError { .. } => {
                     eprintln!("Command was not executed, maybe device is locked? Try to exit the current screen mode.");
                     exit(-1);
                 }
+                proto::ProtoError::DeviceLocked { .. } => {
+                    eprintln!("Command was rejected because of the screen shown by the device (setup, hold, ...). Exit the screen or use --on-locked close.");
+                    exit(-1);
+                }
                 proto::ProtoError::Abort => {
                     eprintln!("Failed to communicate with device, aborting!");
                     exit(-1);
                 }
+                proto::ProtoError::Timeout { .. } => {
+                    eprintln!("Device did not respond, check the port, cable and baudrate!");
+                    exit(-1);
+                }
                 proto::ProtoError::Unexpected(err) => {
                     eprintln!(
                         "Received an unexpected response from device, aborting!: {:?}",
@@ -247,6 +104,33 @@ async fn main() -> tokio_serial::Result<()> {
                     );
                     exit(-1);
                 }
+                proto::ProtoError::FramingError { skipped } => {
+                    eprintln!(
+                        "Lost synchronization with device ({} bytes discarded), aborting!",
+                        skipped
+                    );
+                    exit(-1);
+                }
+                proto::ProtoError::Remote(err) => {
+                    eprintln!("Daemon reported an error: {}", err);
+                    exit(-1);
+                }
+                proto::ProtoError::InvalidArgument(err) => {
+                    eprintln!("{}", err);
+                    exit(1);
+                }
+                proto::ProtoError::InvalidSaveName(err) => {
+                    eprintln!("{}", err);
+                    exit(1);
+                }
+                err @ proto::ProtoError::Unsupported { .. } => {
+                    eprintln!("{}", err);
+                    exit(1);
+                }
+                err => {
+                    eprintln!("{}, aborting!", err);
+                    exit(-1);
+                }
             }
         }
     }
@@ -255,683 +139,2554 @@ async fn main() -> tokio_serial::Result<()> {
 }
 
 async fn handle_args(matches: &clap::ArgMatches) -> Result<()> {
+    #[cfg(feature = "json")]
+    if let Some(("schema", args)) = matches.subcommand() {
+        use f289ctrl::integrations::schema;
+        let schema = match args.get_one::<String>("document").map(String::as_str) {
+            Some("measurement") => schema::measurement_schema(),
+            _ => schema::snapshot_schema(),
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&schema).map_err(std::io::Error::from)?
+        );
+        return Ok(());
+    }
+
+    if let Some(("ports", _args)) = matches.subcommand() {
+        let ports = f289ctrl::transport::candidate_ports()?;
+        if ports.is_empty() {
+            eprintln!("No known IR cable found");
+        }
+        for port in &ports {
+            println!("{}", port_line(port));
+        }
+        return Ok(());
+    }
+
     let baud_rate = matches
         .get_one::<u32>("baudrate")
         .unwrap_or(&DEFAULT_BAUDRATE);
 
     if let Some(port_path) = matches.get_one::<PathBuf>("device") {
-        let mut device = Device::new(port_path.to_string_lossy(), *baud_rate)?;
+        let address = if port_path.as_os_str() == "auto" && !matches.contains_id("replay") {
+            std::borrow::Cow::Owned(auto_port()?)
+        } else {
+            port_path.to_string_lossy()
+        };
+        let lines = LineControl {
+            dtr: matches.get_one::<bool>("dtr").copied(),
+            rts: matches.get_one::<bool>("rts").copied(),
+            settle: Duration::from_millis(*matches.get_one::<u64>("settle").unwrap_or(&0)),
+        };
+        let mut device = if let Some(replay) = matches.get_one::<PathBuf>("replay") {
+            #[cfg(feature = "record")]
+            {
+                Device::with_transport(f289ctrl::record::ReplayTransport::open(replay)?)
+            }
+            #[cfg(not(feature = "record"))]
+            {
+                let _ = replay;
+                eprintln!("Replay requires the record feature");
+                exit(1);
+            }
+        } else if matches.get_flag("auto-baud") {
+            let (device, baud_rate) = Device::connect_auto_with_lines(&address, &lines).await?;
+            eprintln!("Detected baudrate: {}", baud_rate);
+            device
+        } else {
+            let transport = transport::open_with_lines(&address, *baud_rate, &lines).await?;
+            match matches.get_one::<PathBuf>("record") {
+                #[cfg(feature = "record")]
+                Some(record) => Device::with_transport(f289ctrl::record::SessionRecorder::create(
+                    transport, record,
+                )?),
+                #[cfg(not(feature = "record"))]
+                Some(_) => {
+                    eprintln!("Recording requires the record feature");
+                    exit(1);
+                }
+                None => Device::with_transport(transport),
+            }
+        };
+        if let Some(retries) = matches.get_one::<u32>("retries") {
+            device.set_retry_policy(Backoff {
+                max_attempts: retries.saturating_add(1),
+                ..Default::default()
+            });
+        }
+        match matches.get_one::<String>("on-locked").map(String::as_str) {
+            Some("close") => device.set_locked_recovery(LockedRecovery::close_screen()),
+            Some("wait") => device.set_locked_recovery(LockedRecovery::Wait {
+                delay: Duration::from_secs(2),
+                attempts: 30,
+            }),
+            _ => {}
+        }
+        if let Some(ms) = matches.get_one::<u64>("turnaround") {
+            device.set_turnaround(Duration::from_millis(*ms));
+        }
+        if matches.get_count("debug") > 0 {
+            device.set_observer(StderrTracer);
+        }
+        match matches.get_one::<String>("color").map(String::as_str) {
+            Some("always") if !cfg!(feature = "color") => {
+                eprintln!("Colored output requires the color feature");
+                exit(1);
+            }
+            Some("always") => set_color(true),
+            Some("never") => set_color(false),
+            _ => {}
+        }
+        let formatting = Formatting {
+            numeric: match matches
+                .get_one::<String>("numeric-format")
+                .map(String::as_str)
+            {
+                Some("comma") => NumericFormat::Comma,
+                Some("device") => device.numeric_format().await?,
+                _ => NumericFormat::Point,
+            },
+            format: ReadingFormat {
+                scientific: matches.get_flag("scientific"),
+                full_resolution: matches.get_flag("full-resolution"),
+                prefix: matches.get_one::<i16>("prefix").copied(),
+                hide_unit: matches.get_flag("no-unit"),
+            },
+        };
+
+        let output = Output::from_matches(matches);
+        if output != Output::Text && !cfg!(feature = "json") {
+            eprintln!("JSON output requires the json feature");
+            exit(1);
+        }
 
         eprintln!("Connected to: {}\n", port_path.display());
 
         match matches.subcommand() {
-            // Device ID
-            Some(("ident", _args)) => {
-                let ident = device.ident().await?;
-                println!("Model: {}", ident.model);
-                println!("Firmware: {}", ident.firmware);
-                println!("Serial: {}", ident.serial);
+            Some(("run", args)) => {
+                let script = args.get_one::<PathBuf>("script").expect("script parameter");
+                let failed = run_script(
+                    &mut device,
+                    output,
+                    formatting,
+                    script,
+                    args.get_flag("stop-on-error"),
+                )
+                .await?;
+                if failed > 0 {
+                    eprintln!("{} line(s) failed", failed);
+                    exit(1);
+                }
             }
-            // Auto Backlight Timeout
-            Some(("backlight", args)) => {
-                if let Some(minutes) = args.get_one::<String>("minutes") {
-                    // Write value
-                    let allowed = ["5", "10", "15", "20", "25", "30", "off"];
-                    if allowed.contains(&minutes.to_lowercase().as_str()) {
-                        let duration =
-                            Duration::from_secs(minutes.parse::<u64>().unwrap_or(0) * 60);
-                        device.set_backlight(duration).await?;
-                        println!("OK");
-                    } else {
-                        eprintln!("Invalid value: {}", minutes);
+            #[cfg(all(unix, feature = "ipc"))]
+            Some(("daemon", args)) => {
+                let socket = args.get_one::<PathBuf>("socket").expect("socket parameter");
+                let interval = args.get_one::<u64>("interval").expect("interval parameter");
+
+                let maps = load_maps(&mut device, &VALUE_MAP_KEYS).await?;
+
+                let mut server = f289ctrl::ipc::Server::new(device, maps)
+                    .poll_interval(Duration::from_millis(*interval));
+                if let Some(secs) = args.get_one::<u64>("history") {
+                    let resolution = args
+                        .get_one::<u64>("history-resolution")
+                        .expect("history-resolution parameter");
+                    server = server
+                        .history(f289ctrl::ipc::HistoryConfig {
+                            retention: Duration::from_secs(*secs),
+                            resolution: Duration::from_millis(*resolution),
+                            path: args.get_one::<PathBuf>("history-file").cloned(),
+                        })?
+                        .on_history_error(|err| eprintln!("Failed to store history: {}", err));
+                }
+                if let Some(path) = args.get_one::<PathBuf>("alerts") {
+                    #[cfg(feature = "alerts")]
+                    {
+                        use f289ctrl::integrations::alert::{AlertConfig, AlertEngine};
+                        let mut engine = AlertEngine::new(AlertConfig::load(path)?)?.on_error(
+                            |name, _alert, err| {
+                                eprintln!("Alert action '{}' failed: {}", name, err)
+                            },
+                        );
+                        server = server.on_measurement(move |m| engine.observe(m));
                     }
-                } else {
-                    // Read value
-                    let backlight = device.backlight().await?;
-                    if backlight.is_zero() {
-                        println!("Auto Backlight Timeout: OFF");
-                    } else {
-                        println!("Auto Backlight Timeout: {} min", backlight.as_secs() / 60);
+                    #[cfg(not(feature = "alerts"))]
+                    {
+                        eprintln!(
+                            "{}: alert support is not compiled in (feature 'alerts')",
+                            path.display()
+                        );
+                        exit(-1);
                     }
                 }
+                if let Some(addr) = args.get_one::<String>("listen") {
+                    eprintln!("Listening on: {}", addr);
+                    server.serve_tcp(addr.as_str()).await?;
+                } else {
+                    eprintln!("Listening on: {}", socket.display());
+                    server.serve_unix(socket).await?;
+                }
             }
-            // Auto poweroff
-            Some(("poweroff", args)) => {
-                if let Some(minutes) = args.get_one::<String>("minutes") {
-                    // Write value
-                    let allowed = ["15", "25", "35", "45", "60", "off"];
-                    if allowed.contains(&minutes.to_lowercase().as_str()) {
-                        let duration =
-                            Duration::from_secs(minutes.parse::<u64>().unwrap_or(0) * 60);
-                        device.set_poweroff(duration).await?;
-                        println!("OK");
-                    } else {
-                        eprintln!("Invalid value: {}", minutes);
+            #[cfg(feature = "ipc")]
+            Some(("serve", args)) => {
+                use f289ctrl::ipc::Protocol;
+
+                let interval = args.get_one::<u64>("interval").expect("interval parameter");
+
+                let mut listeners = Vec::new();
+                if let Some(addr) = args.get_one::<String>("http") {
+                    eprintln!("Listening on: http://{}", addr);
+                    listeners.push((Protocol::Http, addr.as_str()));
+                }
+                if let Some(addr) = args.get_one::<String>("scpi") {
+                    eprintln!("Listening on: {} (SCPI)", addr);
+                    listeners.push((Protocol::Scpi, addr.as_str()));
+                }
+
+                let maps = load_maps(&mut device, &VALUE_MAP_KEYS).await?;
+
+                let server = f289ctrl::ipc::Server::new(device, maps)
+                    .poll_interval(Duration::from_millis(*interval));
+                let server = if args.get_flag("mdns") {
+                    #[cfg(feature = "mdns")]
+                    {
+                        server.advertise()
                     }
-                } else {
-                    // Read value
-                    let poweroff = device.poweroff().await?;
-                    if poweroff.is_zero() {
-                        println!("Auto Power Off: OFF");
-                    } else {
-                        println!("Auto Power Off: {} min", poweroff.as_secs() / 60);
+                    #[cfg(not(feature = "mdns"))]
+                    {
+                        eprintln!("Announcing requires the mdns feature");
+                        exit(1);
                     }
+                } else {
+                    server
+                };
+                server.serve(listeners).await?;
+            }
+            #[cfg(not(feature = "ipc"))]
+            Some(("serve", _args)) => {
+                eprintln!("Server support is not compiled in (feature 'ipc')");
+                exit(-1);
+            }
+            subcommand => run_command(&mut device, output, formatting, subcommand).await?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Subcommands which only borrow the device, so they can run from scripts.
+async fn run_command(
+    device: &mut Device,
+    output: Output,
+    formatting: Formatting,
+    subcommand: Option<(&str, &clap::ArgMatches)>,
+) -> Result<()> {
+    match subcommand {
+        // Device ID
+        Some(("ident", _args)) => {
+            let ident = device.ident().await?;
+            if output.is_json() {
+                print_json(output, &ident)?;
+            } else {
+                println!("Model: {}", ident.model);
+                println!("Firmware: {}", ident.firmware);
+                println!("Serial: {}", ident.serial);
+            }
+        }
+        // Capability self-test
+        Some(("probe", args)) => {
+            let report = device.probe(args.get_flag("include-writes")).await;
+            if args.get_flag("json") || output.is_json() {
+                print_json(output, &report)?;
+            } else {
+                if let Some(ident) = &report.ident {
+                    println!("Model: {}", ident.model);
+                    println!("Firmware: {}", ident.firmware);
+                }
+                for result in &report.results {
+                    let status = match &result.outcome {
+                        ProbeOutcome::Supported => "supported".to_string(),
+                        ProbeOutcome::Unsupported => "unsupported".to_string(),
+                        ProbeOutcome::NotExecuted => "not executed".to_string(),
+                        ProbeOutcome::Failed(err) => format!("failed ({})", err),
+                        ProbeOutcome::Skipped => "skipped".to_string(),
+                    };
+                    println!("{:<16} {}", result.command, status);
                 }
             }
-            // Operator
-            Some(("operator", args)) => {
-                if let Some(name) = args.get_one::<String>("name") {
-                    // Write value
-                    device.set_operator(name).await?;
-                    println!("OK");
+        }
+        // Auto Backlight Timeout
+        Some(("backlight", args)) => {
+            if let Some(minutes) = args.get_one::<String>("minutes") {
+                // Write value
+                let allowed = ["5", "10", "15", "20", "25", "30", "off"];
+                if allowed.contains(&minutes.to_lowercase().as_str()) {
+                    let duration = Duration::from_secs(minutes.parse::<u64>().unwrap_or(0) * 60);
+                    device.set_backlight(duration).await?;
+                    print_ok(output)?;
                 } else {
-                    // Read value
-                    let operator = device.operator().await?;
-                    println!("Operator: {}", operator);
+                    eprintln!("Invalid value: {}", minutes);
                 }
-            }
-            // Copmany
-            Some(("company", args)) => {
-                if let Some(name) = args.get_one::<String>("name") {
-                    // Write value
-                    device.set_company(name).await?;
-                    println!("OK");
+            } else {
+                // Read value
+                let backlight = device.backlight().await?;
+                if output.is_json() {
+                    print_json(
+                        output,
+                        &setting("backlight_minutes", backlight.as_secs() / 60),
+                    )?;
+                } else if backlight.is_zero() {
+                    println!("Auto Backlight Timeout: OFF");
                 } else {
-                    // Read value
-                    let operator = device.company().await?;
-                    println!("Company: {}", operator);
+                    println!("Auto Backlight Timeout: {} min", backlight.as_secs() / 60);
                 }
             }
-            // Site
-            Some(("site", args)) => {
-                if let Some(name) = args.get_one::<String>("name") {
-                    // Write value
-                    device.set_site(name).await?;
-                    println!("OK");
+        }
+        // Auto poweroff
+        Some(("poweroff", args)) => {
+            if let Some(minutes) = args.get_one::<String>("minutes") {
+                // Write value
+                let allowed = ["15", "25", "35", "45", "60", "off"];
+                if allowed.contains(&minutes.to_lowercase().as_str()) {
+                    let duration = Duration::from_secs(minutes.parse::<u64>().unwrap_or(0) * 60);
+                    device.set_poweroff(duration).await?;
+                    print_ok(output)?;
                 } else {
-                    // Read value
-                    let operator = device.site().await?;
-                    println!("Site: {}", operator);
+                    eprintln!("Invalid value: {}", minutes);
                 }
-            }
-            // Contact
-            Some(("contact", args)) => {
-                if let Some(name) = args.get_one::<String>("name") {
-                    // Write value
-                    device.set_contact(name).await?;
-                    println!("OK");
+            } else {
+                // Read value
+                let poweroff = device.poweroff().await?;
+                if output.is_json() {
+                    print_json(
+                        output,
+                        &setting("poweroff_minutes", poweroff.as_secs() / 60),
+                    )?;
+                } else if poweroff.is_zero() {
+                    println!("Auto Power Off: OFF");
                 } else {
-                    // Read value
-                    let operator = device.contact().await?;
-                    println!("Contact: {}", operator);
+                    println!("Auto Power Off: {} min", poweroff.as_secs() / 60);
                 }
             }
-            // Clock
-            Some(("clock", args)) => {
-                if let Some(true) = args.get_one::<bool>("sync-with-host") {
-                    // Write value
-                    device.set_clock(Local::now()).await?;
-                    println!("OK");
+        }
+        // Operator
+        Some(("operator", args)) => {
+            if let Some(name) = args.get_one::<String>("name") {
+                // Write value
+                device.set_operator(name).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let operator = device.operator().await?;
+                print_setting(output, "operator", "Operator", operator)?;
+            }
+        }
+        // Copmany
+        Some(("company", args)) => {
+            if let Some(name) = args.get_one::<String>("name") {
+                // Write value
+                device.set_company(name).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let operator = device.company().await?;
+                print_setting(output, "company", "Company", operator)?;
+            }
+        }
+        // Site
+        Some(("site", args)) => {
+            if let Some(name) = args.get_one::<String>("name") {
+                // Write value
+                device.set_site(name).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let operator = device.site().await?;
+                print_setting(output, "site", "Site", operator)?;
+            }
+        }
+        // Contact
+        Some(("contact", args)) => {
+            if let Some(name) = args.get_one::<String>("name") {
+                // Write value
+                device.set_contact(name).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let operator = device.contact().await?;
+                print_setting(output, "contact", "Contact", operator)?;
+            }
+        }
+        Some(("apply-profile", args)) => {
+            let operator = args.get_one::<String>("operator");
+            let company = args.get_one::<String>("company");
+            let site = args.get_one::<String>("site");
+            let contact = args.get_one::<String>("contact");
+            if operator.is_none() && company.is_none() && site.is_none() && contact.is_none() {
+                eprintln!("The profile has no operator, company, site or contact");
+                exit(1);
+            }
+            if let Some(name) = operator {
+                device.set_operator(name).await?;
+            }
+            if let Some(name) = company {
+                device.set_company(name).await?;
+            }
+            if let Some(name) = site {
+                device.set_site(name).await?;
+            }
+            if let Some(name) = contact {
+                device.set_contact(name).await?;
+            }
+            print_ok(output)?;
+        }
+        Some(("identify", args)) => {
+            let rounds = args.get_one::<usize>("rounds").expect("rounds parameter");
+            device.identify(*rounds).await?;
+            print_ok(output)?;
+        }
+        // Clock
+        Some(("clock", args)) => {
+            if args.get_flag("drift") {
+                let interval = args.get_one::<u64>("interval").expect("interval parameter");
+
+                let tz = args.get_one::<FixedOffset>("tz").copied();
+
+                let start = tokio::time::Instant::now();
+                let first = clock_offset(device, tz).await?;
+                tokio::time::sleep_until(start + Duration::from_secs(*interval)).await;
+                let offset = clock_offset(device, tz).await?;
+                let elapsed = start.elapsed().as_secs_f64();
+                let drift_ppm = (offset - first) / elapsed * 1e6;
+
+                let adjusted_secs = if args.get_flag("fix") {
+                    // The clock is set in full seconds, so set it right
+                    // after a second boundary of the host clock
+                    let subsec = Local::now().timestamp_subsec_millis() as u64;
+                    tokio::time::sleep(Duration::from_millis(1000 - subsec)).await;
+                    device.set_clock(host_now(tz)).await?;
+                    eprintln!("Adjusted device clock by {:+.3} s", -offset);
+                    Some(-offset)
+                } else {
+                    None
+                };
+
+                if output.is_json() {
+                    print_json(
+                        output,
+                        &ClockDrift {
+                            offset_secs: offset,
+                            drift_ppm,
+                            interval_secs: elapsed,
+                            adjusted_secs,
+                        },
+                    )?;
+                } else {
+                    println!(
+                        "Offset: {:+.3} s ({})",
+                        offset,
+                        if offset >= 0.0 {
+                            "device ahead"
+                        } else {
+                            "device behind"
+                        }
+                    );
+                    println!(
+                        "Drift: {:+.1} ppm ({:+.1} s/day) over {:.1} s",
+                        drift_ppm,
+                        drift_ppm * 86400.0 / 1e6,
+                        elapsed
+                    );
+                }
+            } else if let Some(true) = args.get_one::<bool>("sync-with-host") {
+                // Write value
+                let tz = args.get_one::<FixedOffset>("tz").copied();
+                device.set_clock(host_now(tz)).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let clock = device.clock().await?;
+                let system_time = std::time::UNIX_EPOCH + Duration::from_secs(clock);
+                let datetime: DateTime<chrono::Utc> = system_time.into();
+                if output.is_json() {
+                    print_json(output, &setting("clock", datetime))?;
                 } else {
-                    // Read value
-                    let clock = device.clock().await?;
-                    let system_time = std::time::UNIX_EPOCH + Duration::from_secs(clock);
-                    let datetime: DateTime<chrono::Utc> = system_time.into();
                     println!("Device clock: {}", datetime.naive_local());
                 }
             }
-            // Reset
-            Some(("reset", _)) => {
-                device.reset().await?;
-                println!("OK");
-            }
-            // Beeper
-            Some(("beeper", args)) => {
-                if let Some(state) = args.get_one::<bool>("state") {
-                    // Write value
-                    device.set_beeper(*state).await?;
-                    println!("OK");
+        }
+        // Reset
+        Some(("reset", _)) => {
+            device.reset().await?;
+            print_ok(output)?;
+        }
+        // Beeper
+        Some(("beeper", args)) => {
+            if let Some(state) = args.get_one::<bool>("state") {
+                // Write value
+                device.set_beeper(*state).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let state = device.beeper().await?;
+                if output.is_json() {
+                    print_json(output, &setting("beeper", state))?;
                 } else {
-                    // Read value
-                    let state = device.beeper().await?;
                     println!("Beeper: {}", state);
                 }
             }
-            // Smoothing
-            Some(("smoothing", args)) => {
-                if let Some(state) = args.get_one::<bool>("state") {
-                    // Write value
-                    device.set_smoothing(*state).await?;
-                    println!("OK");
+        }
+        // Smoothing
+        Some(("smoothing", args)) => {
+            if let Some(state) = args.get_one::<bool>("state") {
+                // Write value
+                device.set_smoothing(*state).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let state = device.smoothing().await?;
+                if output.is_json() {
+                    print_json(output, &setting("smoothing", state))?;
                 } else {
-                    // Read value
-                    let state = device.smoothing().await?;
                     println!("AC Smoothing: {}", state);
                 }
             }
-            // Custom dBm
-            Some(("custom-dBm", args)) => {
-                if let Some(dbm) = args.get_one::<u16>("reference") {
-                    // Write value
-                    device.set_custom_dbm(*dbm).await?;
-                    println!("OK");
+        }
+        // Custom dBm
+        Some(("custom-dBm", args)) => {
+            if let Some(dbm) = args.get_one::<u16>("reference") {
+                // Write value
+                device.set_custom_dbm(*dbm).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let dbm = device.custom_dbm().await?;
+                if output.is_json() {
+                    print_json(output, &setting("custom_dbm", dbm))?;
                 } else {
-                    // Read value
-                    let dbm = device.custom_dbm().await?;
                     println!("Custom dBm: {}", dbm);
                 }
             }
-            // dBm-Ref
-            Some(("dBm-reference", args)) => {
-                if let Some(dbm) = args.get_one::<DezibelReference>("reference") {
-                    // Write value
-                    device.set_dbm_ref(*dbm).await?;
-                    println!("OK");
+        }
+        // dBm-Ref
+        Some(("dBm-reference", args)) => {
+            if let Some(dbm) = args.get_one::<DezibelReference>("reference") {
+                // Write value
+                device.set_dbm_ref(*dbm).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let dbm = device.dbm_ref().await?;
+                if output.is_json() {
+                    print_json(output, &setting("dbm_reference", dbm))?;
                 } else {
-                    // Read value
-                    let dbm = device.dbm_ref().await?;
                     println!("dBm reference: {}", dbm);
                 }
             }
-            // Temp Offset
-            Some(("temp-offset", args)) => {
-                if let Some(offset) = args.get_one::<i16>("offset") {
-                    // Write value
-                    device.set_temp_offset(*offset).await?;
-                    println!("OK");
+        }
+        // Temp Offset
+        Some(("temp-offset", args)) => {
+            if let Some(offset) = args.get_one::<i16>("offset") {
+                // Write value
+                device.set_temp_offset(*offset).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let offset = device.temp_offset().await?;
+                if output.is_json() {
+                    print_json(output, &setting("temp_offset", offset))?;
                 } else {
-                    // Read value
-                    let offset = device.temp_offset().await?;
                     println!("Temp. offset: {}", offset);
                 }
             }
-            // Digit count
-            Some(("digits", args)) => {
-                if let Some(count) = args.get_one::<DigitCount>("digits") {
-                    // Write value
-                    device.set_digit_count(*count).await?;
-                    println!("OK");
+        }
+        // Digit count
+        Some(("digits", args)) => {
+            if let Some(count) = args.get_one::<DigitCount>("digits") {
+                // Write value
+                device.set_digit_count(*count).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let count = device.digit_count().await?;
+                if output.is_json() {
+                    print_json(
+                        output,
+                        &setting(
+                            "digits",
+                            match count {
+                                DigitCount::Digit4 => 4,
+                                DigitCount::Digit5 => 5,
+                            },
+                        ),
+                    )?;
                 } else {
-                    // Read value
-                    let count = device.digit_count().await?;
                     match count {
                         DigitCount::Digit4 => println!("Digit count: 4",),
                         DigitCount::Digit5 => println!("Digit count: 5",),
                     }
                 }
             }
-            // Numeric format
-            Some(("numeric-format", args)) => {
-                if let Some(fmt) = args.get_one::<NumericFormat>("fmt") {
-                    // Write value
-                    device.set_numeric_format(*fmt).await?;
-                    println!("OK");
+        }
+        // Numeric format
+        Some(("numeric-format", args)) => {
+            if let Some(fmt) = args.get_one::<NumericFormat>("fmt") {
+                // Write value
+                device.set_numeric_format(*fmt).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let fmt = device.numeric_format().await?;
+                if output.is_json() {
+                    print_json(output, &setting("numeric_format", fmt))?;
                 } else {
-                    // Read value
-                    let fmt = device.numeric_format().await?;
                     match fmt {
                         NumericFormat::Comma => println!("Numeric format: COMMA",),
                         NumericFormat::Point => println!("Numeric format: POINT",),
                     }
                 }
             }
-            // Date Format
-            Some(("date-format", args)) => {
-                if let Some(fmt) = args.get_one::<DateFormat>("fmt") {
-                    // Write value
-                    device.set_date_format(*fmt).await?;
-                    println!("OK");
+        }
+        // Date Format
+        Some(("date-format", args)) => {
+            if let Some(fmt) = args.get_one::<DateFormat>("fmt") {
+                // Write value
+                device.set_date_format(*fmt).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let fmt = device.date_format().await?;
+                if output.is_json() {
+                    print_json(output, &setting("date_format", fmt))?;
                 } else {
-                    // Read value
-                    let fmt = device.date_format().await?;
                     match fmt {
                         DateFormat::MM_DD => println!("Date format: MM/DD",),
                         DateFormat::DD_MM => println!("Date format: DD/MM",),
                     }
                 }
             }
-            // Time Format
-            Some(("time-format", args)) => {
-                if let Some(fmt) = args.get_one::<TimeFormat>("fmt") {
-                    // Write value
-                    device.set_time_format(*fmt).await?;
-                    println!("OK");
+        }
+        // Time Format
+        Some(("time-format", args)) => {
+            if let Some(fmt) = args.get_one::<TimeFormat>("fmt") {
+                // Write value
+                device.set_time_format(*fmt).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let fmt = device.time_format().await?;
+                if output.is_json() {
+                    print_json(output, &setting("time_format", fmt))?;
                 } else {
-                    // Read value
-                    let fmt = device.time_format().await?;
                     match fmt {
                         TimeFormat::Time12 => println!("Time format: 12h",),
                         TimeFormat::Time24 => println!("Time format: 24h",),
                     }
                 }
             }
-            // Language
-            Some(("language", args)) => {
-                if let Some(lang) = args.get_one::<Language>("language") {
-                    // Write value
-                    device.set_language(*lang).await?;
-                    println!("OK");
+        }
+        // Language
+        Some(("language", args)) => {
+            if let Some(lang) = args.get_one::<Language>("language") {
+                // Write value
+                device.set_language(*lang).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let lang = match device.language().await? {
+                    Language::English => "ENGLISH",
+                    Language::German => "GERMAN",
+                    Language::French => "FRENCH",
+                    Language::Italian => "ITALIAN",
+                    Language::Spanish => "SPANISH",
+                    Language::Japanese => "JAPANESE",
+                    Language::Chinese => "CHINESE",
+                };
+                if output.is_json() {
+                    print_json(output, &setting("language", lang))?;
                 } else {
-                    // Read value
-                    let lang = match device.language().await? {
-                        Language::English => "ENGLISH",
-                        Language::German => "GERMAN",
-                        Language::French => "FRENCH",
-                        Language::Italian => "ITALIAN",
-                        Language::Spanish => "SPANISH",
-                        Language::Japanese => "JAPANESE",
-                        Language::Chinese => "CHINESE",
-                    };
                     println!("Language: {}", lang);
                 }
             }
-            // Autohold event thd
-            Some(("autohold-event-thd", args)) => {
-                if let Some(thd) = args.get_one::<u8>("percent") {
-                    // Write value
-                    device.set_autohold_event_threshold(*thd).await?;
-                    println!("OK");
+        }
+        // Autohold event thd
+        Some(("autohold-event-thd", args)) => {
+            if let Some(thd) = args.get_one::<u8>("percent") {
+                // Write value
+                device.set_autohold_event_threshold(*thd).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let thd = device.autohold_event_threshold().await?;
+                if output.is_json() {
+                    print_json(output, &setting("autohold_event_threshold", thd))?;
                 } else {
-                    // Read value
-                    let thd = device.autohold_event_threshold().await?;
                     println!("Autohold event threshold: {}", thd);
                 }
             }
-            // Recording event thd
-            Some(("recording-event-thd", args)) => {
-                if let Some(thd) = args.get_one::<u8>("percent") {
-                    // Write value
-                    device.set_recording_event_threshold(*thd).await?;
-                    println!("OK");
+        }
+        // Recording event thd
+        Some(("recording-event-thd", args)) => {
+            if let Some(thd) = args.get_one::<u8>("percent") {
+                // Write value
+                device.set_recording_event_threshold(*thd).await?;
+                print_ok(output)?;
+            } else {
+                // Read value
+                let thd = device.recording_event_threshold().await?;
+                if output.is_json() {
+                    print_json(output, &setting("recording_event_threshold", thd))?;
                 } else {
-                    // Read value
-                    let thd = device.recording_event_threshold().await?;
                     println!("Recording event threshold: {}", thd);
                 }
             }
-            // Clear
-            Some(("clear", args)) => {
-                if let Some(memory) = args.get_one::<ClearMemory>("memory") {
-                    device.clear(*memory).await?;
-                    println!("OK");
-                } else {
-                    panic!("memory arg missing")
-                }
-            }
-            // Measurement
-            Some(("mea", args)) => {
-                let watch = args.get_one::<bool>("watch").unwrap_or(&false);
-
-                let maps = device.value_maps().await?;
-
-                let mut c = 1;
-
-                let mut prifunction = None;
-                let mut secfunction = None;
-                let mut modes = None;
-
-                loop {
-                    match device.live_measurement().await {
-                        Ok(Some(mea_raw)) => {
-                            let mea = Measurement::from((mea_raw, &maps));
-
-                            if prifunction != Some(mea.pri_function)
-                                || secfunction != Some(mea.sec_function)
-                                || modes.as_ref() != Some(&mea.modes)
-                            {
-                                prifunction = Some(mea.pri_function);
-                                secfunction = Some(mea.sec_function);
-                                modes = Some(mea.modes.clone());
-                                println!(
-                                    "Measurement primary: [{}], secondary: [{}], modes: [{}]",
-                                    mea.pri_function, mea.sec_function, mea.modes
-                                );
-                            }
-                            for r in &mea.readings {
-                                println!(
-                                    "#{:0>4}/{:0>4} {:>15} {:>20}",
-                                    c,
-                                    r.reading_id,
-                                    r.to_string(),
-                                    r.ts.format("%Y-%m-%d %H:%M:%S")
-                                );
-                                //println!("{:?}", r);
-                            }
+        }
+        // Clear
+        Some(("clear", args)) => {
+            if let Some(memory) = args.get_one::<ClearMemory>("memory") {
+                device.clear(*memory).await?;
+                print_ok(output)?;
+            } else {
+                panic!("memory arg missing")
+            }
+        }
+        // Measurement
+        Some(("mea", args)) => {
+            let interval = args.get_one::<u64>("interval").expect("interval parameter");
+            let count = args.get_one::<u64>("count");
+            let duration = args.get_one::<u64>("duration");
+            let watch = args.get_flag("watch") || count.is_some() || duration.is_some();
+            let timestamps = match args.get_one::<String>("timestamps").map(String::as_str) {
+                Some("host") => TimestampSource::Host,
+                Some("both") => TimestampSource::Both,
+                _ => TimestampSource::Device,
+            };
+
+            if args.get_flag("ascii") {
+                return ascii_mea(device, args, output, formatting, watch).await;
+            }
+
+            let maps = load_maps(device, &MEASUREMENT_MAP_KEYS).await?;
+
+            if args.get_flag("value-only") {
+                let mea = device
+                    .live_measurement()
+                    .await?
+                    .map(|raw| Measurement::received(raw, &maps))
+                    .transpose()?;
+                let reading = match mea.as_ref().and_then(|mea| mea.readings.first()) {
+                    Some(reading) => reading,
+                    None => {
+                        eprintln!("No data");
+                        exit(2);
+                    }
+                };
+                match reading.si_value() {
+                    Some(value) if args.get_flag("unit") => {
+                        println!("{} {}", value, reading.unit)
+                    }
+                    Some(value) => println!("{}", value),
+                    None => {
+                        eprintln!("No value: {}", formatting.reading(reading));
+                        exit(2);
+                    }
+                }
+                return Ok(());
+            }
+
+            #[cfg(feature = "influx")]
+            let influx = match args.get_one::<String>("influx") {
+                Some(url) => {
+                    use f289ctrl::integrations::export::influx::{InfluxWriter, LineProtocol};
+                    let measurement = args
+                        .get_one::<String>("influx-measurement")
+                        .expect("influx-measurement parameter");
+                    let serial = device.ident().await?.serial;
+                    let format = LineProtocol::new(measurement)
+                        .tag("serial", serial)
+                        .timestamps(timestamps);
+                    let mut writer = InfluxWriter::new(url, format)?;
+                    if let Some(token) = args.get_one::<String>("influx-token") {
+                        writer = writer.token(token);
+                    }
+                    Some(writer)
+                }
+                None => None,
+            };
+            #[cfg(not(feature = "influx"))]
+            if args.get_one::<String>("influx").is_some() {
+                eprintln!("InfluxDB support is not compiled in (feature 'influx')");
+                exit(-1);
+            }
+
+            #[cfg(feature = "mqtt")]
+            let mut mqtt = match args.get_one::<String>("mqtt") {
+                Some(url) => {
+                    use f289ctrl::integrations::mqtt::{MqttConfig, MqttPublisher};
+                    let config = MqttConfig::from_url(url)?;
+                    let serial = device.ident().await?.serial;
+                    Some(MqttPublisher::connect(config, serial).await?)
+                }
+                None => None,
+            };
+            #[cfg(not(feature = "mqtt"))]
+            if args.get_one::<String>("mqtt").is_some() {
+                eprintln!("MQTT support is not compiled in (feature 'mqtt')");
+                exit(-1);
+            }
+
+            let mut c = 1;
+
+            let mut prifunction = None;
+            let mut secfunction = None;
+            let mut modes = None;
+
+            let mut show = |result: Result<Option<Measurement>>| {
+                match result {
+                    #[cfg(feature = "json")]
+                    Ok(Some(mea)) if output == Output::Ndjson => {
+                        if let Err(err) = f289ctrl::integrations::export::json::write_ndjson(
+                            std::io::stdout().lock(),
+                            &mea,
+                            timestamps,
+                        ) {
+                            eprintln!("Error: {}", err);
+                        }
+                    }
+                    Ok(Some(mea)) if output.is_json() => {
+                        if let Err(err) = print_json(output, &mea) {
+                            eprintln!("Error: {}", err);
+                        }
+                    }
+                    Ok(Some(mea)) => {
+                        if prifunction != Some(mea.pri_function)
+                            || secfunction != Some(mea.sec_function)
+                            || modes != Some(mea.modes)
+                        {
+                            prifunction = Some(mea.pri_function);
+                            secfunction = Some(mea.sec_function);
+                            modes = Some(mea.modes);
+                            println!(
+                                "Measurement primary: [{}], secondary: [{}], modes: [{}]",
+                                mea.pri_function, mea.sec_function, mea.modes
+                            );
                         }
-                        Ok(None) => {
-                            println!("--- NO DATA ---");
+                        for r in &mea.readings {
+                            let host_ts = match (timestamps, mea.host_ts) {
+                                (TimestampSource::Both, Some(host_ts)) => {
+                                    format!(" host {}", host_ts.format("%Y-%m-%d %H:%M:%S%.3f"))
+                                }
+                                _ => String::new(),
+                            };
+                            println!(
+                                "#{:0>4}/{:0>4} {} {}{}",
+                                c,
+                                r.reading_id,
+                                paint_reading(
+                                    format!("{:>15}", formatting.reading(r).to_string()),
+                                    r
+                                ),
+                                paint(
+                                    format!(
+                                        "{:>20}",
+                                        mea.reading_ts(r, timestamps)
+                                            .format("%Y-%m-%d %H:%M:%S")
+                                            .to_string()
+                                    ),
+                                    Paint::Dim
+                                ),
+                                paint(host_ts, Paint::Dim)
+                            );
+                            //println!("{:?}", r);
                         }
-                        Err(err) => {
+                    }
+                    Ok(None) if output == Output::Ndjson => {}
+                    Ok(None) if output.is_json() => {
+                        if let Err(err) = print_json(output, &None::<Measurement>) {
                             eprintln!("Error: {}", err);
                         }
                     }
-
-                    if !watch {
-                        break;
+                    Ok(None) => {
+                        println!("--- NO DATA ---");
+                    }
+                    Err(err) => {
+                        eprintln!("Error: {}", err);
+                    }
+                }
+                c += 1;
+            };
+
+            let mut measurements: Pin<Box<dyn Stream<Item = _>>> = if watch {
+                let stream = device.live_measurements(Duration::from_millis(*interval));
+                match count {
+                    Some(count) => Box::pin(stream.take(*count as usize)),
+                    None => Box::pin(stream),
+                }
+            } else {
+                Box::pin(futures::stream::once(device.live_measurement()))
+            };
+            let deadline =
+                duration.map(|secs| tokio::time::Instant::now() + Duration::from_secs(*secs));
+            let mut primary = Vec::new();
+            loop {
+                let next = match deadline {
+                    Some(deadline) => tokio::time::timeout_at(deadline, measurements.next())
+                        .await
+                        .unwrap_or(None),
+                    None => measurements.next().await,
+                };
+                let result = match next {
+                    Some(result) => result,
+                    None => break,
+                };
+                let result = result
+                    .and_then(|raw| raw.map(|raw| Measurement::received(raw, &maps)).transpose());
+                if let Ok(Some(mea)) = &result {
+                    primary.extend(mea.readings.first().cloned());
+                }
+                #[cfg(feature = "influx")]
+                if let (Some(influx), Ok(Some(mea))) = (&influx, &result) {
+                    if let Err(err) = influx.write(mea).await {
+                        eprintln!("InfluxDB write failed: {}", err);
+                    }
+                }
+                #[cfg(feature = "mqtt")]
+                if let (Some(mqtt), Ok(Some(mea))) = (&mut mqtt, &result) {
+                    if let Err(err) = mqtt.publish(mea).await {
+                        eprintln!("MQTT publish failed: {}", err);
+                    }
+                }
+                show(result);
+            }
+            if watch {
+                let summary = watch_summary(&primary);
+                if output.is_json() {
+                    eprintln!("{}", summary);
+                } else {
+                    println!("{}", summary);
+                }
+            }
+        }
+        Some(("capture", args)) => {
+            let trigger = match args.get_one::<Trigger>("when") {
+                Some(trigger) => *trigger,
+                None => Trigger {
+                    above: args.get_one::<f64>("above").copied(),
+                    below: args.get_one::<f64>("below").copied(),
+                },
+            };
+            let pre = args.get_one::<usize>("pre").expect("pre parameter");
+            let count = args.get_one::<u64>("count");
+            let interval = args.get_one::<u64>("interval").expect("interval parameter");
+
+            let maps = load_maps(device, &MEASUREMENT_MAP_KEYS).await?;
+
+            let mut capture = Capture::new(trigger, *pre);
+            let mut captured = 0;
+            eprintln!("Waiting for trigger: {}", trigger);
+            let mut measurements =
+                Box::pin(device.live_measurements(Duration::from_millis(*interval)));
+            while let Some(result) = measurements.next().await {
+                let mea = match result
+                    .and_then(|raw| raw.map(|raw| Measurement::received(raw, &maps)).transpose())
+                {
+                    Ok(Some(mea)) => mea,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        eprintln!("Error: {}", err);
+                        continue;
+                    }
+                };
+                for mea in capture.push(mea) {
+                    if captured == 0 {
+                        eprintln!("Triggered");
+                    }
+                    if output.is_json() {
+                        print_json(output, &mea)?;
+                    } else {
+                        for r in &mea.readings {
+                            println!(
+                                "{} #{:0>4} {:>15}",
+                                pretty_ts(&r.ts),
+                                r.reading_id,
+                                r.to_string()
+                            );
+                        }
+                    }
+                    captured += 1;
+                }
+                if capture.is_triggered() && count.map_or(false, |count| captured >= *count) {
+                    break;
+                }
+            }
+        }
+        Some(("monitor", args)) => {
+            let band = Trigger {
+                above: args.get_one::<f64>("above").copied(),
+                below: args.get_one::<f64>("below").copied(),
+            };
+            let exec = args.get_one::<String>("exec");
+            let notify = args.get_flag("notify");
+            let keep_going = args.get_flag("keep-going");
+            let interval = args.get_one::<u64>("interval").expect("interval parameter");
+
+            let maps = load_maps(device, &MEASUREMENT_MAP_KEYS).await?;
+
+            if band == Trigger::default() {
+                eprintln!("Monitoring, alert on overload");
+            } else {
+                eprintln!("Monitoring, alert on overload or {}", band);
+            }
+            // Alerts fire once when entering the alarm state
+            let mut alarm = false;
+            let mut measurements =
+                Box::pin(device.live_measurements(Duration::from_millis(*interval)));
+            while let Some(result) = measurements.next().await {
+                let mea = match result
+                    .and_then(|raw| raw.map(|raw| Measurement::received(raw, &maps)).transpose())
+                {
+                    Ok(Some(mea)) => mea,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        eprintln!("Error: {}", err);
+                        continue;
+                    }
+                };
+                let reading = match mea.readings.first() {
+                    Some(reading) => reading,
+                    None => continue,
+                };
+                let message = match reading.reading_value() {
+                    ReadingValue::Overload | ReadingValue::OverloadNegative => {
+                        Some(format!("overload: {}", formatting.reading(reading)))
+                    }
+                    ReadingValue::OpenTc => Some(String::from("open thermocouple")),
+                    ReadingValue::Value(_) if band.is_met(&mea) => {
+                        Some(format!("{} ({})", formatting.reading(reading), band))
+                    }
+                    _ => None,
+                };
+                match message {
+                    Some(message) if !alarm => {
+                        alarm = true;
+                        println!("{} ALERT {}", pretty_ts(&reading.ts), message);
+                        raise_alert(&message, reading.si_value(), exec, notify).await;
+                        if !keep_going {
+                            exit(3);
+                        }
+                    }
+                    Some(_) => {}
+                    None if alarm => {
+                        alarm = false;
+                        println!(
+                            "{} OK {}",
+                            pretty_ts(&reading.ts),
+                            formatting.reading(reading)
+                        );
                     }
-                    tokio::time::sleep(Duration::from_millis(1000)).await;
-                    c += 1;
+                    None => {}
                 }
             }
-            // memory-name
-            Some(("memory-name", args)) => {
-                if let Some(name) = args.get_one::<String>("name") {
-                    let slot = args.get_one::<u16>("slot").expect("Slot expected");
+        }
+        // memory-name
+        Some(("memory-name", args)) => {
+            if args.get_flag("all") {
+                let mut names = BTreeMap::new();
+                for slot in 1..=SAVE_NAME_SLOTS {
+                    names.insert(slot, device.save_name(slot - 1).await?);
+                }
+                if output.is_json() {
+                    print_json(output, &names)?;
+                } else {
+                    for (slot, name) in names {
+                        println!("Name[{}]: {}", slot, name);
+                    }
+                }
+            } else if let Some(path) = args.get_one::<PathBuf>("from") {
+                let names = match parse_save_names(&std::fs::read_to_string(path)?) {
+                    Ok(names) => names,
+                    Err(err) => {
+                        eprintln!("{}: {}", path.display(), err);
+                        exit(1);
+                    }
+                };
+                for (slot, name) in &names {
                     device.set_save_name(slot - 1, name).await?;
-                    println!("OK");
+                    if !output.is_json() {
+                        println!("Name[{}]: {}", slot, name);
+                    }
+                }
+                if output.is_json() {
+                    print_ok(output)?;
+                }
+            } else if let Some(name) = args.get_one::<String>("name") {
+                let slot = args.get_one::<u16>("slot").expect("Slot expected");
+                device.set_save_name(slot - 1, name).await?;
+                print_ok(output)?;
+            } else {
+                let slot = args.get_one::<u16>("slot").expect("Slot expected");
+                let name = device.save_name(slot - 1).await?;
+                println!("Name[{}]: {}", slot, name);
+            }
+        }
+
+        Some(("dump-measurements", _args)) => {
+            //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
+
+            let maps = load_maps(device, &MEASUREMENT_MAP_KEYS).await?;
+
+            let raw_meas = device
+                .saved_measurements_all_with_progress(progress_display())
+                .await?;
+
+            let meas: Vec<SavedMeasurement> = raw_meas
+                .into_iter()
+                .map(|rm| SavedMeasurement::try_from((rm, &maps)))
+                .collect::<Result<_>>()?;
+
+            if output.is_json() {
+                print_json(output, &meas)?;
+                return Ok(());
+            }
+
+            for mea in &meas {
+                println!(
+                    "Saved Measurement: '{}', primary: {}, secondary: {}",
+                    mea.name, mea.pri_function, mea.sec_function,
+                );
+                for reading in &mea.readings {
+                    let ext = reading
+                        .attribute
+                        .as_ref()
+                        .map(|attr| format!(" [{}]", attr))
+                        .unwrap_or_default();
+                    println!(
+                        "#{:04} {}{:>20}",
+                        reading.reading_id,
+                        formatting.reading(reading),
+                        ext
+                    );
+                }
+            }
+        }
+
+        Some(("dump-minmax", _args)) => {
+            //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
+
+            let maps = load_maps(device, &MEASUREMENT_MAP_KEYS).await?;
+
+            let raw_meas = device
+                .saved_minmax_all_with_progress(progress_display())
+                .await?;
+
+            let meas: Vec<SavedMinMaxMeasurement> = raw_meas
+                .into_iter()
+                .map(|rm| SavedMinMaxMeasurement::try_from((rm, &maps)))
+                .collect::<Result<_>>()?;
+
+            if output.is_json() {
+                print_json(output, &meas)?;
+                return Ok(());
+            }
+
+            for mea in &meas {
+                println!(
+                    "Saved Min/max Measurement: '{}', primary: {}, secondary: {}",
+                    mea.name, mea.pri_function, mea.sec_function,
+                );
+                if mea.readings.len() == 4 {
+                    println!(
+                        "Min/Max #{}: NOW: {}, MIN: {}, MAX: {}, AVG: {}",
+                        mea.seq_no,
+                        mea.readings[0],
+                        mea.readings[1],
+                        mea.readings[2],
+                        mea.readings[3]
+                    );
                 } else {
-                    let slot = args.get_one::<u16>("slot").expect("Slot expected");
-                    let name = device.save_name(slot - 1).await?;
-                    println!("Name[{}]: {}", slot, name);
+                    eprintln!(
+                        "Invalid readings count for min/max: {}, expected 4",
+                        mea.readings.len()
+                    );
                 }
             }
+        }
 
-            Some(("dump-measurements", _args)) => {
-                //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
+        Some(("dump-peak", _args)) => {
+            //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
 
-                let maps = device.value_maps().await?;
+            let maps = load_maps(device, &MEASUREMENT_MAP_KEYS).await?;
 
-                let raw_meas = device.saved_measurements_all().await?;
+            let raw_meas = device
+                .saved_peak_all_with_progress(progress_display())
+                .await?;
 
-                let meas: Vec<SavedMeasurement> = raw_meas
-                    .into_iter()
-                    .map(|rm| SavedMeasurement::from((rm, &maps)))
-                    .collect();
+            let meas: Vec<SavedMinMaxMeasurement> = raw_meas
+                .into_iter()
+                .map(|rm| SavedMinMaxMeasurement::try_from((rm, &maps)))
+                .collect::<Result<_>>()?;
 
-                for mea in &meas {
+            if output.is_json() {
+                print_json(output, &meas)?;
+                return Ok(());
+            }
+
+            for mea in &meas {
+                println!(
+                    "Saved Peak Measurement: '{}', primary: {}, secondary: {}",
+                    mea.name, mea.pri_function, mea.sec_function,
+                );
+                if mea.readings.len() == 4 {
+                    println!(
+                        "Peak #{}: NOW: {}, MIN: {}, MAX: {}, AVG: {}",
+                        mea.seq_no,
+                        mea.readings[0],
+                        mea.readings[1],
+                        mea.readings[2],
+                        mea.readings[3]
+                    );
+                } else {
+                    eprintln!(
+                        "Invalid readings count for min/max: {}, expected 4",
+                        mea.readings.len()
+                    );
+                }
+            }
+        }
+
+        Some(("dump-recordings", args)) => {
+            //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
+
+            let maps = load_maps(device, &VALUE_MAP_KEYS).await?;
+
+            let raw_meas = device
+                .saved_recordings_all_with_progress(progress_display())
+                .await?;
+
+            let meas: Vec<SavedRecordingSessionInfo> = raw_meas
+                .into_iter()
+                .map(|rm| SavedRecordingSessionInfo::try_from((rm, &maps)))
+                .collect::<Result<_>>()?;
+
+            let format = args.get_one::<String>("format").map(String::as_str);
+            let csv = format == Some("csv");
+            let parquet = format == Some("parquet");
+            let text = !csv && !parquet && output == Output::Text;
+            let plot = args.get_one::<PathBuf>("plot");
+            let event_options = args.get_flag("events").then(|| EventOptions {
+                thresholds: args
+                    .get_many::<f64>("threshold")
+                    .map(|values| values.copied().collect())
+                    .unwrap_or_default(),
+                hysteresis: args.get_one::<f64>("hysteresis").copied().unwrap_or(0.0),
+            });
+            let mut collected = Vec::new();
+
+            for mea in &meas {
+                if text {
                     println!(
-                        "Saved Measurement: '{}', primary: {}, secondary: {}",
-                        mea.name, mea.pri_function, mea.sec_function,
+                        "Saved Recording: '{}', primary: {}, secondary: {}, Samples: {}",
+                        mea.name, mea.pri_function, mea.sec_function, mea.num_samples,
                     );
-                    for reading in &mea.readings {
-                        let ext = reading
-                            .attribute
-                            .as_ref()
-                            .map(|attr| format!(" [{}]", attr))
-                            .unwrap_or_default();
-                        println!("#{:04} {}{:>20}", reading.reading_id, reading, ext);
+                }
+
+                //for reading in &mea.readings {
+                //    println!("#{:0>4} {}", mea.seq_no, reading.value);
+                //}
+                let recordings = fetch_recording(device, mea, &maps).await?;
+                let events = event_options
+                    .as_ref()
+                    .map(|options| analysis::events(&recordings, options));
+
+                if !text {
+                    collected.push(RecordingDump {
+                        session: mea,
+                        intervals: recordings,
+                        events,
+                    });
+                    continue;
+                }
+
+                for rec in &recordings {
+                    let avg = rec.average_reading();
+
+                    let duration = pretty_duration(&rec.duration());
+
+                    println!(
+                            "[{ts_start}]{value:#8} {duration:>10}, min({min_ts}): {min:8}, avg: {avg:8}, max({max_ts}): {max:8} [{record_type}{stable}]",
+                            value = formatting.reading(&rec.fixed_reading),
+                            ts_start = pretty_ts(&rec.start_ts),
+                            duration = duration,
+                            min = formatting.reading(&rec.span_readings[1]),
+                            min_ts = pretty_ts(&rec.span_readings[1].ts),
+                            avg = formatting.reading(&avg),
+                            max = formatting.reading(&rec.span_readings[0]),
+                            max_ts = pretty_ts(&rec.span_readings[0].ts),
+                            //ts_end = pretty_ts(&rec.end_ts),
+                            record_type = rec.record_type,
+                            stable = if rec.stable.0 { ",Stable" } else {""},
+                        );
+                }
+                pretty_recording_stats(&recordings);
+                if let Some(events) = &events {
+                    println!("Events: {}", events.len());
+                    for event in events {
+                        println!(
+                            "  #{:0>4} [{}] {}{}",
+                            event.index,
+                            pretty_ts(&event.ts),
+                            event.kind,
+                            event
+                                .value
+                                .map(|value| format!(" ({} {})", value, mea.unit))
+                                .unwrap_or_default(),
+                        );
+                    }
+                }
+
+                /*
+                for readings in &rr {
+                    //println!("New RecReading: {:?}", readings);
+                    for reading in &readings.readings {
+                        let r = Reading::from((reading.clone(), &maps));
+                        println!("#{:0>4} {} {:?}", r.reading_id, r.ts, r);
                     }
+                    println!(
+                        "##{:0>4} {}, {}",
+                        readings.reading2.reading_id,
+                        timestamp_to_datetime(readings.reading2.ts),
+                        readings.reading2.value
+                    );
+                }
+                 */
+                println!();
+
+                if plot.is_some() {
+                    collected.push(RecordingDump {
+                        session: mea,
+                        intervals: recordings,
+                        events,
+                    });
+                }
+            }
+
+            if let Some(path) = plot {
+                #[cfg(feature = "plot")]
+                {
+                    f289ctrl::integrations::plot::write_recordings(
+                        std::io::BufWriter::new(std::fs::File::create(path)?),
+                        collected
+                            .iter()
+                            .map(|dump| (dump.session.name.as_str(), dump.intervals.as_slice())),
+                    )?;
+                    eprintln!("Written: {}", path.display());
+                }
+                #[cfg(not(feature = "plot"))]
+                {
+                    eprintln!("Plotting requires the plot feature: {}", path.display());
+                    exit(1);
+                }
+            }
+
+            if parquet {
+                #[cfg(feature = "parquet")]
+                {
+                    let path = args.get_one::<PathBuf>("out").expect("out parameter");
+                    f289ctrl::integrations::export::parquet::write_recordings(
+                        std::fs::File::create(path)?,
+                        collected
+                            .iter()
+                            .map(|dump| (dump.session.name.as_str(), dump.intervals.as_slice())),
+                    )?;
+                    eprintln!("Written: {}", path.display());
+                }
+                #[cfg(not(feature = "parquet"))]
+                {
+                    eprintln!("Parquet output requires the parquet feature");
+                    exit(1);
                 }
+            } else if output.is_json() {
+                print_json(output, &collected)?;
+            } else if csv {
+                #[cfg(feature = "csv")]
+                f289ctrl::integrations::export::csv::write_recordings(
+                    std::io::stdout(),
+                    collected
+                        .iter()
+                        .map(|dump| (dump.session.name.as_str(), dump.intervals.as_slice())),
+                )?;
+                #[cfg(not(feature = "csv"))]
+                {
+                    eprintln!("CSV output requires the csv feature");
+                    exit(1);
+                }
+            }
+        }
+        Some(("memory", _args)) => {
+            //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
+
+            let maps = load_maps(device, &MEASUREMENT_MAP_KEYS).await?;
+
+            let stats = device.memory_statistics().await?;
+            let memory = device
+                .all_memory_with_stats(&maps, &stats, progress_display())
+                .await?;
+
+            if output.is_json() {
+                print_json(
+                    output,
+                    &MemoryListing {
+                        statistics: &stats,
+                        entries: memory.iter().map(MemoryListEntry::from).collect(),
+                    },
+                )?;
+                return Ok(());
             }
 
-            Some(("dump-minmax", _args)) => {
-                //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
+            let sections = [
+                (
+                    "Saved measurements",
+                    MemoryKind::Measurement,
+                    stats.measurement,
+                ),
+                (
+                    "Saved min/max measurements",
+                    MemoryKind::MinMax,
+                    stats.min_max,
+                ),
+                ("Saved peak measurements", MemoryKind::Peak, stats.peak),
+                ("Saved recordings", MemoryKind::Recording, stats.recordings),
+            ];
+            for (i, (caption, kind, count)) in sections.into_iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!("{}: {}", caption, count);
+                for (index, entry) in memory
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.kind() == kind)
+                {
+                    let detail = match entry {
+                        Memory::PeakMeasurement(mea) => {
+                            formatting.reading(&mea.readings[0]).to_string()
+                        }
+                        _ => entry.pri_function().to_string(),
+                    };
+                    println!(
+                        "{:>3} {} {:<30} {}",
+                        index + 1,
+                        paint(
+                            entry.ts().as_ref().map(pretty_ts).unwrap_or_default(),
+                            Paint::Dim
+                        ),
+                        quoted_string(entry.name()),
+                        detail
+                    );
+                }
+            }
+        }
+        #[cfg(feature = "json")]
+        Some(("export", args)) => {
+            use f289ctrl::integrations::export::archive::{write_archive, ArchiveFormat};
+
+            let dir = args.get_one::<PathBuf>("dir").expect("dir parameter");
+            let format = match args.get_one::<String>("format").map(String::as_str) {
+                #[cfg(feature = "csv")]
+                Some("csv") => ArchiveFormat::Csv,
+                #[cfg(not(feature = "csv"))]
+                Some("csv") => {
+                    eprintln!("CSV output requires the csv feature");
+                    exit(1);
+                }
+                _ => ArchiveFormat::Json,
+            };
+
+            let maps = load_maps(device, &VALUE_MAP_KEYS).await?;
+            let snapshot = device
+                .snapshot_memory_with_progress(&maps, progress_display())
+                .await?;
+            let manifest = write_archive(dir, &snapshot, format)?;
+            if output.is_json() {
+                print_json(output, &manifest)?;
+            } else {
+                println!(
+                    "Exported {} entries to {}",
+                    manifest.entries.len(),
+                    dir.display()
+                );
+            }
+        }
+        #[cfg(not(feature = "json"))]
+        Some(("export", _args)) => {
+            eprintln!("Export requires the json feature");
+            exit(1);
+        }
+        Some(("get-memory", args)) => {
+            //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
+
+            let name = args.get_one::<String>("name").expect("name parameter");
+
+            let maps = load_maps(device, &VALUE_MAP_KEYS).await?;
+
+            let memory = device.all_memory(&maps).await?;
+            let entry = match Memory::lookup(&memory, name) {
+                MemoryLookup::Found(index) => Some(&memory[index]),
+                MemoryLookup::Ambiguous(candidates) => {
+                    eprintln!("'{}' is ambiguous, candidates are:", name);
+                    for index in candidates {
+                        eprintln!("{:>3} {}", index + 1, quoted_string(memory[index].name()));
+                    }
+                    exit(1);
+                }
+                MemoryLookup::NotFound => None,
+            };
+            match entry {
+                Some(Memory::Recording(m)) if output.is_json() => {
+                    let intervals = fetch_recording(device, m, &maps).await?;
+                    print_json(
+                        output,
+                        &RecordingDump {
+                            session: m,
+                            intervals,
+                            events: None,
+                        },
+                    )?;
+                }
+                Some(Memory::Measurement(m)) if output.is_json() => print_json(output, m)?,
+                Some(Memory::MinMaxMeasurement(m) | Memory::PeakMeasurement(m))
+                    if output.is_json() =>
+                {
+                    print_json(output, m)?
+                }
+                Some(Memory::Measurement(m)) => {
+                    pretty_measurement(device, formatting, m).await?;
+                }
+                Some(Memory::MinMaxMeasurement(m)) => {
+                    pretty_minmax_or_peak_measurement(device, formatting, m, false).await?;
+                }
+                Some(Memory::PeakMeasurement(m)) => {
+                    pretty_minmax_or_peak_measurement(device, formatting, m, true).await?;
+                }
+                Some(Memory::Recording(m)) => {
+                    pretty_recording(device, formatting, m, &maps).await?;
+                }
+                None if output.is_json() => {
+                    eprintln!("'{}' not found", name);
+                    exit(1);
+                }
+                None => {
+                    println!("'{}' not found", name);
+                }
+            }
+        }
+        Some((name, _)) => {
+            eprintln!("'{}' is not supported here", name);
+            exit(1);
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// Run the subcommands of a script, returns the number of failed lines.
+///
+/// Errors are reported with their line number. Subcommands which take over
+/// the connection (`daemon`, `serve`, `run`) are rejected.
+async fn run_script(
+    device: &mut Device,
+    output: Output,
+    formatting: Formatting,
+    path: &std::path::Path,
+    stop_on_error: bool,
+) -> Result<usize> {
+    let script = std::fs::read_to_string(path)?;
+    let mut failed = 0;
+    for (no, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let result = match split_words(line) {
+            Ok(words) => match cli().no_binary_name(true).try_get_matches_from(words) {
+                Ok(matches) => match matches.subcommand() {
+                    Some((name @ ("daemon" | "serve" | "run" | "schema" | "ports"), _)) => {
+                        Err(format!("'{}' is not supported in scripts", name))
+                    }
+                    subcommand => run_command(device, output, formatting, subcommand)
+                        .await
+                        .map_err(|err| err.to_string()),
+                },
+                // Only the message, without the usage help
+                Err(err) => Err(err
+                    .to_string()
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim_start_matches("error: ")
+                    .to_string()),
+            },
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            eprintln!("{}:{}: {}", path.display(), no + 1, err);
+            failed += 1;
+            if stop_on_error {
+                break;
+            }
+        }
+    }
+    Ok(failed)
+}
+
+/// Split a script line into words, single or double quotes group words
+/// with spaces.
+fn split_words(line: &str) -> std::result::Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(String::from("Unterminated quote"));
+    }
+    words.extend(word.take());
+    Ok(words)
+}
+
+/// Command line definition, also used to parse the lines of `run` scripts.
+fn cli() -> clap::Command {
+    command!() // requires `cargo` feature
+        .arg(
+            arg!(
+                -p --device <PORT> "Port for USB adapter, 'auto', or tcp://, rfc2217://, unix:// or pty:// URL"
+            )
+            .default_value(DEFAULT_TTY)
+            .required(false)
+            .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(arg!(
+            -d --debug ... "Turn debugging information on"
+        ))
+        .arg(
+            arg!(--"auto-baud" "Try common baudrates until the device responds")
+                .conflicts_with_all(["baudrate", "record"]),
+        )
+        .arg(arg!(--dtr <STATE> "Set DTR line (on/off)").value_parser(BoolishValueParser::new()))
+        .arg(arg!(--rts <STATE> "Set RTS line (on/off)").value_parser(BoolishValueParser::new()))
+        .arg(
+            arg!(--retries <N> "Repeat queries up to N times on transient failures")
+                .value_parser(value_parser!(u32)),
+        )
+        .arg(
+            arg!(--"on-locked" <ACTION> "Reaction on commands rejected because of the screen shown by the meter")
+                .value_parser(["fail", "close", "wait"])
+                .default_value("fail"),
+        )
+        .arg(
+            arg!(--turnaround <MS> "Pause between a response and the next command")
+                .value_parser(value_parser!(u64)),
+        )
+        .arg(
+            arg!(--settle <MS> "Wait after setting DTR/RTS")
+                .value_parser(value_parser!(u64))
+                .default_value("0"),
+        )
+        .arg(
+            arg!(--record <FILE> "Record the raw byte stream of the session (gzip compressed)")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--"numeric-format" <FORMAT> "Decimal separator for printed readings")
+                .value_parser(["point", "comma", "device"])
+                .default_value("point"),
+        )
+        .arg(arg!(--scientific "Print readings in scientific notation"))
+        .arg(arg!(--"full-resolution" "Print readings with all digits instead of the display decimals"))
+        .arg(
+            arg!(--prefix <PREFIX> "Print readings with an SI prefix, e.g. m or k, 'none' for the base unit")
+                .value_parser(si_prefix),
+        )
+        .arg(arg!(--"no-unit" "Print readings without unit"))
+        .arg(
+            arg!(--color <WHEN> "Highlight readings by their state in text output")
+                .value_parser(["auto", "always", "never"])
+                .default_value("auto"),
+        )
+        .arg(
+            arg!(--profile <NAME> "Use the port, baudrate and defaults of a configuration profile"),
+        )
+        .arg(
+            arg!(--config <FILE> "Configuration file [default: $F289CTRL_CONFIG or ~/.config/f289ctrl/config.toml]")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            arg!(--output <FORMAT> "Format of the printed results")
+                .value_parser(["text", "json", "ndjson"])
+                .default_value("text"),
+        )
+        .arg(
+            arg!(--replay <FILE> "Answer commands from a recorded session instead of the device")
+                .value_parser(value_parser!(PathBuf))
+                .conflicts_with_all(["auto-baud", "record"]),
+        )
+        .arg(
+            arg!(
+                -b --baudrate <BAUDRATE> "Baudrate"
+            )
+            .default_value(DEFAULT_BAUDRATE.to_string())
+            .value_parser(value_parser!(u32)),
+        )
+        .subcommand(
+            clap::Command::new("backlight")
+                .about("Auto Backlight Timeout")
+                .arg(
+                    arg!([minutes] "Set time in minutes for auto backlight timeout")
+                        .value_parser(["5", "10", "15", "20", "25", "30", "off"]),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("poweroff").about("Auto Power Off").arg(
+                arg!([minutes] "Set time in minutes for auto power off")
+                    .value_parser(["15", "25", "35", "45", "60", "off"]),
+            ),
+        )
+        .subcommand(clap::Command::new("reset-device").about("Reset device"))
+        .subcommand(
+            clap::Command::new("custom-dBm")
+                .about("Custom dBm reference in Ohm")
+                .arg(arg!([reference] "Set custom reference").value_parser(value_parser!(u16))),
+        )
+        .subcommand(
+            clap::Command::new("temp-offset")
+                .about("Temperature offset")
+                .arg(arg!([offset] "Set custom offset").value_parser(value_parser!(i16))),
+        )
+        .subcommand(
+            clap::Command::new("digits").about("Digit count").arg(
+                arg!([digits] "Set display digit count").value_parser(value_parser!(DigitCount)),
+            ),
+        )
+        .subcommand(
+            clap::Command::new("language")
+                .about("Multimeter language")
+                .arg(arg!([language] "Set language").value_parser(value_parser!(Language))),
+        )
+        .subcommand(
+            clap::Command::new("date-format")
+                .about("Date format")
+                .arg(arg!([fmt] "Set format").value_parser(value_parser!(DateFormat))),
+        )
+        .subcommand(
+            clap::Command::new("time-format")
+                .about("Time format")
+                .arg(arg!([fmt] "Set format").value_parser(value_parser!(TimeFormat))),
+        )
+        .subcommand(
+            clap::Command::new("numeric-format")
+                .about("Numeric format")
+                .arg(arg!([fmt] "Set format").value_parser(value_parser!(NumericFormat))),
+        )
+        .subcommand(
+            clap::Command::new("autohold-event-thd")
+                .about("Autohold event threshold in %")
+                .arg(arg!([percent] "Set threshold").value_parser(value_parser!(u8))),
+        )
+        .subcommand(
+            clap::Command::new("recording-event-thd")
+                .about("Recording event threshold in %")
+                .arg(arg!([percent] "Set threshold").value_parser(value_parser!(u8))),
+        )
+        .subcommand(
+            clap::Command::new("dBm-reference")
+                .about("dBm reference in Ohm")
+                .arg(
+                    arg!([reference] "Set dBm reference")
+                        .value_parser(value_parser!(DezibelReference)),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("smoothing")
+                .about("Smoothing (AC)")
+                .arg(arg!([state] "Set smoothing").value_parser(BoolishValueParser::new())),
+        )
+        .subcommand(clap::Command::new("ident").about("Device identification"))
+        .subcommand(
+            clap::Command::new("beeper")
+                .about("Beeper")
+                .arg(arg!([state] "Set beeper").value_parser(BoolishValueParser::new())),
+        )
+        .subcommand(
+            clap::Command::new("identify")
+                .about("Beep and blink the backlight to find the meter on the bench")
+                .arg(
+                    arg!(--rounds <N> "Number of beep and blink rounds")
+                        .default_value("3")
+                        .value_parser(value_parser!(usize)),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("clock")
+                .about("Internal clock")
+                .arg(arg!(
+                    --"sync-with-host" "Sync DMM clock with local host"
+                ))
+                .arg(
+                    arg!(--drift "Measure offset and drift of the DMM clock against the host")
+                        .conflicts_with("sync-with-host"),
+                )
+                .arg(
+                    arg!(--interval <SECS> "Duration of the drift measurement")
+                        .default_value("10")
+                        .value_parser(value_parser!(u64).range(1..)),
+                )
+                .arg(arg!(--fix "Set the DMM clock to the host time after measuring").requires("drift"))
+                .arg(
+                    arg!(--tz <OFFSET> "UTC offset of the DMM clock, e.g. +01:00, instead of the host time zone")
+                        .value_parser(parse_utc_offset),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("apply-profile")
+                .about("Write operator, company, site and contact of the profile to the DMM")
+                .arg(arg!(--operator <NAME> "Operator name"))
+                .arg(arg!(--company <NAME> "Company name"))
+                .arg(arg!(--site <NAME> "Site name"))
+                .arg(arg!(--contact <NAME> "Contact name")),
+        )
+        .subcommand(
+            clap::Command::new("operator")
+                .about("Operator name")
+                .arg(arg!([name] "Set operator name")),
+        )
+        .subcommand(
+            clap::Command::new("company")
+                .about("Company name")
+                .arg(arg!([name] "Set company name")),
+        )
+        .subcommand(
+            clap::Command::new("site")
+                .about("Site name")
+                .arg(arg!([name] "Set site name")),
+        )
+        .subcommand(
+            clap::Command::new("contact")
+                .about("Contact")
+                .arg(arg!([name] "Set contact")),
+        )
+        .subcommand(
+            clap::Command::new("mea")
+                //.alias("mea")
+                .about("Get current measurement")
+                .arg(arg!(
+                    --"watch" "Poll current measurement until interrupted"
+                ))
+                .arg(
+                    arg!(--interval <MS> "Poll interval in milliseconds with --watch")
+                        .default_value("1000")
+                        .value_parser(value_parser!(u64).range(1..)),
+                )
+                .arg(
+                    arg!(--count <N> "Stop after N measurements, implies --watch")
+                        .value_parser(value_parser!(u64).range(1..)),
+                )
+                .arg(
+                    arg!(--duration <SECS> "Stop after SECS seconds, implies --watch")
+                        .value_parser(value_parser!(u64)),
+                )
+                .arg(
+                    arg!(--"value-only" "Print only the primary value in the base SI unit, exit code 2 without a value")
+                        .conflicts_with_all(["watch", "count", "duration"]),
+                )
+                .arg(arg!(--unit "Append the unit to --value-only").requires("value-only"))
+                .arg(arg!(
+                    --ascii "Poll only the primary reading with the ASCII query, for meters without binary measurements"
+                ))
+                .arg(
+                    arg!(--timestamps <CLOCK> "Timestamps of readings from the host or device clock")
+                        .value_parser(["host", "device", "both"])
+                        .default_value("device"),
+                )
+                .arg(arg!(
+                    --influx <URL> "Write readings to an InfluxDB write endpoint (http://)"
+                ))
+                .arg(arg!(--"influx-token" <TOKEN> "InfluxDB API token").requires("influx"))
+                .arg(
+                    arg!(--"influx-measurement" <NAME> "InfluxDB measurement name")
+                        .default_value("fluke289"),
+                )
+                .arg(arg!(
+                    --mqtt <URL> "Publish readings to mqtt[s]://[user[:pass]@]host[:port][/topic][?qos=1&discovery=homeassistant]"
+                )),
+        )
+        .subcommand(
+            clap::Command::new("capture")
+                .about("Poll silently and print measurements once a condition is met")
+                .arg(
+                    arg!(--when <CONDITION> "Trigger condition, 'value > X' or 'value < X'")
+                        .value_parser(value_parser!(Trigger))
+                        .conflicts_with_all(["above", "below"]),
+                )
+                .arg(
+                    arg!(--above <VALUE> "Trigger once the primary value is above VALUE")
+                        .value_parser(value_parser!(f64)),
+                )
+                .arg(
+                    arg!(--below <VALUE> "Trigger once the primary value is below VALUE")
+                        .value_parser(value_parser!(f64)),
+                )
+                .group(
+                    clap::ArgGroup::new("trigger")
+                        .args(["when", "above", "below"])
+                        .multiple(true)
+                        .required(true),
+                )
+                .arg(
+                    arg!(--pre <N> "Measurements before the trigger to include")
+                        .default_value("10")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    arg!(--count <N> "Stop after N measurements from the trigger on")
+                        .value_parser(value_parser!(u64).range(1..)),
+                )
+                .arg(
+                    arg!(--interval <MS> "Poll interval in milliseconds")
+                        .default_value("1000")
+                        .value_parser(value_parser!(u64).range(1..)),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("monitor")
+                .about("Poll continuously and alert when the value leaves a band or overloads")
+                .arg(
+                    arg!(--above <VALUE> "Alert when the primary value is above VALUE")
+                        .value_parser(value_parser!(f64)),
+                )
+                .arg(
+                    arg!(--below <VALUE> "Alert when the primary value is below VALUE")
+                        .value_parser(value_parser!(f64)),
+                )
+                .arg(arg!(--exec <CMD> "Run CMD through the shell on alerts, with F289_MESSAGE and F289_VALUE set"))
+                .arg(arg!(--notify "Show a desktop notification on alerts"))
+                .arg(arg!(--"keep-going" "Keep monitoring after an alert instead of exiting with code 3"))
+                .arg(
+                    arg!(--interval <MS> "Poll interval in milliseconds")
+                        .default_value("1000")
+                        .value_parser(value_parser!(u64).range(1..)),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("memory-name")
+                .about("Get/set memory slot name")
+                .arg(
+                    arg!([slot] "Slot")
+                        .value_parser(clap::value_parser!(u16).range(1..=8))
+                        .required_unless_present_any(["all", "from"]),
+                )
+                .arg(arg!([name] "Set name (max 16 chars)").value_parser(|name: &str| {
+                    validate_save_name(name).map(|_| name.to_string())
+                }))
+                .arg(arg!(--all "List the names of all slots").conflicts_with_all(["slot", "from"]))
+                .arg(
+                    arg!(--from <FILE> "Set names from a file with one 'slot=name' per line")
+                        .value_parser(value_parser!(PathBuf))
+                        .conflicts_with("slot"),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("clear").about("Clear memory").arg(
+                arg!(--"memory" <memory> "Memory type")
+                    .value_parser(value_parser!(ClearMemory))
+                    .default_missing_value("all")
+                    .default_value("all"),
+            ),
+        )
+        .subcommand(
+            clap::Command::new("dump-measurements")
+                .about("Dump memory measurements")
+                .alias("dump-mea"),
+        )
+        .subcommand(clap::Command::new("dump-minmax").about("Dump memory min/max measurements"))
+        .subcommand(clap::Command::new("dump-peak").about("Dump memory peak measurement"))
+        .subcommand(
+            clap::Command::new("dump-recordings")
+                .about("Dump memory recordings")
+                .alias("dump-rec")
+                .arg(
+                    arg!(--format <FORMAT> "Output format")
+                        .value_parser(["text", "csv", "parquet"])
+                        .default_value("text"),
+                )
+                .arg(
+                    arg!(-o --out <FILE> "Output file, required for parquet")
+                        .value_parser(value_parser!(PathBuf))
+                        .required_if_eq("format", "parquet"),
+                )
+                .arg(
+                    arg!(--plot <FILE> "Render value over time with min/max bands as SVG chart")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(arg!(--events "Detect threshold crossings, stability changes and transients"))
+                .arg(
+                    arg!(--threshold <VALUE> "Report crossings of a level in the base unit, repeatable")
+                        .action(clap::ArgAction::Append)
+                        .allow_negative_numbers(true)
+                        .value_parser(value_parser!(f64))
+                        .requires("events"),
+                )
+                .arg(
+                    arg!(--hysteresis <VALUE> "Distance from a threshold before a crossing is reported")
+                        .value_parser(value_parser!(f64))
+                        .default_value("0")
+                        .requires("events"),
+                ),
+        )
+        .subcommand(clap::Command::new("memory").about("List all memory entries"))
+        .subcommand(
+            clap::Command::new("get-memory")
+                .about("Query memory saving by name or index")
+                .arg(
+                    arg!(
+                        [name] "Name, index (see 'memory') or unique part of the name of the saving"
+                    )
+                    .required(true),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("export")
+                .about("Download all memory entries into a directory, one file each")
+                .arg(arg!(<dir> "Target directory").value_parser(value_parser!(PathBuf)))
+                .arg(
+                    arg!(--format <FORMAT> "File format of the entries")
+                        .value_parser(["json", "csv"])
+                        .default_value("json"),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("probe")
+                .about("Test which commands are supported by the connected device")
+                .arg(arg!(
+                    --"include-writes" "Also probe settings by writing back the current values"
+                ))
+                .arg(arg!(
+                    --"json" "Print the capability report as JSON"
+                )),
+        )
+        .subcommand(clap::Command::new("ports").about("List serial ports of known IR cables"))
+        .subcommand(
+            clap::Command::new("schema")
+                .about("Print the JSON Schema of exported documents")
+                .arg(
+                    arg!([document] "Document type")
+                        .value_parser(["snapshot", "measurement"])
+                        .default_value("snapshot"),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("daemon")
+                .about("Share the device with local clients over a Unix or TCP socket")
+                .arg(
+                    arg!(--socket <PATH> "Socket path")
+                        .default_value("/run/f289.sock")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(--listen <ADDR> "Listen on a TCP address instead of the Unix socket")
+                        .required(false),
+                )
+                .arg(
+                    arg!(--history <SECS> "Keep a measurement history for the given duration")
+                        .required(false)
+                        .value_parser(value_parser!(u64)),
+                )
+                .arg(
+                    arg!(--"history-resolution" <MS> "Minimum distance between history samples")
+                        .default_value("1000")
+                        .value_parser(value_parser!(u64)),
+                )
+                .arg(
+                    arg!(--"history-file" <PATH> "Persist the history to this file")
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(--alerts <FILE> "Load alert rules and actions from a TOML file")
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(--interval <MS> "Live measurement poll interval in milliseconds")
+                        .default_value("1000")
+                        .value_parser(value_parser!(u64)),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("serve")
+                .about("Serve a JSON API over HTTP (live measurements via WebSocket at /ws) or SCPI")
+                .arg(arg!(--http <ADDR> "Listen address, e.g. 127.0.0.1:8289").required(false))
+                .arg(
+                    arg!(--scpi <ADDR> "Listen address for SCPI commands, e.g. 0.0.0.0:5025")
+                        .required(false),
+                )
+                .arg(arg!(--mdns "Announce the server via mDNS like an LXI instrument"))
+                .group(
+                    clap::ArgGroup::new("listen")
+                        .args(["http", "scpi"])
+                        .multiple(true)
+                        .required(true),
+                )
+                .arg(
+                    arg!(--interval <MS> "Live measurement poll interval in milliseconds")
+                        .default_value("1000")
+                        .value_parser(value_parser!(u64)),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("run")
+                .about("Run subcommands from a script file over one connection")
+                .arg(arg!(<script> "One subcommand per line, # starts a comment").value_parser(value_parser!(PathBuf)))
+                .arg(arg!(--"stop-on-error" "Stop at the first failing line")),
+        )
+        .subcommand_required(true)
+}
 
-                let maps = device.value_maps().await?;
+/// How readings are printed, see `--numeric-format`, `--scientific`,
+/// `--full-resolution`, `--prefix` and `--no-unit`.
+#[derive(Debug, Clone, Copy)]
+struct Formatting {
+    numeric: NumericFormat,
+    format: ReadingFormat,
+}
 
-                let raw_meas = device.saved_minmax_all().await?;
+impl Formatting {
+    /// Warns on stderr if the meter reported out-of-range decimals.
+    fn reading(self, reading: &Reading) -> FormattedReading<'_> {
+        if !reading.decimals_valid() {
+            eprintln!(
+                "Warning: reading {} reports {} decimals, clamped for display",
+                reading.reading_id, reading.decimals
+            );
+        }
+        reading
+            .with_numeric_format(self.numeric)
+            .with_format(self.format)
+    }
+}
 
-                let meas: Vec<SavedMinMaxMeasurement> = raw_meas
-                    .into_iter()
-                    .map(|rm| SavedMinMaxMeasurement::from((rm, &maps)))
-                    .collect();
+/// Format of the printed results, see `--output`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Text,
+    Json,
+    /// One JSON object per line, readings are streamed flat
+    Ndjson,
+}
 
-                for mea in &meas {
-                    println!(
-                        "Saved Min/max Measurement: '{}', primary: {}, secondary: {}",
-                        mea.name, mea.pri_function, mea.sec_function,
-                    );
-                    if mea.readings.len() == 4 {
-                        println!(
-                            "Min/Max #{}: NOW: {}, MIN: {}, MAX: {}, AVG: {}",
-                            mea.seq_no,
-                            mea.readings[0],
-                            mea.readings[1],
-                            mea.readings[2],
-                            mea.readings[3]
-                        );
-                    } else {
-                        eprintln!(
-                            "Invalid readings count for min/max: {}, expected 4",
-                            mea.readings.len()
-                        );
-                    }
-                }
-            }
+impl Output {
+    fn from_matches(matches: &clap::ArgMatches) -> Self {
+        match matches.get_one::<String>("output").map(String::as_str) {
+            Some("json") => Output::Json,
+            Some("ndjson") => Output::Ndjson,
+            _ => Output::Text,
+        }
+    }
 
-            Some(("dump-peak", _args)) => {
-                //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
+    fn is_json(self) -> bool {
+        self != Output::Text
+    }
+}
 
-                let maps = device.value_maps().await?;
+#[cfg(feature = "json")]
+fn print_json<T: serde::Serialize>(output: Output, value: &T) -> Result<()> {
+    let json = if output == Output::Ndjson {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    };
+    println!("{}", json.map_err(std::io::Error::from)?);
+    Ok(())
+}
 
-                let raw_meas = device.saved_peak_all().await?;
+#[cfg(not(feature = "json"))]
+fn print_json<T>(_output: Output, _value: &T) -> Result<()> {
+    eprintln!("JSON output requires the json feature");
+    Ok(())
+}
 
-                let meas: Vec<SavedMinMaxMeasurement> = raw_meas
-                    .into_iter()
-                    .map(|rm| SavedMinMaxMeasurement::from((rm, &maps)))
-                    .collect();
+/// A single setting as `{"key": value}`.
+fn setting<T>(key: &'static str, value: T) -> BTreeMap<&'static str, T> {
+    BTreeMap::from([(key, value)])
+}
 
-                for mea in &meas {
-                    println!(
-                        "Saved Peak Measurement: '{}', primary: {}, secondary: {}",
-                        mea.name, mea.pri_function, mea.sec_function,
-                    );
-                    if mea.readings.len() == 4 {
-                        println!(
-                            "Peak #{}: NOW: {}, MIN: {}, MAX: {}, AVG: {}",
-                            mea.seq_no,
-                            mea.readings[0],
-                            mea.readings[1],
-                            mea.readings[2],
-                            mea.readings[3]
-                        );
-                    } else {
-                        eprintln!(
-                            "Invalid readings count for min/max: {}, expected 4",
-                            mea.readings.len()
-                        );
-                    }
-                }
-            }
+fn print_setting(output: Output, key: &'static str, label: &str, value: String) -> Result<()> {
+    match output {
+        Output::Json | Output::Ndjson => print_json(output, &setting(key, value)),
+        Output::Text => {
+            println!("{}: {}", label, value);
+            Ok(())
+        }
+    }
+}
 
-            Some(("dump-recordings", _args)) => {
-                //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
+/// Confirmation of a write command.
+fn print_ok(output: Output) -> Result<()> {
+    match output {
+        Output::Json | Output::Ndjson => print_json(output, &setting("ok", true)),
+        Output::Text => {
+            println!("OK");
+            Ok(())
+        }
+    }
+}
 
-                let maps = device.value_maps().await?;
+/// Memory overview printed by `memory`.
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "json"), allow(dead_code))]
+struct MemoryListing<'a> {
+    statistics: &'a MemoryStat,
+    entries: Vec<MemoryListEntry<'a>>,
+}
 
-                let raw_meas = device.saved_recordings_all().await?;
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "json"), allow(dead_code))]
+struct MemoryListEntry<'a> {
+    kind: MemoryKind,
+    seq_no: u16,
+    name: &'a str,
+    ts: Option<DateTime<Utc>>,
+    pri_function: PrimaryFunction,
+    sec_function: SecondaryFunction,
+}
 
-                let meas: Vec<SavedRecordingSessionInfo> = raw_meas
-                    .into_iter()
-                    .map(|rm| SavedRecordingSessionInfo::from((rm, &maps)))
-                    .collect();
+impl<'a> From<&'a Memory> for MemoryListEntry<'a> {
+    fn from(entry: &'a Memory) -> Self {
+        Self {
+            kind: entry.kind(),
+            seq_no: entry.seq_no(),
+            name: entry.name(),
+            ts: entry.ts(),
+            pri_function: entry.pri_function(),
+            sec_function: entry.sec_function(),
+        }
+    }
+}
 
-                for mea in &meas {
-                    println!(
-                        "Saved Recording: '{}', primary: {}, secondary: {}, Samples: {}",
-                        mea.name, mea.pri_function, mea.sec_function, mea.num_samples,
-                    );
+/// Recording session with its downloaded intervals.
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "json"), allow(dead_code))]
+struct RecordingDump<'a> {
+    session: &'a SavedRecordingSessionInfo,
+    intervals: Vec<SessionRecordReadings>,
+    /// Detected with `--events`
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "Option::is_none"))]
+    events: Option<Vec<RecordingEvent>>,
+}
 
-                    //for reading in &mea.readings {
-                    //    println!("#{:0>4} {}", mea.seq_no, reading.value);
-                    //}
-                    let rr = device
-                        .session_record_reading_all_cb(
-                            mea.reading_index as usize,
-                            mea.num_samples as usize,
-                            |index, total| {
-                                print!("\rReading {}/{}", index, total);
-                                std::io::stdout().flush().expect("Unable to flush stdout");
-                            },
-                        )
-                        .await?;
-                    print!("\r");
-
-                    let recordings: Vec<SessionRecordReadings> = rr
-                        .into_iter()
-                        .map(|rm| SessionRecordReadings::try_from((rm, &maps)))
-                        .collect::<std::result::Result<Vec<_>, _>>()?;
-
-                    for rec in &recordings {
-                        let mut avg = rec.span_readings[2].clone();
-                        avg.value /= rec.sampling as f64;
-
-                        let duration = {
-                            let diff = (rec.end_ts - rec.start_ts)
-                                .to_std()
-                                .expect("Invalid timestamp from device");
-                            let seconds = ((diff.as_millis() as f64) % (1000.0 * 60.0)) / 1000.0;
-                            let minutes = (diff.as_secs() / 60) % 60;
-                            let hours = (diff.as_secs() / 60) / 60;
-                            format!("{:02}:{:02}:{:02.1}", hours, minutes, seconds).to_string()
-                        };
+/// Run the alert hook and show a desktop notification, failures are
+/// reported on stderr.
+async fn raise_alert(message: &str, value: Option<f64>, exec: Option<&String>, notify: bool) {
+    if let Some(cmd) = exec {
+        let mut command = if cfg!(windows) {
+            let mut command = tokio::process::Command::new("cmd");
+            command.arg("/C").arg(cmd);
+            command
+        } else {
+            let mut command = tokio::process::Command::new("sh");
+            command.arg("-c").arg(cmd);
+            command
+        };
+        command.env("F289_MESSAGE", message);
+        if let Some(value) = value {
+            command.env("F289_VALUE", value.to_string());
+        }
+        match command.status().await {
+            Ok(status) if !status.success() => eprintln!("Alert command failed: {}", status),
+            Ok(_) => {}
+            Err(err) => eprintln!("Alert command failed: {}", err),
+        }
+    }
+    if notify {
+        let mut command = if cfg!(target_os = "macos") {
+            let mut command = tokio::process::Command::new("osascript");
+            command.arg("-e").arg(format!(
+                "display notification {:?} with title \"f289ctrl\"",
+                message
+            ));
+            command
+        } else {
+            let mut command = tokio::process::Command::new("notify-send");
+            command.arg("f289ctrl").arg(message);
+            command
+        };
+        if let Err(err) = command.status().await {
+            eprintln!("Desktop notification failed: {}", err);
+        }
+    }
+}
 
-                        println!(
-                            "[{ts_start}]{value:#8} {duration:>10}, min({min_ts}): {min:8}, avg: {avg:8}, max({max_ts}): {max:8} [{record_type}{stable}]",
-                            value = rec.fixed_reading,
-                            ts_start = pretty_ts(&rec.start_ts),
-                            duration = duration,
-                            min = rec.span_readings[1],
-                            min_ts = pretty_ts(&rec.span_readings[1].ts),
-                            avg = avg,
-                            max = rec.span_readings[0],
-                            max_ts = pretty_ts(&rec.span_readings[0].ts),
-                            //ts_end = pretty_ts(&rec.end_ts),
-                            record_type = rec.record_type,
-                            stable = if rec.stable.0 { ",Stable" } else {""},
-                        );
-                    }
+/// Number of memory slot names.
+const SAVE_NAME_SLOTS: u16 = 8;
+
+/// Parse `slot=name` lines of a `memory-name --from` file, `#` starts a
+/// comment. All lines are checked before any name is set.
+fn parse_save_names(text: &str) -> std::result::Result<BTreeMap<u16, String>, String> {
+    let mut names = BTreeMap::new();
+    for (no, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (slot, name) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected 'slot=name'", no + 1))?;
+        let slot = slot
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|slot| (1..=SAVE_NAME_SLOTS).contains(slot))
+            .ok_or_else(|| format!("line {}: slot must be 1 to {}", no + 1, SAVE_NAME_SLOTS))?;
+        let name = name.trim();
+        validate_save_name(name).map_err(|err| format!("line {}: {}", no + 1, err))?;
+        names.insert(slot, name.to_string());
+    }
+    Ok(names)
+}
 
-                    /*
-                    for readings in &rr {
-                        //println!("New RecReading: {:?}", readings);
-                        for reading in &readings.readings {
-                            let r = Reading::from((reading.clone(), &maps));
-                            println!("#{:0>4} {} {:?}", r.reading_id, r.ts, r);
-                        }
-                        println!(
-                            "##{:0>4} {}, {}",
-                            readings.reading2.reading_id,
-                            timestamp_to_datetime(readings.reading2.ts),
-                            readings.reading2.value
-                        );
-                    }
-                     */
-                    println!();
-                }
-            }
-            Some(("memory", _args)) => {
-                //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
+/// Result of `clock --drift`.
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "json"), allow(dead_code))]
+struct ClockDrift {
+    /// Device clock minus host clock
+    offset_secs: f64,
+    drift_ppm: f64,
+    interval_secs: f64,
+    /// Correction applied with `--fix`
+    adjusted_secs: Option<f64>,
+}
 
-                let maps = device.value_maps().await?;
+/// Offset of the device clock to the host time in seconds, positive if the
+/// device is ahead. The host time is local time, or the time in `tz`.
+///
+/// The device reports whole seconds, so the clock is polled until it ticks
+/// and compared to the host time at that moment.
+async fn clock_offset(device: &mut Device, tz: Option<FixedOffset>) -> Result<f64> {
+    let start = device.clock().await?;
+    loop {
+        let before = host_now(tz);
+        let clock = device.clock().await?;
+        let after = host_now(tz);
+        if clock != start {
+            let host = before + (after - before) / 2;
+            // The device counts seconds of its local time since 1970
+            let host = Utc
+                .from_utc_datetime(&host.naive_local())
+                .timestamp_millis() as f64
+                / 1000.0;
+            return Ok(clock as f64 - host);
+        }
+    }
+}
 
-                let stats = device.memory_statistics().await?;
-                let memory = device.all_memory(&maps).await?;
+/// Current host time, in the local time zone or the UTC offset `tz`.
+fn host_now(tz: Option<FixedOffset>) -> DateTime<FixedOffset> {
+    match tz {
+        Some(tz) => Utc::now().with_timezone(&tz),
+        None => {
+            let now = Local::now();
+            now.with_timezone(now.offset())
+        }
+    }
+}
 
-                println!("Saved measurements: {}", stats.measurement);
-                memory.iter().for_each(|entry| {
-                    if let Memory::Measurement(mea) = entry {
-                        println!(
-                            "{} {:<30} {}",
-                            pretty_ts(&mea.readings[0].ts),
-                            quoted_string(&mea.name),
-                            mea.pri_function
-                        );
-                    }
-                });
-                println!();
+/// Parse a UTC offset like `+01:00`, `-0530` or `Z`.
+fn parse_utc_offset(s: &str) -> std::result::Result<FixedOffset, String> {
+    let invalid = || format!("Invalid UTC offset '{}', expected e.g. +01:00", s);
+    if s == "Z" || s == "z" {
+        return Ok(FixedOffset::east_opt(0).expect("UTC"));
+    }
+    let (sign, rest) = match s.split_at(s.len().min(1)) {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return Err(invalid()),
+    };
+    let digits = rest.replace(':', "");
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let hours: i32 = digits[..2].parse().map_err(|_| invalid())?;
+    let minutes: i32 = digits[2..].parse().map_err(|_| invalid())?;
+    if minutes >= 60 {
+        return Err(invalid());
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+}
 
-                println!("Saved min/max measurements: {}", stats.min_max);
-                memory.iter().for_each(|entry| {
-                    if let Memory::MinMaxMeasurement(mea) = entry {
-                        println!(
-                            "{} {:<30} {}",
-                            pretty_ts(&mea.ts1),
-                            quoted_string(&mea.name),
-                            mea.pri_function
-                        );
-                    }
-                });
-                println!();
+/// Parse an SI prefix like `m` or `k`, `none` selects the base unit.
+fn si_prefix(s: &str) -> std::result::Result<i16, String> {
+    match s {
+        "none" => Ok(0),
+        "" => Err("Empty SI prefix, use 'none' for the base unit".to_string()),
+        _ => prefix_multiplier(s).ok_or_else(|| format!("Unknown SI prefix '{}'", s)),
+    }
+}
 
-                println!("Saved peak measurements: {}", stats.peak);
-                memory.iter().for_each(|entry| {
-                    if let Memory::PeakMeasurement(mea) = entry {
-                        println!(
-                            "{} {:<30} {}",
-                            pretty_ts(&mea.ts1),
-                            quoted_string(&mea.name),
-                            mea.readings[0]
-                        );
-                    }
-                });
-                println!();
+/// Command line with the values of the selected profile as defaults, or
+/// `None` if no profile is selected.
+#[cfg(feature = "profiles")]
+fn load_profile(matches: &clap::ArgMatches) -> Option<clap::Command> {
+    use f289ctrl::integrations::profile::{default_path, Config};
+
+    let name = matches.get_one::<String>("profile").map(String::as_str);
+    let explicit = matches.get_one::<PathBuf>("config");
+    let path = explicit.cloned().or_else(default_path)?;
+    let config = match Config::load(&path) {
+        Ok(config) => config,
+        // Only an error if a profile or the file was requested
+        Err(err) if err.kind() == ErrorKind::NotFound && name.is_none() && explicit.is_none() => {
+            return None
+        }
+        Err(err) => {
+            eprintln!("{}: {}", path.display(), err);
+            exit(1);
+        }
+    };
+    let profile = match config.profile(name) {
+        Ok(profile) => profile?,
+        Err(err) => {
+            eprintln!("{}: {}", path.display(), err);
+            exit(1);
+        }
+    };
 
-                println!("Saved recordings: {}", stats.recordings);
-                memory.iter().for_each(|entry| {
-                    if let Memory::Recording(mea) = entry {
-                        println!(
-                            "{} {:<30} {}",
-                            pretty_ts(&mea.start_ts),
-                            quoted_string(&mea.name),
-                            mea.pri_function
-                        );
-                    }
-                });
-            }
-            Some(("get-memory", args)) => {
-                //let watch = args.get_one::<bool>("watch").unwrap_or(&false);
+    let mut command = cli();
+    if let Some(device) = &profile.device {
+        command = command.mut_arg("device", |arg| arg.default_value(device.clone()));
+    }
+    if let Some(baudrate) = profile.baudrate {
+        command = command.mut_arg("baudrate", |arg| arg.default_value(baudrate.to_string()));
+    }
+    if let Some(timezone) = &profile.timezone {
+        command = command.mut_subcommand("clock", |clock| {
+            clock.mut_arg("tz", |arg| arg.default_value(timezone.clone()))
+        });
+    }
+    for (id, value) in [
+        ("operator", &profile.operator),
+        ("company", &profile.company),
+        ("site", &profile.site),
+        ("contact", &profile.contact),
+    ] {
+        if let Some(value) = value {
+            command = command.mut_subcommand("apply-profile", |apply| {
+                apply.mut_arg(id, |arg| arg.default_value(value.clone()))
+            });
+        }
+    }
+    Some(command)
+}
 
-                let name = args.get_one::<String>("name").expect("name parameter");
+#[cfg(not(feature = "profiles"))]
+fn load_profile(matches: &clap::ArgMatches) -> Option<clap::Command> {
+    if matches.contains_id("profile") || matches.contains_id("config") {
+        eprintln!("Profiles require the profiles feature");
+        exit(1);
+    }
+    None
+}
 
-                let maps = device.value_maps().await?;
+/// `ports` listing line of a cable.
+fn port_line(port: &transport::CablePort) -> String {
+    format!(
+        "{}\t{:04x}:{:04x}\t{}{}",
+        port.path,
+        port.vid,
+        port.pid,
+        port.cable,
+        port.serial_number
+            .as_ref()
+            .map(|sn| format!(" (S/N {})", sn))
+            .unwrap_or_default()
+    )
+}
 
-                match device
-                    .all_memory(&maps)
-                    .await?
-                    .iter()
-                    .find(|entry| entry.name() == name)
-                {
-                    Some(Memory::Measurement(m)) => {
-                        pretty_measurement(&mut device, m).await?;
-                    }
-                    Some(Memory::MinMaxMeasurement(m)) => {
-                        pretty_minmax_or_peak_measurement(&mut device, m, false).await?;
-                    }
-                    Some(Memory::PeakMeasurement(m)) => {
-                        pretty_minmax_or_peak_measurement(&mut device, m, true).await?;
-                    }
-                    Some(Memory::Recording(m)) => {
-                        pretty_recording(&mut device, m, &maps).await?;
-                    }
-                    None => {
-                        println!("'{}' not found", name);
-                    }
-                }
-            }
-            _ => {
-                todo!()
+/// Port of the only connected IR cable, for `--device auto`.
+fn auto_port() -> Result<String> {
+    let mut ports = transport::candidate_ports()?;
+    match ports.len() {
+        0 => {
+            eprintln!("No known IR cable found");
+            exit(1);
+        }
+        1 => {
+            let port = ports.remove(0);
+            eprintln!("Detected port: {} ({})", port.path, port.cable);
+            Ok(port.path)
+        }
+        _ => {
+            eprintln!("Several IR cables found, select one with --device:");
+            for port in &ports {
+                eprintln!("{}", port_line(port));
             }
+            exit(1);
         }
     }
+}
 
-    Ok(())
+/// Value maps of `keys`, completed from the built-in tables if needed.
+async fn load_maps(device: &mut Device, keys: &[&str]) -> Result<ValueMaps> {
+    let ident = device.ident().await?;
+    let (maps, builtin) = device
+        .value_maps_with_fallback_for(Some(&ident.firmware), keys)
+        .await?;
+    if !builtin.is_empty() {
+        eprintln!(
+            "Warning: value maps not reported by the device, using built-in: {}",
+            builtin.join(", ")
+        );
+    }
+    Ok(maps)
 }
 
 fn quoted_string(s: impl AsRef<str>) -> String {
     String::from("\"") + s.as_ref() + "\""
 }
 
-async fn pretty_measurement(_device: &mut Device, mea: &SavedMeasurement) -> Result<()> {
+async fn pretty_measurement(
+    _device: &mut Device,
+    formatting: Formatting,
+    mea: &SavedMeasurement,
+) -> Result<()> {
     println!(
         "Saved Measurement: '{}', primary: {}, secondary: {}, modes: [{}]",
         mea.name, mea.pri_function, mea.sec_function, mea.modes
     );
     let mut processed = 0;
-    pretty_value("Pri.", &mea.readings[0]);
+    pretty_value(formatting, "Pri.", &mea.readings[0]);
     processed += 1;
     if mea.sec_function != SecondaryFunction::None {
-        pretty_value("Sec.", &mea.readings[1]);
+        pretty_value(formatting, "Sec.", &mea.readings[1]);
         processed += 1;
     }
 
     if mea.modes.is(Mode::Rel) || mea.modes.is(Mode::RelPercent) {
-        pretty_value("Value", &mea.readings[1]);
-        pretty_value("Reference", &mea.readings[2]);
+        pretty_value(formatting, "Value", &mea.readings[1]);
+        pretty_value(formatting, "Reference", &mea.readings[2]);
     } else if mea.readings.len() > processed {
         println!("Additional readings:");
         for (i, reading) in mea.readings.iter().skip(processed).enumerate() {
-            pretty_value(format!("#{:>03}", i + processed), reading);
+            pretty_value(formatting, format!("#{:>03}", i + processed), reading);
         }
     }
     Ok(())
@@ -939,6 +2694,7 @@ async fn pretty_measurement(_device: &mut Device, mea: &SavedMeasurement) -> Res
 
 async fn pretty_minmax_or_peak_measurement(
     _device: &mut Device,
+    formatting: Formatting,
     mea: &SavedMinMaxMeasurement,
     peak: bool,
 ) -> Result<()> {
@@ -968,12 +2724,12 @@ async fn pretty_minmax_or_peak_measurement(
         let min = &mea.readings[2];
         let max = &mea.readings[3];
         let avg = &mea.readings[4];
-        pretty_value("Value", value);
-        pretty_value("Ref1", reference1);
-        pretty_value("Ref2", reference2);
-        pretty_value("Min", min);
-        pretty_value("Max", max);
-        pretty_value("Avg", avg);
+        pretty_value(formatting, "Value", value);
+        pretty_value(formatting, "Ref1", reference1);
+        pretty_value(formatting, "Ref2", reference2);
+        pretty_value(formatting, "Min", min);
+        pretty_value(formatting, "Max", max);
+        pretty_value(formatting, "Avg", avg);
     } else if mea.sec_function == SecondaryFunction::DbmHertz
         || mea.sec_function == SecondaryFunction::DbvHertz
     {
@@ -983,12 +2739,12 @@ async fn pretty_minmax_or_peak_measurement(
         let min = &mea.readings[2];
         let max = &mea.readings[3];
         let avg = &mea.readings[4];
-        pretty_value("Value", value);
-        pretty_value("Ref", reference);
-        pretty_value("Hertz", hertz);
-        pretty_value("Min", min);
-        pretty_value("Max", max);
-        pretty_value("Avg", avg);
+        pretty_value(formatting, "Value", value);
+        pretty_value(formatting, "Ref", reference);
+        pretty_value(formatting, "Hertz", hertz);
+        pretty_value(formatting, "Min", min);
+        pretty_value(formatting, "Max", max);
+        pretty_value(formatting, "Avg", avg);
     } else if mea.sec_function == SecondaryFunction::Dbm
         || mea.sec_function == SecondaryFunction::Dbv
     {
@@ -998,23 +2754,23 @@ async fn pretty_minmax_or_peak_measurement(
         let min = &mea.readings[2];
         let max = &mea.readings[3];
         let avg = &mea.readings[4];
-        pretty_value("Value", value);
-        pretty_value("Ref", reference);
-        pretty_value("VAC", vac);
-        pretty_value("Min", min);
-        pretty_value("Max", max);
-        pretty_value("Avg", avg);
+        pretty_value(formatting, "Value", value);
+        pretty_value(formatting, "Ref", reference);
+        pretty_value(formatting, "VAC", vac);
+        pretty_value(formatting, "Min", min);
+        pretty_value(formatting, "Max", max);
+        pretty_value(formatting, "Avg", avg);
     } else if mea.sec_function == SecondaryFunction::CrestFactor {
         let value = &mea.readings[0];
         let reference = &mea.readings[1];
         let min = &mea.readings[2];
         let max = &mea.readings[3];
         let avg = &mea.readings[4];
-        pretty_value("Value", value);
-        pretty_value("Ref", reference);
-        pretty_value("Min", min);
-        pretty_value("Max", max);
-        pretty_value("Avg", avg);
+        pretty_value(formatting, "Value", value);
+        pretty_value(formatting, "Ref", reference);
+        pretty_value(formatting, "Min", min);
+        pretty_value(formatting, "Max", max);
+        pretty_value(formatting, "Avg", avg);
     } else if mea.sec_function == SecondaryFunction::PulseWidth
         || mea.sec_function == SecondaryFunction::DutyCycle
         || mea.sec_function == SecondaryFunction::Hertz
@@ -1024,11 +2780,11 @@ async fn pretty_minmax_or_peak_measurement(
         let min = &mea.readings[2];
         let max = &mea.readings[3];
         let avg = &mea.readings[4];
-        pretty_value("Value", value);
-        pretty_value("Hertz", hertz);
-        pretty_value("Min", min);
-        pretty_value("Max", max);
-        pretty_value("Avg", avg);
+        pretty_value(formatting, "Value", value);
+        pretty_value(formatting, "Hertz", hertz);
+        pretty_value(formatting, "Min", min);
+        pretty_value(formatting, "Max", max);
+        pretty_value(formatting, "Avg", avg);
     } else if mea.modes.is(Mode::RelPercent)
         || mea.modes.is(Mode::Rel)
         || mea.sec_function == SecondaryFunction::DbmHertz
@@ -1038,11 +2794,11 @@ async fn pretty_minmax_or_peak_measurement(
         let min = &mea.readings[2];
         let max = &mea.readings[3];
         let avg = &mea.readings[4];
-        pretty_value("Value", value);
-        pretty_value("Ref", reference);
-        pretty_value("Min", min);
-        pretty_value("Max", max);
-        pretty_value("Avg", avg);
+        pretty_value(formatting, "Value", value);
+        pretty_value(formatting, "Ref", reference);
+        pretty_value(formatting, "Min", min);
+        pretty_value(formatting, "Max", max);
+        pretty_value(formatting, "Avg", avg);
     } else if mea.pri_function == PrimaryFunction::A_AC_PLUS_DC
         || mea.pri_function == PrimaryFunction::MA_AC_PLUS_DC
         || mea.pri_function == PrimaryFunction::UA_AC_PLUS_DC
@@ -1054,20 +2810,20 @@ async fn pretty_minmax_or_peak_measurement(
         let min = &mea.readings[2];
         let max = &mea.readings[3];
         let avg = &mea.readings[4];
-        pretty_value("Value", value);
+        pretty_value(formatting, "Value", value);
         //pretty_value("Ref", reference);
-        pretty_value("Min", min);
-        pretty_value("Max", max);
-        pretty_value("Avg", avg);
+        pretty_value(formatting, "Min", min);
+        pretty_value(formatting, "Max", max);
+        pretty_value(formatting, "Avg", avg);
     } else {
         let value = &mea.readings[0];
         let min = &mea.readings[1];
         let max = &mea.readings[2];
         let avg = &mea.readings[3];
-        pretty_value("Value", value);
-        pretty_value("Min", min);
-        pretty_value("Max", max);
-        pretty_value("Avg", avg);
+        pretty_value(formatting, "Value", value);
+        pretty_value(formatting, "Min", min);
+        pretty_value(formatting, "Max", max);
+        pretty_value(formatting, "Avg", avg);
     }
     println!("Stopped at: {}", pretty_ts(&mea.ts2));
     Ok(())
@@ -1075,6 +2831,7 @@ async fn pretty_minmax_or_peak_measurement(
 
 async fn pretty_recording(
     device: &mut Device,
+    formatting: Formatting,
     mea: &SavedRecordingSessionInfo,
     maps: &ValueMaps,
 ) -> Result<()> {
@@ -1086,58 +2843,348 @@ async fn pretty_recording(
     //for reading in &mea.readings {
     //    println!("#{:0>4} {}", mea.seq_no, reading.value);
     //}
+    let recordings = fetch_recording(device, mea, maps).await?;
+
+    for rec in &recordings {
+        let avg = rec.average_reading();
+
+        //println!("{:?}", rec);
+
+        let duration = pretty_duration(&rec.duration());
+
+        let value = format!("{:#8}", formatting.reading(&rec.fixed_reading));
+        let value = if rec.stable.0 && !is_alert(&rec.fixed_reading) {
+            paint(value, Paint::Ok)
+        } else {
+            paint_reading(value, &rec.fixed_reading)
+        };
+
+        println!(
+            "[{ts_start}]{value} {duration}, min({min_ts}): {min}, avg: {avg}, max({max_ts}): {max} [{record_type}{stable}]",
+            value = value,
+            ts_start = paint(pretty_ts(&rec.start_ts), Paint::Dim),
+            duration = paint(format!("{:>10}", duration), Paint::Dim),
+            min = paint_reading(
+                format!("{:8}", formatting.reading(&rec.span_readings[1])),
+                &rec.span_readings[1],
+            ),
+            min_ts = paint(pretty_ts(&rec.span_readings[1].ts), Paint::Dim),
+            avg = paint_reading(format!("{:8}", formatting.reading(&avg)), &avg),
+            max = paint_reading(
+                format!("{:8}", formatting.reading(&rec.span_readings[0])),
+                &rec.span_readings[0],
+            ),
+            max_ts = paint(pretty_ts(&rec.span_readings[0].ts), Paint::Dim),
+            //ts_end = pretty_ts(&rec.end_ts),
+            record_type = rec.record_type,
+            stable = if rec.stable.0 { paint(",Stable", Paint::Ok) } else { String::new() },
+        );
+    }
+    Ok(())
+}
+
+/// Download the intervals of a recording session.
+async fn fetch_recording(
+    device: &mut Device,
+    mea: &SavedRecordingSessionInfo,
+    maps: &ValueMaps,
+) -> Result<Vec<SessionRecordReadings>> {
     let rr = device
-        .session_record_reading_all_cb(
+        .session_record_reading_all_with_progress(
             mea.reading_index as usize,
             mea.num_samples as usize,
-            |index, total| {
-                print!("\rReading {}/{}", index, total);
-                std::io::stdout().flush().expect("Unable to flush stdout");
-            },
+            progress_display(),
         )
         .await?;
-    println!();
 
-    let recordings: Vec<SessionRecordReadings> = rr
+    let recordings = rr
         .into_iter()
         .map(|rm| SessionRecordReadings::try_from((rm, maps)))
         .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(recordings)
+}
 
-    for rec in &recordings {
-        let mut avg = rec.span_readings[2].clone();
-        avg.value /= rec.sampling as f64;
-
-        //println!("{:?}", rec);
+fn pretty_recording_stats(recordings: &[SessionRecordReadings]) {
+    let unit = match recordings.first() {
+        Some(rec) => &rec.fixed_reading.unit,
+        None => return,
+    };
+    let stats = stats::recording_stats(recordings);
+    let value =
+        |v: Option<f64>| v.map_or_else(|| "-".to_string(), |v| format!("{:.6} {}", v, unit));
+    println!(
+        "Summary: {} intervals, min: {}, mean: {}, max: {}, stddev: {}, overloads: {}",
+        stats.count,
+        value(stats.min),
+        value(stats.mean),
+        value(stats.max),
+        value(stats.stddev),
+        stats.overloads
+    );
+}
 
-        let duration = {
-            let diff = (rec.end_ts - rec.start_ts)
-                .to_std()
-                .expect("Invalid timestamp from device");
-            let seconds = ((diff.as_millis() as f64) % (1000.0 * 60.0)) / 1000.0;
-            let minutes = (diff.as_secs() / 60) % 60;
-            let hours = (diff.as_secs() / 60) / 60;
-            format!("{:02}:{:02}:{:02.1}", hours, minutes, seconds).to_string()
+/// Summary line of the primary readings polled by `mea --watch`.
+/// `mea --ascii`: poll the primary reading with `qm`.
+async fn ascii_mea(
+    device: &mut Device,
+    args: &clap::ArgMatches,
+    output: Output,
+    formatting: Formatting,
+    watch: bool,
+) -> Result<()> {
+    let interval = args.get_one::<u64>("interval").expect("interval parameter");
+    let count = args.get_one::<u64>("count");
+    let duration = args.get_one::<u64>("duration");
+    device.set_backend(Backend::Ascii);
+
+    if args.get_flag("value-only") {
+        let reading = match device.ascii_reading().await? {
+            Some(reading) => reading,
+            None => {
+                eprintln!("No data");
+                exit(2);
+            }
         };
+        match reading.si_value() {
+            Some(value) if args.get_flag("unit") => println!("{} {}", value, reading.unit),
+            Some(value) => println!("{}", value),
+            None => {
+                eprintln!("No value: {}", formatting.reading(&reading));
+                exit(2);
+            }
+        }
+        return Ok(());
+    }
 
-        println!(
-            "[{ts_start}]{value:#8} {duration:>10}, min({min_ts}): {min:8}, avg: {avg:8}, max({max_ts}): {max:8} [{record_type}{stable}]",
-            value = rec.fixed_reading,
-            ts_start = pretty_ts(&rec.start_ts),
-            duration = duration,
-            min = rec.span_readings[1],
-            min_ts = pretty_ts(&rec.span_readings[1].ts),
-            avg = avg,
-            max = rec.span_readings[0],
-            max_ts = pretty_ts(&rec.span_readings[0].ts),
-            //ts_end = pretty_ts(&rec.end_ts),
-            record_type = rec.record_type,
-            stable = if rec.stable.0 { ",Stable" } else {""},
-        );
+    let deadline = duration.map(|secs| tokio::time::Instant::now() + Duration::from_secs(*secs));
+    let mut ticker = tokio::time::interval(Duration::from_millis(*interval));
+    let mut primary = Vec::new();
+    let mut c = 1;
+    while count.map_or(true, |count| c <= *count) {
+        let tick = match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, ticker.tick()).await.ok(),
+            None => Some(ticker.tick().await),
+        };
+        if tick.is_none() {
+            break;
+        }
+        match device.ascii_reading().await {
+            Ok(Some(reading)) if output.is_json() => {
+                if let Err(err) = print_json(output, &reading) {
+                    eprintln!("Error: {}", err);
+                }
+                primary.push(reading);
+            }
+            Ok(Some(reading)) => {
+                println!(
+                    "#{:0>4} {} {}",
+                    c,
+                    paint_reading(
+                        format!("{:>15}", formatting.reading(&reading).to_string()),
+                        &reading,
+                    ),
+                    paint(
+                        format!(
+                            "{:>20}",
+                            reading
+                                .ts
+                                .with_timezone(&Local)
+                                .format("%Y-%m-%d %H:%M:%S")
+                                .to_string()
+                        ),
+                        Paint::Dim
+                    )
+                );
+                primary.push(reading);
+            }
+            Ok(None) if output.is_json() => {}
+            Ok(None) => println!("--- NO DATA ---"),
+            Err(err) => eprintln!("Error: {}", err),
+        }
+        c += 1;
+        if !watch {
+            break;
+        }
+    }
+    if watch {
+        let summary = watch_summary(&primary);
+        if output.is_json() {
+            eprintln!("{}", summary);
+        } else {
+            println!("{}", summary);
+        }
     }
     Ok(())
 }
 
-fn pretty_value(caption: impl AsRef<str>, reading: &Reading) {
-    let block1 = format!("{:10} {:#8}", caption.as_ref().to_string() + ":", reading);
-    println!("{:<35} [{}]", block1, pretty_ts(&reading.ts));
+fn watch_summary(readings: &[Reading]) -> String {
+    let unit = readings
+        .first()
+        .map(|r| r.unit.to_string())
+        .unwrap_or_default();
+    let stats = stats::reading_stats(readings);
+    let value =
+        |v: Option<f64>| v.map_or_else(|| "-".to_string(), |v| format!("{:.6} {}", v, unit));
+    format!(
+        "Summary: {} samples, min: {}, avg: {}, max: {}, overloads: {}",
+        stats.count,
+        value(stats.min),
+        value(stats.mean),
+        value(stats.max),
+        stats.overloads
+    )
+}
+
+fn pretty_value(formatting: Formatting, caption: impl AsRef<str>, reading: &Reading) {
+    let caption = format!("{:10}", caption.as_ref().to_string() + ":");
+    let value = format!("{:#8}", formatting.reading(reading));
+    // Pad before painting, escape sequences have no width
+    let padding = 35usize.saturating_sub(caption.chars().count() + 1 + value.chars().count());
+    println!(
+        "{} {}{} [{}]",
+        caption,
+        paint_reading(value, reading),
+        " ".repeat(padding),
+        paint(pretty_ts(&reading.ts), Paint::Dim)
+    );
+}
+
+/// Styles of the `--color` output.
+#[derive(Debug, Clone, Copy)]
+enum Paint {
+    /// Overload, invalid or open thermocouple
+    Alert,
+    Ok,
+    /// Timestamps and other metadata
+    Dim,
+}
+
+/// Force colors on or off, `auto` keeps the terminal detection.
+fn set_color(enabled: bool) {
+    #[cfg(feature = "color")]
+    console::set_colors_enabled(enabled);
+    #[cfg(not(feature = "color"))]
+    let _ = enabled;
+}
+
+#[cfg(feature = "color")]
+fn paint(text: impl Into<String>, style: Paint) -> String {
+    let text = console::style(text.into());
+    match style {
+        Paint::Alert => text.red(),
+        Paint::Ok => text.green(),
+        Paint::Dim => text.dim(),
+    }
+    .to_string()
+}
+
+#[cfg(not(feature = "color"))]
+fn paint(text: impl Into<String>, _style: Paint) -> String {
+    text.into()
+}
+
+fn is_alert(reading: &Reading) -> bool {
+    matches!(
+        reading.state,
+        State::OL | State::OL_Minus | State::Invalid | State::OpenTC
+    )
+}
+
+/// Paint the formatted `text` of `reading` red if it has no valid value.
+fn paint_reading(text: String, reading: &Reading) -> String {
+    if is_alert(reading) {
+        paint(text, Paint::Alert)
+    } else {
+        text
+    }
+}
+
+/// Protocol trace on stderr, enabled by `--debug`.
+struct StderrTracer;
+
+impl ProtocolObserver for StderrTracer {
+    fn on_tx(&mut self, ts: DateTime<Utc>, _command: &Command, bytes: &[u8]) {
+        eprintln!("{} TX {}", ts.format("%H:%M:%S%.3f"), bytes.escape_ascii());
+    }
+
+    fn on_rx(&mut self, ts: DateTime<Utc>, frame: &[u8]) {
+        eprintln!("{} RX {}", ts.format("%H:%M:%S%.3f"), frame.escape_ascii());
+    }
+}
+
+/// Progress callback for bulk transfers.
+#[cfg(feature = "progress")]
+fn progress_display() -> impl FnMut(&Progress) {
+    use f289ctrl::progress::Phase;
+    use indicatif::{ProgressBar, ProgressStyle};
+    use std::time::Instant;
+
+    let style =
+        ProgressStyle::with_template("{prefix:<12} [{bar:30}] {pos}/{len} {msg} ETA {eta_precise}")
+            .expect("progress template")
+            .progress_chars("#>-");
+    let mut current: Option<(Phase, ProgressBar, Instant)> = None;
+
+    move |progress: &Progress| {
+        // One bar per phase, e.g. the recordings and then their samples
+        if current
+            .as_ref()
+            .map_or(true, |(phase, _, _)| *phase != progress.phase)
+        {
+            if let Some((_, bar, _)) = current.take() {
+                bar.finish();
+            }
+            let bar = ProgressBar::new(progress.total as u64)
+                .with_style(style.clone())
+                .with_prefix(progress.phase.to_string());
+            current = Some((progress.phase, bar, Instant::now()));
+        }
+        let (_, bar, started) = current.as_ref().expect("progress bar");
+        let secs = started.elapsed().as_secs_f64();
+        let rate = if secs > 0.0 {
+            progress.bytes as f64 / 1024.0 / secs
+        } else {
+            0.0
+        };
+        bar.set_message(format!(
+            "{:.1} KiB ({:.2} KiB/s)",
+            progress.bytes as f64 / 1024.0,
+            rate
+        ));
+        bar.set_position(progress.done as u64);
+        if progress.is_complete() {
+            bar.finish();
+        }
+    }
+}
+
+/// Progress callback for bulk transfers.
+#[cfg(not(feature = "progress"))]
+fn progress_display() -> impl FnMut(&Progress) {
+    render_progress
+}
+
+/// Render a progress event as a single, continuously updated line on stderr.
+#[cfg_attr(feature = "progress", allow(dead_code))]
+fn render_progress(progress: &Progress) {
+    const BAR_WIDTH: usize = 30;
+    let filled = (progress.ratio() * BAR_WIDTH as f64) as usize;
+    let eta = progress
+        .eta
+        .map(|eta| format!("{:02}:{:02}", eta.as_secs() / 60, eta.as_secs() % 60))
+        .unwrap_or_else(|| String::from("--:--"));
+    eprint!(
+        "\r{:<12} [{}{}] {}/{} {:>8.1} KiB ETA {}",
+        progress.phase,
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        progress.done,
+        progress.total,
+        progress.bytes as f64 / 1024.0,
+        eta
+    );
+    if progress.is_complete() {
+        eprintln!();
+    }
+    std::io::stderr().flush().expect("Unable to flush stderr");
 }
@@ -0,0 +1,175 @@
+#![deny(clippy::unwrap_used)]
+
+use clap::{arg, command, value_parser, ArgMatches};
+use f289ctrl::sim::{Faults, SimConfig, Simulator};
+use f289ctrl::Result;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let matches = cli().get_matches();
+    let mut simulator = Simulator::new(config(&matches))?;
+
+    if let Some(addr) = matches.get_one::<String>("tcp") {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        println!("tcp://{}", listener.local_addr()?);
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            eprintln!("{}: connected", peer);
+            if let Err(err) = simulator.serve(stream).await {
+                eprintln!("{}: {}", peer, err);
+            }
+            eprintln!("{}: disconnected", peer);
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        let (master, slave) = f289ctrl::transport::PtyStream::create()?;
+        // Keep the slave side open, otherwise reading the master fails
+        // until the first client opened it and after each client closed it
+        let _slave = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&slave)?;
+        println!("pty://{}", slave);
+        simulator.serve(master).await?;
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        eprintln!("Pseudo-terminals are not supported on this platform, use --tcp");
+        std::process::exit(1);
+    }
+}
+
+fn config(matches: &ArgMatches) -> SimConfig {
+    let rate = |name: &str| matches.get_one::<f64>(name).copied().unwrap_or_default();
+    let defaults = SimConfig::default();
+    SimConfig {
+        model: string(matches, "model", defaults.model),
+        firmware: string(matches, "firmware", defaults.firmware),
+        serial: string(matches, "serial", defaults.serial),
+        function: string(matches, "function", defaults.function),
+        unit: string(matches, "unit", defaults.unit),
+        value: matches
+            .get_one::<f64>("value")
+            .copied()
+            .unwrap_or(defaults.value),
+        noise: matches
+            .get_one::<f64>("noise")
+            .copied()
+            .unwrap_or(defaults.noise),
+        decimals: matches
+            .get_one::<i16>("decimals")
+            .copied()
+            .unwrap_or(defaults.decimals),
+        memory: matches
+            .get_one::<u16>("memory")
+            .copied()
+            .unwrap_or(defaults.memory),
+        samples: matches
+            .get_one::<u16>("samples")
+            .copied()
+            .unwrap_or(defaults.samples),
+        faults: Faults {
+            busy: rate("busy"),
+            syntax_error: rate("syntax-error"),
+            no_data: rate("no-data"),
+            garbage: rate("garbage"),
+            drop: rate("drop"),
+            delay: Duration::from_millis(matches.get_one::<u64>("delay").copied().unwrap_or(0)),
+        },
+        seed: matches
+            .get_one::<u64>("seed")
+            .copied()
+            .unwrap_or(defaults.seed),
+    }
+}
+
+fn string(matches: &ArgMatches, name: &str, default: String) -> String {
+    matches.get_one::<String>(name).cloned().unwrap_or(default)
+}
+
+fn rate(s: &str) -> std::result::Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(rate),
+        _ => Err(format!("{} is not a rate between 0 and 1", s)),
+    }
+}
+
+fn cli() -> clap::Command {
+    command!()
+        .name("f289sim")
+        .about("Simulated Fluke 287/289 for testing without a meter")
+        .arg(
+            arg!(--tcp <ADDR> "Listen on a TCP address instead of a pseudo-terminal")
+                .required(false),
+        )
+        .arg(arg!(--model <MODEL> "Reported model").required(false))
+        .arg(arg!(--firmware <VERSION> "Reported firmware version").required(false))
+        .arg(arg!(--serial <SERIAL> "Reported serial number").required(false))
+        .arg(arg!(--function <NAME> "Primary function, e.g. V_DC or OHMS").required(false))
+        .arg(arg!(--unit <NAME> "Unit, e.g. VDC or OHM").required(false))
+        .arg(
+            arg!(--value <VALUE> "Center of the simulated readings")
+                .required(false)
+                .allow_negative_numbers(true)
+                .value_parser(value_parser!(f64)),
+        )
+        .arg(
+            arg!(--noise <VALUE> "Maximum deviation of a reading")
+                .required(false)
+                .value_parser(value_parser!(f64)),
+        )
+        .arg(
+            arg!(--decimals <N> "Decimals of a reading")
+                .required(false)
+                .value_parser(value_parser!(i16)),
+        )
+        .arg(
+            arg!(--memory <N> "Saved entries of each kind")
+                .required(false)
+                .value_parser(value_parser!(u16)),
+        )
+        .arg(
+            arg!(--samples <N> "Samples of each recording session")
+                .required(false)
+                .value_parser(value_parser!(u16)),
+        )
+        .arg(
+            arg!(--busy <RATE> "Rate of execution error responses")
+                .required(false)
+                .value_parser(rate),
+        )
+        .arg(
+            arg!(--"syntax-error" <RATE> "Rate of syntax error responses")
+                .required(false)
+                .value_parser(rate),
+        )
+        .arg(
+            arg!(--"no-data" <RATE> "Rate of qddb responses without data")
+                .required(false)
+                .value_parser(rate),
+        )
+        .arg(
+            arg!(--garbage <RATE> "Rate of responses prefixed with garbage bytes")
+                .required(false)
+                .value_parser(rate),
+        )
+        .arg(
+            arg!(--drop <RATE> "Rate of commands without response")
+                .required(false)
+                .value_parser(rate),
+        )
+        .arg(
+            arg!(--delay <MS> "Delay of each response in milliseconds")
+                .required(false)
+                .value_parser(value_parser!(u64)),
+        )
+        .arg(
+            arg!(--seed <SEED> "Seed of the noise and fault injection")
+                .required(false)
+                .value_parser(value_parser!(u64)),
+        )
+}
@@ -1,43 +1,11 @@
 //!
-//! This library provides communication with a Fluke 287/289 digital multimeter.
+//! Command line tool and compatibility crate for Fluke 287/289 digital multimeters.
 //!
-//! <br>
+//! The protocol implementation lives in [`f289ctrl_core`], export formats and
+//! service integrations in [`f289ctrl_integrations`]. All items of the core
+//! crate are re-exported here, so existing code using `f289ctrl::...` paths
+//! keeps working.
 //!
-//! # Details
-//!
-//! - You need a Fluke IR cable attached to your DMM.
-//!
-//! - Basic setup and connection
-//!
-//!   ```
-//!   use f289ctrl::{Device, DEFAULT_BAUDRATE};
-//!   #[tokio::main]
-//!   async fn main() -> f289ctrl::Result<()> {
-//!       let path = "/dev/ttyUSB0".to_string();
-//!       let mut device = Device::new(&path, DEFAULT_BAUDRATE)?;
-//!       eprintln!("Connected to: {}\n", device.ident().await?.model);
-//!       Ok(())
-//!   }
-//!   ```
-//!
-//! # Supported devices
-//!
-//!  * Fluke 287
-//!  * Fluke 289
-//!
-
-pub mod device;
-pub mod measurement;
-pub mod proto;
-pub mod rawmea;
-
-pub use device::Device;
-pub use proto::Result;
-
-#[cfg(unix)]
-pub const DEFAULT_TTY: &str = "/dev/ttyUSB0";
-#[cfg(windows)]
-pub const DEFAULT_TTY: &str = "COM1";
 
-/// Default Baudrate for Fluke 287 and 289.
-pub const DEFAULT_BAUDRATE: u32 = 115200;
+pub use f289ctrl_core::*;
+pub use f289ctrl_integrations as integrations;